@@ -0,0 +1,813 @@
+//! Multi-report averaging.
+//!
+//! Repeated profiling runs of the same workload are combined into one view:
+//! entries are matched by (command, shared object, symbol, srcline) and hierarchy
+//! edges by (caller, callee, context), and their percentages are averaged
+//! over the reports in which they appear (or, for entries with
+//! `ReportSet::missing_as_zero`, over all reports).
+
+use std::collections::{HashMap, HashSet};
+use std::path::{Path, PathBuf};
+
+use crate::PperfError;
+use crate::hierarchy::{
+    CallRelation, CallerRelation, collect_tree_symbols, transform_tree_symbols,
+};
+use crate::parser::{
+    ParsedReport, PerfEntry, parse_report_content, read_report, transform_entry_symbols,
+};
+use crate::symbol::{Symbol, SymbolTransform, simplify_symbol};
+
+/// Edges whose relative% varies by more than this (coefficient of variation, %)
+/// across reports are flagged as disagreeing.
+pub const DISAGREEMENT_CV_PCT: f64 = 25.0;
+
+/// A report is flagged as an outlier run when its top entries lie, on average, more
+/// than this many standard deviations from the other reports.
+pub const OUTLIER_Z_SCORE: f64 = 3.0;
+
+/// Number of entries (highest mean Children% present in every report) compared when
+/// looking for outlier runs.
+pub const OUTLIER_TOP_ENTRIES: usize = 10;
+
+/// Floor on the other reports' standard deviation (percentage points), so that a
+/// small difference from otherwise identical runs is not an unbounded z-score.
+const OUTLIER_MIN_STDDEV: f64 = 0.5;
+
+/// A report whose values deviate strongly from the rest of its `ReportSet`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct OutlierRun {
+    /// Position in `ReportSet::reports`
+    pub index: usize,
+    /// Mean z-score of the report's top entries against the other reports
+    pub z_score: f64,
+    /// Number of entries the score was taken over
+    pub entries: usize,
+}
+
+/// How per-report values combine into one (`--agg`).
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Aggregation {
+    /// Arithmetic mean of the reports containing the value
+    #[default]
+    Mean,
+    /// Median, robust against one disturbed run
+    Median,
+    /// Geometric mean, for percentages compared as ratios; 0.0 if any value is 0
+    Geomean,
+    /// Harmonic mean; 0.0 if any value is 0
+    Harmonic,
+}
+
+impl Aggregation {
+    /// Parse a `mean`/`median`/`geomean`/`harmonic` name.
+    pub fn from_setting(value: &str) -> Option<Aggregation> {
+        match value.trim() {
+            "mean" => Some(Aggregation::Mean),
+            "median" => Some(Aggregation::Median),
+            "geomean" => Some(Aggregation::Geomean),
+            "harmonic" => Some(Aggregation::Harmonic),
+            _ => None,
+        }
+    }
+
+    /// Combine the present values (0.0 if none), value `i` counting `weights[i]`
+    /// times (1.0 where `weights` is shorter than `values`).
+    pub fn apply(self, values: &[Option<f64>], weights: &[f64]) -> f64 {
+        match self {
+            Aggregation::Mean => weighted_mean(values, weights),
+            Aggregation::Median => median(values, weights),
+            Aggregation::Geomean => geometric_mean(values, weights),
+            Aggregation::Harmonic => harmonic_mean(values, weights),
+        }
+    }
+}
+
+impl std::fmt::Display for Aggregation {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Aggregation::Mean => write!(f, "mean"),
+            Aggregation::Median => write!(f, "median"),
+            Aggregation::Geomean => write!(f, "geomean"),
+            Aggregation::Harmonic => write!(f, "harmonic"),
+        }
+    }
+}
+
+/// A set of parsed reports, in command-line order.
+#[derive(Debug, Clone)]
+pub struct ReportSet {
+    pub reports: Vec<(PathBuf, ParsedReport)>,
+    /// How much each report counts when combining, parallel to `reports` (1.0 each
+    /// unless set from `--weight`); stats, CV and outlier detection ignore them
+    pub weights: Vec<f64>,
+    /// Count an entry absent from a report as 0% there when combining, instead of
+    /// leaving that report out (`--missing-as-zero`); stats and CV still skip it
+    pub missing_as_zero: bool,
+}
+
+/// An entry averaged over the reports that contain it.
+#[derive(Debug, Clone, PartialEq)]
+pub struct AveragedPerfEntry {
+    pub children_pct: f64,
+    pub self_pct: f64,
+    pub symbol: Symbol,
+    pub comm: String,
+    pub dso: String,
+    pub kernel: bool,
+    pub srcline: String,
+    /// Per-report Children%, in report order; None where the entry is absent
+    pub children_values: Vec<Option<f64>>,
+    /// Per-report Self%, in report order; None where the entry is absent
+    pub self_values: Vec<Option<f64>>,
+    /// Spread of `children_values` over the reports containing the entry
+    pub children_stats: ValueStats,
+    /// Spread of `self_values` over the reports containing the entry
+    pub self_stats: ValueStats,
+}
+
+/// Run-to-run spread of one percentage, over the reports in which it appears.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct ValueStats {
+    /// Population standard deviation
+    pub stddev: f64,
+    pub min: f64,
+    pub max: f64,
+}
+
+impl ValueStats {
+    /// Stats of the present values (all 0.0 if none).
+    pub fn of(values: &[Option<f64>]) -> ValueStats {
+        let present: Vec<f64> = values.iter().flatten().copied().collect();
+        if present.is_empty() {
+            return ValueStats::default();
+        }
+        ValueStats {
+            stddev: stddev(values),
+            min: present.iter().copied().fold(f64::INFINITY, f64::min),
+            max: present.iter().copied().fold(f64::NEG_INFINITY, f64::max),
+        }
+    }
+}
+
+impl AveragedPerfEntry {
+    /// Coefficient of variation (stddev / mean, in %) of Children% across reports
+    pub fn children_cv_pct(&self) -> f64 {
+        coefficient_of_variation(&self.children_values)
+    }
+
+    /// Coefficient of variation (stddev / mean, in %) of Self% across reports
+    pub fn self_cv_pct(&self) -> f64 {
+        coefficient_of_variation(&self.self_values)
+    }
+
+    pub fn to_entry(&self) -> PerfEntry {
+        PerfEntry {
+            children_pct: self.children_pct,
+            self_pct: self.self_pct,
+            symbol: self.symbol.clone(),
+            comm: self.comm.clone(),
+            dso: self.dso.clone(),
+            kernel: self.kernel,
+            srcline: self.srcline.clone(),
+        }
+    }
+}
+
+/// How consistently one caller→callee edge shows up across reports.
+#[derive(Debug, Clone, PartialEq)]
+pub struct EdgeAgreement {
+    pub caller: Symbol,
+    pub callee: Symbol,
+    pub context_root: Option<Symbol>,
+    /// Per-report relative%, in report order; None where the edge is absent
+    pub relative_values: Vec<Option<f64>>,
+    /// Mean relative% over the reports containing the edge
+    pub mean_relative_pct: f64,
+    /// Coefficient of variation (stddev / mean, in %) over the reports containing the edge
+    pub cv_pct: f64,
+}
+
+impl EdgeAgreement {
+    /// True when the edge is missing from some report or its relative% varies too much.
+    pub fn disagrees(&self) -> bool {
+        self.relative_values.iter().any(Option::is_none) || self.cv_pct > DISAGREEMENT_CV_PCT
+    }
+}
+
+impl ReportSet {
+    /// Parse every path (`-` is stdin), failing on the first unreadable or malformed report.
+    pub fn parse_all(paths: &[PathBuf]) -> Result<ReportSet, PperfError> {
+        let mut reports = Vec::new();
+        for path in paths {
+            reports.push((path.clone(), parse_report_content(&read_report(path)?)?));
+        }
+        let weights = vec![1.0; reports.len()];
+        Ok(ReportSet {
+            reports,
+            weights,
+            missing_as_zero: false,
+        })
+    }
+
+    pub fn len(&self) -> usize {
+        self.reports.len()
+    }
+
+    /// Every distinct simplified name of the entries and call trees, sorted: what
+    /// a symbol transform such as `--symbol-filter` is run over.
+    pub fn simplified_symbols(&self) -> Vec<String> {
+        let mut symbols = HashSet::new();
+        for (_, report) in &self.reports {
+            symbols.extend(
+                report
+                    .entries
+                    .iter()
+                    .map(|e| simplify_symbol(&e.symbol).into()),
+            );
+            collect_tree_symbols(&report.trees, &mut symbols);
+        }
+        let mut symbols: Vec<String> = symbols.into_iter().map(String::from).collect();
+        symbols.sort();
+        symbols
+    }
+
+    /// Rewrite the symbols of every entry and call tree.
+    pub fn transform_symbols(&mut self, transform: &dyn SymbolTransform) {
+        for (_, report) in &mut self.reports {
+            transform_entry_symbols(&mut report.entries, transform);
+            transform_tree_symbols(&mut report.trees, transform);
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.reports.is_empty()
+    }
+
+    pub fn paths(&self) -> impl Iterator<Item = &Path> {
+        self.reports.iter().map(|(path, _)| path.as_path())
+    }
+
+    /// Reports that look like outlier runs. Each of the `OUTLIER_TOP_ENTRIES` entries
+    /// found in every report scores a report by the distance of its Children% from
+    /// the mean of the other reports, in their standard deviations; reports whose mean
+    /// score exceeds `OUTLIER_Z_SCORE` are returned. Needs at least three reports.
+    pub fn find_outliers(&self) -> Vec<OutlierRun> {
+        let count = self.reports.len();
+        if count < 3 {
+            return Vec::new();
+        }
+        let mut top: Vec<AveragedPerfEntry> = self
+            .average()
+            .into_iter()
+            .filter(|e| e.children_values.iter().all(Option::is_some))
+            .collect();
+        top.sort_by(|a, b| b.children_pct.total_cmp(&a.children_pct));
+        top.truncate(OUTLIER_TOP_ENTRIES);
+        if top.is_empty() {
+            return Vec::new();
+        }
+
+        let mut outliers = Vec::new();
+        for index in 0..count {
+            let total: f64 = top
+                .iter()
+                .map(|entry| {
+                    let mut others = entry.children_values.clone();
+                    let value = others[index].take().unwrap_or_default();
+                    let spread = stddev(&others).max(OUTLIER_MIN_STDDEV);
+                    (value - mean(&others)).abs() / spread
+                })
+                .sum();
+            let z_score = total / top.len() as f64;
+            if z_score > OUTLIER_Z_SCORE {
+                outliers.push(OutlierRun {
+                    index,
+                    z_score,
+                    entries: top.len(),
+                });
+            }
+        }
+        outliers
+    }
+
+    /// Average entries across reports, in order of first appearance.
+    pub fn average(&self) -> Vec<AveragedPerfEntry> {
+        self.average_with(Aggregation::Mean)
+    }
+
+    /// `average` combining each entry's per-report values by `aggregation`.
+    pub fn average_with(&self, aggregation: Aggregation) -> Vec<AveragedPerfEntry> {
+        let count = self.reports.len();
+        let mut index: HashMap<(String, String, Symbol, String, usize), usize> = HashMap::new();
+        let mut averaged: Vec<AveragedPerfEntry> = Vec::new();
+
+        for (i, (_, report)) in self.reports.iter().enumerate() {
+            // A symbol listed twice in one report stays two entries, matched by occurrence
+            let mut occurrences: HashMap<(String, String, Symbol, String), usize> = HashMap::new();
+            for entry in &report.entries {
+                let id = (
+                    entry.comm.clone(),
+                    entry.dso.clone(),
+                    entry.symbol.clone(),
+                    entry.srcline.clone(),
+                );
+                let nth = occurrences.entry(id.clone()).or_insert(0);
+                let key = (id.0, id.1, id.2, id.3, *nth);
+                *nth += 1;
+
+                let slot = *index.entry(key).or_insert_with(|| {
+                    averaged.push(AveragedPerfEntry {
+                        children_pct: 0.0,
+                        self_pct: 0.0,
+                        symbol: entry.symbol.clone(),
+                        comm: entry.comm.clone(),
+                        dso: entry.dso.clone(),
+                        kernel: entry.kernel,
+                        srcline: entry.srcline.clone(),
+                        children_values: vec![None; count],
+                        self_values: vec![None; count],
+                        children_stats: ValueStats::default(),
+                        self_stats: ValueStats::default(),
+                    });
+                    averaged.len() - 1
+                });
+                averaged[slot].children_values[i] = Some(entry.children_pct);
+                averaged[slot].self_values[i] = Some(entry.self_pct);
+            }
+        }
+
+        let combine = |values: &[Option<f64>]| {
+            if self.missing_as_zero {
+                let filled: Vec<_> = values.iter().map(|v| Some(v.unwrap_or(0.0))).collect();
+                aggregation.apply(&filled, &self.weights)
+            } else {
+                aggregation.apply(values, &self.weights)
+            }
+        };
+        for entry in &mut averaged {
+            entry.children_pct = combine(&entry.children_values);
+            entry.self_pct = combine(&entry.self_values);
+            entry.children_stats = ValueStats::of(&entry.children_values);
+            entry.self_stats = ValueStats::of(&entry.self_values);
+        }
+        averaged
+    }
+}
+
+/// Average per-report relation lists into one, and score each edge's agreement.
+/// Relations are matched by (caller, callee, context_root) and occurrence order,
+/// so repeated edges within one report stay distinct. `weights` are the reports'
+/// (see `ReportSet::weights`). The agreement's mean and CV are always unweighted
+/// means, whatever `aggregation` combines the relations.
+pub fn average_relations(
+    per_report: &[Vec<CallRelation>],
+    aggregation: Aggregation,
+    weights: &[f64],
+) -> (Vec<CallRelation>, Vec<EdgeAgreement>) {
+    type Key = (Symbol, Symbol, Option<Symbol>, usize);
+
+    let count = per_report.len();
+    let mut index: HashMap<Key, usize> = HashMap::new();
+    let mut relations: Vec<CallRelation> = Vec::new();
+    let mut relative_values: Vec<Vec<Option<f64>>> = Vec::new();
+    let mut absolute_values: Vec<Vec<Option<f64>>> = Vec::new();
+
+    for (i, report_relations) in per_report.iter().enumerate() {
+        let mut occurrences: HashMap<(Symbol, Symbol, Option<Symbol>), usize> = HashMap::new();
+        for relation in report_relations {
+            let edge = (
+                relation.caller.clone(),
+                relation.callee.clone(),
+                relation.context_root.clone(),
+            );
+            let nth = occurrences.entry(edge.clone()).or_insert(0);
+            let key = (edge.0, edge.1, edge.2, *nth);
+            *nth += 1;
+
+            let slot = *index.entry(key).or_insert_with(|| {
+                relations.push(relation.clone());
+                relative_values.push(vec![None; count]);
+                absolute_values.push(vec![None; count]);
+                relations.len() - 1
+            });
+            relative_values[slot][i] = Some(relation.relative_pct);
+            absolute_values[slot][i] = Some(relation.absolute_pct);
+        }
+    }
+
+    let mut agreements = Vec::new();
+    for (slot, relation) in relations.iter_mut().enumerate() {
+        relation.relative_pct = aggregation.apply(&relative_values[slot], weights);
+        relation.absolute_pct = aggregation.apply(&absolute_values[slot], weights);
+        agreements.push(EdgeAgreement {
+            caller: relation.caller.clone(),
+            callee: relation.callee.clone(),
+            context_root: relation.context_root.clone(),
+            relative_values: relative_values[slot].clone(),
+            mean_relative_pct: mean(&relative_values[slot]),
+            cv_pct: coefficient_of_variation(&relative_values[slot]),
+        });
+    }
+
+    (relations, agreements)
+}
+
+/// Average per-report `--callers` lists by caller name (by `aggregation` over the
+/// reports that show the caller, with the reports' `weights`), highest first.
+pub fn average_callers(
+    per_report: &[Vec<CallerRelation>],
+    aggregation: Aggregation,
+    weights: &[f64],
+) -> Vec<CallerRelation> {
+    let mut callers: Vec<CallerRelation> = Vec::new();
+    let mut values: Vec<Vec<Option<f64>>> = Vec::new();
+    for (i, report_callers) in per_report.iter().enumerate() {
+        for relation in report_callers {
+            let slot = match callers.iter().position(|c| c.caller == relation.caller) {
+                Some(slot) => slot,
+                None => {
+                    callers.push(relation.clone());
+                    values.push(vec![None; per_report.len()]);
+                    callers.len() - 1
+                }
+            };
+            values[slot][i] = Some(relation.absolute_pct);
+        }
+    }
+    for (caller, values) in callers.iter_mut().zip(&values) {
+        caller.absolute_pct = aggregation.apply(values, weights);
+    }
+    callers.sort_by(|a, b| b.absolute_pct.total_cmp(&a.absolute_pct));
+    callers
+}
+
+/// Mean of the present values (0.0 if none).
+fn mean(values: &[Option<f64>]) -> f64 {
+    let present: Vec<f64> = values.iter().flatten().copied().collect();
+    if present.is_empty() {
+        return 0.0;
+    }
+    present.iter().sum::<f64>() / present.len() as f64
+}
+
+/// The present values paired with their report's weight (1.0 past the end of `weights`).
+fn weighted(values: &[Option<f64>], weights: &[f64]) -> Vec<(f64, f64)> {
+    values
+        .iter()
+        .enumerate()
+        .filter_map(|(i, v)| v.map(|v| (v, weights.get(i).copied().unwrap_or(1.0))))
+        .collect()
+}
+
+/// Weighted mean of the present values (0.0 if none).
+fn weighted_mean(values: &[Option<f64>], weights: &[f64]) -> f64 {
+    let present = weighted(values, weights);
+    let total: f64 = present.iter().map(|(_, w)| w).sum();
+    if total <= 0.0 {
+        return 0.0;
+    }
+    present.iter().map(|(v, w)| v * w).sum::<f64>() / total
+}
+
+/// Weighted median of the present values (0.0 if none): the first value at which the
+/// running weight passes half the total, or the mean of the two values either side
+/// when it lands exactly on half (the middle two for an even count of equal weights).
+fn median(values: &[Option<f64>], weights: &[f64]) -> f64 {
+    let mut present = weighted(values, weights);
+    present.sort_by(|a, b| a.0.total_cmp(&b.0));
+    let half = present.iter().map(|(_, w)| w).sum::<f64>() / 2.0;
+    let mut running = 0.0;
+    for (i, (value, weight)) in present.iter().enumerate() {
+        running += weight;
+        if (running - half).abs() < 1e-9 && i + 1 < present.len() {
+            return (value + present[i + 1].0) / 2.0;
+        }
+        if running > half {
+            return *value;
+        }
+    }
+    present.last().map_or(0.0, |(v, _)| *v)
+}
+
+/// Weighted geometric mean of the present values (0.0 if none or any is 0).
+fn geometric_mean(values: &[Option<f64>], weights: &[f64]) -> f64 {
+    let present = weighted(values, weights);
+    let total: f64 = present.iter().map(|(_, w)| w).sum();
+    if total <= 0.0 || present.iter().any(|(v, _)| *v <= 0.0) {
+        return 0.0;
+    }
+    (present.iter().map(|(v, w)| w * v.ln()).sum::<f64>() / total).exp()
+}
+
+/// Weighted harmonic mean of the present values (0.0 if none or any is 0).
+fn harmonic_mean(values: &[Option<f64>], weights: &[f64]) -> f64 {
+    let present = weighted(values, weights);
+    let total: f64 = present.iter().map(|(_, w)| w).sum();
+    if total <= 0.0 || present.iter().any(|(v, _)| *v <= 0.0) {
+        return 0.0;
+    }
+    total / present.iter().map(|(v, w)| w / v).sum::<f64>()
+}
+
+/// Population standard deviation of the present values (0.0 for fewer than two).
+fn stddev(values: &[Option<f64>]) -> f64 {
+    let present: Vec<f64> = values.iter().flatten().copied().collect();
+    if present.len() < 2 {
+        return 0.0;
+    }
+    let avg = mean(values);
+    let variance = present.iter().map(|v| (v - avg).powi(2)).sum::<f64>() / present.len() as f64;
+    variance.sqrt()
+}
+
+/// Population coefficient of variation of the present values, in percent.
+fn coefficient_of_variation(values: &[Option<f64>]) -> f64 {
+    let avg = mean(values);
+    if avg == 0.0 {
+        return 0.0;
+    }
+    stddev(values) / avg * 100.0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parser::{ReportLayout, ReportMetadata};
+
+    fn entry(symbol: &str, children: f64, self_pct: f64) -> PerfEntry {
+        PerfEntry {
+            children_pct: children,
+            self_pct,
+            symbol: symbol.into(),
+            ..Default::default()
+        }
+    }
+
+    fn report_set(reports: Vec<Vec<PerfEntry>>) -> ReportSet {
+        ReportSet {
+            weights: vec![1.0; reports.len()],
+            missing_as_zero: false,
+            reports: reports
+                .into_iter()
+                .enumerate()
+                .map(|(i, entries)| {
+                    (
+                        PathBuf::from(format!("rep{}.txt", i + 1)),
+                        ParsedReport {
+                            entries,
+                            trees: Vec::new(),
+                            metadata: ReportMetadata::default(),
+                            layout: ReportLayout::ChildrenSelf,
+                            has_call_graph: true,
+                            warnings: Vec::new(),
+                            tree_warnings: Vec::new(),
+                        },
+                    )
+                })
+                .collect(),
+        }
+    }
+
+    fn relation(caller: &str, callee: &str, relative: f64) -> CallRelation {
+        CallRelation {
+            caller: caller.into(),
+            callee: callee.into(),
+            relative_pct: relative,
+            absolute_pct: relative / 2.0,
+            context_root: None,
+            intermediary_path: vec![],
+        }
+    }
+
+    #[test]
+    fn test_average_matches_entries_across_reports() {
+        let set = report_set(vec![
+            vec![entry("main", 90.0, 0.0), entry("foo", 40.0, 10.0)],
+            vec![entry("foo", 50.0, 20.0), entry("main", 94.0, 0.0)],
+        ]);
+        let averaged = set.average();
+
+        assert_eq!(averaged.len(), 2);
+        assert_eq!(averaged[0].symbol, "main");
+        assert!((averaged[0].children_pct - 92.0).abs() < 0.01);
+        assert!((averaged[1].children_pct - 45.0).abs() < 0.01);
+        assert!((averaged[1].self_pct - 15.0).abs() < 0.01);
+        assert_eq!(averaged[1].children_values, vec![Some(40.0), Some(50.0)]);
+    }
+
+    #[test]
+    fn test_average_value_stats() {
+        let set = report_set(vec![
+            vec![entry("foo", 40.0, 10.0)],
+            vec![entry("foo", 50.0, 20.0)],
+            vec![entry("bar", 5.0, 5.0)],
+        ]);
+        let averaged = set.average();
+
+        let foo = &averaged[0].children_stats;
+        assert!((foo.stddev - 5.0).abs() < 0.01);
+        assert_eq!((foo.min, foo.max), (40.0, 50.0));
+        assert_eq!(averaged[0].self_stats.max, 20.0);
+        // A single report has no spread
+        assert_eq!(
+            averaged[1].children_stats,
+            ValueStats {
+                stddev: 0.0,
+                min: 5.0,
+                max: 5.0
+            }
+        );
+    }
+
+    #[test]
+    fn test_average_missing_entry_uses_present_reports() {
+        let set = report_set(vec![
+            vec![entry("main", 90.0, 0.0), entry("rare", 6.0, 6.0)],
+            vec![entry("main", 90.0, 0.0)],
+            vec![entry("main", 90.0, 0.0)],
+        ]);
+        let averaged = set.average();
+
+        let rare = averaged.iter().find(|e| e.symbol == "rare").unwrap();
+        assert!((rare.children_pct - 6.0).abs() < 0.01);
+        assert_eq!(rare.children_values, vec![Some(6.0), None, None]);
+    }
+
+    #[test]
+    fn test_average_missing_as_zero() {
+        let mut set = report_set(vec![
+            vec![entry("main", 90.0, 0.0), entry("rare", 6.0, 6.0)],
+            vec![entry("main", 90.0, 0.0)],
+            vec![entry("main", 90.0, 0.0)],
+        ]);
+        set.missing_as_zero = true;
+        let averaged = set.average();
+
+        let rare = averaged.iter().find(|e| e.symbol == "rare").unwrap();
+        assert!((rare.children_pct - 2.0).abs() < 0.01);
+        assert!((rare.self_pct - 2.0).abs() < 0.01);
+        // The spread is still over the reports that have the entry
+        assert_eq!(rare.children_stats.max, 6.0);
+        assert_eq!(rare.children_stats.min, 6.0);
+    }
+
+    #[test]
+    fn test_average_median() {
+        let set = report_set(vec![
+            vec![entry("main", 90.0, 1.0)],
+            vec![entry("main", 92.0, 2.0)],
+            vec![entry("main", 30.0, 9.0)],
+        ]);
+        let median = &set.average_with(Aggregation::Median)[0];
+        assert_eq!(median.children_pct, 90.0);
+        assert_eq!(median.self_pct, 2.0);
+        assert!((set.average()[0].children_pct - 70.67).abs() < 0.01);
+
+        // Even count: mean of the middle two
+        let set = report_set(vec![
+            vec![entry("main", 90.0, 0.0)],
+            vec![entry("main", 94.0, 0.0)],
+        ]);
+        assert_eq!(set.average_with(Aggregation::Median)[0].children_pct, 92.0);
+        assert_eq!(
+            Aggregation::from_setting("median"),
+            Some(Aggregation::Median)
+        );
+        assert_eq!(Aggregation::from_setting("mode"), None);
+    }
+
+    #[test]
+    fn test_find_outliers() {
+        let run = |main: f64, foo: f64| vec![entry("main", main, 0.0), entry("foo", foo, 5.0)];
+        let set = report_set(vec![
+            run(90.0, 40.0),
+            run(91.0, 41.0),
+            run(60.0, 20.0),
+            run(90.5, 40.0),
+        ]);
+        let outliers = set.find_outliers();
+        assert_eq!(outliers.len(), 1);
+        assert_eq!(outliers[0].index, 2);
+        assert_eq!(outliers[0].entries, 2);
+        assert!(outliers[0].z_score > OUTLIER_Z_SCORE);
+
+        // Two reports cannot tell which one is off
+        assert!(
+            report_set(vec![run(90.0, 40.0), run(60.0, 20.0)])
+                .find_outliers()
+                .is_empty()
+        );
+        // Small run-to-run noise is not an outlier
+        let set = report_set(vec![run(90.0, 40.0), run(91.0, 40.5), run(90.4, 39.8)]);
+        assert!(set.find_outliers().is_empty());
+    }
+
+    #[test]
+    fn test_average_geomean_and_harmonic() {
+        let set = report_set(vec![
+            vec![entry("main", 10.0, 0.0)],
+            vec![entry("main", 40.0, 2.0)],
+        ]);
+        let geomean = &set.average_with(Aggregation::Geomean)[0];
+        assert!((geomean.children_pct - 20.0).abs() < 1e-9);
+        // A zero in any report makes both means zero
+        assert_eq!(geomean.self_pct, 0.0);
+
+        let harmonic = &set.average_with(Aggregation::Harmonic)[0];
+        assert!((harmonic.children_pct - 16.0).abs() < 1e-9);
+        assert_eq!(harmonic.self_pct, 0.0);
+        assert_eq!(
+            Aggregation::from_setting("geomean"),
+            Some(Aggregation::Geomean)
+        );
+        assert_eq!(Aggregation::Harmonic.to_string(), "harmonic");
+    }
+
+    #[test]
+    fn test_average_weighted() {
+        let mut set = report_set(vec![
+            vec![entry("main", 90.0, 0.0)],
+            vec![entry("main", 60.0, 0.0)],
+            vec![entry("main", 30.0, 0.0)],
+        ]);
+        set.weights = vec![2.0, 1.0, 1.0];
+        assert_eq!(set.average()[0].children_pct, 67.5);
+        // Half the weight sits on 90.0, so the median lies between it and 60.0
+        assert_eq!(set.average_with(Aggregation::Median)[0].children_pct, 75.0);
+        set.weights = vec![3.0, 1.0, 1.0];
+        assert_eq!(set.average_with(Aggregation::Median)[0].children_pct, 90.0);
+        let geomean = set.average_with(Aggregation::Geomean)[0].children_pct;
+        assert!((geomean - (90.0f64.powi(3) * 60.0 * 30.0).powf(0.2)).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_average_distinguishes_dso() {
+        let mut a = entry("memcpy", 10.0, 10.0);
+        a.dso = "libc.so.6".to_string();
+        let mut b = entry("memcpy", 4.0, 4.0);
+        b.dso = "app".to_string();
+        let set = report_set(vec![vec![a, b]]);
+
+        assert_eq!(set.average().len(), 2);
+    }
+
+    #[test]
+    fn test_average_callers() {
+        let caller = |name: &str, absolute_pct: f64| CallerRelation {
+            caller: name.into(),
+            absolute_pct,
+        };
+        let averaged = average_callers(
+            &[
+                vec![caller("encode", 30.0), caller("decode", 2.0)],
+                vec![caller("decode", 6.0), caller("encode", 20.0)],
+                vec![caller("decode", 4.0)],
+            ],
+            Aggregation::Mean,
+            &[],
+        );
+
+        assert_eq!(averaged[0], caller("encode", 25.0));
+        assert_eq!(averaged[1], caller("decode", 4.0));
+    }
+
+    #[test]
+    fn test_average_relations_agreement() {
+        let per_report = vec![
+            vec![relation("A", "B", 20.0), relation("A", "C", 10.0)],
+            vec![relation("A", "B", 22.0), relation("A", "C", 30.0)],
+            vec![relation("A", "B", 18.0)],
+        ];
+        let (relations, agreements) = average_relations(&per_report, Aggregation::Mean, &[]);
+
+        assert_eq!(relations.len(), 2);
+        assert!((relations[0].relative_pct - 20.0).abs() < 0.01);
+        assert!((relations[0].absolute_pct - 10.0).abs() < 0.01);
+        assert!(!agreements[0].disagrees());
+        assert!(agreements[0].cv_pct < 10.0);
+
+        // A→C is missing from rep3 and varies 10 vs 30
+        assert!((agreements[1].mean_relative_pct - 20.0).abs() < 0.01);
+        assert!((agreements[1].cv_pct - 50.0).abs() < 0.01);
+        assert!(agreements[1].disagrees());
+    }
+
+    #[test]
+    fn test_average_relations_keeps_repeated_edges_distinct() {
+        let per_report = vec![
+            vec![relation("A", "B", 20.0), relation("A", "B", 5.0)],
+            vec![relation("A", "B", 24.0), relation("A", "B", 7.0)],
+        ];
+        let (relations, _) = average_relations(&per_report, Aggregation::Mean, &[]);
+
+        assert_eq!(relations.len(), 2);
+        assert!((relations[0].relative_pct - 22.0).abs() < 0.01);
+        assert!((relations[1].relative_pct - 6.0).abs() < 0.01);
+    }
+
+    #[test]
+    fn test_coefficient_of_variation_single_value() {
+        assert_eq!(coefficient_of_variation(&[Some(5.0), None]), 0.0);
+    }
+}
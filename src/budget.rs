@@ -0,0 +1,262 @@
+//! Performance budgets (`pperf top --budget`): per-target ceilings on Children%
+//! and Self%, checked against a report so CI can fail on regressions.
+
+use std::fs;
+use std::path::Path;
+
+use crate::PperfError;
+use crate::filter::{TargetSpec, parse_toml_string};
+use crate::parser::PerfEntry;
+use crate::symbol::simplify_symbol;
+
+/// One `[[budget]]` table: what to match and the allowed maxima.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Budget {
+    pub spec: TargetSpec,
+    pub max_children: Option<f64>,
+    pub max_self: Option<f64>,
+}
+
+/// A budget checked against a report. Values are the highest among the matching
+/// entries (every matching function must stay within the budget); `None` when
+/// nothing matched.
+#[derive(Debug, Clone, PartialEq)]
+pub struct BudgetResult {
+    pub budget: Budget,
+    /// Simplified name of the worst matching entry, or the spec when none matched
+    pub symbol: String,
+    pub children_pct: Option<f64>,
+    pub self_pct: Option<f64>,
+}
+
+impl BudgetResult {
+    pub fn exceeded(&self) -> bool {
+        let over = |value: Option<f64>, max: Option<f64>| matches!((value, max), (Some(v), Some(m)) if v > m);
+        over(self.children_pct, self.budget.max_children)
+            || over(self.self_pct, self.budget.max_self)
+    }
+}
+
+/// Read a `--budget` file; see `parse_budgets` for the format.
+pub fn parse_budget_file(path: &Path) -> Result<Vec<Budget>, PperfError> {
+    let content = fs::read_to_string(path)
+        .map_err(|_| PperfError::FileNotFound(path.display().to_string()))?;
+    parse_budgets(&content)
+        .map_err(|reason| PperfError::InvalidBudgetFile(format!("{}: {}", path.display(), reason)))
+}
+
+/// Parse budget tables, the same TOML subset as structured target files:
+/// `signature` or `regex` selects the functions, `max_children` and `max_self`
+/// (at least one) are percentages. Errors are `line N: reason`.
+///
+/// ```toml
+/// [[budget]]
+/// signature = "TransformPartition::rd_optimize_transform(Block4D const&)"
+/// max_children = 75.0
+/// ```
+pub fn parse_budgets(content: &str) -> Result<Vec<Budget>, String> {
+    #[derive(Default)]
+    struct Table {
+        line: usize,
+        signature: Option<String>,
+        regex: Option<String>,
+        max_children: Option<f64>,
+        max_self: Option<f64>,
+    }
+
+    fn finish(table: Table) -> Result<Budget, String> {
+        let spec = match (table.signature, table.regex) {
+            (Some(signature), None) => TargetSpec::exact(&signature),
+            (None, Some(pattern)) => TargetSpec::regex(&pattern)
+                .map_err(|e| format!("line {}: invalid regex: {}", table.line, e))?,
+            _ => {
+                return Err(format!(
+                    "line {}: [[budget]] needs exactly one of signature or regex",
+                    table.line
+                ));
+            }
+        };
+        if table.max_children.is_none() && table.max_self.is_none() {
+            return Err(format!(
+                "line {}: [[budget]] needs max_children or max_self",
+                table.line
+            ));
+        }
+        Ok(Budget {
+            spec,
+            max_children: table.max_children,
+            max_self: table.max_self,
+        })
+    }
+
+    let mut budgets = Vec::new();
+    let mut current: Option<Table> = None;
+    for (index, line) in content.lines().enumerate() {
+        let number = index + 1;
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        if line.starts_with('[') {
+            if line != "[[budget]]" {
+                return Err(format!("line {}: unknown table {}", number, line));
+            }
+            if let Some(table) = current.take() {
+                budgets.push(finish(table)?);
+            }
+            current = Some(Table {
+                line: number,
+                ..Default::default()
+            });
+            continue;
+        }
+
+        let Some((key, value)) = line.split_once('=') else {
+            return Err(format!("line {}: expected key = value", number));
+        };
+        let Some(table) = current.as_mut() else {
+            return Err(format!("line {}: key outside a [[budget]] table", number));
+        };
+        let value = value.trim();
+        let string = || parse_toml_string(value).map_err(|e| format!("line {}: {}", number, e));
+        let pct = || {
+            let number_text = value.split('#').next().unwrap_or("").trim();
+            number_text
+                .parse::<f64>()
+                .ok()
+                .filter(|v| v.is_finite() && *v >= 0.0)
+                .ok_or_else(|| format!("line {}: expected a percentage, got {}", number, value))
+        };
+        match key.trim() {
+            "signature" => table.signature = Some(string()?),
+            "regex" => table.regex = Some(string()?),
+            "max_children" => table.max_children = Some(pct()?),
+            "max_self" => table.max_self = Some(pct()?),
+            other => return Err(format!("line {}: unknown key {}", number, other)),
+        }
+    }
+    if let Some(table) = current {
+        budgets.push(finish(table)?);
+    }
+    Ok(budgets)
+}
+
+/// Check every budget against `entries`, in budget-file order.
+pub fn check_budgets(
+    entries: &[PerfEntry],
+    budgets: &[Budget],
+    ignore_case: bool,
+) -> Vec<BudgetResult> {
+    budgets
+        .iter()
+        .map(|budget| {
+            let matching: Vec<&PerfEntry> = entries
+                .iter()
+                .filter(|e| budget.spec.matches(&e.symbol, ignore_case))
+                .collect();
+            let worst = matching
+                .iter()
+                .max_by(|a, b| a.children_pct.total_cmp(&b.children_pct));
+            let max_of =
+                |value: fn(&PerfEntry) -> f64| matching.iter().map(|e| value(e)).reduce(f64::max);
+            BudgetResult {
+                budget: budget.clone(),
+                symbol: worst
+                    .map(|e| simplify_symbol(&e.symbol))
+                    .unwrap_or_else(|| budget.spec.to_string()),
+                children_pct: max_of(|e| e.children_pct),
+                self_pct: max_of(|e| e.self_pct),
+            }
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_budgets() {
+        let budgets = parse_budgets(
+            "# CI limits\n\
+             [[budget]]\n\
+             signature = \"DCT4DBlock::DCT4DBlock(Block4D const&, double)\"\n\
+             max_children = 40 # percent\n\
+             \n\
+             [[budget]]\n\
+             regex = 'inner_product'\n\
+             max_self = 7.5\n",
+        )
+        .unwrap();
+        assert_eq!(budgets.len(), 2);
+        assert_eq!(budgets[0].max_children, Some(40.0));
+        assert_eq!(budgets[0].max_self, None);
+        assert_eq!(budgets[1].spec.to_string(), "re:inner_product");
+        assert_eq!(budgets[1].max_self, Some(7.5));
+    }
+
+    #[test]
+    fn test_parse_budgets_errors() {
+        let err = |content: &str| parse_budgets(content).unwrap_err();
+        assert_eq!(
+            err("[[budget]]\nregex = 'x'\n"),
+            "line 1: [[budget]] needs max_children or max_self"
+        );
+        assert_eq!(
+            err("[[budget]]\nregex = 'x'\nmax_self = lots\n"),
+            "line 3: expected a percentage, got lots"
+        );
+        assert_eq!(
+            err("[[budget]]\nmax_self = 1\n"),
+            "line 1: [[budget]] needs exactly one of signature or regex"
+        );
+        assert_eq!(
+            err("max_self = 1\n"),
+            "line 1: key outside a [[budget]] table"
+        );
+    }
+
+    #[test]
+    fn test_check_budgets() {
+        let entries = vec![
+            PerfEntry {
+                children_pct: 38.29,
+                self_pct: 0.0,
+                symbol: "DCT4DBlock::DCT4DBlock(Block4D const&, double)".into(),
+                ..Default::default()
+            },
+            PerfEntry {
+                children_pct: 7.47,
+                self_pct: 7.45,
+                symbol: "double std::inner_product<double*>(double*)".into(),
+                ..Default::default()
+            },
+        ];
+        let budgets = vec![
+            Budget {
+                spec: TargetSpec::exact("DCT4DBlock::DCT4DBlock(Block4D const&, double)"),
+                max_children: Some(40.0),
+                max_self: None,
+            },
+            Budget {
+                spec: TargetSpec::regex("inner_product").unwrap(),
+                max_children: None,
+                max_self: Some(5.0),
+            },
+            Budget {
+                spec: TargetSpec::exact("gone"),
+                max_children: Some(1.0),
+                max_self: None,
+            },
+        ];
+
+        let results = check_budgets(&entries, &budgets, false);
+        assert!(!results[0].exceeded());
+        assert_eq!(results[0].symbol, "DCT4DBlock::DCT4DBlock");
+        assert!(results[1].exceeded());
+        assert_eq!(results[1].self_pct, Some(7.45));
+        // Unmatched budgets are reported but cannot be exceeded
+        assert_eq!(results[2].children_pct, None);
+        assert!(!results[2].exceeded());
+    }
+}
@@ -0,0 +1,395 @@
+//! Per-codebase settings (`--config FILE`, or `pperf.toml` in the working
+//! directory): tables in the same TOML subset as structured target files.
+
+use std::fs;
+use std::path::Path;
+
+use regex::Regex;
+
+use crate::PperfError;
+use crate::filter::{TargetSpec, parse_toml_string};
+use crate::symbol::{ClassificationRule, DisplayAlias, RewriteRule, SymbolPattern, SymbolType};
+
+/// Config file read when `--config` is not given, if it exists.
+pub const DEFAULT_CONFIG_FILE: &str = "pperf.toml";
+
+/// Everything a config file can set.
+#[derive(Debug, Clone, Default)]
+pub struct Config {
+    /// `[[rewrite]]` rules, in file order
+    pub rewrites: Vec<RewriteRule>,
+    /// `[[library]]` and `[[user]]` tables, in file order
+    pub classification: Vec<ClassificationRule>,
+    /// `[[alias]]` tables, in file order
+    pub aliases: Vec<DisplayAlias>,
+    /// `[presets]` entries, in file order
+    pub presets: Vec<Preset>,
+}
+
+/// A named command line from the `[presets]` table, run as `pperf preset NAME`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Preset {
+    pub name: String,
+    /// The command line as written
+    pub command: String,
+    /// `command` split into arguments
+    pub args: Vec<String>,
+}
+
+impl Config {
+    pub fn preset(&self, name: &str) -> Option<&Preset> {
+        self.presets.iter().find(|p| p.name == name)
+    }
+}
+
+/// Split a preset command line into arguments: whitespace separates them, except
+/// inside single or double quotes, which are removed (`-t 'A|B'`).
+pub fn split_command_line(line: &str) -> Result<Vec<String>, String> {
+    let mut args = Vec::new();
+    let mut current: Option<String> = None;
+    let mut quote: Option<char> = None;
+    for c in line.chars() {
+        match (quote, c) {
+            (Some(q), c) if c == q => quote = None,
+            (Some(_), c) => current.get_or_insert_with(String::new).push(c),
+            (None, '\'' | '"') => {
+                quote = Some(c);
+                current.get_or_insert_with(String::new);
+            }
+            (None, c) if c.is_whitespace() => args.extend(current.take()),
+            (None, c) => current.get_or_insert_with(String::new).push(c),
+        }
+    }
+    if let Some(q) = quote {
+        return Err(format!("unterminated {} quote", q));
+    }
+    args.extend(current);
+    Ok(args)
+}
+
+/// Read a config file; see `parse_config` for the format.
+pub fn load_config(path: &Path) -> Result<Config, PperfError> {
+    let content = fs::read_to_string(path)
+        .map_err(|_| PperfError::FileNotFound(path.display().to_string()))?;
+    parse_config(&content)
+        .map_err(|reason| PperfError::InvalidConfigFile(format!("{}: {}", path.display(), reason)))
+}
+
+/// The run's config: the `--config` file, else `pperf.toml` in the working
+/// directory if there is one, else the defaults.
+pub fn find_config(explicit: Option<&Path>) -> Result<Config, PperfError> {
+    match explicit {
+        Some(path) => load_config(path),
+        None if Path::new(DEFAULT_CONFIG_FILE).is_file() => {
+            load_config(Path::new(DEFAULT_CONFIG_FILE))
+        }
+        None => Ok(Config::default()),
+    }
+}
+
+/// Parse config tables. Errors are `line N: reason`.
+///
+/// - `[[rewrite]]` rewrites shown symbols after simplification: every match of
+///   the regex `pattern` becomes `replacement` (`$1` for groups, `""` to delete).
+/// - `[[library]]` / `[[user]]` classify the symbols whose simplified name starts
+///   with `prefix` (or matches `regex`) as library or user code for coloring;
+///   `[[user]]` wins over `[[library]]` and the built-in library list.
+/// - `[[alias]]` shows the symbols with the full signature (or simplified name)
+///   `symbol`, or matching the regex `regex`, as `label` in every table.
+/// - `[presets]` names command lines (`codec = "top -H -t rd_optimize"`), run as
+///   `pperf preset codec report.txt`.
+///
+/// ```toml
+/// [[rewrite]]
+/// pattern = "^Acme::"
+/// replacement = ""
+///
+/// [[library]]
+/// prefix = "boost::"
+///
+/// [[alias]]
+/// symbol = "Hierarchical4DEncoder::get_mSubbandLF_significance"
+/// label = "significance"
+///
+/// [presets]
+/// codec = "top -H -t rd_optimize -t DCT4DBlock --no-color"
+/// ```
+pub fn parse_config(content: &str) -> Result<Config, String> {
+    #[derive(Clone, Copy, PartialEq)]
+    enum Kind {
+        Rewrite,
+        Classify(SymbolType),
+        Alias,
+        Presets,
+    }
+
+    struct Table {
+        kind: Kind,
+        line: usize,
+        pattern: Option<String>,
+        replacement: Option<String>,
+        prefix: Option<String>,
+        regex: Option<String>,
+        symbol: Option<String>,
+        label: Option<String>,
+    }
+
+    fn finish(table: Table, config: &mut Config) -> Result<(), String> {
+        let regex = |pattern: &str| {
+            Regex::new(pattern).map_err(|e| format!("line {}: invalid regex: {}", table.line, e))
+        };
+        match table.kind {
+            Kind::Rewrite => {
+                let (Some(pattern), Some(replacement)) =
+                    (&table.pattern, table.replacement.clone())
+                else {
+                    return Err(format!(
+                        "line {}: [[rewrite]] needs pattern and replacement",
+                        table.line
+                    ));
+                };
+                config.rewrites.push(RewriteRule {
+                    pattern: regex(pattern)?,
+                    replacement,
+                });
+            }
+            Kind::Classify(symbol_type) => {
+                let pattern = match (&table.prefix, &table.regex) {
+                    (Some(prefix), None) => SymbolPattern::Prefix(prefix.clone()),
+                    (None, Some(pattern)) => SymbolPattern::Regex(regex(pattern)?),
+                    _ => {
+                        return Err(format!(
+                            "line {}: [[{}]] needs exactly one of prefix or regex",
+                            table.line,
+                            if symbol_type == SymbolType::User {
+                                "user"
+                            } else {
+                                "library"
+                            }
+                        ));
+                    }
+                };
+                config.classification.push(ClassificationRule {
+                    pattern,
+                    symbol_type,
+                });
+            }
+            Kind::Alias => {
+                let spec = match (&table.symbol, &table.regex) {
+                    (Some(symbol), None) => TargetSpec::exact(symbol),
+                    (None, Some(pattern)) => TargetSpec::regex(pattern)
+                        .map_err(|e| format!("line {}: invalid regex: {}", table.line, e))?,
+                    _ => {
+                        return Err(format!(
+                            "line {}: [[alias]] needs exactly one of symbol or regex",
+                            table.line
+                        ));
+                    }
+                };
+                let Some(label) = table.label.clone().filter(|label| !label.is_empty()) else {
+                    return Err(format!("line {}: [[alias]] needs a label", table.line));
+                };
+                config.aliases.push(DisplayAlias { spec, label });
+            }
+            Kind::Presets => {}
+        }
+        Ok(())
+    }
+
+    let mut config = Config::default();
+    let mut current: Option<Table> = None;
+    for (index, line) in content.lines().enumerate() {
+        let number = index + 1;
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        if line.starts_with('[') {
+            let kind = match line {
+                "[[rewrite]]" => Kind::Rewrite,
+                "[[library]]" => Kind::Classify(SymbolType::Library),
+                "[[user]]" => Kind::Classify(SymbolType::User),
+                "[[alias]]" => Kind::Alias,
+                "[presets]" => Kind::Presets,
+                _ => return Err(format!("line {}: unknown table {}", number, line)),
+            };
+            if let Some(table) = current.take() {
+                finish(table, &mut config)?;
+            }
+            current = Some(Table {
+                kind,
+                line: number,
+                pattern: None,
+                replacement: None,
+                prefix: None,
+                regex: None,
+                symbol: None,
+                label: None,
+            });
+            continue;
+        }
+
+        let Some((key, value)) = line.split_once('=') else {
+            return Err(format!("line {}: expected key = value", number));
+        };
+        let Some(table) = current.as_mut() else {
+            return Err(format!("line {}: key outside a table", number));
+        };
+        let value =
+            parse_toml_string(value.trim()).map_err(|e| format!("line {}: {}", number, e))?;
+        if table.kind == Kind::Presets {
+            let name = key.trim().to_string();
+            if config.preset(&name).is_some() {
+                return Err(format!("line {}: preset {} defined twice", number, name));
+            }
+            let args = split_command_line(&value).map_err(|e| format!("line {}: {}", number, e))?;
+            match args.first().map(String::as_str) {
+                None => return Err(format!("line {}: preset {} is empty", number, name)),
+                Some("preset") => {
+                    return Err(format!(
+                        "line {}: preset {} runs another preset",
+                        number, name
+                    ));
+                }
+                Some(_) => {}
+            }
+            config.presets.push(Preset {
+                name,
+                command: value,
+                args,
+            });
+            continue;
+        }
+        let slot = match (table.kind, key.trim()) {
+            (Kind::Rewrite, "pattern") => &mut table.pattern,
+            (Kind::Rewrite, "replacement") => &mut table.replacement,
+            (Kind::Classify(_), "prefix") => &mut table.prefix,
+            (Kind::Classify(_), "regex") => &mut table.regex,
+            (Kind::Alias, "symbol") => &mut table.symbol,
+            (Kind::Alias, "regex") => &mut table.regex,
+            (Kind::Alias, "label") => &mut table.label,
+            (_, other) => return Err(format!("line {}: unknown key {}", number, other)),
+        };
+        *slot = Some(value);
+    }
+    if let Some(table) = current {
+        finish(table, &mut config)?;
+    }
+    Ok(config)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_config_rewrites() {
+        let content = "# project names\n[[rewrite]]\npattern = '^Acme::'\nreplacement = \"\"\n\n[[rewrite]]\npattern = \"Vector<(\\\\w+)>\"\nreplacement = \"Vec<$1>\"\n";
+        let config = parse_config(content).unwrap();
+        assert_eq!(config.rewrites.len(), 2);
+        assert_eq!(config.rewrites[0].apply("Acme::encode"), "encode");
+        assert_eq!(
+            config.rewrites[1].apply("Vector<int>::push"),
+            "Vec<int>::push"
+        );
+    }
+
+    #[test]
+    fn test_parse_config_classification() {
+        let content = "[[library]]\nprefix = \"boost::\"\n[[library]]\nregex = '^(tbb|Eigen)::'\n[[user]]\nprefix = \"std::acme\"\n";
+        let config = parse_config(content).unwrap();
+        let types: Vec<SymbolType> = config
+            .classification
+            .iter()
+            .map(|r| r.symbol_type)
+            .collect();
+        assert_eq!(
+            types,
+            vec![SymbolType::Library, SymbolType::Library, SymbolType::User]
+        );
+        assert!(config.classification[0].pattern.matches("boost::asio::run"));
+        assert!(
+            config.classification[1]
+                .pattern
+                .matches("Eigen::Matrix::solve")
+        );
+        assert!(!config.classification[1].pattern.matches("MyEigen::solve"));
+
+        let err = parse_config("[[user]]\nprefix = \"a\"\nregex = \"b\"\n").unwrap_err();
+        assert!(err.contains("exactly one of prefix or regex"), "{}", err);
+        let err = parse_config("[[library]]\npattern = \"a\"\n").unwrap_err();
+        assert!(err.contains("unknown key pattern"), "{}", err);
+    }
+
+    #[test]
+    fn test_parse_config_aliases() {
+        let content = "[[alias]]\nsymbol = \"DCT4DBlock::DCT4DBlock(Block4D const&, double)\"\nlabel = \"dct\"\n[[alias]]\nregex = '^std::'\nlabel = \"stl\"\n";
+        let config = parse_config(content).unwrap();
+        assert_eq!(config.aliases.len(), 2);
+        let dct = &config.aliases[0];
+        assert_eq!(dct.label, "dct");
+        // The full signature and the simplified name of call trees both match
+        assert!(
+            dct.spec
+                .matches("DCT4DBlock::DCT4DBlock(Block4D const&, double)", false)
+        );
+        assert!(dct.spec.matches("DCT4DBlock::DCT4DBlock", false));
+        assert!(!dct.spec.matches("IDCT4DBlock::IDCT4DBlock", false));
+        assert!(config.aliases[1].spec.matches("std::inner_product", false));
+
+        let err = parse_config("[[alias]]\nlabel = \"x\"\n").unwrap_err();
+        assert!(err.contains("exactly one of symbol or regex"), "{}", err);
+        let err = parse_config("[[alias]]\nsymbol = \"main\"\n").unwrap_err();
+        assert!(err.contains("needs a label"), "{}", err);
+    }
+
+    #[test]
+    fn test_parse_config_presets() {
+        let content = "[presets]\ncodec = \"top -H -t rd_optimize -t 'DCT4DBlock|IDCT4DBlock' --no-color\"\nhot = 'hotpath'\n";
+        let config = parse_config(content).unwrap();
+        assert_eq!(config.presets.len(), 2);
+        let codec = config.preset("codec").unwrap();
+        assert_eq!(
+            codec.args,
+            vec![
+                "top",
+                "-H",
+                "-t",
+                "rd_optimize",
+                "-t",
+                "DCT4DBlock|IDCT4DBlock",
+                "--no-color"
+            ]
+        );
+        assert_eq!(config.preset("hot").unwrap().args, vec!["hotpath"]);
+        assert!(config.preset("missing").is_none());
+
+        let err = parse_config("[presets]\na = \"top\"\na = \"diff\"\n").unwrap_err();
+        assert!(err.contains("line 3: preset a defined twice"), "{}", err);
+        let err = parse_config("[presets]\na = \"preset b\"\n").unwrap_err();
+        assert!(err.contains("runs another preset"), "{}", err);
+        let err = parse_config("[presets]\na = \"top -t 'x\"\n").unwrap_err();
+        assert!(err.contains("unterminated ' quote"), "{}", err);
+    }
+
+    #[test]
+    fn test_split_command_line() {
+        assert_eq!(
+            split_command_line("  top  -t \"A B\" -t ''  x").unwrap(),
+            vec!["top", "-t", "A B", "-t", "", "x"]
+        );
+        assert!(split_command_line("").unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_parse_config_errors() {
+        let err = parse_config("[[rewrite]]\npattern = \"x\"\n").unwrap_err();
+        assert!(err.contains("line 1"), "{}", err);
+        let err = parse_config("[[rewrite]]\npattern = \"(\"\nreplacement = \"\"\n").unwrap_err();
+        assert!(err.contains("invalid regex"), "{}", err);
+        let err = parse_config("[colors]\n").unwrap_err();
+        assert!(err.contains("unknown table"), "{}", err);
+        let err = parse_config("pattern = \"x\"\n").unwrap_err();
+        assert!(err.contains("outside a table"), "{}", err);
+    }
+}
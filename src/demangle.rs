@@ -0,0 +1,577 @@
+//! Itanium C++ name demangling for mangled targets (`_ZN10DCT4DBlockC1ERK7Block4Dd`).
+//!
+//! Covers what shows up in `nm` output and linker maps for ordinary C++ code:
+//! nested and `std::` names, constructors/destructors, operators, builtin types,
+//! pointers/references/cv-qualifiers, template arguments (including literals and
+//! `T_` parameters), substitutions, and `.cold`/`.isra.0` clone suffixes. The output
+//! follows the format perf prints (`Block4D const&`, `std::vector<A, B<A> >`), so a
+//! demangled target compares equal to the report's signature. Anything outside this
+//! subset returns `None` instead of a guess.
+
+/// Demangle an Itanium (`_Z`) symbol, or `None` if it is not one or uses
+/// unsupported grammar.
+pub fn demangle(mangled: &str) -> Option<String> {
+    let rest = mangled.strip_prefix("_Z")?;
+    let (body, clone) = match rest.find('.') {
+        Some(dot) => (&rest[..dot], Some(&rest[dot..])),
+        None => (rest, None),
+    };
+    let mut parser = Demangler {
+        input: body.as_bytes(),
+        pos: 0,
+        subs: Vec::new(),
+        template_args: Vec::new(),
+        in_function_name: false,
+    };
+    let mut result = parser.encoding()?;
+    if parser.pos != parser.input.len() {
+        return None;
+    }
+    if let Some(clone) = clone {
+        result.push_str(&format!(" [clone {}]", clone));
+    }
+    Some(result)
+}
+
+/// True for strings that look like a mangled C++ name.
+pub fn is_mangled(symbol: &str) -> bool {
+    symbol.starts_with("_Z")
+}
+
+/// A parsed name: the printable form, whether it ended in template arguments
+/// (template functions encode their return type), and whether it names a const
+/// member function.
+struct Name {
+    text: String,
+    templated: bool,
+    cv_const: bool,
+}
+
+struct Demangler<'a> {
+    input: &'a [u8],
+    pos: usize,
+    /// Substitution candidates, referenced as `S_`, `S0_`, `S1_`, ...
+    subs: Vec<String>,
+    /// Template arguments of the function being demangled, referenced as `T_`, `T0_`, ...
+    template_args: Vec<String>,
+    /// Set while parsing the function name, whose (outermost) template arguments
+    /// are the ones `T_` refers to
+    in_function_name: bool,
+}
+
+impl Demangler<'_> {
+    fn peek(&self) -> Option<u8> {
+        self.input.get(self.pos).copied()
+    }
+
+    fn eat(&mut self, byte: u8) -> bool {
+        if self.peek() == Some(byte) {
+            self.pos += 1;
+            true
+        } else {
+            false
+        }
+    }
+
+    fn next(&mut self) -> Option<u8> {
+        let byte = self.peek()?;
+        self.pos += 1;
+        Some(byte)
+    }
+
+    fn encoding(&mut self) -> Option<String> {
+        self.in_function_name = true;
+        let name = self.name(false)?;
+        self.in_function_name = false;
+        if self.pos == self.input.len() {
+            // Data symbol (static variable): no parameter list
+            return Some(name.text);
+        }
+        let return_type = if name.templated {
+            Some(self.type_()?)
+        } else {
+            None
+        };
+        let mut params = Vec::new();
+        while self.pos < self.input.len() {
+            params.push(self.type_()?);
+        }
+        let params = if params == ["void"] {
+            String::new()
+        } else {
+            params.join(", ")
+        };
+
+        let mut result = String::new();
+        if let Some(ret) = return_type {
+            result.push_str(&ret);
+            result.push(' ');
+        }
+        result.push_str(&name.text);
+        result.push('(');
+        result.push_str(&params);
+        result.push(')');
+        if name.cv_const {
+            result.push_str(" const");
+        }
+        Some(result)
+    }
+
+    /// `is_type`: names used as types are substitution candidates themselves;
+    /// function names are not.
+    fn name(&mut self, is_type: bool) -> Option<Name> {
+        match self.peek()? {
+            b'N' => self.nested_name(is_type),
+            b'S' if self.input.get(self.pos + 1) == Some(&b't') => {
+                self.pos += 2;
+                let unqualified = self.source_or_operator()?;
+                self.unscoped_rest(format!("std::{}", unqualified), is_type)
+            }
+            b'S' => {
+                let sub = self.substitution()?;
+                if self.peek() == Some(b'I') {
+                    let args = self.template_args()?;
+                    let text = join_template(&sub, &args);
+                    if is_type {
+                        self.subs.push(text.clone());
+                    }
+                    return Some(Name {
+                        text,
+                        templated: true,
+                        cv_const: false,
+                    });
+                }
+                Some(Name {
+                    text: sub,
+                    templated: false,
+                    cv_const: false,
+                })
+            }
+            _ => {
+                let unqualified = self.source_or_operator()?;
+                self.unscoped_rest(unqualified, is_type)
+            }
+        }
+    }
+
+    fn unscoped_rest(&mut self, text: String, is_type: bool) -> Option<Name> {
+        if self.peek() == Some(b'I') {
+            self.subs.push(text.clone());
+            let args = self.template_args()?;
+            let text = join_template(&text, &args);
+            if is_type {
+                self.subs.push(text.clone());
+            }
+            return Some(Name {
+                text,
+                templated: true,
+                cv_const: false,
+            });
+        }
+        if is_type {
+            self.subs.push(text.clone());
+        }
+        Some(Name {
+            text,
+            templated: false,
+            cv_const: false,
+        })
+    }
+
+    fn nested_name(&mut self, is_type: bool) -> Option<Name> {
+        self.pos += 1; // N
+        let mut cv_const = false;
+        while let Some(q) = self.peek() {
+            match q {
+                b'K' => cv_const = true,
+                b'V' | b'r' => {}
+                _ => break,
+            }
+            self.pos += 1;
+        }
+        // Ref-qualifiers (`&`, `&&` member functions) are not printed
+        let _ = self.eat(b'R') || self.eat(b'O');
+
+        let mut current = String::new();
+        // Last plain component, used as a constructor/destructor's name
+        let mut last_component = String::new();
+        let mut templated = false;
+        loop {
+            match self.peek()? {
+                b'E' => {
+                    self.pos += 1;
+                    break;
+                }
+                b'S' if current.is_empty() => {
+                    if self.input.get(self.pos + 1) == Some(&b't') {
+                        self.pos += 2;
+                        current = "std".to_string();
+                        continue;
+                    }
+                    current = self.substitution()?;
+                    last_component = current.rsplit("::").next().unwrap_or("").to_string();
+                    last_component = strip_template(&last_component).to_string();
+                    templated = false;
+                    // Already a candidate
+                    continue;
+                }
+                b'I' => {
+                    // The template prefix (name without arguments) is a candidate
+                    self.subs.push(current.clone());
+                    let args = self.template_args()?;
+                    current = join_template(&current, &args);
+                    templated = true;
+                }
+                b'C' => {
+                    self.pos += 1;
+                    match self.next()? {
+                        b'1'..=b'5' => {}
+                        _ => return None,
+                    }
+                    current = format!("{}::{}", current, last_component);
+                    templated = false;
+                }
+                b'D' if matches!(self.input.get(self.pos + 1), Some(b'0'..=b'5')) => {
+                    self.pos += 2;
+                    current = format!("{}::~{}", current, last_component);
+                    templated = false;
+                }
+                _ => {
+                    let component = self.source_or_operator()?;
+                    last_component = component.clone();
+                    current = if current.is_empty() {
+                        component
+                    } else {
+                        format!("{}::{}", current, component)
+                    };
+                    templated = false;
+                }
+            }
+            // Every proper prefix is a candidate; the complete name only for types
+            if self.peek() != Some(b'E') && self.peek() != Some(b'I') {
+                self.subs.push(current.clone());
+            }
+        }
+        if is_type {
+            self.subs.push(current.clone());
+        }
+        Some(Name {
+            text: current,
+            templated,
+            cv_const,
+        })
+    }
+
+    fn source_or_operator(&mut self) -> Option<String> {
+        match self.peek()? {
+            b'0'..=b'9' => self.source_name(),
+            _ => {
+                let code = self.input.get(self.pos..self.pos + 2)?;
+                let op = operator_name(code)?;
+                self.pos += 2;
+                Some(format!("operator{}", op))
+            }
+        }
+    }
+
+    fn source_name(&mut self) -> Option<String> {
+        let start = self.pos;
+        while matches!(self.peek(), Some(b'0'..=b'9')) {
+            self.pos += 1;
+        }
+        let len: usize = std::str::from_utf8(&self.input[start..self.pos])
+            .ok()?
+            .parse()
+            .ok()?;
+        let end = self.pos.checked_add(len)?;
+        let name = std::str::from_utf8(self.input.get(self.pos..end)?).ok()?;
+        self.pos = end;
+        if name.starts_with("_GLOBAL__N") {
+            return Some("(anonymous namespace)".to_string());
+        }
+        Some(name.to_string())
+    }
+
+    fn substitution(&mut self) -> Option<String> {
+        self.pos += 1; // S
+        let special = match self.peek()? {
+            b'a' => Some("std::allocator"),
+            b'b' => Some("std::basic_string"),
+            b's' => Some("std::string"),
+            b'i' => Some("std::istream"),
+            b'o' => Some("std::ostream"),
+            b'd' => Some("std::iostream"),
+            _ => None,
+        };
+        if let Some(special) = special {
+            self.pos += 1;
+            return Some(special.to_string());
+        }
+        let index = self.seq_id()?;
+        self.subs.get(index).cloned()
+    }
+
+    /// `_` is 0, `0_` is 1, `A_` is 11 (base 36, offset by one).
+    fn seq_id(&mut self) -> Option<usize> {
+        if self.eat(b'_') {
+            return Some(0);
+        }
+        let mut value = 0usize;
+        loop {
+            let byte = self.next()?;
+            let digit = match byte {
+                b'0'..=b'9' => (byte - b'0') as usize,
+                b'A'..=b'Z' => (byte - b'A') as usize + 10,
+                b'_' => return Some(value + 1),
+                _ => return None,
+            };
+            value = value.checked_mul(36)?.checked_add(digit)?;
+        }
+    }
+
+    fn template_args(&mut self) -> Option<Vec<String>> {
+        self.pos += 1; // I
+        let mut args = Vec::new();
+        while !self.eat(b'E') {
+            let arg = if self.peek()? == b'L' {
+                self.literal()?
+            } else {
+                self.type_()?
+            };
+            args.push(arg);
+        }
+        if self.in_function_name {
+            // The outermost list closes last, so it is the one that sticks
+            self.template_args = args.clone();
+        }
+        Some(args)
+    }
+
+    fn literal(&mut self) -> Option<String> {
+        self.pos += 1; // L
+        let ty = self.type_()?;
+        let negative = self.eat(b'n');
+        let start = self.pos;
+        while matches!(self.peek(), Some(b'0'..=b'9')) {
+            self.pos += 1;
+        }
+        let digits = std::str::from_utf8(&self.input[start..self.pos]).ok()?;
+        if !self.eat(b'E') || digits.is_empty() {
+            return None;
+        }
+        let value = format!("{}{}", if negative { "-" } else { "" }, digits);
+        Some(match ty.as_str() {
+            "bool" if value == "0" => "false".to_string(),
+            "bool" => "true".to_string(),
+            "int" => value,
+            "unsigned int" => format!("{}u", value),
+            "long" => format!("{}l", value),
+            "unsigned long" => format!("{}ul", value),
+            _ => format!("({}){}", ty, value),
+        })
+    }
+
+    fn type_(&mut self) -> Option<String> {
+        let byte = self.peek()?;
+        if let Some(builtin) = builtin_type(byte) {
+            self.pos += 1;
+            return Some(builtin.to_string());
+        }
+        let (suffix, substitutable) = match byte {
+            b'P' => ("*", true),
+            b'R' => ("&", true),
+            b'O' => ("&&", true),
+            b'K' => (" const", true),
+            b'V' => (" volatile", true),
+            _ => ("", false),
+        };
+        if substitutable {
+            self.pos += 1;
+            let inner = self.type_()?;
+            let text = format!("{}{}", inner, suffix);
+            self.subs.push(text.clone());
+            return Some(text);
+        }
+        match byte {
+            b'T' => {
+                self.pos += 1;
+                let index = self.seq_id()?;
+                let arg = self.template_args.get(index).cloned()?;
+                self.subs.push(arg.clone());
+                Some(arg)
+            }
+            b'N' | b'S' | b'0'..=b'9' => {
+                // Substitutions that resolve to a candidate are not re-added
+                let is_plain_sub = byte == b'S'
+                    && !matches!(self.input.get(self.pos + 1), Some(b't'))
+                    && !self.template_follows_substitution();
+                let name = self.name(!is_plain_sub)?;
+                Some(name.text)
+            }
+            _ => None,
+        }
+    }
+
+    /// Whether the substitution at `pos` is followed by template arguments
+    /// (`S_IiE`), which makes the combined template-id a new candidate.
+    fn template_follows_substitution(&self) -> bool {
+        let mut pos = self.pos + 1;
+        if matches!(
+            self.input.get(pos),
+            Some(b'a' | b'b' | b's' | b'i' | b'o' | b'd')
+        ) {
+            return self.input.get(pos + 1) == Some(&b'I');
+        }
+        while matches!(self.input.get(pos), Some(b'0'..=b'9' | b'A'..=b'Z')) {
+            pos += 1;
+        }
+        self.input.get(pos) == Some(&b'_') && self.input.get(pos + 1) == Some(&b'I')
+    }
+}
+
+fn builtin_type(code: u8) -> Option<&'static str> {
+    Some(match code {
+        b'v' => "void",
+        b'w' => "wchar_t",
+        b'b' => "bool",
+        b'c' => "char",
+        b'a' => "signed char",
+        b'h' => "unsigned char",
+        b's' => "short",
+        b't' => "unsigned short",
+        b'i' => "int",
+        b'j' => "unsigned int",
+        b'l' => "long",
+        b'm' => "unsigned long",
+        b'x' => "long long",
+        b'y' => "unsigned long long",
+        b'n' => "__int128",
+        b'o' => "unsigned __int128",
+        b'f' => "float",
+        b'd' => "double",
+        b'e' => "long double",
+        b'z' => "...",
+        _ => return None,
+    })
+}
+
+fn operator_name(code: &[u8]) -> Option<&'static str> {
+    Some(match code {
+        b"nw" => " new",
+        b"na" => " new[]",
+        b"dl" => " delete",
+        b"da" => " delete[]",
+        b"pl" => "+",
+        b"mi" => "-",
+        b"ml" => "*",
+        b"dv" => "/",
+        b"rm" => "%",
+        b"an" => "&",
+        b"or" => "|",
+        b"eo" => "^",
+        b"aS" => "=",
+        b"pL" => "+=",
+        b"mI" => "-=",
+        b"mL" => "*=",
+        b"dV" => "/=",
+        b"ls" => "<<",
+        b"rs" => ">>",
+        b"eq" => "==",
+        b"ne" => "!=",
+        b"lt" => "<",
+        b"gt" => ">",
+        b"le" => "<=",
+        b"ge" => ">=",
+        b"nt" => "!",
+        b"co" => "~",
+        b"aa" => "&&",
+        b"oo" => "||",
+        b"pp" => "++",
+        b"mm" => "--",
+        b"cl" => "()",
+        b"ix" => "[]",
+        b"pt" => "->",
+        _ => return None,
+    })
+}
+
+/// `name<a, b>`, with the old demangler's `> >` spacing that perf prints.
+fn join_template(name: &str, args: &[String]) -> String {
+    let joined = args.join(", ");
+    if joined.ends_with('>') {
+        format!("{}<{} >", name, joined)
+    } else {
+        format!("{}<{}>", name, joined)
+    }
+}
+
+fn strip_template(name: &str) -> &str {
+    name.split('<').next().unwrap_or(name)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_demangle_constructor() {
+        assert_eq!(
+            demangle("_ZN10DCT4DBlockC1ERK7Block4Dd").as_deref(),
+            Some("DCT4DBlock::DCT4DBlock(Block4D const&, double)")
+        );
+        assert_eq!(
+            demangle("_ZN10DCT4DBlockD2Ev").as_deref(),
+            Some("DCT4DBlock::~DCT4DBlock()")
+        );
+    }
+
+    #[test]
+    fn test_demangle_substitutions_and_templates() {
+        // Substitutions: S_ = TransformPartition, S0_ = Block4D, S1_ = Block4D const, ...
+        assert_eq!(
+            demangle(
+                "_ZN18TransformPartition21rd_optimize_transformERK7Block4DRS0_RK11PartitionerIijERSt6vectorI13PartitionFlagSaIS9_EE"
+            )
+            .as_deref(),
+            Some(
+                "TransformPartition::rd_optimize_transform(Block4D const&, Block4D&, Partitioner<int, unsigned int> const&, std::vector<PartitionFlag, std::allocator<PartitionFlag> >&)"
+            )
+        );
+        // Template function: return type first, T_ refers to the first argument
+        assert_eq!(
+            demangle("_ZSt13inner_productIPdPKddET1_T_S4_T0_S3_").as_deref(),
+            Some(
+                "double std::inner_product<double*, double const*, double>(double*, double*, double const*, double)"
+            )
+        );
+        assert_eq!(
+            demangle(
+                "_ZNK21Hierarchical4DEncoder27get_mSubbandLF_significanceEjRK20LightfieldCoordinateIjERK19LightfieldDimensionIjLb1EE"
+            )
+            .as_deref(),
+            Some(
+                "Hierarchical4DEncoder::get_mSubbandLF_significance(unsigned int, LightfieldCoordinate<unsigned int> const&, LightfieldDimension<unsigned int, true> const&) const"
+            )
+        );
+    }
+
+    #[test]
+    fn test_demangle_const_member_and_clone() {
+        assert_eq!(
+            demangle("_ZNK21Hierarchical4DEncoder3getEi.cold").as_deref(),
+            Some("Hierarchical4DEncoder::get(int) const [clone .cold]")
+        );
+        assert_eq!(demangle("_Z4mainv").as_deref(), Some("main()"));
+        assert_eq!(
+            demangle("_ZN1AplERKS_").as_deref(),
+            Some("A::operator+(A const&)")
+        );
+    }
+
+    #[test]
+    fn test_demangle_rejects_non_mangled() {
+        assert_eq!(demangle("DCT4DBlock::DCT4DBlock"), None);
+        assert_eq!(demangle("_ZN3fooE?"), None);
+        assert_eq!(demangle("_Z"), None);
+    }
+}
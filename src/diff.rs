@@ -0,0 +1,266 @@
+//! Comparison of two reports (`pperf diff`).
+//!
+//! Entries are matched by symbol and hierarchy relations by (caller, callee,
+//! context_root) and occurrence order; a value missing from one side means the
+//! entry or edge appeared or disappeared between the reports.
+
+use std::collections::HashMap;
+
+use crate::hierarchy::CallRelation;
+use crate::parser::PerfEntry;
+use crate::symbol::Symbol;
+
+/// How one value moved from the old report to the new one.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Change {
+    /// Only in the new report
+    Appeared,
+    /// Only in the old report
+    Disappeared,
+    /// In both, moved by more than the threshold
+    Changed,
+    /// In both, within the threshold
+    Unchanged,
+}
+
+impl Change {
+    /// Classify `old` → `new`; a move counts once its size exceeds `threshold` points.
+    pub fn of(old: Option<f64>, new: Option<f64>, threshold: f64) -> Change {
+        match (old, new) {
+            (None, Some(_)) => Change::Appeared,
+            (Some(_), None) => Change::Disappeared,
+            (Some(old), Some(new)) if (new - old).abs() > threshold => Change::Changed,
+            _ => Change::Unchanged,
+        }
+    }
+}
+
+impl std::fmt::Display for Change {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Change::Appeared => write!(f, "appeared"),
+            Change::Disappeared => write!(f, "disappeared"),
+            Change::Changed => write!(f, "changed"),
+            Change::Unchanged => write!(f, "unchanged"),
+        }
+    }
+}
+
+/// One symbol's percentages in both reports.
+#[derive(Debug, Clone, PartialEq)]
+pub struct EntryDiff {
+    pub symbol: Symbol,
+    /// Children% in the old report; None if absent
+    pub old_children: Option<f64>,
+    /// Children% in the new report; None if absent
+    pub new_children: Option<f64>,
+    pub old_self: Option<f64>,
+    pub new_self: Option<f64>,
+}
+
+impl EntryDiff {
+    /// New minus old Children%, an absent side counting as 0.
+    pub fn delta(&self) -> f64 {
+        self.new_children.unwrap_or(0.0) - self.old_children.unwrap_or(0.0)
+    }
+
+    pub fn change(&self, threshold: f64) -> Change {
+        Change::of(self.old_children, self.new_children, threshold)
+    }
+
+    /// True when Children% grew by more than `limit` points (`--fail-above`); an
+    /// entry new in the second report regresses from 0.
+    pub fn regressed(&self, limit: f64) -> bool {
+        self.delta() > limit
+    }
+}
+
+/// One caller→callee edge's absolute contribution in both reports.
+#[derive(Debug, Clone, PartialEq)]
+pub struct RelationDiff {
+    pub caller: Symbol,
+    pub callee: Symbol,
+    pub context_root: Option<Symbol>,
+    /// `absolute_pct` in the old report; None if the edge is absent
+    pub old_pct: Option<f64>,
+    /// `absolute_pct` in the new report; None if the edge is absent
+    pub new_pct: Option<f64>,
+}
+
+impl RelationDiff {
+    /// New minus old contribution, an absent side counting as 0.
+    pub fn delta(&self) -> f64 {
+        self.new_pct.unwrap_or(0.0) - self.old_pct.unwrap_or(0.0)
+    }
+
+    pub fn change(&self, threshold: f64) -> Change {
+        Change::of(self.old_pct, self.new_pct, threshold)
+    }
+}
+
+/// Pair the entries of two reports by symbol, largest Children% move first.
+/// A symbol listed more than once in a report uses its first line.
+pub fn diff_entries(old: &[PerfEntry], new: &[PerfEntry]) -> Vec<EntryDiff> {
+    let mut index: HashMap<&str, usize> = HashMap::new();
+    let mut rows: Vec<EntryDiff> = Vec::new();
+
+    for (side, entries) in [old, new].into_iter().enumerate() {
+        for entry in entries {
+            let slot = *index.entry(entry.symbol.as_str()).or_insert_with(|| {
+                rows.push(EntryDiff {
+                    symbol: entry.symbol.clone(),
+                    old_children: None,
+                    new_children: None,
+                    old_self: None,
+                    new_self: None,
+                });
+                rows.len() - 1
+            });
+            let row = &mut rows[slot];
+            let (children, self_pct) = if side == 0 {
+                (&mut row.old_children, &mut row.old_self)
+            } else {
+                (&mut row.new_children, &mut row.new_self)
+            };
+            if children.is_none() {
+                *children = Some(entry.children_pct);
+                *self_pct = Some(entry.self_pct);
+            }
+        }
+    }
+
+    rows.sort_by(|a, b| b.delta().abs().total_cmp(&a.delta().abs()));
+    rows
+}
+
+/// Pair the relations of two reports, largest contribution move first. Repeated
+/// edges within one report are matched by occurrence, as when averaging.
+pub fn diff_relations(old: &[CallRelation], new: &[CallRelation]) -> Vec<RelationDiff> {
+    type Key = (Symbol, Symbol, Option<Symbol>, usize);
+
+    let mut index: HashMap<Key, usize> = HashMap::new();
+    let mut rows: Vec<RelationDiff> = Vec::new();
+
+    for (side, relations) in [old, new].into_iter().enumerate() {
+        let mut occurrences: HashMap<(Symbol, Symbol, Option<Symbol>), usize> = HashMap::new();
+        for relation in relations {
+            let edge = (
+                relation.caller.clone(),
+                relation.callee.clone(),
+                relation.context_root.clone(),
+            );
+            let nth = occurrences.entry(edge.clone()).or_insert(0);
+            let key = (edge.0, edge.1, edge.2, *nth);
+            *nth += 1;
+
+            let slot = *index.entry(key).or_insert_with(|| {
+                rows.push(RelationDiff {
+                    caller: relation.caller.clone(),
+                    callee: relation.callee.clone(),
+                    context_root: relation.context_root.clone(),
+                    old_pct: None,
+                    new_pct: None,
+                });
+                rows.len() - 1
+            });
+            if side == 0 {
+                rows[slot].old_pct = Some(relation.absolute_pct);
+            } else {
+                rows[slot].new_pct = Some(relation.absolute_pct);
+            }
+        }
+    }
+
+    rows.sort_by(|a, b| b.delta().abs().total_cmp(&a.delta().abs()));
+    rows
+}
+
+/// The `n` largest increases (largest first) followed by the `n` largest decreases
+/// (largest first) of `delta`, for `--top-diff`; rows that did not move are left out.
+pub fn biggest_movers<T: Clone>(rows: &[T], n: usize, delta: impl Fn(&T) -> f64) -> Vec<T> {
+    let mut up: Vec<&T> = rows.iter().filter(|r| delta(r) > 0.0).collect();
+    let mut down: Vec<&T> = rows.iter().filter(|r| delta(r) < 0.0).collect();
+    up.sort_by(|a, b| delta(b).total_cmp(&delta(a)));
+    down.sort_by(|a, b| delta(a).total_cmp(&delta(b)));
+    up.into_iter()
+        .take(n)
+        .chain(down.into_iter().take(n))
+        .cloned()
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry(symbol: &str, children: f64) -> PerfEntry {
+        PerfEntry {
+            children_pct: children,
+            self_pct: children / 2.0,
+            symbol: symbol.into(),
+            ..Default::default()
+        }
+    }
+
+    fn relation(caller: &str, callee: &str, absolute: f64) -> CallRelation {
+        CallRelation {
+            caller: caller.into(),
+            callee: callee.into(),
+            relative_pct: absolute * 2.0,
+            absolute_pct: absolute,
+            context_root: None,
+            intermediary_path: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn test_diff_entries() {
+        let old = vec![entry("main", 90.0), entry("gone", 5.0), entry("same", 3.0)];
+        let new = vec![entry("main", 80.0), entry("same", 3.1), entry("new", 7.0)];
+        let rows = diff_entries(&old, &new);
+
+        let symbols: Vec<&str> = rows.iter().map(|r| r.symbol.as_str()).collect();
+        assert_eq!(symbols, vec!["main", "new", "gone", "same"]);
+        assert!((rows[0].delta() + 10.0).abs() < 0.01);
+        assert_eq!(rows[0].new_self, Some(40.0));
+        assert_eq!(rows[1].change(0.5), Change::Appeared);
+        assert_eq!(rows[2].change(0.5), Change::Disappeared);
+        assert_eq!(rows[3].change(0.5), Change::Unchanged);
+        assert_eq!(rows[3].change(0.05), Change::Changed);
+
+        let regressed: Vec<&str> = rows
+            .iter()
+            .filter(|r| r.regressed(2.0))
+            .map(|r| r.symbol.as_str())
+            .collect();
+        assert_eq!(regressed, vec!["new"]);
+    }
+
+    #[test]
+    fn test_biggest_movers() {
+        let deltas = [0.5, -4.0, 3.0, 0.0, -1.0, 2.0];
+        assert_eq!(
+            biggest_movers(&deltas, 2, |d| *d),
+            vec![3.0, 2.0, -4.0, -1.0]
+        );
+        assert_eq!(biggest_movers(&deltas, 1, |d| *d), vec![3.0, -4.0]);
+    }
+
+    #[test]
+    fn test_diff_relations() {
+        let old = vec![relation("A", "B", 17.2), relation("A", "C", 2.0)];
+        let mut in_context = relation("A", "B", 1.0);
+        in_context.context_root = Some("R".into());
+        let new = vec![relation("A", "B", 9.8), in_context];
+        let rows = diff_relations(&old, &new);
+
+        assert_eq!(rows.len(), 3);
+        assert_eq!((rows[0].old_pct, rows[0].new_pct), (Some(17.2), Some(9.8)));
+        assert_eq!(rows[0].change(0.5), Change::Changed);
+        // The same edge in another caller's tree is a different relation
+        assert_eq!(rows[1].callee, "C");
+        assert_eq!(rows[1].change(0.5), Change::Disappeared);
+        assert_eq!(rows[2].context_root.as_deref(), Some("R"));
+        assert_eq!(rows[2].change(0.5), Change::Appeared);
+    }
+}
@@ -0,0 +1,175 @@
+//! How concentrated a profile is (`pperf stats`): counts of entries above fixed
+//! shares of runtime, how few entries cover most of the self time, and a histogram
+//! of Self% on a decade scale.
+
+use crate::parser::PerfEntry;
+
+/// Shares of runtime (%) whose exceeding entries are counted.
+pub const THRESHOLDS: [f64; 3] = [10.0, 1.0, 0.1];
+
+/// Shares (%) of the report's total Self% that the Pareto coverage is given for.
+pub const COVERAGE_LEVELS: [f64; 4] = [50.0, 80.0, 90.0, 99.0];
+
+/// Lower bounds (%) of the histogram buckets, highest first; each bucket runs up to
+/// the bound before it and a last bucket holds everything at or below the final one.
+pub const HISTOGRAM_BOUNDS: [f64; 4] = [10.0, 1.0, 0.1, 0.01];
+
+/// Entries above one threshold.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ThresholdCount {
+    pub threshold: f64,
+    /// Entries whose Children% exceeds the threshold
+    pub children: usize,
+    /// Entries whose Self% exceeds the threshold
+    pub self_: usize,
+}
+
+/// The fewest entries, hottest Self% first, that reach a share of the total Self%.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Coverage {
+    pub level: f64,
+    /// None when the report has no self time at all
+    pub functions: Option<usize>,
+    /// Self% summed over those entries (% of runtime)
+    pub self_pct: f64,
+}
+
+/// Entries whose Self% lies in `(min, max]`; `min` is None for the last bucket
+/// (from 0) and `max` None for the first (no upper bound).
+#[derive(Debug, Clone, PartialEq)]
+pub struct HistogramBucket {
+    pub min: Option<f64>,
+    pub max: Option<f64>,
+    pub functions: usize,
+    pub self_pct: f64,
+}
+
+/// Distribution statistics over every entry of a report.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Distribution {
+    pub entries: usize,
+    /// Sum of every entry's Self%, below 100% when perf dropped small entries
+    pub self_total: f64,
+    pub above: Vec<ThresholdCount>,
+    pub coverage: Vec<Coverage>,
+    pub histogram: Vec<HistogramBucket>,
+}
+
+/// Tolerance for a running Self% sum reaching a coverage target despite rounding.
+const EPSILON: f64 = 1e-9;
+
+pub fn distribution(entries: &[PerfEntry]) -> Distribution {
+    let above = THRESHOLDS
+        .iter()
+        .map(|&threshold| ThresholdCount {
+            threshold,
+            children: entries
+                .iter()
+                .filter(|e| e.children_pct > threshold)
+                .count(),
+            self_: entries.iter().filter(|e| e.self_pct > threshold).count(),
+        })
+        .collect();
+
+    let mut self_pcts: Vec<f64> = entries.iter().map(|e| e.self_pct).collect();
+    self_pcts.sort_by(|a, b| b.total_cmp(a));
+    let self_total: f64 = self_pcts.iter().sum();
+    let coverage = COVERAGE_LEVELS
+        .iter()
+        .map(|&level| {
+            let target = self_total * level / 100.0;
+            let mut sum = 0.0;
+            let mut functions = None;
+            for (i, pct) in self_pcts.iter().enumerate() {
+                sum += pct;
+                if sum >= target - EPSILON {
+                    functions = Some(i + 1);
+                    break;
+                }
+            }
+            Coverage {
+                level,
+                functions: functions.filter(|_| self_total > 0.0),
+                self_pct: sum,
+            }
+        })
+        .collect();
+
+    let mut histogram = Vec::new();
+    let mut max = None;
+    for min in HISTOGRAM_BOUNDS.iter().copied().map(Some).chain([None]) {
+        let in_bucket =
+            |pct: f64| min.is_none_or(|min| pct > min) && max.is_none_or(|max: f64| pct <= max);
+        let pcts: Vec<f64> = self_pcts
+            .iter()
+            .copied()
+            .filter(|&p| in_bucket(p))
+            .collect();
+        histogram.push(HistogramBucket {
+            min,
+            max,
+            functions: pcts.len(),
+            // `sum` of nothing is -0.0, printed as a negative share
+            self_pct: pcts.iter().fold(0.0, |sum, pct| sum + pct),
+        });
+        max = min;
+    }
+
+    Distribution {
+        entries: entries.len(),
+        self_total,
+        above,
+        coverage,
+        histogram,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry(children: f64, self_: f64) -> PerfEntry {
+        PerfEntry {
+            children_pct: children,
+            self_pct: self_,
+            symbol: "f".into(),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_distribution() {
+        let entries = vec![
+            entry(90.0, 60.0),
+            entry(40.0, 25.0),
+            entry(12.0, 10.0),
+            entry(5.0, 4.0),
+            entry(0.5, 0.5),
+            entry(0.05, 0.0),
+        ];
+        let stats = distribution(&entries);
+        assert_eq!(stats.entries, 6);
+        assert!((stats.self_total - 99.5).abs() < 1e-9);
+
+        let counts: Vec<_> = stats.above.iter().map(|a| (a.children, a.self_)).collect();
+        assert_eq!(counts, vec![(3, 2), (4, 4), (5, 5)]);
+
+        // 80% of 99.5 is 79.6: the two hottest (85.0) reach it
+        let covering: Vec<_> = stats.coverage.iter().map(|c| c.functions).collect();
+        assert_eq!(covering, vec![Some(1), Some(2), Some(3), Some(4)]);
+        assert!((stats.coverage[1].self_pct - 85.0).abs() < 1e-9);
+
+        let buckets: Vec<_> = stats.histogram.iter().map(|b| b.functions).collect();
+        assert_eq!(buckets, vec![2, 2, 1, 0, 1]);
+        assert_eq!(stats.histogram[0].max, None);
+        assert_eq!(stats.histogram[4].min, None);
+        assert!((stats.histogram[1].self_pct - 14.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_distribution_without_self_time() {
+        let stats = distribution(&[entry(50.0, 0.0)]);
+        assert!(stats.coverage.iter().all(|c| c.functions.is_none()));
+        assert_eq!(stats.histogram.last().unwrap().functions, 1);
+    }
+}
@@ -1,13 +1,547 @@
+use std::collections::{HashMap, HashSet};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use regex::{Regex, RegexBuilder};
+
+use crate::PperfError;
+use crate::demangle::{demangle, is_mangled};
+use crate::hierarchy::ContributionMode;
 use crate::parser::PerfEntry;
+use crate::symbol::{Symbol, simplify_symbol};
+
+/// Separates the alternatives of an OR-group target: `-t 'DCT4DBlock|IDCT4DBlock'`
+pub const GROUP_SEPARATOR: char = '|';
 
 pub fn filter_entries(entries: &[PerfEntry], targets: &[String]) -> Vec<PerfEntry> {
-    if targets.is_empty() {
+    filter_entries_with(entries, targets, &MatchOptions::default())
+}
+
+/// `filter_entries` honoring match options such as `--ignore-case`.
+pub fn filter_entries_with(
+    entries: &[PerfEntry],
+    targets: &[String],
+    options: &MatchOptions,
+) -> Vec<PerfEntry> {
+    if targets.is_empty() && options.target_specs.is_empty() {
+        return entries.to_vec();
+    }
+
+    entries
+        .iter()
+        .filter(|entry| options.is_any_target(&entry.symbol, targets))
+        .cloned()
+        .collect()
+}
+
+/// Keep entries matched by any `--target-file` line.
+pub fn filter_entries_exact(entries: &[PerfEntry], specs: &[TargetSpec]) -> Vec<PerfEntry> {
+    let options = MatchOptions {
+        target_specs: specs.to_vec(),
+        ..Default::default()
+    };
+    filter_entries_with(entries, &[], &options)
+}
+
+/// Prefix marking a target-file line as a regular expression.
+pub const REGEX_PREFIX: &str = "re:";
+
+/// One line of a `--target-file`.
+#[derive(Debug, Clone)]
+pub enum TargetSpec {
+    /// A full signature as printed by perf, compared exactly. Call-tree nodes carry
+    /// simplified names, so those are compared against the simplified signature.
+    Exact {
+        signature: String,
+        simplified: String,
+    },
+    /// A `re:` line, searched anywhere in the symbol; `ignoring_case` is the same
+    /// pattern compiled case-insensitively for `-i`
+    Regex { re: Regex, ignoring_case: Regex },
+}
+
+impl TargetSpec {
+    /// Match mode shown by `--dry-run`
+    pub fn kind(&self) -> &'static str {
+        match self {
+            TargetSpec::Exact { .. } => "exact",
+            TargetSpec::Regex { .. } => "regex",
+        }
+    }
+
+    pub fn regex(pattern: &str) -> Result<TargetSpec, regex::Error> {
+        Ok(TargetSpec::Regex {
+            re: Regex::new(pattern)?,
+            ignoring_case: RegexBuilder::new(pattern).case_insensitive(true).build()?,
+        })
+    }
+
+    /// A mangled signature (`_ZN...`) is demangled so it compares against perf's
+    /// output; one outside the supported grammar is kept as written.
+    pub fn exact(signature: &str) -> TargetSpec {
+        let signature = demangle(signature).unwrap_or_else(|| signature.to_string());
+        TargetSpec::Exact {
+            simplified: simplify_symbol(&signature),
+            signature,
+        }
+    }
+
+    pub fn matches(&self, symbol: &str, ignore_case: bool) -> bool {
+        match self {
+            TargetSpec::Exact {
+                signature,
+                simplified,
+            } => {
+                // Reports recorded with --no-demangle carry mangled symbols
+                let demangled = if is_mangled(symbol) {
+                    demangle(symbol)
+                } else {
+                    None
+                };
+                let symbol = demangled.as_deref().unwrap_or(symbol);
+                if ignore_case {
+                    let symbol = symbol.to_lowercase();
+                    symbol == signature.to_lowercase() || symbol == simplified.to_lowercase()
+                } else {
+                    symbol == signature || symbol == simplified
+                }
+            }
+            TargetSpec::Regex { re, ignoring_case } => {
+                if ignore_case {
+                    ignoring_case.is_match(symbol)
+                } else {
+                    re.is_match(symbol)
+                }
+            }
+        }
+    }
+}
+
+impl PartialEq for TargetSpec {
+    fn eq(&self, other: &TargetSpec) -> bool {
+        self.to_string() == other.to_string()
+    }
+}
+
+impl std::fmt::Display for TargetSpec {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            TargetSpec::Exact { signature, .. } => write!(f, "{}", signature),
+            TargetSpec::Regex { re, .. } => write!(f, "{}{}", REGEX_PREFIX, re.as_str()),
+        }
+    }
+}
+
+/// A target-file entry: what to match, plus optional presentation details from
+/// the structured (`.toml`) format.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Target {
+    pub spec: TargetSpec,
+    /// Short display name shown instead of the matched symbol
+    pub alias: Option<String>,
+    /// Free-form description, listed by `--dry-run`
+    pub notes: Option<String>,
+    /// Section the target was listed under (`[entropy]`), summed by `--group`
+    pub group: Option<String>,
+}
+
+impl Target {
+    pub fn new(spec: TargetSpec) -> Target {
+        Target {
+            spec,
+            alias: None,
+            notes: None,
+            group: None,
+        }
+    }
+}
+
+/// Directive pulling another target file in: `#include other-targets.txt`.
+pub const INCLUDE_DIRECTIVE: &str = "#include";
+
+/// Read a `--target-file`: one exact signature per line, or `re:PATTERN` for a
+/// regular expression. Blank lines and `#` comments are skipped. `#include PATH`
+/// (relative to the including file) splices another file in at that point; each
+/// file is loaded once, so shared includes and include cycles are harmless.
+/// A `[name]` line starts a section: the targets below it (including those of
+/// files it includes) belong to group `name` until the next section line.
+/// Files ending in `.toml` use the structured format (`parse_toml_targets`).
+pub fn parse_target_file(path: &Path) -> Result<Vec<Target>, PperfError> {
+    let mut loaded = HashSet::new();
+    let mut targets = Vec::new();
+    load_target_file(path, None, &mut loaded, &mut targets)?;
+    Ok(targets)
+}
+
+fn load_target_file(
+    path: &Path,
+    section: Option<&str>,
+    loaded: &mut HashSet<PathBuf>,
+    targets: &mut Vec<Target>,
+) -> Result<(), PperfError> {
+    let not_found = || PperfError::FileNotFound(path.display().to_string());
+    let canonical = fs::canonicalize(path).map_err(|_| not_found())?;
+    if !loaded.insert(canonical) {
+        return Ok(());
+    }
+    let content = fs::read_to_string(path).map_err(|_| not_found())?;
+    let invalid = |index: usize, reason: String| {
+        PperfError::InvalidTargetFile(format!(
+            "{}: line {}: {}",
+            path.display(),
+            index + 1,
+            reason
+        ))
+    };
+
+    if path.extension().is_some_and(|ext| ext == "toml") {
+        let parsed = parse_toml_targets(&content).map_err(|reason| {
+            PperfError::InvalidTargetFile(format!("{}: {}", path.display(), reason))
+        })?;
+        targets.extend(parsed.into_iter().map(|mut target| {
+            if target.group.is_none() {
+                target.group = section.map(str::to_string);
+            }
+            target
+        }));
+        return Ok(());
+    }
+
+    let mut section = section.map(str::to_string);
+    for (index, line) in content.lines().enumerate() {
+        let line = line.trim();
+        if let Some(name) = line.strip_prefix('[').and_then(|l| l.strip_suffix(']')) {
+            let name = name.trim();
+            if name.is_empty() {
+                return Err(invalid(index, "empty section name".to_string()));
+            }
+            section = Some(name.to_string());
+            continue;
+        }
+        if let Some(rest) = line.strip_prefix(INCLUDE_DIRECTIVE)
+            && (rest.is_empty() || rest.starts_with(char::is_whitespace))
+        {
+            let include = rest.trim();
+            if include.is_empty() {
+                return Err(invalid(index, "#include needs a path".to_string()));
+            }
+            let base = path.parent().unwrap_or(Path::new(""));
+            load_target_file(&base.join(include), section.as_deref(), loaded, targets)?;
+            continue;
+        }
+        if let Some(spec) = parse_target_line(line).map_err(|reason| invalid(index, reason))? {
+            targets.push(Target {
+                group: section.clone(),
+                ..Target::new(spec)
+            });
+        }
+    }
+    Ok(())
+}
+
+/// Parse the structured target-file format, a small TOML subset: one `[[target]]`
+/// table per target with string keys `signature` (exact) or `regex`, and optional
+/// `alias`, `notes` and `group`. Errors are `line N: reason`.
+///
+/// ```toml
+/// [[target]]
+/// signature = "Hierarchical4DEncoder::get_mSubbandLF_significance(...) const"
+/// alias = "subband-significance"
+/// notes = "Entropy coder hot loop"
+/// group = "entropy"
+/// ```
+pub fn parse_toml_targets(content: &str) -> Result<Vec<Target>, String> {
+    #[derive(Default)]
+    struct Table {
+        line: usize,
+        signature: Option<String>,
+        regex: Option<String>,
+        alias: Option<String>,
+        notes: Option<String>,
+        group: Option<String>,
+    }
+
+    fn finish(table: Table) -> Result<Target, String> {
+        let spec = match (table.signature, table.regex) {
+            (Some(signature), None) => TargetSpec::exact(&signature),
+            (None, Some(pattern)) => TargetSpec::regex(&pattern)
+                .map_err(|e| format!("line {}: invalid regex: {}", table.line, e))?,
+            _ => {
+                return Err(format!(
+                    "line {}: [[target]] needs exactly one of signature or regex",
+                    table.line
+                ));
+            }
+        };
+        Ok(Target {
+            spec,
+            alias: table.alias,
+            notes: table.notes,
+            group: table.group,
+        })
+    }
+
+    let mut targets = Vec::new();
+    let mut current: Option<Table> = None;
+    for (index, line) in content.lines().enumerate() {
+        let number = index + 1;
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        if line.starts_with('[') {
+            if line != "[[target]]" {
+                return Err(format!("line {}: unknown table {}", number, line));
+            }
+            if let Some(table) = current.take() {
+                targets.push(finish(table)?);
+            }
+            current = Some(Table {
+                line: number,
+                ..Default::default()
+            });
+            continue;
+        }
+
+        let Some((key, value)) = line.split_once('=') else {
+            return Err(format!("line {}: expected key = \"value\"", number));
+        };
+        let Some(table) = current.as_mut() else {
+            return Err(format!("line {}: key outside a [[target]] table", number));
+        };
+        let value =
+            parse_toml_string(value.trim()).map_err(|e| format!("line {}: {}", number, e))?;
+        let slot = match key.trim() {
+            "signature" => &mut table.signature,
+            "regex" => &mut table.regex,
+            "alias" => &mut table.alias,
+            "notes" => &mut table.notes,
+            "group" => &mut table.group,
+            other => return Err(format!("line {}: unknown key {}", number, other)),
+        };
+        *slot = Some(value);
+    }
+    if let Some(table) = current {
+        targets.push(finish(table)?);
+    }
+    Ok(targets)
+}
+
+/// Parse a TOML basic (`"..."`, with `\\` escapes) or literal (`'...'`) string,
+/// allowing a trailing `#` comment.
+pub(crate) fn parse_toml_string(value: &str) -> Result<String, String> {
+    let mut chars = value.chars();
+    let quote = chars.next().filter(|c| *c == '"' || *c == '\'');
+    let Some(quote) = quote else {
+        return Err("expected a quoted string".to_string());
+    };
+
+    let mut result = String::new();
+    loop {
+        match chars.next() {
+            None => return Err("unterminated string".to_string()),
+            Some(c) if c == quote => break,
+            Some('\\') if quote == '"' => match chars.next() {
+                Some('"') => result.push('"'),
+                Some('\\') => result.push('\\'),
+                Some('n') => result.push('\n'),
+                Some('t') => result.push('\t'),
+                other => return Err(format!("unsupported escape \\{}", other.unwrap_or(' '))),
+            },
+            Some(c) => result.push(c),
+        }
+    }
+    let rest = chars.as_str().trim();
+    if !rest.is_empty() && !rest.starts_with('#') {
+        return Err(format!("unexpected text after string: {}", rest));
+    }
+    Ok(result)
+}
+
+/// Parse one target-file line; None for blank lines and comments.
+fn parse_target_line(line: &str) -> Result<Option<TargetSpec>, String> {
+    let line = line.trim();
+    if line.is_empty() || line.starts_with('#') {
+        return Ok(None);
+    }
+    match line.strip_prefix(REGEX_PREFIX) {
+        Some(pattern) => TargetSpec::regex(pattern.trim())
+            .map(Some)
+            .map_err(|e| format!("invalid regex: {}", e)),
+        None => Ok(Some(TargetSpec::exact(line))),
+    }
+}
+
+/// Render a signature as a target-file line that matches exactly that symbol.
+/// Signatures the line parser would misread (`re:`, `#`, `[section]`) become an
+/// anchored, escaped regex instead.
+pub fn format_target_line(signature: &str) -> String {
+    let signature = signature.trim();
+    if signature.starts_with(REGEX_PREFIX)
+        || signature.starts_with('#')
+        || signature.starts_with('[')
+    {
+        format!("{}^{}$", REGEX_PREFIX, regex::escape(signature))
+    } else {
+        signature.to_string()
+    }
+}
+
+/// Matching settings shared by entry filtering and hierarchy callee discovery.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct MatchOptions {
+    /// `--exclude` patterns: matching symbols are never treated as targets
+    pub excludes: Vec<String>,
+    /// `--ignore-case`: compare targets and exclusions case-insensitively
+    pub ignore_case: bool,
+    /// `--target-file` lines, matched in addition to the substring targets
+    pub target_specs: Vec<TargetSpec>,
+    /// `--max-depth`: call-tree levels below a caller that callee discovery searches
+    pub max_depth: Option<usize>,
+    /// Every call tree lists callees below its root (`--assume-callee-trees`, or a
+    /// header naming caller order), so `is_leaf_function` is not consulted
+    pub assume_callee_trees: bool,
+    /// `--contribution-mode`: how distinct paths from one caller add up
+    pub contribution_mode: ContributionMode,
+}
+
+impl MatchOptions {
+    /// Exclusions match the simplified (displayed) name, so `std::` does not hide
+    /// every function that merely takes a `std::vector` argument.
+    pub fn is_excluded(&self, symbol: &str) -> bool {
+        if self.excludes.is_empty() {
+            return false;
+        }
+        let simplified = simplify_symbol(symbol);
+        if self.ignore_case {
+            let folded = simplified.to_lowercase();
+            return self
+                .excludes
+                .iter()
+                .any(|p| matches_pattern(&folded, &p.to_lowercase()));
+        }
+        self.excludes
+            .iter()
+            .any(|p| matches_pattern(&simplified, p))
+    }
+
+    /// `matches_target`, case-insensitive when `ignore_case` is set.
+    pub fn matches(&self, symbol: &str, target: &str) -> bool {
+        if self.ignore_case {
+            matches_target(&symbol.to_lowercase(), &target.to_lowercase())
+        } else {
+            matches_target(symbol, target)
+        }
+    }
+
+    /// True if `symbol` matches any substring target or target-file line.
+    pub fn is_any_target(&self, symbol: &str, targets: &[String]) -> bool {
+        targets.iter().any(|t| self.matches(symbol, t))
+            || self
+                .target_specs
+                .iter()
+                .any(|spec| spec.matches(symbol, self.ignore_case))
+    }
+
+    /// True if `symbol` matches any target and no exclusion.
+    pub fn is_target(&self, symbol: &str, targets: &[String]) -> bool {
+        self.is_any_target(symbol, targets) && !self.is_excluded(symbol)
+    }
+}
+
+/// Read a pattern file (`--ignore-file`): one pattern per line, surrounding
+/// whitespace trimmed, blank lines and `#` comments skipped.
+pub fn read_pattern_file(path: &Path) -> Result<Vec<String>, PperfError> {
+    let content = fs::read_to_string(path)
+        .map_err(|_| PperfError::FileNotFound(path.display().to_string()))?;
+    Ok(parse_pattern_lines(&content))
+}
+
+fn parse_pattern_lines(content: &str) -> Vec<String> {
+    content
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty() && !line.starts_with('#'))
+        .map(str::to_string)
+        .collect()
+}
+
+/// Drop entries matching any `--exclude` pattern.
+pub fn exclude_entries(entries: &[PerfEntry], options: &MatchOptions) -> Vec<PerfEntry> {
+    entries
+        .iter()
+        .filter(|entry| !options.is_excluded(&entry.symbol))
+        .cloned()
+        .collect()
+}
+
+/// Keep entries whose Command column equals one of `comms` (all entries if none
+/// are given). Process names are compared exactly, as `perf report --comms` does.
+pub fn filter_entries_by_comm(entries: &[PerfEntry], comms: &[String]) -> Vec<PerfEntry> {
+    if comms.is_empty() {
+        return entries.to_vec();
+    }
+    entries
+        .iter()
+        .filter(|e| comms.contains(&e.comm))
+        .cloned()
+        .collect()
+}
+
+/// Keep entries whose Shared Object contains any of `dsos` (all entries if none are
+/// given). Reports without a Shared Object column match nothing.
+pub fn filter_entries_by_dso(entries: &[PerfEntry], dsos: &[String]) -> Vec<PerfEntry> {
+    if dsos.is_empty() {
         return entries.to_vec();
     }
+    entries
+        .iter()
+        .filter(|e| dsos.iter().any(|d| matches_pattern(&e.dso, d)))
+        .cloned()
+        .collect()
+}
+
+/// `--user-only` / `--kernel-only` selection, by the entry's privilege marker.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum SymbolSpace {
+    User,
+    Kernel,
+}
+
+impl SymbolSpace {
+    pub fn contains(&self, entry: &PerfEntry) -> bool {
+        entry.kernel == (*self == SymbolSpace::Kernel)
+    }
+}
+
+impl std::fmt::Display for SymbolSpace {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            SymbolSpace::User => write!(f, "user"),
+            SymbolSpace::Kernel => write!(f, "kernel"),
+        }
+    }
+}
+
+/// Keep entries in the given symbol space.
+pub fn filter_entries_by_space(entries: &[PerfEntry], space: SymbolSpace) -> Vec<PerfEntry> {
+    entries
+        .iter()
+        .filter(|e| space.contains(e))
+        .cloned()
+        .collect()
+}
 
+/// Keep entries at or above both thresholds (`--min-children`, `--min-self`).
+pub fn filter_entries_by_min_pct(
+    entries: &[PerfEntry],
+    min_children: Option<f64>,
+    min_self: Option<f64>,
+) -> Vec<PerfEntry> {
     entries
         .iter()
-        .filter(|entry| targets.iter().any(|t| matches_pattern(&entry.symbol, t)))
+        .filter(|e| min_children.is_none_or(|min| e.children_pct >= min))
+        .filter(|e| min_self.is_none_or(|min| e.self_pct >= min))
         .cloned()
         .collect()
 }
@@ -16,10 +550,355 @@ pub fn matches_pattern(symbol: &str, pattern: &str) -> bool {
     symbol.contains(pattern)
 }
 
+pub fn is_target_group(target: &str) -> bool {
+    target.contains(GROUP_SEPARATOR)
+}
+
+/// Match a target, which is either a plain substring or an OR-group of substrings.
+pub fn matches_target(symbol: &str, target: &str) -> bool {
+    if !is_target_group(target) {
+        return matches_pattern(symbol, target);
+    }
+    target
+        .split(GROUP_SEPARATOR)
+        .map(str::trim)
+        .filter(|alt| !alt.is_empty())
+        .any(|alt| matches_pattern(symbol, alt))
+}
+
+/// Collapse the entries matched by each OR-group target into one logical entry named
+/// after the group, summing Children% and Self%. Plain targets are left untouched.
+/// The merged entry takes the position of the group's first (highest) member.
+pub fn merge_target_groups(
+    entries: &[PerfEntry],
+    targets: &[String],
+    options: &MatchOptions,
+) -> Vec<PerfEntry> {
+    let groups: Vec<&String> = targets.iter().filter(|t| is_target_group(t)).collect();
+    if groups.is_empty() {
+        return entries.to_vec();
+    }
+
+    let mut result: Vec<PerfEntry> = Vec::new();
+    let mut group_slots: Vec<Option<usize>> = vec![None; groups.len()];
+
+    for entry in entries {
+        let Some(group_index) = groups
+            .iter()
+            .position(|g| options.matches(&entry.symbol, g))
+        else {
+            result.push(entry.clone());
+            continue;
+        };
+
+        match group_slots[group_index] {
+            Some(slot) => {
+                result[slot].children_pct += entry.children_pct;
+                result[slot].self_pct += entry.self_pct;
+            }
+            None => {
+                group_slots[group_index] = Some(result.len());
+                result.push(PerfEntry {
+                    symbol: groups[group_index].as_str().into(),
+                    srcline: String::new(),
+                    ..entry.clone()
+                });
+            }
+        }
+    }
+
+    result
+}
+
+/// `--group`: collapse the entries matched by target-file targets into one entry per
+/// section, summing Children% and Self% like `merge_target_groups`. An entry counts
+/// toward the first target it matches; entries of ungrouped targets stay as they are.
+pub fn merge_target_sections(
+    entries: &[PerfEntry],
+    targets: &[Target],
+    ignore_case: bool,
+) -> Vec<PerfEntry> {
+    let mut result: Vec<PerfEntry> = Vec::new();
+    let mut slots: Vec<(String, usize)> = Vec::new();
+
+    for entry in entries {
+        let group = targets
+            .iter()
+            .find(|t| t.spec.matches(&entry.symbol, ignore_case))
+            .and_then(|t| t.group.as_ref());
+        let Some(group) = group else {
+            result.push(entry.clone());
+            continue;
+        };
+
+        match slots.iter().find(|(name, _)| name == group) {
+            Some(&(_, slot)) => {
+                result[slot].children_pct += entry.children_pct;
+                result[slot].self_pct += entry.self_pct;
+            }
+            None => {
+                slots.push((group.clone(), result.len()));
+                result.push(PerfEntry {
+                    symbol: group.as_str().into(),
+                    srcline: String::new(),
+                    ..entry.clone()
+                });
+            }
+        }
+    }
+
+    result
+}
+
+/// `--merge-overloads`: collapse the entries whose simplified names are identical
+/// (overloads, template specializations) into one entry named after the simplified
+/// name, summing Children% and Self% like `merge_target_groups`. The merged entry
+/// takes the position of its first member. Also returns, per merged name, the
+/// symbols it was merged from in report order; lone entries are left untouched.
+pub fn merge_overloads(entries: &[PerfEntry]) -> (Vec<PerfEntry>, HashMap<String, Vec<Symbol>>) {
+    let mut result: Vec<PerfEntry> = Vec::new();
+    let mut slots: HashMap<String, usize> = HashMap::new();
+    let mut members: Vec<Vec<Symbol>> = Vec::new();
+
+    for entry in entries {
+        let name = simplify_symbol(&entry.symbol);
+        match slots.get(&name) {
+            Some(&slot) => {
+                result[slot].children_pct += entry.children_pct;
+                result[slot].self_pct += entry.self_pct;
+                result[slot].symbol = name.as_str().into();
+                // The merged lines no longer share one source location
+                result[slot].srcline.clear();
+                members[slot].push(entry.symbol.clone());
+            }
+            None => {
+                slots.insert(name, result.len());
+                result.push(entry.clone());
+                members.push(vec![entry.symbol.clone()]);
+            }
+        }
+    }
+
+    let signatures = members
+        .into_iter()
+        .zip(&result)
+        .filter(|(symbols, _)| symbols.len() > 1)
+        .map(|(symbols, entry)| (entry.symbol.to_string(), symbols))
+        .collect();
+    (result, signatures)
+}
+
+/// `--group-by`: the scope whose functions are summed into one entry.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum GroupBy {
+    /// The first two `::` components of the qualifier (`std::vector::`)
+    Class,
+    /// The first `::` component of the qualifier (`std::`)
+    Namespace,
+}
+
+impl GroupBy {
+    /// Parse a `class`/`namespace` mode name.
+    pub fn from_setting(value: &str) -> Option<GroupBy> {
+        match value.trim() {
+            "class" => Some(GroupBy::Class),
+            "namespace" => Some(GroupBy::Namespace),
+            _ => None,
+        }
+    }
+
+    /// How many leading `::` components name the scope.
+    fn depth(self) -> usize {
+        match self {
+            GroupBy::Class => 2,
+            GroupBy::Namespace => 1,
+        }
+    }
+}
+
+/// The `--group-by` row of functions outside any namespace or class.
+pub const GLOBAL_SCOPE: &str = "[global]";
+
+/// The `::` components of a signature's qualified name, not splitting inside
+/// template arguments; a return type in front and the argument list are left out.
+fn scope_components(signature: &str) -> Vec<&str> {
+    let mut components = Vec::new();
+    let mut depth: usize = 0;
+    let mut start = 0;
+    let mut end = signature.len();
+    let mut chars = signature.char_indices().peekable();
+    while let Some((i, c)) = chars.next() {
+        let operator = signature[start..i].ends_with("operator");
+        match c {
+            '(' if depth == 0 && !operator => {
+                end = i;
+                break;
+            }
+            '<' | '(' | '[' => depth += 1,
+            '>' | ')' | ']' => depth = depth.saturating_sub(1),
+            // Everything so far was the return type
+            ' ' if depth == 0 && !operator => {
+                components.clear();
+                start = i + 1;
+            }
+            ':' if depth == 0 && chars.peek().is_some_and(|&(_, next)| next == ':') => {
+                components.push(&signature[start..i]);
+                chars.next();
+                start = i + 2;
+            }
+            _ => {}
+        }
+    }
+    components.push(&signature[start..end]);
+    components
+}
+
+/// The scope `group_by` files a symbol under: the first one or two `::` components
+/// of its simplified name, short of the function name itself, as `Foo::`.
+/// Functions without a qualifier fall under `GLOBAL_SCOPE`.
+pub fn symbol_scope(symbol: &str, group_by: GroupBy) -> String {
+    let simplified = simplify_symbol(symbol);
+    let mut components = scope_components(&simplified);
+    components.pop();
+    if components.is_empty() || components[0].is_empty() {
+        return GLOBAL_SCOPE.to_string();
+    }
+    let mut scope = String::new();
+    for component in components.iter().take(group_by.depth()) {
+        scope.push_str(component);
+        scope.push_str("::");
+    }
+    scope
+}
+
+/// `--group-by`: sum every entry's Self% into one entry per scope (`symbol_scope`),
+/// named after it, at the position of the scope's first entry. Children% is
+/// inclusive, so a scope whose functions call each other would count the same time
+/// several times; scope entries leave it at 0.
+pub fn merge_scopes(entries: &[PerfEntry], group_by: GroupBy) -> Vec<PerfEntry> {
+    let mut result: Vec<PerfEntry> = Vec::new();
+    let mut slots: HashMap<String, usize> = HashMap::new();
+
+    for entry in entries {
+        let scope = symbol_scope(&entry.symbol, group_by);
+        match slots.get(&scope) {
+            Some(&slot) => result[slot].self_pct += entry.self_pct,
+            None => {
+                result.push(PerfEntry {
+                    symbol: scope.as_str().into(),
+                    srcline: String::new(),
+                    children_pct: 0.0,
+                    ..entry.clone()
+                });
+                slots.insert(scope, result.len() - 1);
+            }
+        }
+    }
+
+    result
+}
+
+/// Up to `limit` displayed (simplified) symbol names closest to any target, for
+/// "did you mean" hints. A name qualifies if a target is a case-insensitive
+/// substring of it or is within a few edits of the whole name or one of its
+/// `::` components; closer names come first, ties keep report order.
+pub fn suggest_symbols(entries: &[PerfEntry], targets: &[String], limit: usize) -> Vec<String> {
+    let mut scored: Vec<(usize, String)> = Vec::new();
+    for entry in entries {
+        let name = simplify_symbol(&entry.symbol);
+        if scored.iter().any(|(_, seen)| *seen == name) {
+            continue;
+        }
+        let folded = name.to_lowercase();
+        let best = targets
+            .iter()
+            .flat_map(|t| {
+                t.split(GROUP_SEPARATOR)
+                    .map(str::trim)
+                    .filter(|a| !a.is_empty())
+            })
+            .filter_map(|alt| {
+                let alt = alt.to_lowercase();
+                if folded.contains(&alt) {
+                    return Some(0);
+                }
+                let distance = std::iter::once(folded.as_str())
+                    .chain(folded.split("::"))
+                    .map(|part| edit_distance(part, &alt))
+                    .min()?;
+                (distance <= (alt.chars().count() / 3).max(2)).then_some(distance)
+            })
+            .min();
+        if let Some(distance) = best {
+            scored.push((distance, name));
+        }
+    }
+    // Stable sort keeps the report's hottest-first order among equal distances
+    scored.sort_by_key(|(distance, _)| *distance);
+    scored
+        .into_iter()
+        .take(limit)
+        .map(|(_, name)| name)
+        .collect()
+}
+
+/// The report signature closest to an unmatched exact target, for `pperf targets
+/// check`. Overloads of the same function (equal simplified name) are preferred;
+/// otherwise any signature within a third of the target's length in edits.
+pub fn closest_signature(entries: &[PerfEntry], signature: &str) -> Option<Symbol> {
+    fn closest<'a>(
+        candidates: impl Iterator<Item = &'a PerfEntry>,
+        signature: &str,
+    ) -> Option<(usize, &'a Symbol)> {
+        candidates
+            .map(|e| (edit_distance(&e.symbol, signature), &e.symbol))
+            .min_by_key(|(distance, _)| *distance)
+    }
+
+    let simplified = simplify_symbol(signature);
+    let overloads = entries
+        .iter()
+        .filter(|e| simplify_symbol(&e.symbol) == simplified);
+    if let Some((_, symbol)) = closest(overloads, signature) {
+        return Some(symbol.clone());
+    }
+    let limit = (signature.chars().count() / 3).max(2);
+    closest(entries.iter(), signature)
+        .filter(|(distance, _)| *distance <= limit)
+        .map(|(_, symbol)| symbol.clone())
+}
+
+/// Levenshtein distance over characters.
+fn edit_distance(a: &str, b: &str) -> usize {
+    let b: Vec<char> = b.chars().collect();
+    let mut previous: Vec<usize> = (0..=b.len()).collect();
+    for (i, ca) in a.chars().enumerate() {
+        let mut current = vec![i + 1; b.len() + 1];
+        for (j, cb) in b.iter().enumerate() {
+            let substitution = previous[j] + usize::from(ca != *cb);
+            current[j + 1] = substitution.min(previous[j + 1] + 1).min(current[j] + 1);
+        }
+        previous = current;
+    }
+    previous[b.len()]
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
 
+    fn parse_target_lines(content: &str) -> Result<Vec<TargetSpec>, String> {
+        let mut specs = Vec::new();
+        for (index, line) in content.lines().enumerate() {
+            if let Some(spec) =
+                parse_target_line(line).map_err(|r| format!("line {}: {}", index + 1, r))?
+            {
+                specs.push(spec);
+            }
+        }
+        Ok(specs)
+    }
+
     #[test]
     fn test_matches_pattern_exact() {
         assert!(matches_pattern("DCT4DBlock", "DCT4DBlock"));
@@ -60,17 +939,20 @@ mod tests {
             PerfEntry {
                 children_pct: 90.0,
                 self_pct: 1.0,
-                symbol: "DCT4DBlock::new".to_string(),
+                symbol: "DCT4DBlock::new".into(),
+                ..Default::default()
             },
             PerfEntry {
                 children_pct: 50.0,
                 self_pct: 5.0,
-                symbol: "Block4D::get".to_string(),
+                symbol: "Block4D::get".into(),
+                ..Default::default()
             },
             PerfEntry {
                 children_pct: 30.0,
                 self_pct: 3.0,
-                symbol: "DCT4DBlock::transform".to_string(),
+                symbol: "DCT4DBlock::transform".into(),
+                ..Default::default()
             },
         ];
         let targets = vec!["DCT4D".to_string()];
@@ -86,17 +968,20 @@ mod tests {
             PerfEntry {
                 children_pct: 90.0,
                 self_pct: 1.0,
-                symbol: "DCT4DBlock::new".to_string(),
+                symbol: "DCT4DBlock::new".into(),
+                ..Default::default()
             },
             PerfEntry {
                 children_pct: 50.0,
                 self_pct: 5.0,
-                symbol: "Block4D::get".to_string(),
+                symbol: "Block4D::get".into(),
+                ..Default::default()
             },
             PerfEntry {
                 children_pct: 30.0,
                 self_pct: 3.0,
-                symbol: "std::sort".to_string(),
+                symbol: "std::sort".into(),
+                ..Default::default()
             },
         ];
         let targets = vec!["DCT4D".to_string(), "std::".to_string()];
@@ -107,18 +992,186 @@ mod tests {
         assert!(filtered.iter().any(|e| e.symbol.starts_with("std::")));
     }
 
+    #[test]
+    fn test_matches_target_group() {
+        let group = "DCT4DBlock|IDCT4DBlock";
+        assert!(matches_target("DCT4DBlock::DCT4DBlock", group));
+        assert!(matches_target("IDCT4DBlock::run", group));
+        assert!(!matches_target("Block4D::get", group));
+
+        // Empty alternatives are ignored rather than matching everything
+        assert!(!matches_target("anything", "|Missing|"));
+        assert!(matches_target("DCT4DBlock", " DCT4DBlock | nope "));
+
+        // Plain targets keep substring semantics
+        assert!(matches_target("DCT4DBlock::new", "DCT4D"));
+    }
+
+    #[test]
+    fn test_merge_target_groups_sums_members() {
+        let entries = vec![
+            PerfEntry {
+                children_pct: 38.0,
+                self_pct: 1.0,
+                symbol: "DCT4DBlock::DCT4DBlock".into(),
+                ..Default::default()
+            },
+            PerfEntry {
+                children_pct: 20.0,
+                self_pct: 5.0,
+                symbol: "Block4D::get".into(),
+                ..Default::default()
+            },
+            PerfEntry {
+                children_pct: 7.5,
+                self_pct: 7.0,
+                symbol: "std::inner_product".into(),
+                ..Default::default()
+            },
+        ];
+        let targets = vec![
+            "DCT4DBlock|inner_product".to_string(),
+            "Block4D".to_string(),
+        ];
+
+        let merged = merge_target_groups(&entries, &targets, &MatchOptions::default());
+        assert_eq!(merged.len(), 2);
+        assert_eq!(merged[0].symbol, "DCT4DBlock|inner_product");
+        assert!((merged[0].children_pct - 45.5).abs() < 0.001);
+        assert!((merged[0].self_pct - 8.0).abs() < 0.001);
+        assert_eq!(merged[1].symbol, "Block4D::get");
+    }
+
+    #[test]
+    fn test_merge_overloads() {
+        let entry = |symbol: &str, children: f64| PerfEntry {
+            children_pct: children,
+            self_pct: 1.0,
+            symbol: symbol.into(),
+            ..Default::default()
+        };
+        let entries = vec![
+            entry(
+                "TransformPartition::rd_optimize_transform(Block4D const&)",
+                71.8,
+            ),
+            entry("DCT4DBlock::DCT4DBlock(Block4D const&, double)", 38.3),
+            entry(
+                "TransformPartition::rd_optimize_transform(Block4D const&, Block4D&)",
+                20.5,
+            ),
+        ];
+
+        let (merged, signatures) = merge_overloads(&entries);
+        assert_eq!(merged.len(), 2);
+        assert_eq!(
+            merged[0].symbol,
+            "TransformPartition::rd_optimize_transform"
+        );
+        assert!((merged[0].children_pct - 92.3).abs() < 0.001);
+        assert!((merged[0].self_pct - 2.0).abs() < 0.001);
+        // A lone entry keeps its signature and is not listed
+        assert_eq!(
+            merged[1].symbol,
+            "DCT4DBlock::DCT4DBlock(Block4D const&, double)"
+        );
+        assert_eq!(signatures.len(), 1);
+        assert_eq!(
+            signatures["TransformPartition::rd_optimize_transform"],
+            vec![entries[0].symbol.clone(), entries[2].symbol.clone()]
+        );
+    }
+
+    #[test]
+    fn test_symbol_scope() {
+        let scope = |symbol, group_by| symbol_scope(symbol, group_by);
+        assert_eq!(
+            scope("std::vector<int>::push_back(int const&)", GroupBy::Class),
+            "std::vector::"
+        );
+        assert_eq!(
+            scope(
+                "std::vector<int>::push_back(int const&)",
+                GroupBy::Namespace
+            ),
+            "std::"
+        );
+        assert_eq!(
+            scope(
+                "void TransformPartition::rd_optimize_transform(Block4D const&)",
+                GroupBy::Class
+            ),
+            "TransformPartition::"
+        );
+        assert_eq!(scope("main", GroupBy::Namespace), GLOBAL_SCOPE);
+        assert_eq!(scope("0x7f3a2b", GroupBy::Class), GLOBAL_SCOPE);
+    }
+
+    #[test]
+    fn test_merge_scopes() {
+        let entry = |symbol: &str, children: f64, self_pct: f64| PerfEntry {
+            children_pct: children,
+            self_pct,
+            symbol: symbol.into(),
+            ..Default::default()
+        };
+        let entries = vec![
+            entry("Hierarchical4DEncoder::rd_optimize", 60.0, 1.0),
+            entry("std::inner_product", 20.0, 7.5),
+            entry(
+                "Hierarchical4DEncoder::get_mSubbandLF_significance",
+                10.0,
+                4.0,
+            ),
+            entry("std::vector<int>::push_back(int const&)", 5.0, 2.5),
+            entry("main", 90.0, 0.0),
+        ];
+
+        let merged = merge_scopes(&entries, GroupBy::Namespace);
+        assert_eq!(merged.len(), 3);
+        assert_eq!(merged[0].symbol, "Hierarchical4DEncoder::");
+        assert_eq!(merged[0].children_pct, 0.0);
+        assert!((merged[0].self_pct - 5.0).abs() < 0.001);
+        assert_eq!(merged[1].symbol, "std::");
+        assert!((merged[1].self_pct - 10.0).abs() < 0.001);
+        assert_eq!(merged[2].symbol, GLOBAL_SCOPE);
+
+        let merged = merge_scopes(&entries, GroupBy::Class);
+        assert_eq!(merged.len(), 4);
+        // A namespace-level function stays under its namespace
+        assert_eq!(merged[1].symbol, "std::");
+        assert_eq!(merged[2].symbol, "std::vector::");
+    }
+
+    #[test]
+    fn test_merge_target_groups_without_groups() {
+        let entries = vec![PerfEntry {
+            children_pct: 10.0,
+            self_pct: 1.0,
+            symbol: "foo".into(),
+            ..Default::default()
+        }];
+        let targets = vec!["foo".to_string()];
+        assert_eq!(
+            merge_target_groups(&entries, &targets, &MatchOptions::default()),
+            entries
+        );
+    }
+
     #[test]
     fn test_filter_entries_empty_targets() {
         let entries = vec![
             PerfEntry {
                 children_pct: 90.0,
                 self_pct: 1.0,
-                symbol: "foo".to_string(),
+                symbol: "foo".into(),
+                ..Default::default()
             },
             PerfEntry {
                 children_pct: 50.0,
                 self_pct: 5.0,
-                symbol: "bar".to_string(),
+                symbol: "bar".into(),
+                ..Default::default()
             },
         ];
         let targets: Vec<String> = vec![];
@@ -133,12 +1186,14 @@ mod tests {
             PerfEntry {
                 children_pct: 90.0,
                 self_pct: 1.0,
-                symbol: "foo".to_string(),
+                symbol: "foo".into(),
+                ..Default::default()
             },
             PerfEntry {
                 children_pct: 50.0,
                 self_pct: 5.0,
-                symbol: "bar".to_string(),
+                symbol: "bar".into(),
+                ..Default::default()
             },
         ];
         let targets = vec!["NonExistent".to_string()];
@@ -146,4 +1201,457 @@ mod tests {
 
         assert!(filtered.is_empty());
     }
+
+    #[test]
+    fn test_exclude_entries() {
+        let entries = vec![
+            PerfEntry {
+                symbol: "std::inner_product".into(),
+                ..Default::default()
+            },
+            PerfEntry {
+                symbol: "DCT4DBlock::DCT4DBlock".into(),
+                ..Default::default()
+            },
+            PerfEntry {
+                symbol: "{lambda(auto:1 const&)#1}".into(),
+                ..Default::default()
+            },
+            PerfEntry {
+                symbol: "parallel_for(std::vector<int> const&)".into(),
+                ..Default::default()
+            },
+        ];
+        let options = MatchOptions {
+            excludes: vec!["std::".to_string(), "lambda".to_string()],
+            ..Default::default()
+        };
+        let kept = exclude_entries(&entries, &options);
+        let symbols: Vec<&str> = kept.iter().map(|e| e.symbol.as_str()).collect();
+        // `std::` only in the argument list does not exclude parallel_for
+        assert_eq!(
+            symbols,
+            vec![
+                "DCT4DBlock::DCT4DBlock",
+                "parallel_for(std::vector<int> const&)"
+            ]
+        );
+    }
+
+    #[test]
+    fn test_match_options_is_target() {
+        let options = MatchOptions {
+            excludes: vec!["IDCT".to_string()],
+            ..Default::default()
+        };
+        let targets = vec!["DCT4D".to_string()];
+        assert!(options.is_target("DCT4DBlock", &targets));
+        assert!(!options.is_target("IDCT4DBlock", &targets));
+        assert!(!options.is_target("main", &targets));
+    }
+
+    #[test]
+    fn test_filter_entries_ignore_case() {
+        let entries = vec![
+            PerfEntry {
+                symbol: "DCT4DBlock::DCT4DBlock".into(),
+                ..Default::default()
+            },
+            PerfEntry {
+                symbol: "Block4D::get".into(),
+                ..Default::default()
+            },
+        ];
+        let targets = vec!["dct4dblock".to_string()];
+        assert!(filter_entries(&entries, &targets).is_empty());
+
+        let options = MatchOptions {
+            ignore_case: true,
+            ..Default::default()
+        };
+        let filtered = filter_entries_with(&entries, &targets, &options);
+        assert_eq!(filtered.len(), 1);
+        assert_eq!(filtered[0].symbol, "DCT4DBlock::DCT4DBlock");
+        assert!(options.matches("IDCT4DBlock", "nope|idct"));
+    }
+
+    #[test]
+    fn test_edit_distance() {
+        assert_eq!(edit_distance("DCT4DBlock", "DCT4DBlock"), 0);
+        assert_eq!(edit_distance("DCT4DBlok", "DCT4DBlock"), 1);
+        assert_eq!(edit_distance("", "abc"), 3);
+        assert_eq!(edit_distance("kitten", "sitting"), 3);
+    }
+
+    #[test]
+    fn test_suggest_symbols() {
+        let entries: Vec<PerfEntry> = [
+            "TransformPartition::rd_optimize_transform(Block4D const&)",
+            "DCT4DBlock::DCT4DBlock(Block4D const&, double)",
+            "IDCT4DBlock::IDCT4DBlock(Block4D const&, double)",
+            "Block4D::get_linear_position",
+            "main",
+        ]
+        .iter()
+        .map(|symbol| PerfEntry {
+            symbol: (*symbol).into(),
+            ..Default::default()
+        })
+        .collect();
+
+        // A typo in one `::` component
+        let suggestions = suggest_symbols(&entries, &["DTC4DBlock".to_string()], 3);
+        assert_eq!(suggestions[0], "DCT4DBlock::DCT4DBlock");
+
+        // Wrong case is a substring match, ranked first
+        let suggestions = suggest_symbols(&entries, &["rd_OPTIMIZE".to_string()], 3);
+        assert_eq!(
+            suggestions,
+            vec!["TransformPartition::rd_optimize_transform"]
+        );
+
+        assert!(suggest_symbols(&entries, &["zzzzzzzz".to_string()], 3).is_empty());
+        assert!(suggest_symbols(&entries, &["Block".to_string()], 2).len() == 2);
+    }
+
+    #[test]
+    fn test_filter_entries_by_min_pct() {
+        let entries: Vec<PerfEntry> = [(90.0, 0.0), (20.0, 5.0), (0.4, 0.4)]
+            .iter()
+            .map(|&(children_pct, self_pct)| PerfEntry {
+                children_pct,
+                self_pct,
+                ..Default::default()
+            })
+            .collect();
+
+        assert_eq!(filter_entries_by_min_pct(&entries, None, None), entries);
+        assert_eq!(
+            filter_entries_by_min_pct(&entries, Some(0.5), None).len(),
+            2
+        );
+        assert_eq!(
+            filter_entries_by_min_pct(&entries, None, Some(1.0)).len(),
+            1
+        );
+        assert!(filter_entries_by_min_pct(&entries, Some(95.0), None).is_empty());
+    }
+
+    #[test]
+    fn test_filter_entries_by_dso() {
+        let entries: Vec<PerfEntry> = ["jpl-encoder-bin", "libc.so.6", "libm.so.6", ""]
+            .iter()
+            .map(|dso| PerfEntry {
+                dso: dso.to_string(),
+                ..Default::default()
+            })
+            .collect();
+
+        assert_eq!(filter_entries_by_dso(&entries, &[]), entries);
+        let kept = filter_entries_by_dso(&entries, &["libc.so".to_string(), "libm".to_string()]);
+        let dsos: Vec<&str> = kept.iter().map(|e| e.dso.as_str()).collect();
+        assert_eq!(dsos, vec!["libc.so.6", "libm.so.6"]);
+    }
+
+    #[test]
+    fn test_filter_entries_by_comm() {
+        let entries: Vec<PerfEntry> = ["encoder", "encoder-worker", "kworker/0:1"]
+            .iter()
+            .map(|comm| PerfEntry {
+                comm: comm.to_string(),
+                ..Default::default()
+            })
+            .collect();
+
+        assert_eq!(filter_entries_by_comm(&entries, &[]).len(), 3);
+        let kept = filter_entries_by_comm(&entries, &["encoder".to_string()]);
+        assert_eq!(kept.len(), 1);
+        assert_eq!(kept[0].comm, "encoder");
+    }
+
+    #[test]
+    fn test_filter_entries_by_space() {
+        let entries = vec![
+            PerfEntry {
+                symbol: "main".into(),
+                ..Default::default()
+            },
+            PerfEntry {
+                symbol: "do_syscall_64".into(),
+                kernel: true,
+                ..Default::default()
+            },
+        ];
+        let user = filter_entries_by_space(&entries, SymbolSpace::User);
+        assert_eq!(user.len(), 1);
+        assert_eq!(user[0].symbol, "main");
+        let kernel = filter_entries_by_space(&entries, SymbolSpace::Kernel);
+        assert_eq!(kernel[0].symbol, "do_syscall_64");
+    }
+
+    #[test]
+    fn test_parse_pattern_lines() {
+        let content = "# thread pool glue\nparallel_for_with_progress\n\n  GOMP_parallel  \n";
+        assert_eq!(
+            parse_pattern_lines(content),
+            vec!["parallel_for_with_progress", "GOMP_parallel"]
+        );
+    }
+
+    #[test]
+    fn test_parse_target_lines() {
+        let content = "# codec core\n\
+                       DCT4DBlock::DCT4DBlock(Block4D const&, double)\n\
+                       \n\
+                       re:^TransformPartition::rd_optimize\n";
+        let specs = parse_target_lines(content).unwrap();
+        assert_eq!(specs.len(), 2);
+        assert_eq!(
+            specs[0],
+            TargetSpec::exact("DCT4DBlock::DCT4DBlock(Block4D const&, double)")
+        );
+        assert_eq!(specs[1].to_string(), "re:^TransformPartition::rd_optimize");
+
+        let err = parse_target_lines("ok\nre:(unclosed\n").unwrap_err();
+        assert!(err.starts_with("line 2: invalid regex"), "{}", err);
+    }
+
+    #[test]
+    fn test_filter_entries_exact() {
+        let entries: Vec<PerfEntry> = [
+            "DCT4DBlock::DCT4DBlock(Block4D const&, double)",
+            "IDCT4DBlock::IDCT4DBlock(Block4D const&, double)",
+            "TransformPartition::rd_optimize_transform(Block4D const&)",
+        ]
+        .iter()
+        .map(|symbol| PerfEntry {
+            symbol: (*symbol).into(),
+            ..Default::default()
+        })
+        .collect();
+
+        // An exact signature does not match as a substring of a longer name
+        let specs = vec![TargetSpec::exact(
+            "DCT4DBlock::DCT4DBlock(Block4D const&, double)",
+        )];
+        let kept = filter_entries_exact(&entries, &specs);
+        assert_eq!(kept.len(), 1);
+        assert_eq!(kept[0].symbol, entries[0].symbol);
+
+        let specs = parse_target_lines("re:^I?DCT4DBlock::").unwrap();
+        assert_eq!(filter_entries_exact(&entries, &specs).len(), 2);
+    }
+
+    #[test]
+    fn test_target_spec_matches_simplified_tree_symbols() {
+        let spec = TargetSpec::exact("DCT4DBlock::DCT4DBlock(Block4D const&, double)");
+        assert!(spec.matches("DCT4DBlock::DCT4DBlock", false));
+        assert!(spec.matches("dct4dblock::dct4dblock", true));
+        assert!(!spec.matches("IDCT4DBlock::IDCT4DBlock", false));
+    }
+
+    #[test]
+    fn test_regex_target_spec_ignore_case() {
+        let spec = parse_target_lines("re:dct4dblock").unwrap().remove(0);
+        assert!(!spec.matches("DCT4DBlock::DCT4DBlock", false));
+        assert!(spec.matches("DCT4DBlock::DCT4DBlock", true));
+        assert_eq!(spec.to_string(), "re:dct4dblock");
+    }
+
+    #[test]
+    fn test_parse_target_file_includes() {
+        let path = std::path::Path::new(env!("CARGO_MANIFEST_DIR"))
+            .join("tests/fixtures/targets-include/codec.txt");
+        let targets = parse_target_file(&path).unwrap();
+        let names: Vec<String> = targets.iter().map(|t| t.spec.to_string()).collect();
+        // core.txt is included twice (directly and via entropy.txt) and entropy.txt
+        // includes codec.txt back; each file still contributes once, in order
+        assert_eq!(
+            names,
+            vec![
+                "TransformPartition::rd_optimize_transform(Block4D const&)",
+                "DCT4DBlock::DCT4DBlock(Block4D const&, double)",
+                "re:get_mSubband",
+                "re:^(double )?std::inner_product",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_closest_signature() {
+        let entries = vec![
+            PerfEntry {
+                symbol: "DCT4DBlock::DCT4DBlock(Block4D const&, double)".into(),
+                ..Default::default()
+            },
+            PerfEntry {
+                symbol: "Block4D::get(int) const".into(),
+                ..Default::default()
+            },
+        ];
+        // An overload wins even when another signature is textually closer
+        assert_eq!(
+            closest_signature(&entries, "DCT4DBlock::DCT4DBlock(int)").as_deref(),
+            Some("DCT4DBlock::DCT4DBlock(Block4D const&, double)")
+        );
+        assert_eq!(
+            closest_signature(&entries, "Block4D::gt(int) const").as_deref(),
+            Some("Block4D::get(int) const")
+        );
+        assert_eq!(closest_signature(&entries, "main"), None);
+    }
+
+    #[test]
+    fn test_mangled_exact_target() {
+        let spec = parse_target_line("_ZN10DCT4DBlockC1ERK7Block4Dd")
+            .unwrap()
+            .unwrap();
+        assert_eq!(
+            spec.to_string(),
+            "DCT4DBlock::DCT4DBlock(Block4D const&, double)"
+        );
+        assert!(spec.matches("DCT4DBlock::DCT4DBlock(Block4D const&, double)", false));
+        // Symbols of a --no-demangle report are demangled before comparing
+        assert!(spec.matches("_ZN10DCT4DBlockC2ERK7Block4Dd", false));
+        assert!(!spec.matches("_ZN10DCT4DBlockD2Ev", false));
+    }
+
+    #[test]
+    fn test_format_target_line_round_trips() {
+        for signature in [
+            "DCT4DBlock::DCT4DBlock(Block4D const&, double)",
+            "[unknown]",
+            "#hash",
+            "re:literal",
+        ] {
+            let line = format_target_line(signature);
+            let spec = parse_target_line(&line).unwrap().unwrap();
+            assert!(spec.matches(signature, false), "{} -> {}", signature, line);
+            assert!(!spec.matches(&format!("x{}", signature), false), "{}", line);
+        }
+        assert_eq!(format_target_line("main"), "main");
+        assert_eq!(format_target_line("[unknown]"), r"re:^\[unknown\]$");
+    }
+
+    #[test]
+    fn test_parse_target_file_sections() {
+        let path = std::path::Path::new(env!("CARGO_MANIFEST_DIR"))
+            .join("tests/fixtures/targets-groups.txt");
+        let targets = parse_target_file(&path).unwrap();
+        let groups: Vec<Option<&str>> = targets.iter().map(|t| t.group.as_deref()).collect();
+        assert_eq!(
+            groups,
+            vec![
+                Some("transform"),
+                Some("transform"),
+                Some("entropy"),
+                Some("entropy")
+            ]
+        );
+
+        let toml = parse_toml_targets("[[target]]\nregex = 'x'\ngroup = \"entropy\"\n").unwrap();
+        assert_eq!(toml[0].group.as_deref(), Some("entropy"));
+    }
+
+    #[test]
+    fn test_merge_target_sections() {
+        let entries = vec![
+            PerfEntry {
+                children_pct: 38.0,
+                self_pct: 1.0,
+                symbol: "DCT4DBlock::DCT4DBlock(Block4D const&, double)".into(),
+                ..Default::default()
+            },
+            PerfEntry {
+                children_pct: 20.0,
+                self_pct: 5.0,
+                symbol: "Block4D::get(int) const".into(),
+                ..Default::default()
+            },
+            PerfEntry {
+                children_pct: 7.5,
+                self_pct: 7.0,
+                symbol: "double std::inner_product<double*>(double*)".into(),
+                ..Default::default()
+            },
+        ];
+        let mut dct = Target::new(TargetSpec::exact(
+            "DCT4DBlock::DCT4DBlock(Block4D const&, double)",
+        ));
+        dct.group = Some("transform".to_string());
+        let mut inner = Target::new(TargetSpec::regex("inner_product").unwrap());
+        inner.group = Some("transform".to_string());
+        let targets = vec![
+            dct,
+            Target::new(TargetSpec::regex("Block4D::get").unwrap()),
+            inner,
+        ];
+
+        let merged = merge_target_sections(&entries, &targets, false);
+        assert_eq!(merged.len(), 2);
+        assert_eq!(merged[0].symbol, "transform");
+        assert!((merged[0].children_pct - 45.5).abs() < 0.001);
+        assert!((merged[0].self_pct - 8.0).abs() < 0.001);
+        assert_eq!(merged[1].symbol, "Block4D::get(int) const");
+    }
+
+    #[test]
+    fn test_parse_target_file_missing_include() {
+        let path = std::path::Path::new(env!("CARGO_MANIFEST_DIR"))
+            .join("tests/fixtures/targets-include/broken.txt");
+        match parse_target_file(&path) {
+            Err(PperfError::FileNotFound(missing)) => assert!(missing.ends_with("missing.txt")),
+            other => panic!("Expected FileNotFound, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_parse_toml_targets() {
+        let content = r#"
+# Subsystem hot paths
+[[target]]
+signature = "DCT4DBlock::DCT4DBlock(Block4D const&, double)"
+alias = "dct"   # short name
+
+[[target]]
+regex = '^(double )?std::inner_product'
+notes = "Any \"inner product\" instantiation"
+"#;
+        let targets = parse_toml_targets(content).unwrap();
+        assert_eq!(targets.len(), 2);
+        assert_eq!(
+            targets[0].spec,
+            TargetSpec::exact("DCT4DBlock::DCT4DBlock(Block4D const&, double)")
+        );
+        assert_eq!(targets[0].alias.as_deref(), Some("dct"));
+        assert_eq!(targets[0].notes, None);
+        assert_eq!(
+            targets[1].spec.to_string(),
+            "re:^(double )?std::inner_product"
+        );
+        assert_eq!(
+            targets[1].notes.as_deref(),
+            Some("Any \"inner product\" instantiation")
+        );
+    }
+
+    #[test]
+    fn test_parse_toml_targets_errors() {
+        let err = parse_toml_targets("[[target]]\nalias = \"x\"\n").unwrap_err();
+        assert!(
+            err.contains("line 1: [[target]] needs exactly one"),
+            "{}",
+            err
+        );
+
+        let err = parse_toml_targets("signature = \"f\"\n").unwrap_err();
+        assert!(err.contains("outside a [[target]]"), "{}", err);
+
+        let err =
+            parse_toml_targets("[[target]]\nsignature = \"f\"\ncolor = \"red\"\n").unwrap_err();
+        assert_eq!(err, "line 3: unknown key color");
+
+        let err = parse_toml_targets("[[target]]\nsignature = \"f\n").unwrap_err();
+        assert_eq!(err, "line 2: unterminated string");
+    }
 }
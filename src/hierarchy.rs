@@ -3,30 +3,41 @@
 //! This module handles parsing perf report call trees and computing
 //! caller-callee relationships between target functions.
 
-use crate::parser::PerfEntry;
-use crate::symbol::simplify_symbol;
-use std::collections::HashSet;
+use crate::demangle::demangle;
+use crate::filter::{MatchOptions, is_target_group};
+use crate::parser::{ParseWarning, PerfEntry, TreeOrder};
+use crate::symbol::{
+    DemangleMode, Symbol, SymbolInterner, SymbolTransform, demangle_mode, jvm_symbol_name,
+    simplify_symbol,
+};
+use std::collections::{HashMap, HashSet};
 
 /// T001: Represents a single line from the perf report call tree section.
 #[derive(Debug, Clone, PartialEq)]
 pub struct CallTreeLine {
-    /// Call stack depth (0 = top-level entry, 1+ = call tree)
-    pub depth: usize,
+    /// Column the line hangs from (see `line_column`)
+    pub column: usize,
+    /// True for a marker-less line continuing the chain of the line above
+    pub is_continuation: bool,
     /// Percentage if present (from `--XX.XX%--` pattern)
     pub relative_pct: Option<f64>,
     /// Function name (simplified via symbol module)
-    pub symbol: String,
+    pub symbol: Symbol,
     /// True if this is a top-level perf entry with absolute %
     pub is_top_level: bool,
+    /// True for a frame perf marked `(inlined)` (`perf report --inline`)
+    pub inlined: bool,
 }
 
 /// T003: Hierarchical representation of a function and its callees.
 #[derive(Debug, Clone, PartialEq)]
 pub struct CallTreeNode {
     /// Simplified function name
-    pub symbol: String,
+    pub symbol: Symbol,
     /// Percentage relative to parent (0.0-100.0)
     pub relative_pct: f64,
+    /// True for a frame inlined into its caller (`--inline` reports)
+    pub inlined: bool,
     /// Direct callees in the call tree
     pub children: Vec<CallTreeNode>,
 }
@@ -36,7 +47,7 @@ pub struct CallTreeNode {
 #[derive(Debug, Clone, PartialEq)]
 pub struct IntermediaryStep {
     /// Simplified function name of the intermediary
-    pub symbol: String,
+    pub symbol: Symbol,
     /// Relative percentage at this step in the call chain
     pub percentage: f64,
 }
@@ -46,18 +57,53 @@ pub struct IntermediaryStep {
 #[derive(Debug, Clone, PartialEq)]
 pub struct CallerContribution {
     /// Simplified name of the calling target function
-    pub caller: String,
+    pub caller: Symbol,
     /// The contribution amount (absolute %) subtracted from original
     pub absolute_pct: f64,
+    /// Absolute % of each distinct call path from the caller, highest first
+    pub path_pcts: Vec<f64>,
+    /// How `path_pcts` were combined into `absolute_pct`
+    pub mode: ContributionMode,
+}
+
+/// How the distinct call paths from one caller to a callee combine into the amount
+/// subtracted from the callee's standalone percentage (`--contribution-mode`).
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum ContributionMode {
+    /// The heaviest path only; the other paths are assumed to overlap with it
+    #[default]
+    Max,
+    /// The sum of all paths, treating them as disjoint samples
+    Sum,
+}
+
+impl ContributionMode {
+    /// Parse a `max`/`sum` mode name.
+    pub fn from_setting(value: &str) -> Option<ContributionMode> {
+        match value.trim() {
+            "max" => Some(ContributionMode::Max),
+            "sum" => Some(ContributionMode::Sum),
+            _ => None,
+        }
+    }
+}
+
+impl std::fmt::Display for ContributionMode {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ContributionMode::Max => write!(f, "max"),
+            ContributionMode::Sum => write!(f, "sum"),
+        }
+    }
 }
 
 /// T004: Represents a caller→callee relationship between two target functions.
 #[derive(Debug, Clone, PartialEq)]
 pub struct CallRelation {
     /// Caller target function (simplified name)
-    pub caller: String,
+    pub caller: Symbol,
     /// Callee target function (simplified name)
-    pub callee: String,
+    pub callee: Symbol,
     /// Callee's contribution as % of caller's time (in context if context_root is set)
     pub relative_pct: f64,
     /// Absolute contribution: root.children_pct × path_product / 100
@@ -65,7 +111,7 @@ pub struct CallRelation {
     /// If this relation was found in another caller's tree, store that root caller.
     /// None = this is from the caller's own tree (overall relationship)
     /// Some(root) = this is path-specific, found when traversing root's tree
-    pub context_root: Option<String>,
+    pub context_root: Option<Symbol>,
     /// Ordered list of non-target functions traversed between caller and callee.
     /// Empty if this is a direct call (no intermediaries).
     pub intermediary_path: Vec<IntermediaryStep>,
@@ -75,7 +121,7 @@ pub struct CallRelation {
 #[derive(Debug, Clone, PartialEq)]
 pub struct HierarchyEntry {
     /// Simplified function name
-    pub symbol: String,
+    pub symbol: Symbol,
     /// Original Children% from perf report
     pub original_children_pct: f64,
     /// Original Self% from perf report
@@ -94,27 +140,25 @@ pub struct HierarchyEntry {
 // Phase 2: Call Tree Parsing Functions
 // ============================================================================
 
-/// T013: Count the depth of a call tree line based on column position.
-/// In perf report, each nesting level adds approximately 11 characters of indentation.
-/// We find the position of the `--XX.XX%--` or `---` pattern and divide by 11.
-pub fn count_depth(line: &str) -> usize {
-    // Find the position of the percentage pattern (--XX.XX%--)
+/// T013: Column a call tree line hangs from, and whether it is a continuation.
+/// Marker lines hang from the `|` of `|--XX%--`, the blank before a last-child
+/// ` --XX%--`, or the first `-` of `---`; their children's markers sit further
+/// right, however wide the perf version's indentation is. A continuation line (a
+/// name with no marker) is the next frame of a single-path chain and hangs from
+/// the column its name starts at. `None` for lines with no name.
+pub fn line_column(line: &str) -> Option<(usize, bool)> {
     if let Some(pct_end) = line.find("%--") {
         // Search backwards from %-- to find the leading --
-        let before = &line[..pct_end];
-        if let Some(dash_pos) = before.rfind("--") {
-            // Each tree level is approximately 11 characters wide
-            return (dash_pos / 11) + 1;
-        }
+        let dash_pos = line[..pct_end].rfind("--")?;
+        return Some((dash_pos.saturating_sub(1), false));
     }
 
-    // Fallback: look for --- pattern (function without percentage)
     if let Some(pos) = line.find("---") {
-        return (pos / 11) + 1;
+        return Some((pos, false));
     }
 
-    // Final fallback: count pipes (for lines that don't match above patterns)
-    line.chars().filter(|&c| c == '|').count()
+    let symbol = line.find(|c: char| c != '|' && c != ' ')?;
+    Some((symbol, true))
 }
 
 /// T014: Extract percentage from `--XX.XX%--` pattern.
@@ -129,12 +173,30 @@ pub fn extract_percentage(line: &str) -> Option<f64> {
         let search_region = &line[..end_pos];
         if let Some(start_pos) = search_region.rfind(start_marker) {
             let pct_str = &line[start_pos + 2..end_pos];
-            return pct_str.trim().parse().ok();
+            return crate::parser::parse_percentage(pct_str);
         }
     }
     None
 }
 
+/// Suffix `perf report --inline` gives frames inlined into their caller.
+const INLINED_SUFFIX: &str = " (inlined)";
+
+/// Whether a call tree line names an `(inlined)` frame.
+fn is_inlined_line(line: &str) -> bool {
+    line.trim_end().ends_with(INLINED_SUFFIX)
+}
+
+/// Simplified name of a call-chain symbol, demangled first if perf left it mangled
+/// (unless `--demangle none`). The `(inlined)` marker is dropped.
+fn chain_symbol(symbol: &str) -> String {
+    let symbol = symbol.strip_suffix(INLINED_SUFFIX).unwrap_or(symbol);
+    match demangle(symbol).or_else(|| jvm_symbol_name(symbol)) {
+        Some(demangled) if demangle_mode() != DemangleMode::None => simplify_symbol(&demangled),
+        _ => simplify_symbol(symbol),
+    }
+}
+
 /// T015: Extract the function symbol from a call tree line.
 pub fn extract_symbol(line: &str) -> Option<String> {
     // If line contains percentage pattern, extract symbol after it
@@ -142,7 +204,7 @@ pub fn extract_symbol(line: &str) -> Option<String> {
         let after_pct = &line[end_pos + 3..];
         let symbol = after_pct.trim();
         if !symbol.is_empty() {
-            return Some(simplify_symbol(symbol));
+            return Some(chain_symbol(symbol));
         }
     }
 
@@ -161,7 +223,7 @@ pub fn extract_symbol(line: &str) -> Option<String> {
         let after = &trimmed[pos + 3..];
         let symbol = after.trim();
         if !symbol.is_empty() {
-            return Some(simplify_symbol(symbol));
+            return Some(chain_symbol(symbol));
         }
     }
 
@@ -172,7 +234,7 @@ pub fn extract_symbol(line: &str) -> Option<String> {
         .collect();
     let content = content.trim();
     if !content.is_empty() && !content.starts_with('-') {
-        return Some(simplify_symbol(content));
+        return Some(chain_symbol(content));
     }
 
     None
@@ -180,6 +242,15 @@ pub fn extract_symbol(line: &str) -> Option<String> {
 
 /// T016: Parse a single call tree line into a CallTreeLine struct.
 pub fn parse_call_tree_line(line: &str) -> Option<CallTreeLine> {
+    parse_call_tree_line_interned(line, &mut SymbolInterner::default())
+}
+
+/// `parse_call_tree_line` taking its symbol from `interner`, so the lines of a whole
+/// report share one allocation per distinct name.
+pub fn parse_call_tree_line_interned(
+    line: &str,
+    interner: &mut SymbolInterner,
+) -> Option<CallTreeLine> {
     let trimmed = line.trim_start();
 
     // Skip empty lines and comments
@@ -194,92 +265,72 @@ pub fn parse_call_tree_line(line: &str) -> Option<CallTreeLine> {
         return None;
     }
 
-    // Must be a call tree line (contains | or starts with ---)
-    if !trimmed.starts_with('|') && !trimmed.starts_with('-') {
-        // Could be a function continuation line
-        if line.contains('|') {
-            // Parse as call tree line
-        } else {
-            return None;
-        }
+    // Must be a call tree line (contains | or starts with ---), or an indented
+    // continuation line, which has no `|` once the chain is the last branch
+    if !trimmed.starts_with('|')
+        && !trimmed.starts_with('-')
+        && !line.contains('|')
+        && !line.starts_with(' ')
+    {
+        return None;
     }
 
-    let depth = count_depth(line);
+    let (column, is_continuation) = line_column(line)?;
     let relative_pct = extract_percentage(line);
-    let symbol = extract_symbol(line)?;
+    let symbol = interner.intern(&extract_symbol(line)?);
 
     Some(CallTreeLine {
-        depth,
+        column,
+        is_continuation,
         relative_pct,
         symbol,
         is_top_level: false,
+        inlined: is_inlined_line(line),
     })
 }
 
-/// T017: Build a call tree from a list of CallTreeLine entries.
+/// T017: Build a call tree from a list of CallTreeLine entries. A line's parent is
+/// the most recent open node hanging from a column left of it, so any indentation
+/// width works. Continuation frames take their chain head's column: they are children
+/// of the frame above, and branches below the chain attach to its last frame.
+/// Lines left of every open node start a new root.
 pub fn build_call_tree(lines: &[CallTreeLine]) -> Vec<CallTreeNode> {
-    if lines.is_empty() {
-        return Vec::new();
+    fn close(stack: &mut Vec<(usize, CallTreeNode)>, roots: &mut Vec<CallTreeNode>) {
+        let (_, child) = stack.pop().unwrap();
+        match stack.last_mut() {
+            Some((_, parent)) => parent.children.push(child),
+            None => roots.push(child),
+        }
     }
 
     let mut roots: Vec<CallTreeNode> = Vec::new();
-    let mut stack: Vec<(usize, CallTreeNode)> = Vec::new(); // (depth, node)
+    let mut stack: Vec<(usize, CallTreeNode)> = Vec::new(); // (column, node)
 
     for line in lines {
-        let node = CallTreeNode {
-            symbol: line.symbol.clone(),
-            relative_pct: line.relative_pct.unwrap_or(100.0),
-            children: Vec::new(),
-        };
-
-        if stack.is_empty() {
-            // First node becomes root
-            stack.push((line.depth, node));
-        } else {
-            let current_depth = line.depth;
-            let (last_depth, _) = stack.last().unwrap();
-
-            if current_depth > *last_depth {
-                // Deeper: this is a child of the previous node
-                stack.push((current_depth, node));
-            } else {
-                // Same or shallower: pop until we find the parent level
-                while stack.len() > 1 {
-                    let (d, _) = stack.last().unwrap();
-                    if *d < current_depth {
-                        break;
-                    }
-                    let (_, child) = stack.pop().unwrap();
-                    if let Some((_, parent)) = stack.last_mut() {
-                        parent.children.push(child);
-                    } else {
-                        roots.push(child);
-                    }
-                }
-
-                if current_depth == 0 || stack.is_empty() {
-                    // This is a new root
-                    if !stack.is_empty() {
-                        let (_, child) = stack.pop().unwrap();
-                        roots.push(child);
-                    }
-                    stack.push((current_depth, node));
-                } else {
-                    stack.push((current_depth, node));
-                }
-            }
+        while stack
+            .last()
+            .is_some_and(|(column, _)| *column >= line.column)
+        {
+            close(&mut stack, &mut roots);
         }
+        let column = match stack.last() {
+            Some((head_column, _)) if line.is_continuation => *head_column,
+            _ => line.column,
+        };
+        stack.push((
+            column,
+            CallTreeNode {
+                symbol: line.symbol.clone(),
+                relative_pct: line.relative_pct.unwrap_or(100.0),
+                inlined: line.inlined,
+                children: Vec::new(),
+            },
+        ));
     }
 
-    // Flush remaining stack
-    while let Some((_, child)) = stack.pop() {
-        if let Some((_, parent)) = stack.last_mut() {
-            parent.children.push(child);
-        } else {
-            roots.push(child);
-        }
+    while !stack.is_empty() {
+        close(&mut stack, &mut roots);
     }
-
     roots
 }
 
@@ -289,68 +340,322 @@ pub fn parse_file_call_trees(
     content: &str,
     _entries: &[PerfEntry],
 ) -> Vec<(PerfEntry, Vec<CallTreeNode>)> {
-    let mut result: Vec<(PerfEntry, Vec<CallTreeNode>)> = Vec::new();
-    let lines: Vec<&str> = content.lines().collect();
+    parse_file_call_trees_with_warnings(content, &mut Vec::new())
+}
+
+/// Like `parse_file_call_trees`, but records malformed call-graph lines in `warnings`.
+pub fn parse_file_call_trees_with_warnings(
+    content: &str,
+    warnings: &mut Vec<ParseWarning>,
+) -> Vec<(PerfEntry, Vec<CallTreeNode>)> {
+    let columns = crate::parser::parse_header_columns(content);
+    let mut collector = CallTreeCollector::default();
+    for (index, line) in content.lines().enumerate() {
+        let entry = crate::parser::parse_entry_line(line, columns.as_ref());
+        collector.push_line(index, line, entry.as_ref(), warnings);
+    }
+    collector.finish()
+}
 
-    let mut current_entry: Option<PerfEntry> = None;
-    let mut current_tree_lines: Vec<CallTreeLine> = Vec::new();
+/// Builds call trees one report line at a time, so `parse_report_content` can
+/// collect them in the same pass as the entries.
+#[derive(Debug, Default)]
+pub struct CallTreeCollector {
+    trees: Vec<(PerfEntry, Vec<CallTreeNode>)>,
+    current_entry: Option<PerfEntry>,
+    current_tree_lines: Vec<CallTreeLine>,
+    orphan_reported: bool,
+    interner: SymbolInterner,
+}
 
-    for line in &lines {
+impl CallTreeCollector {
+    /// Feed the line at 0-based `index`; `entry` is the line parsed as a top-level
+    /// entry, if it parses as one.
+    pub fn push_line(
+        &mut self,
+        index: usize,
+        line: &str,
+        entry: Option<&PerfEntry>,
+        warnings: &mut Vec<ParseWarning>,
+    ) {
         let trimmed = line.trim_start();
 
         // Skip empty lines and comments
         if trimmed.is_empty() || trimmed.starts_with('#') {
-            continue;
+            return;
         }
 
-        // Check if this is a top-level entry
-        if trimmed.chars().next().is_some_and(|c| c.is_ascii_digit()) {
+        // Check if this is a top-level entry (bare hex addresses in call chains also
+        // start with a digit, but carry no `%`)
+        if trimmed.chars().next().is_some_and(|c| c.is_ascii_digit()) && trimmed.contains('%') {
             // Finalize previous entry if any
-            if let Some(entry) = current_entry.take() {
-                let tree = build_call_tree(&current_tree_lines);
-                result.push((entry, tree));
-                current_tree_lines.clear();
+            self.finish_entry();
+            // Lines under an unparseable entry must not leak into the next one
+            self.current_tree_lines.clear();
+            self.orphan_reported = false;
+
+            // Simplify the symbol of the new top-level entry
+            self.current_entry = entry.map(|parsed| PerfEntry {
+                symbol: self.interner.intern(&simplify_symbol(&parsed.symbol)),
+                ..parsed.clone()
+            });
+        } else if let Some(tree_line) = parse_call_tree_line_interned(line, &mut self.interner) {
+            if line.contains("%--") && tree_line.relative_pct.is_none() {
+                warnings.push(ParseWarning {
+                    line: index + 1,
+                    reason: "malformed call-graph percentage".to_string(),
+                });
+            }
+            if self.current_entry.is_none() && !self.orphan_reported {
+                warnings.push(ParseWarning {
+                    line: index + 1,
+                    reason: "call-graph lines without a parsed entry".to_string(),
+                });
+                self.orphan_reported = true;
             }
+            self.current_tree_lines.push(tree_line);
+        }
+    }
+
+    fn finish_entry(&mut self) {
+        if let Some(entry) = self.current_entry.take() {
+            let tree = build_call_tree(&self.current_tree_lines);
+            self.trees.push((entry, tree));
+        }
+    }
+
+    /// The (top-level entry, call tree) pairs, in report order.
+    pub fn finish(mut self) -> Vec<(PerfEntry, Vec<CallTreeNode>)> {
+        self.finish_entry();
+        self.trees
+    }
+}
+
+/// Rename every tree node and top-level entry matched by an OR-group target to the
+/// group itself, so the group behaves as one logical function during traversal.
+pub fn relabel_target_groups(
+    trees: &mut [(PerfEntry, Vec<CallTreeNode>)],
+    targets: &[String],
+    options: &MatchOptions,
+) {
+    let groups: Vec<Symbol> = targets
+        .iter()
+        .filter(|t| is_target_group(t))
+        .map(Symbol::from)
+        .collect();
+    if groups.is_empty() {
+        return;
+    }
+
+    fn relabel_node(node: &mut CallTreeNode, groups: &[Symbol], options: &MatchOptions) {
+        if let Some(group) = groups.iter().find(|g| options.matches(&node.symbol, g)) {
+            node.symbol = group.clone();
+        }
+        for child in &mut node.children {
+            relabel_node(child, groups, options);
+        }
+    }
+
+    for (entry, roots) in trees.iter_mut() {
+        if let Some(group) = groups.iter().find(|g| options.matches(&entry.symbol, g)) {
+            entry.symbol = group.clone();
+        }
+        for root in roots {
+            relabel_node(root, &groups, options);
+        }
+    }
+}
 
-            // Parse this as a new top-level entry
-            if let Some(parsed) = crate::parser::parse_line(line) {
-                // Simplify the symbol
-                let simplified = PerfEntry {
-                    children_pct: parsed.children_pct,
-                    self_pct: parsed.self_pct,
-                    symbol: simplify_symbol(&parsed.symbol),
-                };
-                current_entry = Some(simplified);
+/// Rewrite call-graph percentages of a `PercentType::Absolute` report (shares of all
+/// samples) into shares of each node's parent, which the relation math expects.
+/// Top-level nodes are measured against their entry's Children%.
+pub fn convert_absolute_percentages(trees: &mut [(PerfEntry, Vec<CallTreeNode>)]) {
+    fn share(part: f64, whole: f64) -> f64 {
+        if whole > 0.0 {
+            (part / whole * 100.0).min(100.0)
+        } else {
+            0.0
+        }
+    }
+
+    fn convert_children(node: &mut CallTreeNode, node_absolute: f64) {
+        for child in &mut node.children {
+            let child_absolute = child.relative_pct;
+            child.relative_pct = share(child_absolute, node_absolute);
+            convert_children(child, child_absolute);
+        }
+    }
+
+    for (entry, roots) in trees.iter_mut() {
+        for root in roots {
+            // The `---` line carries no percentage and stands for the entry itself
+            let root_absolute = root.relative_pct.min(entry.children_pct);
+            root.relative_pct = share(root_absolute, entry.children_pct);
+            convert_children(root, root_absolute);
+        }
+    }
+}
+
+/// `--collapse-inlined`: fold every `(inlined)` frame into the frame above it, so
+/// the inlined function's callees become its parent's, scaled by the inlined frame's
+/// share. A callee the parent also reaches directly is merged into one node instead
+/// of being split across both paths. Roots stand for their entry and are kept.
+pub fn collapse_inlined_frames(trees: &mut [(PerfEntry, Vec<CallTreeNode>)]) {
+    fn collapse(node: &mut CallTreeNode) {
+        let children = std::mem::take(&mut node.children);
+        for mut child in children {
+            collapse(&mut child);
+            if child.inlined {
+                let share = child.relative_pct / 100.0;
+                for mut grandchild in child.children {
+                    grandchild.relative_pct *= share;
+                    add_child(&mut node.children, grandchild);
+                }
+            } else {
+                add_child(&mut node.children, child);
             }
-        } else if let Some(tree_line) = parse_call_tree_line(line) {
-            current_tree_lines.push(tree_line);
         }
     }
 
-    // Finalize last entry
-    if let Some(entry) = current_entry {
-        let tree = build_call_tree(&current_tree_lines);
-        result.push((entry, tree));
+    // Merge `child` into a sibling with the same name, re-weighting both nodes'
+    // children by their shares of the combined node.
+    fn add_child(siblings: &mut Vec<CallTreeNode>, mut child: CallTreeNode) {
+        let Some(existing) = siblings.iter_mut().find(|s| s.symbol == child.symbol) else {
+            siblings.push(child);
+            return;
+        };
+        let total = existing.relative_pct + child.relative_pct;
+        if total <= 0.0 {
+            return;
+        }
+        let (existing_share, child_share) =
+            (existing.relative_pct / total, child.relative_pct / total);
+        existing.relative_pct = total.min(100.0);
+        let children = std::mem::take(&mut existing.children);
+        for mut grandchild in children {
+            grandchild.relative_pct *= existing_share;
+            add_child(&mut existing.children, grandchild);
+        }
+        for mut grandchild in std::mem::take(&mut child.children) {
+            grandchild.relative_pct *= child_share;
+            add_child(&mut existing.children, grandchild);
+        }
     }
 
-    result
+    for (_, roots) in trees.iter_mut() {
+        for root in roots {
+            collapse(root);
+        }
+    }
+}
+
+/// Add every symbol in the trees (entries and nodes) to `symbols`.
+pub fn collect_tree_symbols(
+    trees: &[(PerfEntry, Vec<CallTreeNode>)],
+    symbols: &mut HashSet<Symbol>,
+) {
+    fn collect_node(node: &CallTreeNode, symbols: &mut HashSet<Symbol>) {
+        symbols.insert(node.symbol.clone());
+        for child in &node.children {
+            collect_node(child, symbols);
+        }
+    }
+
+    for (entry, roots) in trees {
+        symbols.insert(entry.symbol.clone());
+        for root in roots {
+            collect_node(root, symbols);
+        }
+    }
+}
+
+/// Apply a user symbol transform to every tree entry and node.
+pub fn transform_tree_symbols(
+    trees: &mut [(PerfEntry, Vec<CallTreeNode>)],
+    transform: &dyn SymbolTransform,
+) {
+    fn transform_node(
+        node: &mut CallTreeNode,
+        transform: &dyn SymbolTransform,
+        interner: &mut SymbolInterner,
+    ) {
+        node.symbol = interner.intern(&transform.transform(&node.symbol));
+        for child in &mut node.children {
+            transform_node(child, transform, interner);
+        }
+    }
+
+    let mut interner = SymbolInterner::default();
+    for (entry, roots) in trees.iter_mut() {
+        entry.symbol = interner.intern(&transform.transform(&entry.symbol));
+        for root in roots {
+            transform_node(root, transform, &mut interner);
+        }
+    }
 }
 
 // ============================================================================
 // Phase 3: Target Relationship Discovery
 // ============================================================================
 
-/// T027: Check if a target exists in the call tree.
-pub fn find_target_in_tree(tree: &CallTreeNode, target: &str) -> bool {
-    if tree.symbol.contains(target) {
-        return true;
-    }
-    for child in &tree.children {
-        if find_target_in_tree(child, target) {
-            return true;
+/// Symbol lookups over one report's call trees, built in a single pass so target
+/// matching runs once per distinct symbol rather than once per node and target.
+#[derive(Debug, Default)]
+pub struct CallTreeIndex {
+    /// Entry and node symbols that match a target and no exclusion
+    targets: HashSet<Symbol>,
+    /// Positions of the trees with at least one target node
+    trees_with_targets: HashSet<usize>,
+}
+
+impl CallTreeIndex {
+    pub fn build(
+        trees: &[(PerfEntry, Vec<CallTreeNode>)],
+        targets: &[String],
+        options: &MatchOptions,
+    ) -> CallTreeIndex {
+        fn add<'a>(
+            trees_by_symbol: &mut HashMap<&'a str, Vec<usize>>,
+            tree: usize,
+            node: &'a CallTreeNode,
+        ) {
+            trees_by_symbol.entry(&node.symbol).or_default().push(tree);
+            for child in &node.children {
+                add(trees_by_symbol, tree, child);
+            }
         }
+
+        // Every node symbol, with the positions of the trees holding it
+        let mut trees_by_symbol: HashMap<&str, Vec<usize>> = HashMap::new();
+        for (tree, (_, roots)) in trees.iter().enumerate() {
+            for root in roots {
+                add(&mut trees_by_symbol, tree, root);
+            }
+        }
+        let mut index = CallTreeIndex::default();
+        for symbol in trees.iter().map(|(entry, _)| &entry.symbol) {
+            if options.is_target(symbol, targets) {
+                index.targets.insert(symbol.clone());
+            }
+        }
+        for (symbol, positions) in trees_by_symbol {
+            if options.is_target(symbol, targets) {
+                index.targets.insert(symbol.into());
+                index.trees_with_targets.extend(positions);
+            }
+        }
+        index
+    }
+
+    /// True if `symbol` (an entry signature or a node name) is a target.
+    pub fn is_target(&self, symbol: &str) -> bool {
+        self.targets.contains(symbol)
+    }
+
+    /// True if the tree at `tree` has a target node anywhere below its entry.
+    pub fn tree_has_targets(&self, tree: usize) -> bool {
+        self.trees_with_targets.contains(&tree)
     }
-    false
 }
 
 /// T028-T029: Find target callees under a caller, with context tracking.
@@ -359,7 +664,7 @@ pub fn find_target_in_tree(tree: &CallTreeNode, target: &str) -> bool {
 ///
 /// Parameters:
 /// - node: Current node in the call tree
-/// - targets: List of target function patterns
+/// - index: The report's `CallTreeIndex`, which decides what is a target
 /// - root_caller: The root caller we're traversing from (for context_root)
 /// - root_children_pct: Root caller's Children% (for absolute calculations)
 /// - target_stack: Stack of (target_symbol, cumulative_pct_to_target) for intermediate targets
@@ -367,17 +672,22 @@ pub fn find_target_in_tree(tree: &CallTreeNode, target: &str) -> bool {
 /// - seen: Set of targets already recorded (prevents duplicate recording)
 /// - inside_root_recursion: True if path only contains root caller recursive calls (no other intermediates)
 /// - current_path: Accumulator for non-target intermediary functions traversed
+/// - depth: Call-tree levels between root caller and `node` (0 at the root)
+/// - options: `max_depth` skips children more than that many levels below the root caller;
+///   excluded symbols are not targets in `index` and are traversed as intermediaries
 #[allow(clippy::too_many_arguments)]
 pub fn find_target_callees(
     node: &CallTreeNode,
-    targets: &[String],
-    root_caller: &str,
+    index: &CallTreeIndex,
+    options: &MatchOptions,
+    root_caller: &Symbol,
     root_children_pct: f64,
-    target_stack: &mut Vec<(String, f64)>,
+    target_stack: &mut Vec<(Symbol, f64)>,
     cumulative_pct: f64,
-    seen: &mut HashSet<String>,
+    seen: &mut HashSet<Symbol>,
     inside_root_recursion: bool,
     current_path: &mut Vec<IntermediaryStep>,
+    depth: usize,
 ) -> Vec<CallRelation> {
     let mut relations = Vec::new();
     let root_caller_simplified = simplify_symbol(root_caller);
@@ -388,6 +698,13 @@ pub fn find_target_callees(
         // Check if this child is a recursive call of the root caller
         let is_root_recursion = child.symbol == root_caller_simplified;
 
+        // Root-caller recursion does not count as a level, so a recursive caller's
+        // direct callees stay within --max-depth 1
+        let child_depth = if is_root_recursion { depth } else { depth + 1 };
+        if options.max_depth.is_some_and(|max| child_depth > max) {
+            continue;
+        }
+
         // Track whether we're inside root-caller recursion
         // If we encounter root caller again, restore to inside=true
         // This handles: rd_optimize -> eval -> rd_optimize -> DCT4DBlock
@@ -407,7 +724,7 @@ pub fn find_target_callees(
         };
 
         // Check if this child matches any target
-        let is_target = targets.iter().any(|t| child.symbol.contains(t));
+        let is_target = index.is_target(&child.symbol);
 
         if is_target {
             // Check for recursion - if already seen, skip recording but continue traversing
@@ -417,7 +734,8 @@ pub fn find_target_callees(
                 let mut fresh_path = Vec::new();
                 let deeper = find_target_callees(
                     child,
-                    targets,
+                    index,
+                    options,
                     root_caller,
                     root_children_pct,
                     target_stack,
@@ -425,6 +743,7 @@ pub fn find_target_callees(
                     seen,
                     still_inside_root_recursion,
                     &mut fresh_path,
+                    child_depth,
                 );
                 relations.extend(deeper);
             } else {
@@ -441,7 +760,7 @@ pub fn find_target_callees(
                         new_cumulative // Path through other intermediates: use cumulative
                     };
                     let relation = CallRelation {
-                        caller: root_caller.to_string(),
+                        caller: root_caller.clone(),
                         callee: child.symbol.clone(),
                         relative_pct: effective_pct,
                         absolute_pct: root_children_pct * effective_pct / 100.0,
@@ -464,7 +783,7 @@ pub fn find_target_callees(
                         callee: child.symbol.clone(),
                         relative_pct: relative_to_caller,
                         absolute_pct: root_children_pct * new_cumulative / 100.0,
-                        context_root: Some(root_caller.to_string()),
+                        context_root: Some(root_caller.clone()),
                         intermediary_path: current_path.clone(), // T011: Include accumulated path
                     };
                     relations.push(relation);
@@ -481,7 +800,8 @@ pub fn find_target_callees(
                 let mut fresh_path = Vec::new();
                 let deeper = find_target_callees(
                     child,
-                    targets,
+                    index,
+                    options,
                     root_caller,
                     root_children_pct,
                     target_stack,
@@ -489,9 +809,15 @@ pub fn find_target_callees(
                     seen,
                     true, // Reset: entering target's own subtree
                     &mut fresh_path,
+                    child_depth,
                 );
                 relations.extend(deeper);
                 target_stack.pop();
+                // Summed contributions need every sibling path, so the callee only
+                // stays seen within its own subtree
+                if options.contribution_mode == ContributionMode::Sum {
+                    seen.remove(&child.symbol);
+                }
             }
         } else {
             // Not a target, continue traversing
@@ -507,7 +833,8 @@ pub fn find_target_callees(
             // Pass still_inside_root_recursion - becomes false if we went through non-root intermediate
             let deeper = find_target_callees(
                 child,
-                targets,
+                index,
+                options,
                 root_caller,
                 root_children_pct,
                 target_stack,
@@ -515,6 +842,7 @@ pub fn find_target_callees(
                 seen,
                 still_inside_root_recursion,
                 current_path,
+                child_depth,
             );
             relations.extend(deeper);
 
@@ -538,21 +866,94 @@ fn is_leaf_function(entry: &PerfEntry) -> bool {
     diff < 1.0 || entry.self_pct > entry.children_pct * 0.5
 }
 
+/// Whether an entry's call tree lists callers rather than callees: every tree does in
+/// a callee-ordered report and none in a caller-ordered one. Without a known order
+/// `--assume-callee-trees` reads every tree as listing callees, and only otherwise is
+/// the `is_leaf_function` guess taken (see `guessed_caller_entries`).
+pub fn shows_callers(
+    entry: &PerfEntry,
+    tree_order: Option<TreeOrder>,
+    options: &MatchOptions,
+) -> bool {
+    match tree_order {
+        Some(TreeOrder::Callee) => true,
+        Some(TreeOrder::Caller) => false,
+        None => !options.assume_callee_trees && is_leaf_function(entry),
+    }
+}
+
+/// Simplified names of the `entries` whose call trees `shows_callers` reads as caller
+/// lists on the `is_leaf_function` guess alone, for warning that the guess was used.
+pub fn guessed_caller_entries<'a>(
+    entries: impl IntoIterator<Item = &'a PerfEntry>,
+    tree_order: Option<TreeOrder>,
+    options: &MatchOptions,
+) -> Vec<String> {
+    let mut symbols: Vec<String> = Vec::new();
+    if tree_order.is_some() {
+        return symbols;
+    }
+    for entry in entries {
+        if shows_callers(entry, None, options) {
+            let symbol = simplify_symbol(&entry.symbol);
+            if !symbols.contains(&symbol) {
+                symbols.push(symbol);
+            }
+        }
+    }
+    symbols
+}
+
+/// Simplified names of the target entries whose call trees `compute_call_relations_with`
+/// skips on the `is_leaf_function` guess alone, for warning that the guess was used.
+pub fn guessed_caller_trees(
+    trees: &[(PerfEntry, Vec<CallTreeNode>)],
+    targets: &[String],
+    options: &MatchOptions,
+) -> Vec<String> {
+    guessed_caller_entries(
+        trees
+            .iter()
+            .map(|(entry, _)| entry)
+            .filter(|entry| options.is_target(&entry.symbol, targets)),
+        None,
+        options,
+    )
+}
+
+/// True if any node of a call tree is named `symbol` (a simplified name).
+pub fn tree_contains(roots: &[CallTreeNode], symbol: &str) -> bool {
+    roots
+        .iter()
+        .any(|root| root.symbol == symbol || tree_contains(&root.children, symbol))
+}
+
 /// T030: Compute all call relations between targets.
 /// Now returns both direct relations and context-specific nested relations.
 pub fn compute_call_relations(
     trees: &[(PerfEntry, Vec<CallTreeNode>)],
     targets: &[String],
+) -> Vec<CallRelation> {
+    compute_call_relations_with(trees, targets, &MatchOptions::default())
+}
+
+/// `compute_call_relations` honoring `--exclude` and other match options.
+pub fn compute_call_relations_with(
+    trees: &[(PerfEntry, Vec<CallTreeNode>)],
+    targets: &[String],
+    options: &MatchOptions,
 ) -> Vec<CallRelation> {
     let mut all_relations = Vec::new();
+    let index = CallTreeIndex::build(trees, targets, options);
 
-    for (entry, tree_roots) in trees {
+    for (position, (entry, tree_roots)) in trees.iter().enumerate() {
         // Check if this entry is a target
-        let is_target = targets.iter().any(|t| entry.symbol.contains(t));
+        let is_target = index.is_target(&entry.symbol);
 
         if is_target {
             // Skip leaf functions - their call tree shows callers, not callees
-            if is_leaf_function(entry) {
+            // Trees without target nodes cannot yield relations
+            if shows_callers(entry, None, options) || !index.tree_has_targets(position) {
                 continue;
             }
 
@@ -565,7 +966,8 @@ pub fn compute_call_relations(
 
                 let relations = find_target_callees(
                     root,
-                    targets,
+                    &index,
+                    options,
                     &entry.symbol,
                     entry.children_pct,
                     &mut target_stack,
@@ -573,6 +975,7 @@ pub fn compute_call_relations(
                     &mut seen,
                     true, // Start inside root caller's "recursion zone"
                     &mut current_path,
+                    0,
                 );
                 all_relations.extend(relations);
             }
@@ -582,6 +985,448 @@ pub fn compute_call_relations(
     all_relations
 }
 
+/// `compute_call_relations_with` for callee-ordered trees (`-g ...,callee`), where a
+/// node's children are the functions calling it. Each target's own tree is walked
+/// outward and the first other target on every chain is taken as its caller; chains
+/// through the same caller are summed. Relations are overall (no `context_root`) and
+/// their intermediary paths are reversed into caller→callee order.
+pub fn compute_callee_order_relations(
+    trees: &[(PerfEntry, Vec<CallTreeNode>)],
+    targets: &[String],
+    options: &MatchOptions,
+) -> Vec<CallRelation> {
+    struct Walk<'a> {
+        callee: &'a str,
+        index: &'a CallTreeIndex,
+        options: &'a MatchOptions,
+        // (caller, summed path %, heaviest chain %, that chain's path from the callee outward)
+        found: Vec<(Symbol, f64, f64, Vec<IntermediaryStep>)>,
+    }
+
+    fn collect(
+        walk: &mut Walk,
+        node: &CallTreeNode,
+        parent_pct: f64,
+        depth: usize,
+        path: &mut Vec<IntermediaryStep>,
+    ) {
+        let pct = parent_pct * node.relative_pct / 100.0;
+        // Recursion of the callee itself is not a level and not a caller
+        if node.symbol == walk.callee {
+            for child in &node.children {
+                collect(walk, child, pct, depth, path);
+            }
+            return;
+        }
+        let depth = depth + 1;
+        if walk.options.max_depth.is_some_and(|max| depth > max) {
+            return;
+        }
+        if walk.index.is_target(&node.symbol) {
+            match walk
+                .found
+                .iter_mut()
+                .find(|(caller, ..)| *caller == node.symbol)
+            {
+                Some((_, total, heaviest, best_path)) => {
+                    *total += pct;
+                    if pct > *heaviest {
+                        *heaviest = pct;
+                        *best_path = path.clone();
+                    }
+                }
+                None => walk
+                    .found
+                    .push((node.symbol.clone(), pct, pct, path.clone())),
+            }
+            return;
+        }
+        path.push(IntermediaryStep {
+            symbol: node.symbol.clone(),
+            percentage: node.relative_pct,
+        });
+        for child in &node.children {
+            collect(walk, child, pct, depth, path);
+        }
+        path.pop();
+    }
+
+    let caller_children = |caller: &str| {
+        trees
+            .iter()
+            .filter(|(entry, _)| simplify_symbol(&entry.symbol) == caller)
+            .map(|(entry, _)| entry.children_pct)
+            .fold(0.0, f64::max)
+    };
+
+    let index = CallTreeIndex::build(trees, targets, options);
+    let mut relations = Vec::new();
+    for (position, (entry, tree_roots)) in trees.iter().enumerate() {
+        if !index.is_target(&entry.symbol) || !index.tree_has_targets(position) {
+            continue;
+        }
+        let callee = Symbol::from(simplify_symbol(&entry.symbol));
+        let mut walk = Walk {
+            callee: &callee,
+            index: &index,
+            options,
+            found: Vec::new(),
+        };
+        for root in tree_roots {
+            collect(&mut walk, root, 100.0, 0, &mut Vec::new());
+        }
+
+        for (caller, path_pct, _, mut path) in walk.found {
+            let absolute_pct = entry.children_pct * path_pct / 100.0;
+            let children = caller_children(&caller);
+            let relative_pct = if children > 0.0 {
+                absolute_pct / children * 100.0
+            } else {
+                0.0
+            };
+            path.reverse();
+            relations.push(CallRelation {
+                caller,
+                callee: callee.clone(),
+                relative_pct,
+                absolute_pct,
+                context_root: None,
+                intermediary_path: path,
+            });
+        }
+    }
+
+    relations
+}
+
+/// One direct caller of a function, found by the bottom-up `--callers` view.
+#[derive(Debug, Clone, PartialEq)]
+pub struct CallerRelation {
+    /// Simplified name of the node directly above the function in a call tree
+    pub caller: Symbol,
+    /// Time spent in the function when called from `caller`, as % of total runtime
+    pub absolute_pct: f64,
+}
+
+/// Find every direct caller of `symbol` (a simplified name) by walking all entries'
+/// call trees, not only the trees rooted at targets. Contributions are summed per
+/// caller within one tree; because a tree repeats the paths of every tree below it,
+/// the largest per-tree sum is kept instead of adding trees together. The function's
+/// own subtree is not searched, so recursion is not counted twice. A tree that lists
+/// callers (`shows_callers`) names them directly below its root instead, so only the
+/// function's own such tree is read. Highest first.
+pub fn find_callers(
+    trees: &[(PerfEntry, Vec<CallTreeNode>)],
+    symbol: &str,
+    tree_order: Option<TreeOrder>,
+    options: &MatchOptions,
+) -> Vec<CallerRelation> {
+    fn add(found: &mut Vec<(Symbol, f64)>, caller: &Symbol, pct: f64) {
+        match found.iter_mut().find(|(symbol, _)| symbol == caller) {
+            Some((_, total)) => *total += pct,
+            None => found.push((caller.clone(), pct)),
+        }
+    }
+
+    fn collect(node: &CallTreeNode, node_pct: f64, symbol: &str, found: &mut Vec<(Symbol, f64)>) {
+        for child in &node.children {
+            let child_pct = node_pct * child.relative_pct / 100.0;
+            if child.symbol == symbol {
+                add(found, &node.symbol, child_pct);
+            } else {
+                collect(child, child_pct, symbol, found);
+            }
+        }
+    }
+
+    let mut callers: Vec<CallerRelation> = Vec::new();
+    for (entry, roots) in trees {
+        let mut found = Vec::new();
+        if shows_callers(entry, tree_order, options) {
+            for root in roots.iter().filter(|r| r.symbol == symbol) {
+                let root_pct = entry.children_pct * root.relative_pct / 100.0;
+                for caller in root.children.iter().filter(|c| c.symbol != symbol) {
+                    add(
+                        &mut found,
+                        &caller.symbol,
+                        root_pct * caller.relative_pct / 100.0,
+                    );
+                }
+            }
+        } else {
+            for root in roots.iter().filter(|r| r.symbol != symbol) {
+                collect(
+                    root,
+                    entry.children_pct * root.relative_pct / 100.0,
+                    symbol,
+                    &mut found,
+                );
+            }
+        }
+        for (caller, pct) in found {
+            match callers.iter_mut().find(|c| c.caller == caller) {
+                Some(existing) => existing.absolute_pct = existing.absolute_pct.max(pct),
+                None => callers.push(CallerRelation {
+                    caller,
+                    absolute_pct: pct,
+                }),
+            }
+        }
+    }
+    callers.sort_by(|a, b| b.absolute_pct.total_cmp(&a.absolute_pct));
+    callers
+}
+
+/// One direct callee of a function, read from the function's own call tree.
+#[derive(Debug, Clone, PartialEq)]
+pub struct CalleeRelation {
+    /// Simplified name of the node directly below the function
+    pub callee: Symbol,
+    /// Share of the function's time spent in the callee
+    pub relative_pct: f64,
+    /// The same time as % of total runtime
+    pub absolute_pct: f64,
+}
+
+/// Find the direct callees of `entry` in its own call tree (`roots`), summing
+/// children of the same name. When its tree lists callers (`shows_callers`), the
+/// callees are instead the other caller-list trees naming the function directly
+/// below their root. Highest first.
+pub fn find_callees(
+    trees: &[(PerfEntry, Vec<CallTreeNode>)],
+    entry: &PerfEntry,
+    roots: &[CallTreeNode],
+    tree_order: Option<TreeOrder>,
+    options: &MatchOptions,
+) -> Vec<CalleeRelation> {
+    let symbol = simplify_symbol(&entry.symbol);
+    let mut callees: Vec<CalleeRelation> = Vec::new();
+    let mut add =
+        |callee: &Symbol, relative_pct: f64| match callees.iter_mut().find(|c| c.callee == *callee)
+        {
+            Some(existing) => existing.relative_pct += relative_pct,
+            None => callees.push(CalleeRelation {
+                callee: callee.clone(),
+                relative_pct,
+                absolute_pct: 0.0,
+            }),
+        };
+    if shows_callers(entry, tree_order, options) {
+        if entry.children_pct > 0.0 {
+            for (callee, callee_roots) in trees {
+                if !shows_callers(callee, tree_order, options) || callee.symbol == entry.symbol {
+                    continue;
+                }
+                for root in callee_roots.iter().filter(|r| r.symbol != symbol) {
+                    for caller in root.children.iter().filter(|c| c.symbol == symbol) {
+                        let absolute_pct = callee.children_pct * root.relative_pct / 100.0
+                            * caller.relative_pct
+                            / 100.0;
+                        add(&root.symbol, absolute_pct / entry.children_pct * 100.0);
+                    }
+                }
+            }
+        }
+    } else {
+        for root in roots.iter().filter(|r| r.symbol == symbol) {
+            for child in &root.children {
+                add(
+                    &child.symbol,
+                    root.relative_pct * child.relative_pct / 100.0,
+                );
+            }
+        }
+    }
+    for callee in &mut callees {
+        callee.absolute_pct = entry.children_pct * callee.relative_pct / 100.0;
+    }
+    callees.sort_by(|a, b| b.relative_pct.total_cmp(&a.relative_pct));
+    callees
+}
+
+/// One call chain from a function down to another, found by `pperf paths`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct CallPath {
+    /// Functions below the caller, ending with the callee, each with its % of the step above
+    pub steps: Vec<IntermediaryStep>,
+    /// Product of the steps: the callee's time via this chain as % of the caller's
+    pub relative_pct: f64,
+    /// The same time as % of total runtime
+    pub absolute_pct: f64,
+}
+
+/// Enumerate every chain from `entry` to a node matching `to` in the entry's own
+/// call tree. A chain ends at the first matching node, so chains never overlap and
+/// their percentages can be summed. Trees listing callers (`shows_callers`) hold no
+/// such chains. Highest first.
+pub fn find_call_paths(
+    entry: &PerfEntry,
+    roots: &[CallTreeNode],
+    to: &str,
+    tree_order: Option<TreeOrder>,
+    options: &MatchOptions,
+) -> Vec<CallPath> {
+    fn walk(
+        node: &CallTreeNode,
+        cumulative_pct: f64,
+        to: &str,
+        options: &MatchOptions,
+        path: &mut Vec<IntermediaryStep>,
+        found: &mut Vec<(Vec<IntermediaryStep>, f64)>,
+    ) {
+        for child in &node.children {
+            let child_pct = cumulative_pct * child.relative_pct / 100.0;
+            path.push(IntermediaryStep {
+                symbol: child.symbol.clone(),
+                percentage: child.relative_pct,
+            });
+            if options.matches(&child.symbol, to) {
+                found.push((path.clone(), child_pct));
+            } else {
+                walk(child, child_pct, to, options, path, found);
+            }
+            path.pop();
+        }
+    }
+
+    if shows_callers(entry, tree_order, options) {
+        return Vec::new();
+    }
+    let symbol = simplify_symbol(&entry.symbol);
+    let mut found = Vec::new();
+    for root in roots.iter().filter(|r| r.symbol == symbol) {
+        walk(
+            root,
+            root.relative_pct,
+            to,
+            options,
+            &mut Vec::new(),
+            &mut found,
+        );
+    }
+    let mut paths: Vec<CallPath> = found
+        .into_iter()
+        .map(|(steps, relative_pct)| CallPath {
+            steps,
+            relative_pct,
+            absolute_pct: entry.children_pct * relative_pct / 100.0,
+        })
+        .collect();
+    paths.sort_by(|a, b| b.relative_pct.total_cmp(&a.relative_pct));
+    paths
+}
+
+/// The heaviest root-to-leaf chain found by `pperf hotpath`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct HotPath {
+    /// Children% of the entry whose call tree holds the chain
+    pub root_pct: f64,
+    /// Every node from the tree root down to the leaf, each with its % of the node above
+    pub steps: Vec<IntermediaryStep>,
+}
+
+impl HotPath {
+    /// Time spent at the end of the chain, as % of total runtime
+    pub fn leaf_pct(&self) -> f64 {
+        self.steps
+            .iter()
+            .fold(self.root_pct, |pct, step| pct * step.percentage / 100.0)
+    }
+}
+
+/// Find the root-to-leaf chain whose leaf carries the largest share of total runtime,
+/// across every call tree listing callees. Trees listing callers (`shows_callers`)
+/// are skipped, as are roots perf cut off without children (a chain of one frame says
+/// nothing about where the time goes). `None` when no tree qualifies.
+pub fn find_hot_path(
+    trees: &[(PerfEntry, Vec<CallTreeNode>)],
+    tree_order: Option<TreeOrder>,
+    options: &MatchOptions,
+) -> Option<HotPath> {
+    fn walk(
+        node: &CallTreeNode,
+        pct: f64,
+        path: &mut Vec<IntermediaryStep>,
+        best: &mut Option<(f64, Vec<IntermediaryStep>)>,
+    ) {
+        let pct = pct * node.relative_pct / 100.0;
+        path.push(IntermediaryStep {
+            symbol: node.symbol.clone(),
+            percentage: node.relative_pct,
+        });
+        if node.children.is_empty() {
+            if best.as_ref().is_none_or(|(best_pct, _)| pct > *best_pct) {
+                *best = Some((pct, path.clone()));
+            }
+        } else {
+            for child in &node.children {
+                walk(child, pct, path, best);
+            }
+        }
+        path.pop();
+    }
+
+    let mut best: Option<(f64, HotPath)> = None;
+    for (entry, roots) in trees {
+        if shows_callers(entry, tree_order, options) {
+            continue;
+        }
+        let mut tree_best = None;
+        for root in roots.iter().filter(|root| !root.children.is_empty()) {
+            walk(root, entry.children_pct, &mut Vec::new(), &mut tree_best);
+        }
+        if let Some((pct, steps)) = tree_best
+            && best.as_ref().is_none_or(|(best_pct, _)| pct > *best_pct)
+        {
+            best = Some((
+                pct,
+                HotPath {
+                    root_pct: entry.children_pct,
+                    steps,
+                },
+            ));
+        }
+    }
+    best.map(|(_, path)| path)
+}
+
+/// Restrict relations to those discovered while traversing `context`'s own call tree:
+/// its direct relations (context_root = None) plus nested ones recorded under it.
+pub fn filter_relations_by_context(relations: &[CallRelation], context: &str) -> Vec<CallRelation> {
+    relations
+        .iter()
+        .filter(|r| match &r.context_root {
+            Some(root) => root.contains(context),
+            None => r.caller.contains(context),
+        })
+        .cloned()
+        .collect()
+}
+
+/// Keep only relations whose absolute contribution reaches `min_total` percent of
+/// total runtime, regardless of how large they are relative to their caller.
+pub fn filter_relations_by_min_total(
+    relations: &[CallRelation],
+    min_total: f64,
+) -> Vec<CallRelation> {
+    relations
+        .iter()
+        .filter(|r| r.absolute_pct >= min_total)
+        .cloned()
+        .collect()
+}
+
+/// Keep only relations that reach `min_pct` percent both relative to their caller
+/// and of total runtime, so neither a small share of a hot caller nor a large
+/// share of a cold one survives.
+pub fn filter_relations_by_min_pct(relations: &[CallRelation], min_pct: f64) -> Vec<CallRelation> {
+    relations
+        .iter()
+        .filter(|r| r.relative_pct >= min_pct && r.absolute_pct >= min_pct)
+        .cloned()
+        .collect()
+}
+
 // ============================================================================
 // Phase 4: Percentage Adjustment
 // ============================================================================
@@ -592,11 +1437,141 @@ pub fn compute_adjusted_percentage(original: f64, contributions: &[f64]) -> f64
     (original - sum).max(0.0)
 }
 
+/// Contributions of each caller to `callee` (a simplified name), for subtracting from
+/// its Children%. Relations are grouped by caller, then by call path (its intermediary
+/// steps with their percentages): the same path may appear multiple times (duplicate
+/// caller trees, other callers' contexts), so each path keeps its MAX absolute_pct; the
+/// distinct paths are then combined per `mode`. Highest contribution first.
+fn caller_contributions(
+    callee: &str,
+    relations: &[CallRelation],
+    mode: ContributionMode,
+) -> Vec<CallerContribution> {
+    // (caller, [(path, absolute %)])
+    type CallerPaths<'a> = (&'a Symbol, Vec<(&'a [IntermediaryStep], f64)>);
+    let mut paths_by_caller: Vec<CallerPaths> = Vec::new();
+    for r in relations.iter().filter(|r| r.callee == callee) {
+        let path = r.intermediary_path.as_slice();
+        let paths = match paths_by_caller.iter().position(|(c, _)| **c == r.caller) {
+            Some(index) => &mut paths_by_caller[index].1,
+            None => {
+                paths_by_caller.push((&r.caller, Vec::new()));
+                &mut paths_by_caller.last_mut().unwrap().1
+            }
+        };
+        match paths.iter_mut().find(|(p, _)| *p == path) {
+            Some((_, pct)) => *pct = pct.max(r.absolute_pct),
+            None => paths.push((path, r.absolute_pct)),
+        }
+    }
+
+    let mut contributions: Vec<CallerContribution> = paths_by_caller
+        .into_iter()
+        .map(|(caller, paths)| {
+            let mut path_pcts: Vec<f64> = paths.iter().map(|(_, pct)| *pct).collect();
+            path_pcts.sort_by(|a, b| b.total_cmp(a));
+            let absolute_pct = match mode {
+                ContributionMode::Max => path_pcts[0],
+                ContributionMode::Sum => path_pcts.iter().sum(),
+            };
+            CallerContribution {
+                caller: caller.clone(),
+                absolute_pct,
+                path_pcts,
+                mode,
+            }
+        })
+        .collect();
+    contributions.sort_by(|a, b| b.absolute_pct.total_cmp(&a.absolute_pct));
+    contributions
+}
+
+/// Percentage points by which contributions may exceed Children% before
+/// `find_contribution_overflows` reports them (rounding in perf's two decimals).
+pub const CONTRIBUTION_EPSILON: f64 = 0.01;
+
+/// A target whose callers account for more time than its own Children%, which
+/// `compute_adjusted_percentage` would otherwise silently clamp to zero.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ContributionOverflow {
+    /// Simplified function name
+    pub symbol: Symbol,
+    pub children_pct: f64,
+    /// Every caller's contribution, highest first
+    pub contributions: Vec<CallerContribution>,
+}
+
+impl ContributionOverflow {
+    pub fn total_pct(&self) -> f64 {
+        self.contributions.iter().map(|c| c.absolute_pct).sum()
+    }
+}
+
+impl std::fmt::Display for ContributionOverflow {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let callers: Vec<String> = self
+            .contributions
+            .iter()
+            .map(|c| format!("{} {:.2}%", c.caller, c.absolute_pct))
+            .collect();
+        write!(
+            f,
+            "callers of {} account for {:.2}% but it has {:.2}% Children% ({})",
+            self.symbol,
+            self.total_pct(),
+            self.children_pct,
+            callers.join(", ")
+        )
+    }
+}
+
+/// Targets whose caller contributions exceed their Children% by more than
+/// `CONTRIBUTION_EPSILON`, checked the way `build_hierarchy_entries_with` subtracts them.
+pub fn find_contribution_overflows(
+    entries: &[PerfEntry],
+    targets: &[String],
+    relations: &[CallRelation],
+    options: &MatchOptions,
+) -> Vec<ContributionOverflow> {
+    let mut overflows = Vec::new();
+    let mut checked: HashSet<Symbol> = HashSet::new();
+    for entry in entries {
+        if !options.is_any_target(&entry.symbol, targets) {
+            continue;
+        }
+        let symbol = Symbol::from(simplify_symbol(&entry.symbol));
+        // Like build_hierarchy_entries_with, the first entry per simplified name counts
+        if !checked.insert(symbol.clone()) {
+            continue;
+        }
+        let contributions = caller_contributions(&symbol, relations, options.contribution_mode);
+        let overflow = ContributionOverflow {
+            symbol,
+            children_pct: entry.children_pct,
+            contributions,
+        };
+        if overflow.total_pct() > entry.children_pct + CONTRIBUTION_EPSILON {
+            overflows.push(overflow);
+        }
+    }
+    overflows
+}
+
 /// T037: Build hierarchy entries from entries and relations.
 pub fn build_hierarchy_entries(
     entries: &[PerfEntry],
     targets: &[String],
     relations: &[CallRelation],
+) -> Vec<HierarchyEntry> {
+    build_hierarchy_entries_with(entries, targets, relations, &MatchOptions::default())
+}
+
+/// `build_hierarchy_entries` honoring `--ignore-case` and other match options.
+pub fn build_hierarchy_entries_with(
+    entries: &[PerfEntry],
+    targets: &[String],
+    relations: &[CallRelation],
+    options: &MatchOptions,
 ) -> Vec<HierarchyEntry> {
     use crate::symbol::simplify_symbol;
 
@@ -607,11 +1582,11 @@ pub fn build_hierarchy_entries(
     let mut added_symbols: HashSet<String> = HashSet::new();
 
     // Collect unique callers from relations (these are the "root" callers)
-    let callers: HashSet<String> = relations.iter().map(|r| r.caller.clone()).collect();
+    let callers: HashSet<Symbol> = relations.iter().map(|r| r.caller.clone()).collect();
 
     for entry in entries {
         // Check if this entry matches any target
-        let is_target = targets.iter().any(|t| entry.symbol.contains(t));
+        let is_target = options.is_any_target(&entry.symbol, targets);
         if !is_target {
             continue;
         }
@@ -628,10 +1603,10 @@ pub fn build_hierarchy_entries(
         // These are used for standalone display and remainder calculations
         // Deduplicate by callee symbol, keeping only unique callees
         let mut callees: Vec<CallRelation> = Vec::new();
-        let mut seen_callees: HashSet<String> = HashSet::new();
+        let mut seen_callees: HashSet<Symbol> = HashSet::new();
         for r in relations
             .iter()
-            .filter(|r| entry.symbol.contains(&r.caller) && r.context_root.is_none())
+            .filter(|r| entry.symbol.contains(r.caller.as_str()) && r.context_root.is_none())
         {
             if !seen_callees.contains(&r.callee) {
                 seen_callees.insert(r.callee.clone());
@@ -640,31 +1615,13 @@ pub fn build_hierarchy_entries(
         }
 
         // Find contributions TO this entry (when it's a callee)
-        // Group by caller and take MAX absolute_pct per caller
-        // (same caller->callee pair may appear multiple times from different contexts)
-        let mut contribution_by_caller: std::collections::HashMap<String, f64> =
-            std::collections::HashMap::new();
-        for r in relations.iter() {
-            if simplified == r.callee {
-                let entry = contribution_by_caller
-                    .entry(r.caller.clone())
-                    .or_insert(0.0);
-                if r.absolute_pct > *entry {
-                    *entry = r.absolute_pct;
-                }
-            }
-        }
+        let contributions_breakdown =
+            caller_contributions(&simplified, relations, options.contribution_mode);
 
-        // Build contributions breakdown for debug mode
-        let contributions_breakdown: Vec<CallerContribution> = contribution_by_caller
+        let contribution_values: Vec<f64> = contributions_breakdown
             .iter()
-            .map(|(caller, &pct)| CallerContribution {
-                caller: caller.clone(),
-                absolute_pct: pct,
-            })
+            .map(|c| c.absolute_pct)
             .collect();
-
-        let contribution_values: Vec<f64> = contribution_by_caller.values().copied().collect();
         let adjusted = compute_adjusted_percentage(entry.children_pct, &contribution_values);
 
         // Determine if this entry is a caller (has callees) or just a callee
@@ -672,7 +1629,7 @@ pub fn build_hierarchy_entries(
 
         // If this is purely a callee (not a caller), check if it's called by another target
         // and only show it as standalone if it has unique standalone time
-        let is_callee_of_target = callers.iter().any(|c| entry.symbol.contains(c));
+        let is_callee_of_target = callers.iter().any(|c| entry.symbol.contains(c.as_str()));
 
         // Skip entries that are both a callee AND have no callees themselves
         // unless they're also a caller
@@ -708,26 +1665,178 @@ mod tests {
         let result = parse_call_tree_line(line);
         assert!(result.is_some());
         let tree_line = result.unwrap();
-        // Depth is now based on column position: -- at column 16, 16/11+1 = 2
-        assert_eq!(tree_line.depth, 2);
+        // Hangs from the `|` at column 15
+        assert_eq!(tree_line.column, 15);
+        assert!(!tree_line.is_continuation);
         assert!((tree_line.relative_pct.unwrap() - 17.23).abs() < 0.01);
         assert!(tree_line.symbol.contains("DCT4DBlock"));
     }
 
-    // T009: Test count_depth - now based on column position of --XX%-- pattern
     #[test]
-    fn test_count_depth() {
-        // Depth 1: -- at column ~16 (16/11 + 1 = 2, but actual perf uses ~16)
-        assert_eq!(count_depth("               |--17.23%--func"), 2);
-        // Depth 2: -- at column ~27
-        assert_eq!(count_depth("               |           --5.00%--func"), 3);
-        // Depth 3: |-- at column ~37
+    fn test_parse_call_tree_line_inlined() {
+        let tree_line = parse_call_tree_line(
+            "               |--45.00%--Block4D::get_linear_position() const (inlined)",
+        )
+        .unwrap();
+        assert!(tree_line.inlined);
+        assert_eq!(tree_line.symbol, "Block4D::get_linear_position");
+
+        let continuation =
+            parse_call_tree_line("               std::vector<int>::size() (inlined)").unwrap();
+        assert!(continuation.inlined);
+        assert_eq!(continuation.symbol, "std::vector::size");
+
+        assert!(
+            !parse_call_tree_line("               |--17.23%--DCT4DBlock::DCT4DBlock")
+                .unwrap()
+                .inlined
+        );
+    }
+
+    // T009: Test line_column for every kind of call tree line
+    #[test]
+    fn test_line_column() {
         assert_eq!(
-            count_depth("               |                     |--5.00%--func"),
-            4
+            line_column("               |--17.23%--func"),
+            Some((15, false))
         );
-        // No pattern found: fallback to pipe count
-        assert_eq!(count_depth("no pipes here"), 0);
+        // Last child: the blank where the `|` would be is the anchor
+        assert_eq!(
+            line_column("               |           --5.00%--func"),
+            Some((26, false))
+        );
+        assert_eq!(line_column("            ---func"), Some((12, false)));
+        // Continuation lines hang from their own name
+        assert_eq!(
+            line_column("               |          func"),
+            Some((26, true))
+        );
+        assert_eq!(line_column("               func"), Some((15, true)));
+        assert_eq!(line_column("               |"), None);
+    }
+
+    fn tree_lines(text: &str) -> Vec<CallTreeLine> {
+        text.lines().filter_map(parse_call_tree_line).collect()
+    }
+
+    fn shape(nodes: &[CallTreeNode]) -> String {
+        nodes
+            .iter()
+            .map(|n| {
+                if n.children.is_empty() {
+                    n.symbol.to_string()
+                } else {
+                    format!("{}({})", n.symbol, shape(&n.children))
+                }
+            })
+            .collect::<Vec<_>>()
+            .join(" ")
+    }
+
+    #[test]
+    fn test_build_call_tree_continuations_chain() {
+        let lines = tree_lines(
+            "            |
+            ---main
+               encode
+               |
+               |--60.00%--split
+               |          dct
+               |          |
+               |           --50.00%--quantize
+               |
+                --40.00%--decode
+",
+        );
+        let roots = build_call_tree(&lines);
+        // Continuation frames are the callees of the frame above, not new roots
+        assert_eq!(shape(&roots), "main(encode(split(dct(quantize)) decode))");
+        assert_eq!(roots[0].children[0].relative_pct, 100.0);
+    }
+
+    #[test]
+    fn test_collapse_inlined_frames() {
+        let lines = tree_lines(
+            "            ---encode
+               |
+               |--50.00%--transform (inlined)
+               |          |
+               |          |--60.00%--dct
+               |          |          |
+               |          |           --50.00%--butterfly (inlined)
+               |          |                     |
+               |          |                      --100.00%--twiddle
+               |          |
+               |           --40.00%--quantize
+               |
+               |--30.00%--dct
+               |          |
+               |           --100.00%--twiddle
+               |
+                --20.00%--bounds_check (inlined)
+",
+        );
+        let roots = build_call_tree(&lines);
+        assert!(roots[0].children[0].inlined);
+        let mut trees = vec![(PerfEntry::default(), roots)];
+        collapse_inlined_frames(&mut trees);
+
+        let roots = &trees[0].1;
+        assert_eq!(shape(roots), "encode(dct(twiddle) quantize)");
+        let dct = &roots[0].children[0];
+        // 50% x 60% through the inlined frame plus the direct 30%
+        assert!((dct.relative_pct - 60.0).abs() < 1e-9);
+        assert!((roots[0].children[1].relative_pct - 20.0).abs() < 1e-9);
+        // twiddle: half of the inlined path's dct share (30 of 60), all of the direct one
+        assert!((dct.children[0].relative_pct - 75.0).abs() < 1e-9);
+        assert!(!dct.inlined);
+    }
+
+    #[test]
+    fn test_build_call_tree_any_indent_width() {
+        // perf's fixed 11-column levels; a 100.00% name starts right of its children
+        let standard = tree_lines(
+            "            ---main
+               |
+               |--100.00%--encode
+               |          |
+               |          |--70.00%--dct
+               |          |          |
+               |          |           --50.00%--inner
+               |          |
+               |           --30.00%--quantize
+               |
+                --0.00%--idle
+",
+        );
+        // A narrower layout of the same tree
+        let narrow = tree_lines(
+            "      ---main
+         |--100.00%--encode
+         |     |--70.00%--dct
+         |     |      --50.00%--inner
+         |      --30.00%--quantize
+          --0.00%--idle
+",
+        );
+        let expected = "main(encode(dct(inner) quantize) idle)";
+        assert_eq!(shape(&build_call_tree(&standard)), expected);
+        assert_eq!(shape(&build_call_tree(&narrow)), expected);
+    }
+
+    #[test]
+    fn test_build_call_tree_root_branches() {
+        // Leaf entries list their callers as root-level branches without `---`
+        let lines = tree_lines(
+            "            |
+            |--60.00%--encode
+            |          main
+            |
+             --40.00%--decode
+                       main
+",
+        );
+        assert_eq!(shape(&build_call_tree(&lines)), "encode(main) decode(main)");
     }
 
     // T010: Test extract_percentage
@@ -738,6 +1847,12 @@ mod tests {
         assert!(extract_percentage("func without percentage").is_none());
     }
 
+    #[test]
+    fn test_extract_percentage_comma_decimal() {
+        assert!((extract_percentage("|--17,23%--func").unwrap() - 17.23).abs() < 0.01);
+        assert!((extract_percentage("           --4,98%--func").unwrap() - 4.98).abs() < 0.01);
+    }
+
     // T011: Test extract_symbol
     #[test]
     fn test_extract_symbol() {
@@ -771,7 +1886,7 @@ mod tests {
     #[test]
     fn test_intermediary_step_creation() {
         let step = IntermediaryStep {
-            symbol: "do_4d_transform".to_string(),
+            symbol: "do_4d_transform".into(),
             percentage: 42.0,
         };
         assert_eq!(step.symbol, "do_4d_transform");
@@ -782,13 +1897,13 @@ mod tests {
     #[test]
     fn test_call_relation_with_intermediary_path() {
         let relation = CallRelation {
-            caller: "rd_optimize".to_string(),
-            callee: "inner_product".to_string(),
+            caller: "rd_optimize".into(),
+            callee: "inner_product".into(),
             relative_pct: 7.23,
             absolute_pct: 5.19,
             context_root: None,
             intermediary_path: vec![IntermediaryStep {
-                symbol: "do_4d_transform".to_string(),
+                symbol: "do_4d_transform".into(),
                 percentage: 42.0,
             }],
         };
@@ -796,12 +1911,784 @@ mod tests {
         assert_eq!(relation.intermediary_path[0].symbol, "do_4d_transform");
     }
 
+    fn relation(caller: &str, callee: &str, context_root: Option<&str>) -> CallRelation {
+        CallRelation {
+            caller: caller.into(),
+            callee: callee.into(),
+            relative_pct: 10.0,
+            absolute_pct: 5.0,
+            context_root: context_root.map(Symbol::from),
+            intermediary_path: vec![],
+        }
+    }
+
+    #[test]
+    fn test_relabel_target_groups() {
+        let mut trees = vec![(
+            PerfEntry {
+                children_pct: 70.0,
+                self_pct: 0.0,
+                symbol: "rd_optimize".into(),
+                ..Default::default()
+            },
+            vec![CallTreeNode {
+                symbol: "rd_optimize".into(),
+                relative_pct: 100.0,
+                inlined: false,
+                children: vec![
+                    CallTreeNode {
+                        symbol: "DCT4DBlock::DCT4DBlock".into(),
+                        relative_pct: 17.0,
+                        inlined: false,
+                        children: vec![],
+                    },
+                    CallTreeNode {
+                        symbol: "IDCT4DBlock::IDCT4DBlock".into(),
+                        relative_pct: 3.0,
+                        inlined: false,
+                        children: vec![],
+                    },
+                ],
+            }],
+        )];
+        let targets = vec!["rd_optimize".to_string(), "DCT4DBlock|IDCT4D".to_string()];
+        relabel_target_groups(&mut trees, &targets, &MatchOptions::default());
+
+        let children = &trees[0].1[0].children;
+        assert_eq!(trees[0].0.symbol, "rd_optimize");
+        assert_eq!(children[0].symbol, "DCT4DBlock|IDCT4D");
+        assert_eq!(children[1].symbol, "DCT4DBlock|IDCT4D");
+    }
+
+    #[test]
+    fn test_convert_absolute_percentages() {
+        let mut trees = vec![(
+            PerfEntry {
+                children_pct: 80.0,
+                symbol: "rd_optimize".into(),
+                ..Default::default()
+            },
+            vec![CallTreeNode {
+                symbol: "rd_optimize".into(),
+                relative_pct: 100.0,
+                inlined: false,
+                children: vec![CallTreeNode {
+                    symbol: "evaluate_split".into(),
+                    relative_pct: 40.0,
+                    inlined: false,
+                    children: vec![CallTreeNode {
+                        symbol: "DCT4DBlock".into(),
+                        relative_pct: 10.0,
+                        inlined: false,
+                        children: vec![],
+                    }],
+                }],
+            }],
+        )];
+        convert_absolute_percentages(&mut trees);
+
+        let root = &trees[0].1[0];
+        assert!((root.relative_pct - 100.0).abs() < 0.001);
+        assert!((root.children[0].relative_pct - 50.0).abs() < 0.001);
+        assert!((root.children[0].children[0].relative_pct - 25.0).abs() < 0.001);
+    }
+
+    #[test]
+    fn test_parse_file_call_trees_crlf_and_bom() {
+        let raw = b"\xEF\xBB\xBF    50.00%     0.00%  app  app  [.] rd_optimize\r\n\
+                   \x20           |\r\n\
+                   \x20           ---rd_optimize\r\n\
+                   \x20              |--20.00%--DCT4DBlock::DCT4DBlock\r\n\
+                   \x20               --10.00%--caf\xe9\r\n\r\n";
+        let content = crate::parser::normalize_content(raw);
+        let entries: Vec<PerfEntry> = content
+            .lines()
+            .filter_map(crate::parser::parse_line)
+            .collect();
+        assert_eq!(entries.len(), 1);
+
+        let trees = parse_file_call_trees(&content, &entries);
+        assert_eq!(trees.len(), 1);
+        let root = &trees[0].1[0];
+        assert_eq!(root.symbol, "rd_optimize");
+        assert_eq!(root.children.len(), 2);
+        assert_eq!(root.children[0].symbol, "DCT4DBlock::DCT4DBlock");
+        assert!((root.children[1].relative_pct - 10.0).abs() < 0.01);
+    }
+
+    #[test]
+    fn test_parse_file_call_trees_warnings() {
+        let content = "    50.00%     0.00%  app  app  [.] rd_optimize\n\
+                       \x20           ---rd_optimize\n\
+                       \x20              |--2x.00%--DCT4DBlock::DCT4DBlock\n\
+                       \x20               --10.00%--inner_product\n\n\
+                       \x20   4x.00%  app  [.] broken\n\
+                       \x20           ---broken\n\
+                       \x20               --10.00%--orphan\n\n\
+                       \x20   20.00%     0.00%  app  app  [.] inner_product\n\
+                       \x20           ---inner_product\n";
+        let mut warnings = Vec::new();
+        let trees = parse_file_call_trees_with_warnings(content, &mut warnings);
+
+        let lines: Vec<usize> = warnings.iter().map(|w| w.line).collect();
+        assert_eq!(lines, vec![3, 7]);
+        assert_eq!(warnings[0].reason, "malformed call-graph percentage");
+
+        // The orphaned `broken` tree must not be attached to `inner_product`
+        assert_eq!(trees.len(), 2);
+        assert_eq!(trees[1].0.symbol, "inner_product");
+        assert_eq!(trees[1].1.len(), 1);
+        assert!(trees[1].1[0].children.is_empty());
+    }
+
+    #[test]
+    fn test_filter_relations_by_min_total() {
+        let mut big = relation("rd_optimize", "DCT4DBlock", None);
+        big.absolute_pct = 12.37;
+        let mut small = relation("DCT4DBlock", "inner_product", Some("rd_optimize"));
+        small.absolute_pct = 0.07;
+
+        let filtered = filter_relations_by_min_total(&[big.clone(), small], 0.5);
+        assert_eq!(filtered, vec![big]);
+    }
+
+    #[test]
+    fn test_filter_relations_by_min_pct() {
+        let mut kept = relation("rd_optimize", "DCT4DBlock", None);
+        kept.relative_pct = 17.23;
+        kept.absolute_pct = 12.37;
+        let mut low_absolute = relation("DCT4DBlock", "inner_product", None);
+        low_absolute.relative_pct = 2.21;
+        low_absolute.absolute_pct = 0.58;
+        let mut low_relative = relation("rd_optimize", "inner_product", None);
+        low_relative.relative_pct = 0.8;
+        low_relative.absolute_pct = 1.5;
+
+        let filtered =
+            filter_relations_by_min_pct(&[kept.clone(), low_absolute, low_relative], 1.0);
+        assert_eq!(filtered, vec![kept]);
+    }
+
+    #[test]
+    fn test_build_hierarchy_entries_contribution_mode() {
+        let entries = vec![
+            PerfEntry {
+                children_pct: 70.0,
+                symbol: "rd_optimize".into(),
+                ..Default::default()
+            },
+            PerfEntry {
+                children_pct: 30.0,
+                self_pct: 30.0,
+                symbol: "DCT4DBlock".into(),
+                ..Default::default()
+            },
+        ];
+        let step = |percentage| IntermediaryStep {
+            symbol: "evaluate".into(),
+            percentage,
+        };
+        // Two distinct paths, the first repeated as if from a duplicate caller tree
+        let mut first = relation("rd_optimize", "DCT4DBlock", None);
+        first.absolute_pct = 10.0;
+        first.intermediary_path = vec![step(40.0)];
+        let mut second = first.clone();
+        second.absolute_pct = 4.0;
+        second.intermediary_path = vec![step(16.0)];
+        let relations = vec![first.clone(), first, second];
+        let targets = vec!["rd_optimize".to_string(), "DCT4D".to_string()];
+        let standalone = |mode| {
+            let options = MatchOptions {
+                contribution_mode: mode,
+                ..Default::default()
+            };
+            build_hierarchy_entries_with(&entries, &targets, &relations, &options)
+                .into_iter()
+                .find(|e| e.symbol == "DCT4DBlock")
+                .unwrap()
+        };
+
+        let max = standalone(ContributionMode::Max);
+        assert!((max.adjusted_children_pct - 20.0).abs() < 0.001);
+        assert_eq!(max.contributions[0].path_pcts, vec![10.0, 4.0]);
+
+        let sum = standalone(ContributionMode::Sum);
+        assert!((sum.adjusted_children_pct - 16.0).abs() < 0.001);
+        assert!((sum.contributions[0].absolute_pct - 14.0).abs() < 0.001);
+    }
+
+    #[test]
+    fn test_find_contribution_overflows() {
+        let entries = vec![
+            PerfEntry {
+                children_pct: 30.0,
+                self_pct: 30.0,
+                symbol: "quantize(Block const&)".into(),
+                ..Default::default()
+            },
+            PerfEntry {
+                children_pct: 40.0,
+                symbol: "encode_frame".into(),
+                ..Default::default()
+            },
+        ];
+        let mut from_frame = relation("encode_frame", "quantize", None);
+        from_frame.absolute_pct = 32.0;
+        let mut from_slice = relation("encode_slice", "quantize", None);
+        from_slice.absolute_pct = 10.0;
+        let targets = vec!["encode_".to_string(), "quantize".to_string()];
+        let options = MatchOptions::default();
+
+        let overflows = find_contribution_overflows(
+            &entries,
+            &targets,
+            &[from_slice.clone(), from_frame.clone()],
+            &options,
+        );
+        assert_eq!(overflows.len(), 1);
+        assert_eq!(
+            overflows[0].to_string(),
+            "callers of quantize account for 42.00% but it has 30.00% Children% (encode_frame 32.00%, encode_slice 10.00%)"
+        );
+
+        // Within the epsilon, perf's rounding is not an overflow
+        from_slice.absolute_pct = -2.0 + CONTRIBUTION_EPSILON;
+        assert!(
+            find_contribution_overflows(&entries, &targets, &[from_frame, from_slice], &options)
+                .is_empty()
+        );
+    }
+
+    #[test]
+    fn test_transform_tree_symbols() {
+        let mut trees = vec![(
+            PerfEntry {
+                symbol: "Acme::rd_optimize".into(),
+                ..Default::default()
+            },
+            vec![CallTreeNode {
+                symbol: "Acme::rd_optimize".into(),
+                relative_pct: 100.0,
+                inlined: false,
+                children: vec![CallTreeNode {
+                    symbol: "Acme::DCT4DBlock".into(),
+                    relative_pct: 17.0,
+                    inlined: false,
+                    children: vec![],
+                }],
+            }],
+        )];
+        let mut symbols = HashSet::new();
+        collect_tree_symbols(&trees, &mut symbols);
+        assert_eq!(symbols.len(), 2);
+        assert!(symbols.contains("Acme::DCT4DBlock"));
+
+        let strip = |s: &str| s.replace("Acme::", "");
+        transform_tree_symbols(&mut trees, &strip);
+        assert_eq!(trees[0].0.symbol, "rd_optimize");
+        assert_eq!(trees[0].1[0].symbol, "rd_optimize");
+        assert_eq!(trees[0].1[0].children[0].symbol, "DCT4DBlock");
+    }
+
+    #[test]
+    fn test_compute_call_relations_with_excludes() {
+        let trees = vec![(
+            PerfEntry {
+                children_pct: 50.0,
+                self_pct: 0.0,
+                symbol: "rd_optimize".into(),
+                ..Default::default()
+            },
+            vec![CallTreeNode {
+                symbol: "rd_optimize".into(),
+                relative_pct: 100.0,
+                inlined: false,
+                children: vec![CallTreeNode {
+                    symbol: "IDCT4DBlock".into(),
+                    relative_pct: 40.0,
+                    inlined: false,
+                    children: vec![CallTreeNode {
+                        symbol: "DCT4DBlock".into(),
+                        relative_pct: 50.0,
+                        inlined: false,
+                        children: vec![],
+                    }],
+                }],
+            }],
+        )];
+        let targets = vec!["rd_optimize".to_string(), "DCT4D".to_string()];
+
+        let relations = compute_call_relations(&trees, &targets);
+        assert_eq!(relations[0].callee, "IDCT4DBlock");
+
+        let options = MatchOptions {
+            excludes: vec!["IDCT".to_string()],
+            ..Default::default()
+        };
+        let relations = compute_call_relations_with(&trees, &targets, &options);
+        assert_eq!(relations.len(), 1);
+        assert_eq!(relations[0].callee, "DCT4DBlock");
+        assert!((relations[0].relative_pct - 20.0).abs() < 0.01);
+        assert_eq!(relations[0].intermediary_path[0].symbol, "IDCT4DBlock");
+    }
+
+    #[test]
+    fn test_call_tree_index() {
+        let leaf = |symbol: &str| CallTreeNode {
+            symbol: symbol.into(),
+            relative_pct: 50.0,
+            inlined: false,
+            children: vec![],
+        };
+        let entry = |symbol: &str| PerfEntry {
+            children_pct: 50.0,
+            symbol: symbol.into(),
+            ..Default::default()
+        };
+        let trees = vec![
+            (
+                entry("rd_optimize"),
+                vec![CallTreeNode {
+                    symbol: "rd_optimize".into(),
+                    relative_pct: 100.0,
+                    inlined: false,
+                    children: vec![leaf("DCT4DBlock"), leaf("IDCT4DBlock")],
+                }],
+            ),
+            (entry("main"), vec![leaf("encode")]),
+        ];
+        let targets = vec!["DCT4D".to_string()];
+        let options = MatchOptions {
+            excludes: vec!["IDCT".to_string()],
+            ..Default::default()
+        };
+
+        let index = CallTreeIndex::build(&trees, &targets, &options);
+        assert!(index.is_target("DCT4DBlock"));
+        assert!(!index.is_target("IDCT4DBlock"));
+        assert!(!index.is_target("rd_optimize"));
+        assert!(index.tree_has_targets(0));
+        assert!(!index.tree_has_targets(1));
+    }
+
+    #[test]
+    fn test_compute_call_relations_max_depth() {
+        let trees = vec![(
+            PerfEntry {
+                children_pct: 50.0,
+                self_pct: 0.0,
+                symbol: "rd_optimize".into(),
+                ..Default::default()
+            },
+            vec![CallTreeNode {
+                symbol: "rd_optimize".into(),
+                relative_pct: 100.0,
+                inlined: false,
+                children: vec![CallTreeNode {
+                    symbol: "rd_optimize".into(),
+                    relative_pct: 90.0,
+                    inlined: false,
+                    children: vec![CallTreeNode {
+                        symbol: "evaluate".into(),
+                        relative_pct: 40.0,
+                        inlined: false,
+                        children: vec![CallTreeNode {
+                            symbol: "DCT4DBlock".into(),
+                            relative_pct: 50.0,
+                            inlined: false,
+                            children: vec![],
+                        }],
+                    }],
+                }],
+            }],
+        )];
+        let targets = vec!["rd_optimize".to_string(), "DCT4D".to_string()];
+        let with_depth = |max_depth| MatchOptions {
+            max_depth: Some(max_depth),
+            ..Default::default()
+        };
+
+        // The recursive rd_optimize frame is not a level; evaluate is 1, DCT4DBlock 2
+        assert!(compute_call_relations_with(&trees, &targets, &with_depth(1)).is_empty());
+        let relations = compute_call_relations_with(&trees, &targets, &with_depth(2));
+        assert_eq!(relations.len(), 1);
+        assert_eq!(relations[0].callee, "DCT4DBlock");
+    }
+
+    #[test]
+    fn test_assume_callee_trees_skips_leaf_guess() {
+        // Self% is 60% of Children%, so the guess reads this tree as a caller list
+        let trees = vec![(
+            PerfEntry {
+                children_pct: 50.0,
+                self_pct: 30.0,
+                symbol: "encode".into(),
+                ..Default::default()
+            },
+            vec![CallTreeNode {
+                symbol: "encode".into(),
+                relative_pct: 100.0,
+                inlined: false,
+                children: vec![CallTreeNode {
+                    symbol: "DCT4DBlock".into(),
+                    relative_pct: 40.0,
+                    inlined: false,
+                    children: vec![],
+                }],
+            }],
+        )];
+        let targets = vec!["encode".to_string(), "DCT4D".to_string()];
+
+        let guessing = MatchOptions::default();
+        assert!(compute_call_relations_with(&trees, &targets, &guessing).is_empty());
+        assert_eq!(
+            guessed_caller_trees(&trees, &targets, &guessing),
+            vec!["encode"]
+        );
+
+        let assuming = MatchOptions {
+            assume_callee_trees: true,
+            ..Default::default()
+        };
+        let relations = compute_call_relations_with(&trees, &targets, &assuming);
+        assert_eq!(relations.len(), 1);
+        assert!((relations[0].absolute_pct - 20.0).abs() < 0.001);
+        assert!(guessed_caller_trees(&trees, &targets, &assuming).is_empty());
+    }
+
+    #[test]
+    fn test_compute_callee_order_relations() {
+        let leaf = |symbol: &str, relative_pct: f64, children| CallTreeNode {
+            symbol: symbol.into(),
+            relative_pct,
+            inlined: false,
+            children,
+        };
+        let entry = |symbol: &str, children_pct: f64| PerfEntry {
+            children_pct,
+            self_pct: children_pct,
+            symbol: symbol.into(),
+            ..Default::default()
+        };
+        // DCT4DBlock is called by rd_optimize directly (60%) and through evaluate (30%),
+        // and by main (10%); its tree lists callers as children
+        let trees = vec![
+            (
+                entry("DCT4DBlock", 20.0),
+                vec![leaf(
+                    "DCT4DBlock",
+                    100.0,
+                    vec![
+                        leaf("rd_optimize", 60.0, vec![leaf("main", 100.0, vec![])]),
+                        leaf("evaluate", 30.0, vec![leaf("rd_optimize", 100.0, vec![])]),
+                        leaf("main", 10.0, vec![]),
+                    ],
+                )],
+            ),
+            (entry("rd_optimize", 40.0), vec![]),
+        ];
+        let targets = vec!["rd_optimize".to_string(), "DCT4D".to_string()];
+
+        let relations = compute_callee_order_relations(&trees, &targets, &MatchOptions::default());
+        assert_eq!(relations.len(), 1);
+        let relation = &relations[0];
+        assert_eq!(relation.caller, "rd_optimize");
+        assert_eq!(relation.callee, "DCT4DBlock");
+        // 90% of DCT4DBlock's 20% comes through rd_optimize: 18% total, 45% of its 40%
+        assert!((relation.absolute_pct - 18.0).abs() < 0.001);
+        assert!((relation.relative_pct - 45.0).abs() < 0.001);
+        assert!(relation.context_root.is_none());
+        assert!(relation.intermediary_path.is_empty());
+
+        // The caller-order walk finds nothing in these trees
+        assert!(compute_call_relations_with(&trees, &targets, &MatchOptions::default()).is_empty());
+    }
+
+    #[test]
+    fn test_find_callers() {
+        let node = |symbol: &str, relative_pct: f64, children: Vec<CallTreeNode>| CallTreeNode {
+            symbol: symbol.into(),
+            relative_pct,
+            inlined: false,
+            children,
+        };
+        let entry = |symbol: &str, children_pct: f64| PerfEntry {
+            children_pct,
+            self_pct: 0.0,
+            symbol: symbol.into(),
+            ..Default::default()
+        };
+        let trees = vec![
+            (
+                entry("main", 100.0),
+                vec![node(
+                    "main",
+                    100.0,
+                    vec![
+                        node(
+                            "encode",
+                            60.0,
+                            vec![node("dct", 50.0, vec![node("dct", 40.0, vec![])])],
+                        ),
+                        node("decode", 20.0, vec![node("dct", 10.0, vec![])]),
+                    ],
+                )],
+            ),
+            // encode's own tree repeats (part of) what main's tree already shows
+            (
+                entry("encode", 60.0),
+                vec![node("encode", 100.0, vec![node("dct", 50.0, vec![])])],
+            ),
+        ];
+
+        let callers = find_callers(&trees, "dct", None, &MatchOptions::default());
+        assert_eq!(callers.len(), 2);
+        assert_eq!(callers[0].caller, "encode");
+        // 60% × 50%, counted once; the recursive dct -> dct call is not a caller
+        assert!((callers[0].absolute_pct - 30.0).abs() < 0.001);
+        assert_eq!(callers[1].caller, "decode");
+        assert!((callers[1].absolute_pct - 2.0).abs() < 0.001);
+    }
+
+    #[test]
+    fn test_find_callees() {
+        let node = |symbol: &str, relative_pct: f64| CallTreeNode {
+            symbol: symbol.into(),
+            relative_pct,
+            inlined: false,
+            children: vec![],
+        };
+        let entry = PerfEntry {
+            children_pct: 40.0,
+            self_pct: 2.0,
+            symbol: "encode(Block const&)".into(),
+            ..Default::default()
+        };
+        let roots = vec![CallTreeNode {
+            symbol: "encode".into(),
+            relative_pct: 100.0,
+            inlined: false,
+            children: vec![node("dct", 30.0), node("quantize", 50.0), node("dct", 10.0)],
+        }];
+
+        let callees = find_callees(&[], &entry, &roots, None, &MatchOptions::default());
+        assert_eq!(callees.len(), 2);
+        assert_eq!(callees[0].callee, "quantize");
+        // Both dct children are summed: 40% of 40% children
+        assert_eq!(callees[1].callee, "dct");
+        assert!((callees[1].relative_pct - 40.0).abs() < 0.001);
+        assert!((callees[1].absolute_pct - 16.0).abs() < 0.001);
+
+        let leaf = PerfEntry {
+            self_pct: 40.0,
+            ..entry
+        };
+        assert!(find_callees(&[], &leaf, &roots, None, &MatchOptions::default()).is_empty());
+        // A caller-ordered report says the tree lists callees, whatever Self% suggests
+        let ordered = find_callees(
+            &[],
+            &leaf,
+            &roots,
+            Some(TreeOrder::Caller),
+            &MatchOptions::default(),
+        );
+        assert_eq!(ordered.len(), 2);
+    }
+
+    #[test]
+    fn test_shows_callers_follows_tree_order() {
+        // Self% is over half of Children%, which the guess reads as a caller list
+        let entry = PerfEntry {
+            children_pct: 21.72,
+            self_pct: 11.94,
+            symbol: "get_mSubbandLF_significance".into(),
+            ..Default::default()
+        };
+        let guessing = MatchOptions::default();
+        assert!(shows_callers(&entry, None, &guessing));
+        assert!(!shows_callers(&entry, Some(TreeOrder::Caller), &guessing));
+        assert!(shows_callers(&entry, Some(TreeOrder::Callee), &guessing));
+        let assuming = MatchOptions {
+            assume_callee_trees: true,
+            ..Default::default()
+        };
+        assert!(!shows_callers(&entry, None, &assuming));
+
+        let entries = [entry];
+        assert_eq!(
+            guessed_caller_entries(&entries, None, &guessing),
+            vec!["get_mSubbandLF_significance"]
+        );
+        assert!(guessed_caller_entries(&entries, Some(TreeOrder::Callee), &guessing).is_empty());
+        assert!(guessed_caller_entries(&entries, None, &assuming).is_empty());
+    }
+
+    #[test]
+    fn test_butterfly_finders_on_callee_order_trees() {
+        let node = |symbol: &str, relative_pct: f64, children: Vec<CallTreeNode>| CallTreeNode {
+            symbol: symbol.into(),
+            relative_pct,
+            inlined: false,
+            children,
+        };
+        let entry = |symbol: &str, children_pct: f64| PerfEntry {
+            children_pct,
+            self_pct: 0.0,
+            symbol: symbol.into(),
+            ..Default::default()
+        };
+        // Each tree lists the callers of its root: encode is called by main, and
+        // calls dct (dct's tree names encode for 75% of its 20%)
+        let trees = vec![
+            (
+                entry("encode", 40.0),
+                vec![node("encode", 100.0, vec![node("main", 100.0, vec![])])],
+            ),
+            (
+                entry("dct", 20.0),
+                vec![node(
+                    "dct",
+                    100.0,
+                    vec![node("encode", 75.0, vec![]), node("main", 25.0, vec![])],
+                )],
+            ),
+        ];
+        let order = Some(TreeOrder::Callee);
+        let options = MatchOptions::default();
+
+        let callers = find_callers(&trees, "dct", order, &options);
+        assert_eq!(callers.len(), 2);
+        assert_eq!(callers[0].caller, "encode");
+        assert!((callers[0].absolute_pct - 15.0).abs() < 0.001);
+
+        let (encode, roots) = &trees[0];
+        let callees = find_callees(&trees, encode, roots, order, &options);
+        assert_eq!(callees.len(), 1);
+        assert_eq!(callees[0].callee, "dct");
+        // 15% of runtime is 37.5% of encode's 40%
+        assert!((callees[0].absolute_pct - 15.0).abs() < 0.001);
+        assert!((callees[0].relative_pct - 37.5).abs() < 0.001);
+
+        assert!(find_hot_path(&trees, order, &options).is_none());
+        assert!(tree_contains(&trees[1].1, "main"));
+        assert!(!tree_contains(&trees[0].1, "dct"));
+    }
+
+    #[test]
+    fn test_find_call_paths() {
+        let node = |symbol: &str, relative_pct: f64, children: Vec<CallTreeNode>| CallTreeNode {
+            symbol: symbol.into(),
+            relative_pct,
+            inlined: false,
+            children,
+        };
+        let entry = PerfEntry {
+            children_pct: 50.0,
+            self_pct: 0.0,
+            symbol: "encode".into(),
+            ..Default::default()
+        };
+        let roots = vec![node(
+            "encode",
+            100.0,
+            vec![
+                node("dct", 20.0, vec![node("dct", 50.0, vec![])]),
+                node(
+                    "split",
+                    60.0,
+                    vec![node("dct", 50.0, vec![]), node("quantize", 10.0, vec![])],
+                ),
+            ],
+        )];
+
+        let paths = find_call_paths(&entry, &roots, "dct", None, &MatchOptions::default());
+        assert_eq!(paths.len(), 2);
+        // encode -> split -> dct: 60% × 50%
+        assert_eq!(paths[0].steps.len(), 2);
+        assert_eq!(paths[0].steps[0].symbol, "split");
+        assert!((paths[0].relative_pct - 30.0).abs() < 0.001);
+        assert!((paths[0].absolute_pct - 15.0).abs() < 0.001);
+        // The chain stops at the first dct; its recursive call is not another path
+        assert_eq!(paths[1].steps.len(), 1);
+        assert!((paths[1].relative_pct - 20.0).abs() < 0.001);
+
+        assert!(
+            find_call_paths(&entry, &roots, "missing", None, &MatchOptions::default()).is_empty()
+        );
+    }
+
+    #[test]
+    fn test_find_hot_path() {
+        let node = |symbol: &str, relative_pct: f64, children: Vec<CallTreeNode>| CallTreeNode {
+            symbol: symbol.into(),
+            relative_pct,
+            inlined: false,
+            children,
+        };
+        let entry = |symbol: &str, children_pct: f64, self_pct: f64| PerfEntry {
+            children_pct,
+            self_pct,
+            symbol: symbol.into(),
+            ..Default::default()
+        };
+        let trees = vec![
+            // Leaf trees list callers, so their chain is not a call path
+            (
+                entry("dct", 95.0, 94.5),
+                vec![node("dct", 100.0, vec![node("encode", 100.0, vec![])])],
+            ),
+            // Nor is a tree perf cut off at its root
+            (
+                entry("worker", 92.0, 0.0),
+                vec![node("worker", 100.0, vec![])],
+            ),
+            (
+                entry("main", 90.0, 0.0),
+                vec![node(
+                    "main",
+                    100.0,
+                    vec![
+                        node("encode", 60.0, vec![node("dct", 40.0, vec![])]),
+                        // The lighter child leads to the heaviest leaf, so it wins
+                        node("decode", 40.0, vec![node("idct", 95.0, vec![])]),
+                    ],
+                )],
+            ),
+        ];
+
+        let path = find_hot_path(&trees, None, &MatchOptions::default()).unwrap();
+        let symbols: Vec<&str> = path.steps.iter().map(|s| s.symbol.as_str()).collect();
+        assert_eq!(symbols, vec!["main", "decode", "idct"]);
+        // 90% × 40% × 95%
+        assert!((path.leaf_pct() - 34.2).abs() < 0.001);
+
+        assert!(find_hot_path(&[], None, &MatchOptions::default()).is_none());
+    }
+
+    #[test]
+    fn test_filter_relations_by_context() {
+        let relations = vec![
+            relation("rd_optimize", "DCT4DBlock", None),
+            relation("DCT4DBlock", "inner_product", Some("rd_optimize")),
+            relation("DCT4DBlock", "inner_product", None),
+            relation("other_root", "DCT4DBlock", None),
+        ];
+
+        let filtered = filter_relations_by_context(&relations, "rd_optimize");
+        assert_eq!(filtered.len(), 2);
+        assert_eq!(filtered[0].caller, "rd_optimize");
+        assert_eq!(filtered[1].context_root.as_deref(), Some("rd_optimize"));
+
+        let none = filter_relations_by_context(&relations, "missing");
+        assert!(none.is_empty());
+    }
+
     // T007: Test empty intermediary_path (direct call)
     #[test]
     fn test_call_relation_direct_call() {
         let relation = CallRelation {
-            caller: "rd_optimize".to_string(),
-            callee: "DCT4DBlock".to_string(),
+            caller: "rd_optimize".into(),
+            callee: "DCT4DBlock".into(),
             relative_pct: 17.23,
             absolute_pct: 12.37,
             context_root: None,
@@ -1,8 +1,19 @@
+pub mod averaging;
+pub mod budget;
+pub mod config;
+pub mod demangle;
+pub mod diff;
+pub mod distribution;
 pub mod filter;
 pub mod hierarchy;
+pub mod log;
 pub mod output;
 pub mod parser;
+pub mod resolve;
+pub mod setops;
 pub mod symbol;
+pub mod synth;
+pub mod trend;
 
 use std::fmt;
 
@@ -11,9 +22,38 @@ pub enum PperfError {
     FileNotFound(String),
     InvalidFormat,
     InvalidCount,
-    NoMatches,
+    /// No entry matched; carries up to three similar symbol names to suggest
+    NoMatches(Vec<String>),
     /// T046: --hierarchy requires --targets
     HierarchyRequiresTargets,
+    /// Output file could not be written
+    WriteFailed(String),
+    /// --hierarchy on a report recorded without call graphs
+    NoCallGraph,
+    /// `paths`/`hotpath` (named) on call trees that list callers, not callees
+    CalleeOrderedCallGraph(String),
+    /// --strict and the parser skipped this many lines
+    StrictWarnings(usize),
+    /// --symbol-filter program could not be run or misbehaved
+    SymbolFilterFailed(String),
+    /// --target-file has a line that cannot be used as a target
+    InvalidTargetFile(String),
+    /// --budget file has a table that cannot be used
+    InvalidBudgetFile(String),
+    /// --budget and this many budgets were exceeded
+    BudgetExceeded(usize),
+    /// --strict-math and callers account for more than a target's Children%
+    ContributionOverflow(String),
+    /// --weight / `FILE:WEIGHT` values that cannot be matched to the reports
+    InvalidWeights(String),
+    /// `diff --fail-above` and this many functions regressed beyond the threshold
+    RegressionFound(usize),
+    /// --config file (or `pperf.toml`) has a table that cannot be used
+    InvalidConfigFile(String),
+    /// `addr2line` could not be run on a --binary or misbehaved
+    AddressResolutionFailed(String),
+    /// `pperf preset NAME` and the config file defines no such preset
+    UnknownPreset(String),
 }
 
 impl fmt::Display for PperfError {
@@ -24,20 +64,298 @@ impl fmt::Display for PperfError {
             PperfError::InvalidCount => {
                 write!(f, "Invalid value for -n: expected positive integer")
             }
-            PperfError::NoMatches => write!(f, "No matching functions found"),
+            PperfError::NoMatches(suggestions) => {
+                write!(f, "No matching functions found")?;
+                if !suggestions.is_empty() {
+                    write!(f, "; did you mean: {}?", suggestions.join(", "))?;
+                }
+                Ok(())
+            }
             PperfError::HierarchyRequiresTargets => {
                 write!(f, "--hierarchy requires --targets to be specified")
             }
+            PperfError::WriteFailed(path) => write!(f, "Failed to write: {}", path),
+            PperfError::NoCallGraph => write!(
+                f,
+                "Report contains no call graph data; --hierarchy needs a profile recorded with `perf record -g` (or `--call-graph dwarf`)"
+            ),
+            PperfError::CalleeOrderedCallGraph(command) => write!(
+                f,
+                "Call graph is callee-ordered; {} follows calls down from callers, so re-run `perf report` with `-g caller` (or pass --tree-order caller if the header is wrong)",
+                command
+            ),
+            PperfError::StrictWarnings(count) => {
+                write!(f, "{} unparseable line(s) in report (--strict)", count)
+            }
+            PperfError::SymbolFilterFailed(reason) => write!(f, "Symbol filter failed: {}", reason),
+            PperfError::InvalidTargetFile(reason) => write!(f, "Invalid target file: {}", reason),
+            PperfError::InvalidBudgetFile(reason) => write!(f, "Invalid budget file: {}", reason),
+            PperfError::BudgetExceeded(count) => write!(f, "{} budget(s) exceeded", count),
+            PperfError::ContributionOverflow(details) => {
+                write!(
+                    f,
+                    "Hierarchy arithmetic does not add up (--strict-math): {}",
+                    details
+                )
+            }
+            PperfError::InvalidWeights(reason) => write!(f, "Invalid report weights: {}", reason),
+            PperfError::RegressionFound(count) => {
+                write!(f, "{} function(s) regressed beyond --fail-above", count)
+            }
+            PperfError::InvalidConfigFile(reason) => write!(f, "Invalid config file: {}", reason),
+            PperfError::AddressResolutionFailed(reason) => {
+                write!(f, "Address resolution failed: {}", reason)
+            }
+            PperfError::UnknownPreset(reason) => write!(f, "Unknown preset: {}", reason),
         }
     }
 }
 
 impl std::error::Error for PperfError {}
 
+/// One row of the exit-code reference.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ExitCode {
+    pub code: i32,
+    /// Stable identifier; matches the `PperfError` variant name where one exists
+    pub name: &'static str,
+    pub description: &'static str,
+}
+
+/// Exit code for successful runs, including `--help` and `--version`.
+pub const EXIT_SUCCESS: i32 = 0;
+/// Exit code for command-line usage errors reported by the argument parser.
+pub const EXIT_USAGE: i32 = 3;
+
+/// The complete, stable exit-code mapping. Codes are part of the CLI contract:
+/// existing rows must never change, new outcomes get new rows.
+pub const EXIT_CODES: &[ExitCode] = &[
+    ExitCode {
+        code: EXIT_SUCCESS,
+        name: "Success",
+        description: "Analysis completed",
+    },
+    ExitCode {
+        code: 1,
+        name: "FileNotFound",
+        description: "Input file could not be read",
+    },
+    ExitCode {
+        code: 1,
+        name: "WriteFailed",
+        description: "Output file could not be written",
+    },
+    ExitCode {
+        code: 1,
+        name: "SymbolFilterFailed",
+        description: "--symbol-filter program failed",
+    },
+    ExitCode {
+        code: 1,
+        name: "AddressResolutionFailed",
+        description: "addr2line failed on a --binary",
+    },
+    ExitCode {
+        code: 2,
+        name: "InvalidFormat",
+        description: "Input is not a perf report",
+    },
+    ExitCode {
+        code: 2,
+        name: "NoCallGraph",
+        description: "--hierarchy used on a report recorded without call graphs",
+    },
+    ExitCode {
+        code: 2,
+        name: "CalleeOrderedCallGraph",
+        description: "paths or hotpath used on a callee-ordered call graph",
+    },
+    ExitCode {
+        code: 2,
+        name: "StrictWarnings",
+        description: "--strict and the report has unparseable lines",
+    },
+    ExitCode {
+        code: 2,
+        name: "ContributionOverflow",
+        description: "--strict-math and callers exceed a target's Children%",
+    },
+    ExitCode {
+        code: EXIT_USAGE,
+        name: "Usage",
+        description: "Invalid command-line arguments",
+    },
+    ExitCode {
+        code: EXIT_USAGE,
+        name: "InvalidCount",
+        description: "Invalid value for -n",
+    },
+    ExitCode {
+        code: EXIT_USAGE,
+        name: "HierarchyRequiresTargets",
+        description: "--hierarchy used without --targets",
+    },
+    ExitCode {
+        code: EXIT_USAGE,
+        name: "InvalidTargetFile",
+        description: "--target-file has an invalid line",
+    },
+    ExitCode {
+        code: EXIT_USAGE,
+        name: "InvalidBudgetFile",
+        description: "--budget file has an invalid table",
+    },
+    ExitCode {
+        code: EXIT_USAGE,
+        name: "InvalidWeights",
+        description: "--weight values do not match the report files",
+    },
+    ExitCode {
+        code: EXIT_USAGE,
+        name: "InvalidConfigFile",
+        description: "--config file has an invalid table",
+    },
+    ExitCode {
+        code: EXIT_USAGE,
+        name: "UnknownPreset",
+        description: "preset names no [presets] entry of the config file",
+    },
+    ExitCode {
+        code: 4,
+        name: "NoMatches",
+        description: "No function matched the given targets",
+    },
+    ExitCode {
+        code: 5,
+        name: "BudgetExceeded",
+        description: "--budget and a function exceeded its budget",
+    },
+    ExitCode {
+        code: 6,
+        name: "RegressionFound",
+        description: "diff --fail-above and a function regressed beyond the threshold",
+    },
+];
+
+impl PperfError {
+    /// Stable name of the error kind, used as the key into `EXIT_CODES`.
+    pub fn name(&self) -> &'static str {
+        match self {
+            PperfError::FileNotFound(_) => "FileNotFound",
+            PperfError::InvalidFormat => "InvalidFormat",
+            PperfError::InvalidCount => "InvalidCount",
+            PperfError::NoMatches(_) => "NoMatches",
+            PperfError::HierarchyRequiresTargets => "HierarchyRequiresTargets",
+            PperfError::WriteFailed(_) => "WriteFailed",
+            PperfError::NoCallGraph => "NoCallGraph",
+            PperfError::CalleeOrderedCallGraph(_) => "CalleeOrderedCallGraph",
+            PperfError::StrictWarnings(_) => "StrictWarnings",
+            PperfError::SymbolFilterFailed(_) => "SymbolFilterFailed",
+            PperfError::InvalidTargetFile(_) => "InvalidTargetFile",
+            PperfError::InvalidBudgetFile(_) => "InvalidBudgetFile",
+            PperfError::BudgetExceeded(_) => "BudgetExceeded",
+            PperfError::ContributionOverflow(_) => "ContributionOverflow",
+            PperfError::InvalidWeights(_) => "InvalidWeights",
+            PperfError::RegressionFound(_) => "RegressionFound",
+            PperfError::InvalidConfigFile(_) => "InvalidConfigFile",
+            PperfError::AddressResolutionFailed(_) => "AddressResolutionFailed",
+            PperfError::UnknownPreset(_) => "UnknownPreset",
+        }
+    }
+
+    pub fn exit_code(&self) -> i32 {
+        let name = self.name();
+        EXIT_CODES
+            .iter()
+            .find(|e| e.name == name)
+            .map(|e| e.code)
+            .expect("every PperfError variant has an EXIT_CODES row")
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
 
+    fn all_variants() -> Vec<PperfError> {
+        vec![
+            PperfError::FileNotFound("f".to_string()),
+            PperfError::InvalidFormat,
+            PperfError::InvalidCount,
+            PperfError::NoMatches(Vec::new()),
+            PperfError::HierarchyRequiresTargets,
+            PperfError::WriteFailed("f".to_string()),
+            PperfError::NoCallGraph,
+            PperfError::CalleeOrderedCallGraph("hotpath".to_string()),
+            PperfError::StrictWarnings(1),
+            PperfError::SymbolFilterFailed("f".to_string()),
+            PperfError::InvalidTargetFile("f".to_string()),
+            PperfError::InvalidBudgetFile("f".to_string()),
+            PperfError::BudgetExceeded(1),
+            PperfError::ContributionOverflow("f".to_string()),
+            PperfError::InvalidWeights("f".to_string()),
+            PperfError::RegressionFound(1),
+            PperfError::InvalidConfigFile("f".to_string()),
+            PperfError::AddressResolutionFailed("f".to_string()),
+            PperfError::UnknownPreset("f".to_string()),
+        ]
+    }
+
+    #[test]
+    fn test_exit_codes_cover_every_variant() {
+        for err in all_variants() {
+            assert!(
+                EXIT_CODES.iter().any(|e| e.name == err.name()),
+                "{} missing from EXIT_CODES",
+                err.name()
+            );
+        }
+    }
+
+    #[test]
+    fn test_exit_codes_are_stable() {
+        assert_eq!(PperfError::FileNotFound("f".to_string()).exit_code(), 1);
+        assert_eq!(PperfError::InvalidFormat.exit_code(), 2);
+        assert_eq!(PperfError::InvalidCount.exit_code(), 3);
+        assert_eq!(PperfError::NoMatches(Vec::new()).exit_code(), 4);
+        assert_eq!(PperfError::HierarchyRequiresTargets.exit_code(), 3);
+        assert_eq!(PperfError::WriteFailed("f".to_string()).exit_code(), 1);
+        assert_eq!(PperfError::NoCallGraph.exit_code(), 2);
+        assert_eq!(PperfError::StrictWarnings(1).exit_code(), 2);
+        assert_eq!(
+            PperfError::SymbolFilterFailed("f".to_string()).exit_code(),
+            1
+        );
+        assert_eq!(
+            PperfError::InvalidTargetFile("f".to_string()).exit_code(),
+            3
+        );
+        assert_eq!(
+            PperfError::InvalidBudgetFile("f".to_string()).exit_code(),
+            3
+        );
+        assert_eq!(PperfError::BudgetExceeded(1).exit_code(), 5);
+        assert_eq!(PperfError::InvalidWeights("f".to_string()).exit_code(), 3);
+        assert_eq!(PperfError::RegressionFound(1).exit_code(), 6);
+        assert_eq!(
+            PperfError::InvalidConfigFile("f".to_string()).exit_code(),
+            3
+        );
+        assert_eq!(
+            PperfError::AddressResolutionFailed("f".to_string()).exit_code(),
+            1
+        );
+        assert_eq!(PperfError::UnknownPreset("f".to_string()).exit_code(), 3);
+    }
+
+    #[test]
+    fn test_exit_code_names_unique() {
+        let mut names: Vec<&str> = EXIT_CODES.iter().map(|e| e.name).collect();
+        names.sort();
+        names.dedup();
+        assert_eq!(names.len(), EXIT_CODES.len());
+    }
+
     #[test]
     fn test_error_file_not_found() {
         let err = PperfError::FileNotFound("test.txt".to_string());
@@ -61,10 +379,19 @@ mod tests {
 
     #[test]
     fn test_error_no_matches() {
-        let err = PperfError::NoMatches;
+        let err = PperfError::NoMatches(Vec::new());
         assert_eq!(format!("{}", err), "No matching functions found");
     }
 
+    #[test]
+    fn test_error_no_matches_with_suggestions() {
+        let err = PperfError::NoMatches(vec!["DCT4DBlock".to_string(), "IDCT4DBlock".to_string()]);
+        assert_eq!(
+            format!("{}", err),
+            "No matching functions found; did you mean: DCT4DBlock, IDCT4DBlock?"
+        );
+    }
+
     #[test]
     fn test_error_hierarchy_requires_targets() {
         let err = PperfError::HierarchyRequiresTargets;
@@ -73,4 +400,45 @@ mod tests {
             "--hierarchy requires --targets to be specified"
         );
     }
+
+    #[test]
+    fn test_error_write_failed() {
+        let err = PperfError::WriteFailed("out.txt".to_string());
+        assert_eq!(format!("{}", err), "Failed to write: out.txt");
+    }
+
+    #[test]
+    fn test_error_no_call_graph() {
+        let err = PperfError::NoCallGraph;
+        let message = format!("{}", err);
+        assert!(message.starts_with("Report contains no call graph data"));
+        assert!(message.contains("perf record -g"));
+    }
+
+    #[test]
+    fn test_error_strict_warnings() {
+        let err = PperfError::StrictWarnings(3);
+        assert_eq!(
+            format!("{}", err),
+            "3 unparseable line(s) in report (--strict)"
+        );
+    }
+
+    #[test]
+    fn test_error_symbol_filter_failed() {
+        let err = PperfError::SymbolFilterFailed("strip: exited with 1".to_string());
+        assert_eq!(
+            format!("{}", err),
+            "Symbol filter failed: strip: exited with 1"
+        );
+    }
+
+    #[test]
+    fn test_error_invalid_target_file() {
+        let err = PperfError::InvalidTargetFile("t.txt: line 2: invalid regex".to_string());
+        assert_eq!(
+            format!("{}", err),
+            "Invalid target file: t.txt: line 2: invalid regex"
+        );
+    }
 }
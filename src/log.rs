@@ -0,0 +1,129 @@
+//! Leveled diagnostics on stderr (`-v`, `-vv`, `-vvv`): what pperf decided while
+//! reading a report, one `LEVEL target: message key=value ...` line per event so
+//! the output can be grepped or split on whitespace.
+//!
+//! The `tracing` crate is not available to this build, so events go through this
+//! small logger instead; it keeps tracing's shape (a level, a target and
+//! `key=value` fields) so call sites map onto `tracing::event!` one to one.
+
+use std::fmt;
+use std::sync::atomic::{AtomicU8, Ordering};
+
+static VERBOSITY: AtomicU8 = AtomicU8::new(0);
+
+/// How much detail an event carries; an event is printed when the verbosity
+/// (number of `-v` flags) is at least its level.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Level {
+    /// `-v`: per-report summaries such as the detected format and counts
+    Info = 1,
+    /// `-vv`: per-stage statistics such as tree sizes and relation counts
+    Debug = 2,
+    /// `-vvv`: per-line decisions such as each skipped line
+    Trace = 3,
+}
+
+impl fmt::Display for Level {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.pad(match self {
+            Level::Info => "INFO",
+            Level::Debug => "DEBUG",
+            Level::Trace => "TRACE",
+        })
+    }
+}
+
+/// Set the verbosity for the rest of the process (0 logs nothing).
+pub fn set_verbosity(verbosity: u8) {
+    VERBOSITY.store(verbosity, Ordering::Relaxed);
+}
+
+pub fn verbosity() -> u8 {
+    VERBOSITY.load(Ordering::Relaxed)
+}
+
+pub fn enabled(level: Level) -> bool {
+    verbosity() >= level as u8
+}
+
+/// Format one event line (without the newline). Values containing whitespace
+/// or `=` are quoted so each field stays a single `key=value` word.
+pub fn format_event(
+    level: Level,
+    target: &str,
+    message: &str,
+    fields: &[(&str, &dyn fmt::Display)],
+) -> String {
+    let mut line = format!("{:>5} {}: {}", level, target, message);
+    for (key, value) in fields {
+        let value = value.to_string();
+        if value.is_empty() || value.contains(|c: char| c.is_whitespace() || c == '=' || c == '"') {
+            line.push_str(&format!(" {}={:?}", key, value));
+        } else {
+            line.push_str(&format!(" {}={}", key, value));
+        }
+    }
+    line
+}
+
+/// Print an event to stderr if its level is enabled; see the `log!` macro.
+pub fn event(level: Level, target: &str, message: &str, fields: &[(&str, &dyn fmt::Display)]) {
+    if enabled(level) {
+        eprintln!("{}", format_event(level, target, message, fields));
+    }
+}
+
+/// Log an event: `log!(Info, "parser", "report parsed", entries = n, skipped = m)`.
+/// Fields are only formatted when the level is enabled.
+#[macro_export]
+macro_rules! log {
+    ($level:ident, $target:expr, $message:expr $(, $key:ident = $value:expr)* $(,)?) => {
+        if $crate::log::enabled($crate::log::Level::$level) {
+            $crate::log::event(
+                $crate::log::Level::$level,
+                $target,
+                $message,
+                &[$((stringify!($key), &$value as &dyn ::std::fmt::Display)),*],
+            );
+        }
+    };
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_format_event_fields() {
+        let line = format_event(
+            Level::Info,
+            "parser",
+            "report parsed",
+            &[("entries", &12), ("layout", &"children-self")],
+        );
+        assert_eq!(
+            line,
+            " INFO parser: report parsed entries=12 layout=children-self"
+        );
+    }
+
+    #[test]
+    fn test_format_event_quotes_spaced_values() {
+        let line = format_event(
+            Level::Trace,
+            "parser",
+            "line skipped",
+            &[("reason", &"unparseable entry line"), ("symbol", &"")],
+        );
+        assert_eq!(
+            line,
+            "TRACE parser: line skipped reason=\"unparseable entry line\" symbol=\"\""
+        );
+    }
+
+    #[test]
+    fn test_level_order() {
+        assert!(Level::Info < Level::Debug);
+        assert!(Level::Debug < Level::Trace);
+    }
+}
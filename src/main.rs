@@ -1,14 +1,123 @@
+use std::collections::{HashMap, HashSet};
+use std::ffi::OsString;
 use std::fs;
+use std::io::Write;
 use std::path::PathBuf;
 use std::process;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Mutex, OnceLock};
 
-use clap::{Args, Parser, Subcommand};
+use clap::{ArgAction, Args, CommandFactory, Parser, Subcommand};
 
-use pperf::PperfError;
-use pperf::hierarchy::{build_hierarchy_entries, compute_call_relations, parse_file_call_trees};
-use pperf::output::{format_hierarchy_table, format_table};
-use pperf::parser::{SortOrder, parse_file, sort_entries};
-use pperf::symbol::should_use_color;
+use pperf::averaging::{Aggregation, ReportSet, average_callers, average_relations};
+use pperf::budget::{check_budgets, parse_budget_file};
+use pperf::config::find_config;
+use pperf::demangle::is_mangled;
+use pperf::diff::{Change, EntryDiff, RelationDiff, biggest_movers, diff_entries, diff_relations};
+use pperf::distribution::distribution;
+use pperf::filter::{
+    GroupBy, MatchOptions, SymbolSpace, Target, TargetSpec, closest_signature, exclude_entries,
+    filter_entries, filter_entries_by_comm, filter_entries_by_dso, filter_entries_by_min_pct,
+    filter_entries_by_space, filter_entries_with, format_target_line, merge_overloads,
+    merge_scopes, merge_target_groups, merge_target_sections, parse_target_file, read_pattern_file,
+    suggest_symbols,
+};
+use pperf::hierarchy::{
+    CallRelation, CallTreeNode, ContributionMode, build_hierarchy_entries_with,
+    collapse_inlined_frames, compute_call_relations_with, compute_callee_order_relations,
+    convert_absolute_percentages, filter_relations_by_context, filter_relations_by_min_pct,
+    filter_relations_by_min_total, find_call_paths, find_callees, find_callers,
+    find_contribution_overflows, find_hot_path, guessed_caller_entries, guessed_caller_trees,
+    relabel_target_groups, transform_tree_symbols, tree_contains,
+};
+use pperf::log::{self, Level};
+use pperf::output::{
+    SymbolAliases, TreeStyle, TruncateMode, add_bar_column, add_rank_column, add_srcline_column,
+    decoration_width, format_budget_table, format_butterfly, format_call_paths, format_call_tree,
+    format_callers, format_distribution, format_edge_agreement, format_entry_diff,
+    format_hierarchy_table, format_hot_path, format_metadata, format_overhead_table,
+    format_regression_summary, format_relation_diff, format_scope_table, format_set_table,
+    format_stats_table, format_table, format_trend_table, mark_high_variance, mark_overloads,
+    reserve_columns, set_heat, set_precision, set_terminal_width, set_truncate_mode,
+    strip_table_header,
+};
+use pperf::parser::{
+    ParsedReport, PercentType, PerfEntry, ReportLayout, SortOrder, TreeOrder, sort_entries,
+    transform_entry_symbols,
+};
+use pperf::resolve::{Kallsyms, PROC_KALLSYMS, PerfMap, find_perf_maps, resolve_binary_addresses};
+use pperf::setops::{SetMode, symbol_set_rows};
+use pperf::symbol::{
+    DemangleMode, FILE_LINK_TEMPLATE, Symbol, run_symbol_filter, set_classification_rules,
+    set_demangle_mode, set_display_aliases, set_hyperlink_template, set_rewrite_rules,
+    set_source_locations, should_use_color, simplify_symbol, terminal_width,
+};
+use pperf::synth::{SynthConfig, generate_report};
+use pperf::trend::trend_rows;
+use pperf::{EXIT_CODES, EXIT_SUCCESS, EXIT_USAGE, PperfError};
+
+/// The `--output` file, once opened; results go to stdout while it is None.
+static OUTPUT_FILE: Mutex<Option<fs::File>> = Mutex::new(None);
+/// Set when a write to `OUTPUT_FILE` failed, reported once the command finishes.
+static OUTPUT_FAILED: AtomicBool = AtomicBool::new(false);
+/// `--no-header`: results are written without table header lines.
+static NO_HEADER: AtomicBool = AtomicBool::new(false);
+/// `--quiet`: results are written without header, title or legend lines, and
+/// warnings are not printed.
+static QUIET: AtomicBool = AtomicBool::new(false);
+/// `--symbol-filter`: the program every parsed report's symbols are rewritten through.
+static SYMBOL_FILTER: OnceLock<PathBuf> = OnceLock::new();
+
+/// `print!` to the results: the `--output` file if given, else stdout.
+macro_rules! out {
+    ($($arg:tt)*) => {
+        write_output(&format!($($arg)*))
+    };
+}
+
+/// `println!` to the results: the `--output` file if given, else stdout.
+macro_rules! outln {
+    () => {
+        write_output("\n")
+    };
+    ($($arg:tt)*) => {
+        write_output(&format!("{}\n", format_args!($($arg)*)))
+    };
+}
+
+/// `eprintln!` for warnings, which `--quiet` silences.
+macro_rules! warnln {
+    ($($arg:tt)*) => {
+        if !QUIET.load(Ordering::Relaxed) {
+            eprintln!($($arg)*)
+        }
+    };
+}
+
+fn write_output(text: &str) {
+    // Every table is written whole, so headers can be told apart from rows here
+    let quiet = QUIET.load(Ordering::Relaxed);
+    let stripped;
+    let text = if quiet || NO_HEADER.load(Ordering::Relaxed) {
+        stripped = strip_table_header(text, quiet);
+        stripped.as_str()
+    } else {
+        text
+    };
+    match OUTPUT_FILE.lock().unwrap().as_mut() {
+        Some(file) => {
+            if file.write_all(text.as_bytes()).is_err() {
+                OUTPUT_FAILED.store(true, Ordering::Relaxed);
+            }
+        }
+        None => print!("{}", text),
+    }
+}
+
+/// `should_use_color`, but never when the results go to an `--output` file.
+fn color_enabled(no_color_flag: bool) -> bool {
+    OUTPUT_FILE.lock().unwrap().is_none() && should_use_color(no_color_flag)
+}
 
 /// Parse count argument, ensuring it's >= 1
 fn parse_count(s: &str) -> Result<usize, String> {
@@ -22,10 +131,170 @@ fn parse_count(s: &str) -> Result<usize, String> {
     }
 }
 
+/// Parse a decimal place count: 0 to 6
+fn parse_precision(s: &str) -> Result<usize, String> {
+    match s.parse::<usize>() {
+        Ok(places) if places <= 6 => Ok(places),
+        _ => Err(format!("'{}' is not a number from 0 to 6", s)),
+    }
+}
+
+/// Parse a report weight: a positive number
+fn parse_weight(s: &str) -> Result<f64, String> {
+    match s.parse::<f64>() {
+        Ok(weight) if weight > 0.0 && weight.is_finite() => Ok(weight),
+        _ => Err(format!("'{}' is not a positive number", s)),
+    }
+}
+
+/// Split `FILE:WEIGHT` suffixes off the report paths, or take one `--weight` per
+/// file in order; 1.0 each without either. A path that exists as given is never split.
+fn report_weights(
+    files: &[PathBuf],
+    weights: &[f64],
+) -> Result<(Vec<PathBuf>, Vec<f64>), PperfError> {
+    let mut paths = Vec::new();
+    let mut suffixed = Vec::new();
+    for file in files {
+        let split = file
+            .to_str()
+            .filter(|_| !file.exists())
+            .and_then(|name| name.rsplit_once(':'))
+            .and_then(|(path, weight)| Some((path, parse_weight(weight).ok()?)));
+        match split {
+            Some((path, weight)) => {
+                paths.push(PathBuf::from(path));
+                suffixed.push(weight);
+            }
+            None => {
+                paths.push(file.clone());
+                suffixed.push(1.0);
+            }
+        }
+    }
+    let has_suffix = paths.iter().zip(files).any(|(path, file)| path != file);
+    if weights.is_empty() {
+        return Ok((paths, suffixed));
+    }
+    if has_suffix {
+        return Err(PperfError::InvalidWeights(
+            "use either --weight or FILE:WEIGHT, not both".to_string(),
+        ));
+    }
+    if weights.len() != files.len() {
+        return Err(PperfError::InvalidWeights(format!(
+            "{} --weight value(s) for {} report(s)",
+            weights.len(),
+            files.len()
+        )));
+    }
+    Ok((paths, weights.to_vec()))
+}
+
+/// Parse a percentage argument such as `0.5` or `0.5%`, within 0..=100
+fn parse_min_pct(s: &str) -> Result<f64, String> {
+    let pct = pperf::parser::parse_percentage(s.trim_end_matches('%'))
+        .ok_or_else(|| format!("'{}' is not a valid percentage", s))?;
+    if (0.0..=100.0).contains(&pct) {
+        Ok(pct)
+    } else {
+        Err("percentage must be between 0 and 100".to_string())
+    }
+}
+
+/// Parse a `--tree-order` value: `caller` or `callee`
+fn parse_tree_order(s: &str) -> Result<TreeOrder, String> {
+    TreeOrder::from_setting(s).ok_or_else(|| format!("'{}' is not caller or callee", s))
+}
+
+/// Parse a `--contribution-mode` value: `max` or `sum`
+fn parse_contribution_mode(s: &str) -> Result<ContributionMode, String> {
+    ContributionMode::from_setting(s).ok_or_else(|| format!("'{}' is not max or sum", s))
+}
+
+/// Parse a `--tree-style` value: `unicode`, `ascii` or `plain`
+fn parse_tree_style(s: &str) -> Result<TreeStyle, String> {
+    TreeStyle::from_setting(s).ok_or_else(|| format!("'{}' is not unicode, ascii or plain", s))
+}
+
+/// Parse a `--truncate` value: `end` or `middle`
+fn parse_truncate_mode(s: &str) -> Result<TruncateMode, String> {
+    TruncateMode::from_setting(s).ok_or_else(|| format!("'{}' is not end or middle", s))
+}
+
+/// Parse a `--demangle` value: `none`, `simplified` or `full`
+fn parse_demangle_mode(s: &str) -> Result<DemangleMode, String> {
+    DemangleMode::from_setting(s).ok_or_else(|| format!("'{}' is not none, simplified or full", s))
+}
+
+/// Parse a `--group-by` value: `class` or `namespace`
+fn parse_group_by(s: &str) -> Result<GroupBy, String> {
+    GroupBy::from_setting(s).ok_or_else(|| format!("'{}' is not class or namespace", s))
+}
+
+fn parse_aggregation(s: &str) -> Result<Aggregation, String> {
+    Aggregation::from_setting(s)
+        .ok_or_else(|| format!("'{}' is not mean, median, geomean or harmonic", s))
+}
+
 /// Perf report analyzer
 #[derive(Parser)]
 #[command(name = "pperf", version, about)]
 struct Cli {
+    /// Print skipped report lines and what was detected while reading (-v), plus
+    /// call-tree and relation statistics (-vv) and each skipped line's reason (-vvv)
+    #[arg(short = 'v', long = "verbose", global = true, action = ArgAction::Count)]
+    verbose: u8,
+
+    /// Leave out the column header line of every table, so each line is a row
+    #[arg(long = "no-header", global = true)]
+    no_header: bool,
+
+    /// Print only table rows: no headers, titles, report legends, notes or warnings
+    #[arg(short = 'q', long = "quiet", global = true, conflicts_with = "verbose")]
+    quiet: bool,
+
+    /// Write the results to FILE instead of stdout (without colors)
+    #[arg(short = 'o', long = "output", value_name = "FILE", global = true)]
+    output: Option<PathBuf>,
+
+    /// Read settings such as symbol rewrite rules from FILE instead of ./pperf.toml
+    #[arg(long = "config", value_name = "FILE", global = true)]
+    config: Option<PathBuf>,
+
+    /// Rewrite every simplified symbol through EXE (one symbol per line on stdin and
+    /// stdout) right after the reports are parsed
+    #[arg(long = "symbol-filter", value_name = "EXE", global = true)]
+    symbol_filter: Option<PathBuf>,
+
+    /// Lay tables out for an N-column terminal instead of the detected width
+    #[arg(long = "width", value_name = "N", global = true, value_parser = parse_count)]
+    width: Option<usize>,
+
+    /// Show percentages with N decimal places (0-6)
+    #[arg(long = "precision", value_name = "N", global = true, default_value_t = 2, value_parser = parse_precision)]
+    precision: usize,
+
+    /// Cut long symbols at the `end` or in the `middle`, keeping the class prefix
+    /// and the method name
+    #[arg(long = "truncate", value_name = "MODE", global = true, default_value = "end", value_parser = parse_truncate_mode)]
+    truncate: TruncateMode,
+
+    /// Show symbols `none` (as in the report), `simplified` (Class::function) or
+    /// `full` (demangled with templates and arguments)
+    #[arg(long = "demangle", value_name = "MODE", global = true, default_value = "simplified", value_parser = parse_demangle_mode)]
+    demangle: DemangleMode,
+
+    /// Never simplify symbols, so overloads such as `f(int)` and `f(double)` stay
+    /// apart in output, matching and the hierarchy (`--demangle full`)
+    #[arg(long = "raw-symbols", global = true, conflicts_with = "demangle")]
+    raw_symbols: bool,
+
+    /// Link symbols with a known source location to it in the terminal: to the file,
+    /// or `--hyperlinks=TEMPLATE` with `{file}` and `{line}` (e.g. a GitHub URL)
+    #[arg(long = "hyperlinks", value_name = "TEMPLATE", global = true, num_args = 0..=1, require_equals = true, default_missing_value = FILE_LINK_TEMPLATE)]
+    hyperlinks: Option<String>,
+
     #[command(subcommand)]
     command: Commands,
 }
@@ -33,10 +302,58 @@ struct Cli {
 #[derive(Subcommand)]
 enum Commands {
     /// Display top functions by CPU time
-    Top(TopArgs),
+    Top(Box<TopArgs>),
+    /// Generate a synthetic perf report for reproducible bug reports
+    Synth(SynthArgs),
+    /// Compare the symbol sets of several reports
+    Set(SetArgs),
+    /// Show what changed between two reports
+    Diff(DiffArgs),
+    /// Show how each function moves over an ordered series of reports
+    Trend(TrendArgs),
+    /// Show one function's callers above it and its callees below it
+    Butterfly(ButterflyArgs),
+    /// List every call chain from one function down to another
+    Paths(PathsArgs),
+    /// Print the call chain ending in the largest share of runtime
+    Hotpath(HotpathArgs),
+    /// Show how concentrated a profile is: entries above 10%/1%/0.1%, how many
+    /// cover most of the self time, and a Self% histogram
+    Stats(StatsArgs),
+    /// Work with `--target-file` files
+    Targets {
+        #[command(subcommand)]
+        command: TargetsCommand,
+    },
+    /// List exit codes as tab-separated `code name description` rows
+    ExitCodes,
+    /// Run a command line saved in the config file's `[presets]` table; without a
+    /// name, list the presets as tab-separated `name command` rows
+    Preset(PresetArgs),
+}
+
+#[derive(Args)]
+struct PresetArgs {
+    /// Preset to run, then arguments appended to its command line (e.g. the report
+    /// file); everything after the name is passed on as written
+    #[arg(
+        value_name = "NAME [ARGS]",
+        trailing_var_arg = true,
+        allow_hyphen_values = true
+    )]
+    words: Vec<String>,
+}
+
+#[derive(Subcommand)]
+enum TargetsCommand {
+    /// Write a target file with the exact signatures of a report's top functions
+    Generate(GenerateArgs),
+    /// List the report entries each target-file line matches
+    Check(CheckArgs),
 }
 
 #[derive(Args)]
+#[command(group(clap::ArgGroup::new("tree_views").multiple(true).args(["hierarchy", "callers"])))]
 struct TopArgs {
     /// Sort by Self% instead of Children%
     #[arg(short = 's', long = "self")]
@@ -46,110 +363,1958 @@ struct TopArgs {
     #[arg(short = 'n', long = "number", default_value = "10", value_parser = parse_count)]
     number: usize,
 
-    /// Filter by function name substrings (repeatable: -t val1 -t val2)
+    /// Filter by function name substrings (repeatable: -t val1 -t val2; 'A|B' groups alternatives)
     #[arg(short = 't', long = "targets")]
     targets: Vec<String>,
 
+    /// Read targets from FILE: one exact signature per line, or `re:PATTERN`
+    #[arg(long = "target-file", value_name = "FILE")]
+    target_file: Option<PathBuf>,
+
+    /// Check the report against the per-target maxima in FILE; exit 5 if any is exceeded
+    #[arg(long = "budget", value_name = "FILE", conflicts_with_all = ["hierarchy", "dry_run"])]
+    budget: Option<PathBuf>,
+
+    /// Sum Children%/Self% per target-file section instead of listing each member
+    #[arg(long = "group", requires = "target_file", conflicts_with = "hierarchy")]
+    group: bool,
+
+    /// Sum Self% per `class` (first two `::` components) or `namespace` (first
+    /// component) instead of listing each function, ranked by it
+    #[arg(long = "group-by", value_name = "SCOPE", value_parser = parse_group_by, conflicts_with_all = ["hierarchy", "callers", "tree", "group", "merge_overloads", "budget", "min_children"])]
+    group_by: Option<GroupBy>,
+
+    /// Hide symbols containing PATTERN, also from hierarchy callee discovery (repeatable)
+    #[arg(long = "exclude", value_name = "PATTERN")]
+    exclude: Vec<String>,
+
+    /// Only analyze samples whose Command column is exactly NAME (repeatable)
+    #[arg(long = "comm", value_name = "NAME")]
+    comms: Vec<String>,
+
+    /// Only show symbols from shared objects containing NAME (repeatable)
+    #[arg(long = "dso", value_name = "NAME")]
+    dsos: Vec<String>,
+
+    /// Only show user-space (`[.]`) symbols
+    #[arg(long = "user-only", conflicts_with = "kernel_only")]
+    user_only: bool,
+
+    /// Only show kernel (`[k]`) symbols
+    #[arg(long = "kernel-only")]
+    kernel_only: bool,
+
+    /// Hide every symbol matching a pattern in FILE, one per line (`#` comments)
+    #[arg(long = "ignore-file", value_name = "FILE")]
+    ignore_file: Option<PathBuf>,
+
+    /// Match targets and exclusions case-insensitively
+    #[arg(short = 'i', long = "ignore-case")]
+    ignore_case: bool,
+
     /// Display call relationships between targets
     #[arg(short = 'H', long = "hierarchy")]
     hierarchy: bool,
 
+    /// Print the full call tree under the hottest function matching NAME
+    #[arg(long = "tree", value_name = "NAME", conflicts_with_all = ["hierarchy", "callers", "group", "budget", "dry_run"])]
+    tree: Option<String>,
+
+    /// Show who calls each displayed function, found by walking every call tree
+    #[arg(long = "callers", conflicts_with_all = ["hierarchy", "group"])]
+    callers: bool,
+
     /// Show calculation path for hierarchy percentages
     #[arg(short = 'D', long = "debug")]
     debug: bool,
 
+    /// Only show hierarchy relations found inside this caller's call tree
+    #[arg(long = "context", value_name = "CALLER", requires = "hierarchy")]
+    context: Option<String>,
+
+    /// Search at most N call-tree levels below each hierarchy caller
+    #[arg(long = "max-depth", value_name = "N", requires = "hierarchy", value_parser = parse_count)]
+    max_depth: Option<usize>,
+
+    /// Only show hierarchy relations contributing at least PCT% of total runtime
+    #[arg(long = "min-total", value_name = "PCT", requires = "hierarchy", value_parser = parse_min_pct)]
+    min_total: Option<f64>,
+
+    /// Only show hierarchy relations reaching PCT% both of their caller and of total runtime
+    #[arg(long = "min-pct", value_name = "PCT", requires = "hierarchy", value_parser = parse_min_pct)]
+    min_pct: Option<f64>,
+
+    /// Read call trees as `caller` (children are callees) or `callee` (children are
+    /// callers) ordered, overriding the report header
+    #[arg(long = "tree-order", value_name = "ORDER", requires = "tree_views", value_parser = parse_tree_order)]
+    tree_order: Option<TreeOrder>,
+
+    /// Read every call tree as listing callees instead of guessing from Self%/Children%
+    #[arg(
+        long = "assume-callee-trees",
+        requires = "tree_views",
+        conflicts_with = "tree_order"
+    )]
+    assume_callee_trees: bool,
+
+    /// Fold `(inlined)` call-graph frames (`perf report --inline`) into the frame
+    /// they were inlined into
+    #[arg(long = "collapse-inlined")]
+    collapse_inlined: bool,
+
+    /// Combine a caller's distinct paths to a callee by `max` (heaviest path) or `sum`
+    #[arg(long = "contribution-mode", value_name = "MODE", default_value = "max", requires = "hierarchy", value_parser = parse_contribution_mode)]
+    contribution_mode: ContributionMode,
+
+    /// Draw hierarchy nesting as `unicode` (├──/└──) or `ascii` (|--/`--) connectors,
+    /// or `plain` indentation
+    #[arg(long = "tree-style", value_name = "STYLE", default_value = "plain", requires = "hierarchy", value_parser = parse_tree_style)]
+    tree_style: TreeStyle,
+
+    /// Fail instead of warning when callers account for more than a target's Children%
+    #[arg(long = "strict-math", requires = "hierarchy")]
+    strict_math: bool,
+
+    /// Drop entries (and hierarchy callees) below PCT Children%
+    #[arg(long = "min-children", value_name = "PCT", value_parser = parse_min_pct)]
+    min_children: Option<f64>,
+
+    /// Drop entries (and hierarchy callees) below PCT Self%
+    #[arg(long = "min-self", value_name = "PCT", value_parser = parse_min_pct)]
+    min_self: Option<f64>,
+
+    /// Combine several reports' values by `mean`, `median`, `geomean` or `harmonic`
+    #[arg(long = "agg", value_name = "MODE", default_value = "mean", value_parser = parse_aggregation)]
+    agg: Aggregation,
+
+    /// Count an entry missing from a report as 0% there, averaging over every report;
+    /// by default it is averaged over the reports that contain it
+    #[arg(long = "missing-as-zero")]
+    missing_as_zero: bool,
+
+    /// Leave reports flagged as outlier runs out of the average
+    #[arg(long = "drop-outliers")]
+    drop_outliers: bool,
+
+    /// Print each report's own table (headed by its file name) before the combined one
+    #[arg(long = "per-file", conflicts_with_all = ["hierarchy", "callers", "tree", "budget", "dry_run"])]
+    per_file: bool,
+
+    /// Add standard deviation, min and max across reports after each percentage
+    #[arg(long = "stats", conflicts_with_all = ["hierarchy", "callers", "tree", "group", "budget", "dry_run"])]
+    stats: bool,
+
+    /// Mark entries whose Children% varies across reports by more than PCT (coefficient
+    /// of variation)
+    #[arg(long = "flag-variance", value_name = "PCT", value_parser = parse_min_pct, conflicts_with_all = ["hierarchy", "callers", "tree", "group", "budget", "dry_run"])]
+    flag_variance: Option<f64>,
+
+    /// List overloads and template specializations with the same simplified name as
+    /// one entry, summing Children%/Self%; `--debug` lists the merged signatures
+    #[arg(long = "merge-overloads", conflicts_with_all = ["hierarchy", "callers", "tree", "budget", "dry_run"])]
+    merge_overloads: bool,
+
+    /// Number the listed entries 1..N in a leading `#` column
+    #[arg(long = "rank", conflicts_with_all = ["hierarchy", "callers", "tree", "group", "budget", "dry_run"])]
+    rank: bool,
+
+    /// Draw a bar of each entry's Children% (Overhead% for `--no-children` reports)
+    /// in front of its name
+    #[arg(long = "bars", conflicts_with_all = ["hierarchy", "callers", "tree", "group", "budget", "dry_run"])]
+    bars: bool,
+
+    /// Show each entry's `file:line` in a Source column (reports sorted by `srcline`)
+    #[arg(long = "show-srcline", conflicts_with_all = ["hierarchy", "callers", "tree", "budget", "dry_run"])]
+    show_srcline: bool,
+
+    /// Color Children%/Self% by magnitude: red above 50%, yellow above 10%
+    #[arg(long = "heat")]
+    heat: bool,
+
+    /// Disable colored output
+    #[arg(long = "no-color")]
+    no_color: bool,
+
+    /// Print report header metadata (samples, event count, command line)
+    #[arg(long = "show-meta")]
+    show_meta: bool,
+
+    /// Fail if any report line could not be parsed
+    #[arg(long = "strict")]
+    strict: bool,
+
+    /// Name JIT-compiled functions shown as addresses from perf map FILE (repeatable);
+    /// by default `/tmp/perf-<pid>.map` of each JIT process in the report
+    #[arg(long = "perf-map", value_name = "FILE")]
+    perf_maps: Vec<PathBuf>,
+
+    /// Name address-only symbols of the Shared Object PATH (matched by file name)
+    /// through `addr2line` and its debug info (repeatable)
+    #[arg(long = "binary", value_name = "PATH")]
+    binaries: Vec<PathBuf>,
+
+    /// Name `[k]` symbols shown as addresses from a kallsyms listing: `--kallsyms=FILE`,
+    /// or /proc/kallsyms for a bare `--kallsyms`
+    #[arg(long = "kallsyms", value_name = "FILE", num_args = 0..=1, require_equals = true, default_missing_value = PROC_KALLSYMS)]
+    kallsyms: Option<PathBuf>,
+
+    /// Show the resolved inputs, options and target matches instead of the table
+    #[arg(long = "dry-run")]
+    dry_run: bool,
+
+    /// Weight of each report when combining, one per file in order (`FILE:WEIGHT` also works)
+    #[arg(long = "weight", value_name = "W", value_parser = parse_weight)]
+    weights: Vec<f64>,
+
+    /// Perf report file(s) to analyze (`-` reads stdin); several runs are averaged
+    #[arg(required = true)]
+    files: Vec<PathBuf>,
+}
+
+#[derive(Args)]
+#[command(group(clap::ArgGroup::new("mode").required(true).args(["intersect", "union", "only_in"])))]
+struct SetArgs {
+    /// Symbols present in every report
+    #[arg(long = "intersect")]
+    intersect: bool,
+
+    /// Symbols present in any report
+    #[arg(long = "union")]
+    union: bool,
+
+    /// Symbols present in FILE and in none of the other reports
+    #[arg(long = "only-in", value_name = "FILE")]
+    only_in: Option<PathBuf>,
+
+    /// Number of symbols to display
+    #[arg(short = 'n', long = "number", default_value = "10", value_parser = parse_count)]
+    number: usize,
+
+    /// Disable colored output
+    #[arg(long = "no-color")]
+    no_color: bool,
+
+    /// Perf report files to compare (`--only-in FILE` is added if not listed)
+    #[arg(required = true)]
+    files: Vec<PathBuf>,
+}
+
+#[derive(Args)]
+struct DiffArgs {
+    /// Only compare functions matching these substrings (as in `top -t`)
+    #[arg(short = 't', long = "targets")]
+    targets: Vec<String>,
+
+    /// Compare the call relationships between targets instead of the entries
+    #[arg(short = 'H', long = "hierarchy")]
+    hierarchy: bool,
+
+    /// Only show changes of more than PCT percentage points
+    #[arg(long = "threshold", value_name = "PCT", default_value = "0.5", value_parser = parse_min_pct)]
+    threshold: f64,
+
+    /// Show only the N largest increases and the N largest decreases (instead of -n)
+    #[arg(long = "top-diff", value_name = "N", value_parser = parse_count)]
+    top_diff: Option<usize>,
+
+    /// Color growth (red) and drops (green) of more than PCT points; smaller moves stay
+    /// uncolored
+    #[arg(long = "neutral-band", value_name = "PCT", default_value = "1.0", value_parser = parse_min_pct)]
+    neutral_band: f64,
+
+    /// Exit with code 6 if any compared function's Children% grew by more than PCT
+    /// points, after a tab-separated summary
+    #[arg(long = "fail-above", value_name = "PCT", value_parser = parse_min_pct, conflicts_with = "hierarchy")]
+    fail_above: Option<f64>,
+
+    /// Number of functions to display
+    #[arg(short = 'n', long = "number", default_value = "10", value_parser = parse_count)]
+    number: usize,
+
+    /// Match case-insensitively (as in `top -i`)
+    #[arg(short = 'i', long = "ignore-case")]
+    ignore_case: bool,
+
+    /// Disable colored output
+    #[arg(long = "no-color")]
+    no_color: bool,
+
+    /// Baseline perf report (`-` reads stdin)
+    old: PathBuf,
+
+    /// Perf report compared against the baseline
+    new: PathBuf,
+}
+
+#[derive(Args)]
+struct TrendArgs {
+    /// Only follow functions matching these substrings (as in `top -t`)
+    #[arg(short = 't', long = "targets")]
+    targets: Vec<String>,
+
+    /// Number of functions to display
+    #[arg(short = 'n', long = "number", default_value = "10", value_parser = parse_count)]
+    number: usize,
+
+    /// Match case-insensitively (as in `top -i`)
+    #[arg(short = 'i', long = "ignore-case")]
+    ignore_case: bool,
+
+    /// Disable colored output
+    #[arg(long = "no-color")]
+    no_color: bool,
+
+    /// Show only the N steepest rising and the N steepest falling functions (instead of -n)
+    #[arg(long = "top-diff", value_name = "N", value_parser = parse_count)]
+    top_diff: Option<usize>,
+
+    /// Perf report files, oldest first; a directory stands for its files in name order
+    #[arg(required = true)]
+    files: Vec<PathBuf>,
+}
+
+#[derive(Args)]
+struct ButterflyArgs {
+    /// Function name substring; the hottest matching function is shown
+    #[arg(short = 't', long = "target", value_name = "FUNC")]
+    target: String,
+
+    /// Match case-insensitively (as in `top -i`)
+    #[arg(short = 'i', long = "ignore-case")]
+    ignore_case: bool,
+
+    /// Read call trees as `caller` (children are callees) or `callee` (children are
+    /// callers) ordered, overriding the report header
+    #[arg(long = "tree-order", value_name = "ORDER", value_parser = parse_tree_order)]
+    tree_order: Option<TreeOrder>,
+
+    /// Read every call tree as listing callees instead of guessing from Self%/Children%
+    #[arg(long = "assume-callee-trees", conflicts_with = "tree_order")]
+    assume_callee_trees: bool,
+
+    /// Disable colored output
+    #[arg(long = "no-color")]
+    no_color: bool,
+
+    /// Perf report file to read (`-` reads stdin)
+    file: PathBuf,
+}
+
+#[derive(Args)]
+struct PathsArgs {
+    /// Caller name substring; the hottest matching function's call tree is searched
+    #[arg(long = "from", value_name = "FUNC")]
+    from: String,
+
+    /// Callee name substring; each chain ends at the first matching function
+    #[arg(long = "to", value_name = "FUNC")]
+    to: String,
+
+    /// Match case-insensitively (as in `top -i`)
+    #[arg(short = 'i', long = "ignore-case")]
+    ignore_case: bool,
+
+    /// Read call trees as `caller` (children are callees) or `callee` (children are
+    /// callers) ordered, overriding the report header
+    #[arg(long = "tree-order", value_name = "ORDER", value_parser = parse_tree_order)]
+    tree_order: Option<TreeOrder>,
+
+    /// Read every call tree as listing callees instead of guessing from Self%/Children%
+    #[arg(long = "assume-callee-trees", conflicts_with = "tree_order")]
+    assume_callee_trees: bool,
+
+    /// Disable colored output
+    #[arg(long = "no-color")]
+    no_color: bool,
+
+    /// Perf report file to read (`-` reads stdin)
+    file: PathBuf,
+}
+
+#[derive(Args)]
+struct HotpathArgs {
+    /// Read call trees as `caller` (children are callees) or `callee` (children are
+    /// callers) ordered, overriding the report header
+    #[arg(long = "tree-order", value_name = "ORDER", value_parser = parse_tree_order)]
+    tree_order: Option<TreeOrder>,
+
+    /// Read every call tree as listing callees instead of guessing from Self%/Children%
+    #[arg(long = "assume-callee-trees", conflicts_with = "tree_order")]
+    assume_callee_trees: bool,
+
     /// Disable colored output
     #[arg(long = "no-color")]
     no_color: bool,
 
-    /// Perf report file to analyze
+    /// Perf report file to read (`-` reads stdin)
     file: PathBuf,
 }
 
+#[derive(Args)]
+struct StatsArgs {
+    /// Perf report file to read (`-` reads stdin)
+    file: PathBuf,
+}
+
+#[derive(Args)]
+struct GenerateArgs {
+    /// Rank by Self% instead of Children%
+    #[arg(short = 's', long = "self")]
+    sort_self: bool,
+
+    /// Number of functions to write
+    #[arg(short = 'n', long = "number", default_value = "10", value_parser = parse_count)]
+    number: usize,
+
+    /// Only consider functions matching these substrings (as in `top -t`)
+    #[arg(short = 't', long = "targets")]
+    targets: Vec<String>,
+
+    /// Perf report file to read (`-` reads stdin)
+    file: PathBuf,
+}
+
+#[derive(Args)]
+struct CheckArgs {
+    /// Target file to check
+    #[arg(long = "target-file", value_name = "FILE")]
+    target_file: PathBuf,
+
+    /// Match case-insensitively (as in `top -i`)
+    #[arg(short = 'i', long = "ignore-case")]
+    ignore_case: bool,
+
+    /// Perf report file to check against (`-` reads stdin)
+    file: PathBuf,
+}
+
+#[derive(Args)]
+struct SynthArgs {
+    /// Number of top-level entries to generate
+    #[arg(long = "entries", default_value = "20", value_parser = parse_count)]
+    entries: usize,
+
+    /// Maximum call tree depth below each entry
+    #[arg(long = "depth", default_value = "3")]
+    depth: usize,
+
+    /// Seed for the deterministic generator
+    #[arg(long = "seed", default_value = "42")]
+    seed: u64,
+}
+
+/// Print a command-line parse error (or `--help`/`--version`) and exit.
+fn exit_with_clap_error(e: clap::Error) -> ! {
+    e.print().expect("Failed to print error");
+    // Use Clap's exit code for help/version (0), otherwise use 3 for arg errors
+    let exit_code = if e.use_stderr() {
+        EXIT_USAGE
+    } else {
+        EXIT_SUCCESS
+    };
+    process::exit(exit_code);
+}
+
+/// `pperf [OPTIONS] preset [OPTIONS] NAME ARGS...`: the same command line with
+/// `preset NAME` replaced by the preset's arguments, parsed again. Global options
+/// between `preset` and NAME are kept in front of the preset's arguments.
+fn expand_preset(argv: &[OsString]) -> Result<Cli, PperfError> {
+    let (preset_at, name_at) = locate_preset(argv);
+    let globals = argv[..preset_at]
+        .iter()
+        .chain(&argv[preset_at + 1..name_at])
+        .cloned();
+    let Some(name) = argv.get(name_at) else {
+        // No name: list the presets, with the global options wherever they stood
+        let listing = globals.chain([OsString::from("preset")]);
+        return Ok(Cli::try_parse_from(listing).unwrap_or_else(|e| exit_with_clap_error(e)));
+    };
+    let name = name.to_string_lossy();
+    let head = Cli::try_parse_from(globals.clone().chain([OsString::from("preset")]))
+        .unwrap_or_else(|e| exit_with_clap_error(e));
+    let config = find_config(head.config.as_deref())?;
+    let Some(preset) = config.preset(&name) else {
+        let known: Vec<&str> = config.presets.iter().map(|p| p.name.as_str()).collect();
+        return Err(PperfError::UnknownPreset(if known.is_empty() {
+            format!("{} (the config file defines no presets)", name)
+        } else {
+            format!("{} (defined: {})", name, known.join(", "))
+        }));
+    };
+    let args = globals
+        .chain(preset.args.iter().map(Into::into))
+        .chain(argv[name_at + 1..].iter().cloned());
+    Ok(Cli::try_parse_from(args).unwrap_or_else(|e| exit_with_clap_error(e)))
+}
+
+/// Positions of the `preset` subcommand and of the preset NAME in `argv`, skipping
+/// global options and their values before and between them. NAME's position is
+/// `argv.len()` when no name is given.
+fn locate_preset(argv: &[OsString]) -> (usize, usize) {
+    let command = Cli::command();
+    let globals: Vec<&clap::Arg> = command
+        .get_arguments()
+        .filter(|arg| arg.is_global_set())
+        .collect();
+    // An option written without `=` whose value is the next word
+    let takes_next = |word: &str| {
+        globals.iter().any(|arg| {
+            let takes_value = arg.get_action().takes_values() && !arg.is_require_equals_set();
+            takes_value
+                && (word
+                    .strip_prefix("--")
+                    .is_some_and(|long| arg.get_long() == Some(long))
+                    || word
+                        .strip_prefix('-')
+                        .is_some_and(|short| short.chars().eq(arg.get_short())))
+        })
+    };
+    let mut preset_at = None;
+    let mut i = 1;
+    while i < argv.len() {
+        let word = argv[i].to_string_lossy();
+        if word.starts_with('-') && word != "-" {
+            if takes_next(&word) {
+                i += 1;
+            }
+        } else if preset_at.is_none() {
+            preset_at = Some(i);
+        } else {
+            break;
+        }
+        i += 1;
+    }
+    (preset_at.unwrap_or(argv.len()), i.min(argv.len()))
+}
+
 fn main() {
-    let cli = match Cli::try_parse() {
-        Ok(cli) => cli,
+    let cli = Cli::try_parse().unwrap_or_else(|e| exit_with_clap_error(e));
+    let cli = match cli.command {
+        Commands::Preset(PresetArgs { ref words }) if !words.is_empty() => {
+            let argv: Vec<OsString> = std::env::args_os().collect();
+            match expand_preset(&argv) {
+                Ok(expanded) => expanded,
+                Err(e) => {
+                    eprintln!("Error: {}", e);
+                    process::exit(e.exit_code());
+                }
+            }
+        }
+        _ => cli,
+    };
+
+    if let Some(ref path) = cli.output {
+        match fs::File::create(path) {
+            Ok(file) => *OUTPUT_FILE.lock().unwrap() = Some(file),
+            Err(_) => {
+                let e = PperfError::WriteFailed(path.display().to_string());
+                eprintln!("Error: {}", e);
+                process::exit(e.exit_code());
+            }
+        }
+    }
+    // A file has no terminal width to follow
+    let detected_width = match cli.output {
+        Some(_) => None,
+        None => terminal_width(),
+    };
+    if let Some(width) = cli.width.or(detected_width) {
+        set_terminal_width(width);
+    }
+    log::set_verbosity(cli.verbose);
+    NO_HEADER.store(cli.no_header, Ordering::Relaxed);
+    QUIET.store(cli.quiet, Ordering::Relaxed);
+    if let Some(ref exe) = cli.symbol_filter {
+        let _ = SYMBOL_FILTER.set(exe.clone());
+    }
+    set_precision(cli.precision);
+    set_truncate_mode(cli.truncate);
+    if let Some(template) = cli.hyperlinks {
+        set_hyperlink_template(template);
+    }
+    set_demangle_mode(if cli.raw_symbols {
+        DemangleMode::Full
+    } else {
+        cli.demangle
+    });
+    let presets = match find_config(cli.config.as_deref()) {
+        Ok(config) => {
+            set_rewrite_rules(config.rewrites);
+            set_classification_rules(config.classification);
+            set_display_aliases(config.aliases);
+            config.presets
+        }
         Err(e) => {
-            e.print().expect("Failed to print error");
-            // Use Clap's exit code for help/version (0), otherwise use 3 for arg errors
-            let exit_code = if e.use_stderr() { 3 } else { 0 };
-            process::exit(exit_code);
+            eprintln!("Error: {}", e);
+            process::exit(e.exit_code());
         }
     };
 
-    let result = match cli.command {
-        Commands::Top(args) => run_top(args),
+    let mut result = match cli.command {
+        Commands::Top(args) => run_top(*args),
+        Commands::Synth(args) => run_synth(args),
+        Commands::Set(args) => run_set(args),
+        Commands::Diff(args) => run_diff(args),
+        Commands::Trend(args) => run_trend(args),
+        Commands::Butterfly(args) => run_butterfly(args),
+        Commands::Paths(args) => run_paths(args),
+        Commands::Hotpath(args) => run_hotpath(args),
+        Commands::Stats(args) => run_stats(args),
+        Commands::Targets {
+            command: TargetsCommand::Generate(args),
+        } => run_targets_generate(args),
+        Commands::Targets {
+            command: TargetsCommand::Check(args),
+        } => run_targets_check(args),
+        Commands::ExitCodes => {
+            for e in EXIT_CODES {
+                outln!("{}\t{}\t{}", e.code, e.name, e.description);
+            }
+            Ok(())
+        }
+        // A named preset was expanded above
+        Commands::Preset(_) => {
+            for preset in &presets {
+                outln!("{}\t{}", preset.name, preset.command);
+            }
+            Ok(())
+        }
     };
 
+    if let Some(path) = cli.output
+        && OUTPUT_FAILED.load(Ordering::Relaxed)
+    {
+        result = Err(PperfError::WriteFailed(path.display().to_string()));
+    }
+
     if let Err(e) = result {
         eprintln!("Error: {}", e);
-        let exit_code = match e {
-            PperfError::FileNotFound(_) => 1,
-            PperfError::InvalidFormat => 2,
-            PperfError::InvalidCount => 3,
-            PperfError::NoMatches => 4,
-            PperfError::HierarchyRequiresTargets => 3,
-        };
+        let exit_code = e.exit_code();
         process::exit(exit_code);
     }
 }
 
+/// Parse the reports, with their symbols run through `--symbol-filter` if given.
+fn parse_reports(paths: &[PathBuf]) -> Result<ReportSet, PperfError> {
+    let mut set = ReportSet::parse_all(paths)?;
+    filter_symbols(&mut set)?;
+    Ok(set)
+}
+
+/// `--symbol-filter`: run the program once over every distinct simplified symbol of
+/// the reports, then rewrite each entry and call-tree symbol with its output.
+fn filter_symbols(set: &mut ReportSet) -> Result<(), PperfError> {
+    if let Some(exe) = SYMBOL_FILTER.get() {
+        let mapped = run_symbol_filter(exe, &set.simplified_symbols())?;
+        set.transform_symbols(&mapped);
+    }
+    Ok(())
+}
+
 fn run_top(args: TopArgs) -> Result<(), PperfError> {
     // Map Clap args to existing variable names
-    let sort_order = if args.sort_self {
+    // Scopes only carry Self%
+    let sort_order = if args.sort_self || args.group_by.is_some() {
         SortOrder::Self_
     } else {
         SortOrder::Children
     };
     let count = args.number;
-    let targets = args.targets;
+    // Mangled -t names become exact targets, like target-file signatures
+    let (mangled_targets, targets): (Vec<String>, Vec<String>) =
+        args.targets.iter().cloned().partition(|t| is_mangled(t));
     let hierarchy_flag = args.hierarchy;
+    // Both call-graph views need the parsed trees
+    let needs_trees = hierarchy_flag || args.callers || args.tree.is_some();
     let debug_flag = args.debug;
     let no_color_flag = args.no_color;
 
     // Validate --hierarchy requires --targets
-    if hierarchy_flag && targets.is_empty() {
+    if hierarchy_flag
+        && targets.is_empty()
+        && mangled_targets.is_empty()
+        && args.target_file.is_none()
+    {
         return Err(PperfError::HierarchyRequiresTargets);
     }
 
-    let path = &args.file;
-    let mut entries = parse_file(path)?;
+    let (files, weights) = report_weights(&args.files, &args.weights)?;
+    let mut set = ReportSet::parse_all(&files)?;
+    set.weights = weights;
+    set.missing_as_zero = args.missing_as_zero;
+    let outliers = set.find_outliers();
+    for outlier in &outliers {
+        warnln!(
+            "Warning: {} looks like an outlier run (mean z-score {:.1} over the top {} entries){}",
+            set.reports[outlier.index].0.display(),
+            outlier.z_score,
+            outlier.entries,
+            if args.drop_outliers {
+                "; dropped from the average"
+            } else {
+                ""
+            }
+        );
+    }
+    if args.drop_outliers {
+        // Outliers are in report order, so removing from the back keeps indices valid
+        for outlier in outliers.iter().rev() {
+            set.reports.remove(outlier.index);
+            set.weights.remove(outlier.index);
+        }
+    }
+    let multi_file = set.len() > 1;
+    if needs_trees && set.reports.iter().any(|(_, r)| !r.has_call_graph) {
+        return Err(PperfError::NoCallGraph);
+    }
+    let percent_type_of = |report: &ParsedReport| report.metadata.percent_type.unwrap_or_default();
+    if multi_file
+        && set
+            .reports
+            .iter()
+            .any(|(_, r)| percent_type_of(r) != percent_type_of(&set.reports[0].1))
+    {
+        let types: Vec<String> = set
+            .reports
+            .iter()
+            .map(|(path, r)| format!("{}: {}", path.display(), percent_type_of(r)))
+            .collect();
+        warnln!(
+            "Warning: reports use different percent types ({}); averaged percentages are not comparable",
+            types.join(", ")
+        );
+    }
 
-    if !targets.is_empty() {
-        entries = pperf::filter::filter_entries(&entries, &targets);
-        if entries.is_empty() {
-            return Err(PperfError::NoMatches);
+    // Call trees are parsed up front so their warnings are known before any output
+    let mut warning_counts = Vec::new();
+    let mut per_report_trees = Vec::new();
+    for (path, report) in &mut set.reports {
+        let mut warnings = report.warnings.clone();
+        if needs_trees {
+            warnings.extend(report.tree_warnings.iter().cloned());
+            let mut trees = std::mem::take(&mut report.trees);
+            if percent_type_of(report) == PercentType::Absolute {
+                convert_absolute_percentages(&mut trees);
+            }
+            if args.collapse_inlined {
+                collapse_inlined_frames(&mut trees);
+            }
+            per_report_trees.push(trees);
         }
+        if log::enabled(Level::Info) || args.strict {
+            for warning in &warnings {
+                if multi_file {
+                    warnln!("Warning: {}: {}", path.display(), warning);
+                } else {
+                    warnln!("Warning: {}", warning);
+                }
+            }
+        }
+        warning_counts.push(warnings.len());
+    }
+    let warning_count: usize = warning_counts.iter().sum();
+    if args.strict && warning_count > 0 {
+        return Err(PperfError::StrictWarnings(warning_count));
     }
 
+    // Narrow to the requested processes before anything is averaged or traversed
+    if !args.comms.is_empty() {
+        for (_, report) in &mut set.reports {
+            report.entries = filter_entries_by_comm(&report.entries, &args.comms);
+        }
+        for trees in &mut per_report_trees {
+            trees.retain(|(entry, _)| args.comms.contains(&entry.comm));
+        }
+    }
+
+    // Frames perf could only print as addresses, named before anything matches names
+    let all_entries: Vec<PerfEntry> = set
+        .reports
+        .iter()
+        .flat_map(|(_, report)| report.entries.iter().cloned())
+        .collect();
+    let map_paths = if args.perf_maps.is_empty() {
+        find_perf_maps(&all_entries)
+    } else {
+        args.perf_maps.clone()
+    };
+    let perf_map = PerfMap::load(&map_paths)?;
+    let binary_names = resolve_binary_addresses(&all_entries, &args.binaries)?;
+    let kallsyms = match args.kallsyms {
+        Some(ref path) => Kallsyms::load(path)?,
+        None => Kallsyms::default(),
+    };
+    if let Some(ref path) = args.kallsyms
+        && kallsyms.is_empty()
+    {
+        warnln!(
+            "Warning: {} lists no kernel function addresses (all zero without privileges?)",
+            path.display()
+        );
+    }
+    if !perf_map.is_empty() || !binary_names.is_empty() || !kallsyms.is_empty() {
+        for (_, report) in &mut set.reports {
+            transform_entry_symbols(&mut report.entries, &perf_map);
+            binary_names.apply_to_entries(&mut report.entries);
+            kallsyms.apply_to_entries(&mut report.entries);
+        }
+        for trees in &mut per_report_trees {
+            transform_tree_symbols(trees, &perf_map);
+            transform_tree_symbols(trees, &binary_names);
+            transform_tree_symbols(trees, &kallsyms);
+        }
+    }
+    // Hyperlink targets: `--binary` lookups, then the first Source:Line row per function
+    let mut source_locations = binary_names.source_locations.clone();
+    for (_, report) in &set.reports {
+        for entry in report.entries.iter().filter(|e| !e.srcline.is_empty()) {
+            source_locations
+                .entry(simplify_symbol(&entry.symbol))
+                .or_insert_with(|| entry.srcline.clone());
+        }
+    }
+    set_source_locations(source_locations);
+
+    // After the address naming above, so the filter also sees the names it found
+    if SYMBOL_FILTER.get().is_some() {
+        for ((_, report), trees) in set.reports.iter_mut().zip(&mut per_report_trees) {
+            report.trees = std::mem::take(trees);
+        }
+        filter_symbols(&mut set)?;
+        for ((_, report), trees) in set.reports.iter_mut().zip(&mut per_report_trees) {
+            *trees = std::mem::take(&mut report.trees);
+        }
+    }
+
+    // `[k]`/`[.]` markers only appear on entry lines, so callees are hidden by the
+    // space of their own entry
+    let mut hidden_callees: HashSet<String> = HashSet::new();
+    if let Some(space) = symbol_space(&args) {
+        let mut kept_names = HashSet::new();
+        for (_, report) in &mut set.reports {
+            let kept = filter_entries_by_space(&report.entries, space);
+            kept_names.extend(kept.iter().map(|e| simplify_symbol(&e.symbol)));
+            hidden_callees.extend(
+                report
+                    .entries
+                    .iter()
+                    .filter(|e| !space.contains(e))
+                    .map(|e| simplify_symbol(&e.symbol)),
+            );
+            report.entries = kept;
+        }
+        hidden_callees.retain(|name| !kept_names.contains(name));
+        for trees in &mut per_report_trees {
+            trees.retain(|(entry, _)| space.contains(entry));
+        }
+    }
+
+    let averaged = if multi_file || args.stats || args.flag_variance.is_some() {
+        set.average_with(args.agg)
+    } else {
+        Vec::new()
+    };
+    let mut entries: Vec<PerfEntry> = if multi_file {
+        averaged.iter().map(|e| e.to_entry()).collect()
+    } else {
+        set.reports[0].1.entries.clone()
+    };
+
+    if let Some(ref path) = args.budget {
+        let budgets = parse_budget_file(path)?;
+        let results = check_budgets(&entries, &budgets, args.ignore_case);
+        out!(
+            "{}",
+            format_budget_table(&results, color_enabled(args.no_color))
+        );
+        let exceeded = results.iter().filter(|r| r.exceeded()).count();
+        if exceeded > 0 {
+            return Err(PperfError::BudgetExceeded(exceeded));
+        }
+        return Ok(());
+    }
+
+    if let Some(ref name) = args.tree {
+        let use_color = color_enabled(no_color_flag);
+        let name = name.clone();
+        let options = MatchOptions {
+            ignore_case: args.ignore_case,
+            ..Default::default()
+        };
+        let mut output = Vec::new();
+        for ((path, _), trees) in set.reports.iter().zip(&per_report_trees) {
+            // Tree entries carry simplified names; the hottest match is the one shown
+            let Some((entry, roots)) = trees
+                .iter()
+                .filter(|(entry, _)| options.matches(&entry.symbol, &name))
+                .max_by(|a, b| a.0.children_pct.total_cmp(&b.0.children_pct))
+            else {
+                return Err(PperfError::NoMatches(suggest_symbols(
+                    &entries,
+                    std::slice::from_ref(&name),
+                    3,
+                )));
+            };
+            let tree = format_call_tree(entry, roots, use_color);
+            output.push(if multi_file {
+                format!("{}:\n{}", path.display(), tree)
+            } else {
+                tree
+            });
+        }
+        out!("{}", output.join("\n"));
+        return Ok(());
+    }
+
+    let mut file_targets: Vec<Target> = mangled_targets
+        .iter()
+        .map(|t| Target::new(TargetSpec::exact(t)))
+        .collect();
+    if let Some(ref path) = args.target_file {
+        file_targets.extend(parse_target_file(path)?);
+    }
+    let match_options = match_options(&args, &file_targets)?;
+    if args.dry_run {
+        out!(
+            "{}",
+            format_dry_run(
+                &args,
+                &set,
+                &warning_counts,
+                &entries,
+                &match_options,
+                &file_targets
+            )
+        );
+        return Ok(());
+    }
+    let aliases = SymbolAliases::new(&file_targets, args.ignore_case);
+    let mut overloads = HashMap::new();
+    entries = select_entries(
+        &entries,
+        &args,
+        &targets,
+        &match_options,
+        &file_targets,
+        &mut hidden_callees,
+        &mut overloads,
+    )?;
     sort_entries(&mut entries, sort_order);
 
-    let use_color = should_use_color(no_color_flag);
+    let use_color = color_enabled(no_color_flag);
+    set_heat(args.heat && use_color);
 
-    // T048: Wire hierarchy computation when --hierarchy is specified
-    if hierarchy_flag {
-        // Read file content for call tree parsing
-        let content = fs::read_to_string(path)
-            .map_err(|_| PperfError::FileNotFound(path.display().to_string()))?;
+    if args.show_meta {
+        for (path, report) in &set.reports {
+            let meta = format_metadata(&report.metadata);
+            if !meta.is_empty() {
+                if multi_file {
+                    outln!("{}:", path.display());
+                }
+                outln!("{}", meta);
+            }
+        }
+    }
 
-        // Parse call trees from content
-        let trees = parse_file_call_trees(&content, &entries);
+    if args.callers {
+        let mut display_entries: Vec<_> = entries.into_iter().take(count).collect();
+        let mut blocks = Vec::new();
+        let mut guessed = Vec::new();
+        for entry in &display_entries {
+            let symbol = simplify_symbol(&entry.symbol);
+            let per_report: Vec<_> = per_report_trees
+                .iter()
+                .zip(&set.reports)
+                .map(|(trees, (_, report))| {
+                    let tree_order = args.tree_order.or(report.metadata.tree_order);
+                    add_guessed(
+                        &mut guessed,
+                        guessed_symbol_trees(trees, &symbol, tree_order, &match_options),
+                    );
+                    find_callers(trees, &symbol, tree_order, &match_options)
+                })
+                .collect();
+            blocks.push(average_callers(&per_report, args.agg, &set.weights));
+        }
+        warn_guessed_tree_order(&guessed);
+        aliases.apply_to_entries(&mut display_entries);
+        let output: Vec<String> = display_entries
+            .iter()
+            .zip(&blocks)
+            .map(|(entry, callers)| format_callers(entry, callers, use_color))
+            .collect();
+        out!("{}", output.join("\n"));
+        return Ok(());
+    }
 
-        // Compute relationships between targets
-        let relations = compute_call_relations(&trees, &targets);
+    // T048: Wire hierarchy computation when --hierarchy is specified
+    if hierarchy_flag {
+        // Compute relationships between targets, per report
+        let mut guessed = Vec::new();
+        let per_report_relations: Vec<_> = per_report_trees
+            .iter_mut()
+            .zip(&set.reports)
+            .map(|(trees, (_, report))| {
+                let tree_order = args.tree_order.or(report.metadata.tree_order);
+                let relations =
+                    report_relations(trees, tree_order, &targets, &match_options, &mut guessed);
+                match args.context {
+                    Some(ref context) => filter_relations_by_context(&relations, context),
+                    None => relations,
+                }
+            })
+            .collect();
+        warn_guessed_tree_order(&guessed);
+        let (mut relations, mut agreements) = if multi_file {
+            average_relations(&per_report_relations, args.agg, &set.weights)
+        } else {
+            (per_report_relations.into_iter().next().unwrap(), Vec::new())
+        };
+        // Applied after averaging so the thresholds hold for the mean values
+        relations.retain(|r| !hidden_callees.contains(r.callee.as_str()));
+        if let Some(min_total) = args.min_total {
+            relations = filter_relations_by_min_total(&relations, min_total);
+        }
+        if let Some(min_pct) = args.min_pct {
+            relations = filter_relations_by_min_pct(&relations, min_pct);
+        }
+        agreements.retain(|a| {
+            relations.iter().any(|r| {
+                r.caller == a.caller && r.callee == a.callee && r.context_root == a.context_root
+            })
+        });
 
         // Build hierarchy entries with adjusted percentages
-        let hierarchy_entries = build_hierarchy_entries(&entries, &targets, &relations);
+        let overflows = find_contribution_overflows(&entries, &targets, &relations, &match_options);
+        if args.strict_math && !overflows.is_empty() {
+            let details: Vec<String> = overflows.iter().map(|o| o.to_string()).collect();
+            return Err(PperfError::ContributionOverflow(details.join("; ")));
+        }
+        for overflow in &overflows {
+            warnln!(
+                "Warning: {}; its standalone time is clamped to 0.00%",
+                overflow
+            );
+        }
+        let mut hierarchy_entries =
+            build_hierarchy_entries_with(&entries, &targets, &relations, &match_options);
+        pperf::log!(
+            Info,
+            "hierarchy",
+            "hierarchy built",
+            reports = set.len(),
+            relations = relations.len(),
+            entries = hierarchy_entries.len(),
+            overflows = overflows.len(),
+        );
+        aliases.apply_to_hierarchy(&mut hierarchy_entries, &mut relations);
+        aliases.apply_to_agreements(&mut agreements);
 
         // Format and output (T005: pass debug_flag to format_hierarchy_table)
         let display_entries: Vec<_> = hierarchy_entries.into_iter().take(count).collect();
-        let output = format_hierarchy_table(&display_entries, &relations, use_color, debug_flag);
-        print!("{}", output);
+        let output = format_hierarchy_table(
+            &display_entries,
+            &relations,
+            use_color,
+            debug_flag,
+            args.tree_style,
+        );
+        out!("{}", output);
+
+        if !agreements.is_empty() {
+            outln!();
+            out!("{}", format_edge_agreement(&agreements, debug_flag));
+        }
+    } else {
+        // Scopes have only self time, shown like a `--no-children` Overhead column
+        let layout = match args.group_by {
+            Some(_) => ReportLayout::Overhead,
+            None => set.reports[0].1.layout,
+        };
+        // Titles go out with the tables below them, so `--quiet` can drop them
+        let mut per_file = String::new();
+        if args.per_file {
+            for (path, report) in &set.reports {
+                per_file.push_str(&format!("{}:\n", path.display()));
+                let mut report_overloads = HashMap::new();
+                match select_entries(
+                    &report.entries,
+                    &args,
+                    &targets,
+                    &match_options,
+                    &file_targets,
+                    &mut HashSet::new(),
+                    &mut report_overloads,
+                ) {
+                    Ok(mut report_entries) => {
+                        sort_entries(&mut report_entries, sort_order);
+                        report_entries.truncate(count);
+                        let members = overload_members(&report_entries, &report_overloads);
+                        aliases.apply_to_entries(&mut report_entries);
+                        reserve_columns(decoration_width(
+                            args.rank.then_some(report_entries.len()),
+                            args.bars,
+                            args.show_srcline
+                                .then(|| srclines(&report_entries))
+                                .as_deref(),
+                            false,
+                            args.merge_overloads,
+                        ));
+                        let table = match layout {
+                            _ if args.group_by.is_some() => {
+                                format_scope_table(&report_entries, use_color)
+                            }
+                            ReportLayout::ChildrenSelf => format_table(&report_entries, use_color),
+                            ReportLayout::Overhead => {
+                                format_overhead_table(&report_entries, use_color)
+                            }
+                        };
+                        let mut table = table;
+                        if args.show_srcline {
+                            table = add_srcline_column(&table, &srclines(&report_entries));
+                        }
+                        if args.bars {
+                            table = add_bar_column(&table, &bar_values(&report_entries, layout));
+                        }
+                        if args.rank {
+                            table = add_rank_column(&table);
+                        }
+                        if args.merge_overloads {
+                            table = mark_overloads(&table, &members, debug_flag);
+                        }
+                        per_file.push_str(&table);
+                    }
+                    // The run may lack what matches on average
+                    Err(PperfError::NoMatches(_)) => per_file.push_str("(no matching entries)\n"),
+                    Err(err) => return Err(err),
+                }
+                per_file.push('\n');
+            }
+            // A single report's table is the combined one, so it is shown once
+            if set.len() > 1 {
+                per_file.push_str(&format!(
+                    "Combined ({}) over {} reports:\n",
+                    args.agg,
+                    set.len()
+                ));
+            } else {
+                per_file.pop();
+            }
+        }
+
+        let mut display_entries: Vec<_> = entries.into_iter().take(count).collect();
+        // Looked up before aliases rename the entries; merged groups have no stats
+        let per_report: Vec<_> = display_entries
+            .iter()
+            .map(|entry| {
+                averaged.iter().find(|a| {
+                    a.symbol == entry.symbol && a.comm == entry.comm && a.dso == entry.dso
+                })
+            })
+            .collect();
+        let stats: Vec<_> = per_report
+            .iter()
+            .map(|a| a.map(|a| (a.children_stats, a.self_stats)))
+            .collect();
+        // Overhead is self time, so its variance is the Self% one
+        let variances: Vec<_> = per_report
+            .iter()
+            .map(|a| {
+                a.map(|a| match layout {
+                    ReportLayout::ChildrenSelf => a.children_cv_pct(),
+                    ReportLayout::Overhead => a.self_cv_pct(),
+                })
+            })
+            .collect();
+        let members = overload_members(&display_entries, &overloads);
+        aliases.apply_to_entries(&mut display_entries);
+        reserve_columns(decoration_width(
+            args.rank.then_some(display_entries.len()),
+            args.bars,
+            args.show_srcline
+                .then(|| srclines(&display_entries))
+                .as_deref(),
+            args.flag_variance.is_some(),
+            args.merge_overloads,
+        ));
+        let mut output = match layout {
+            _ if args.group_by.is_some() => format_scope_table(&display_entries, use_color),
+            _ if args.stats => format_stats_table(&display_entries, &stats, layout, use_color),
+            ReportLayout::ChildrenSelf => format_table(&display_entries, use_color),
+            ReportLayout::Overhead => format_overhead_table(&display_entries, use_color),
+        };
+        if let Some(threshold) = args.flag_variance {
+            output = mark_high_variance(&output, &variances, threshold, use_color);
+        }
+        if args.show_srcline {
+            output = add_srcline_column(&output, &srclines(&display_entries));
+        }
+        if args.bars {
+            output = add_bar_column(&output, &bar_values(&display_entries, layout));
+        }
+        if args.rank {
+            output = add_rank_column(&output);
+        }
+        if args.merge_overloads {
+            output = mark_overloads(&output, &members, debug_flag);
+        }
+        if args.per_file && set.len() == 1 {
+            out!("{}", per_file);
+        } else {
+            out!("{}{}", per_file, output);
+        }
+
+        // Which average is right depends on the analysis, so say which one was used
+        let missing = per_report
+            .iter()
+            .flatten()
+            .filter(|a| a.children_values.iter().any(Option::is_none))
+            .count();
+        if multi_file && missing > 0 && !QUIET.load(Ordering::Relaxed) {
+            outln!();
+            if args.missing_as_zero {
+                outln!(
+                    "Note: entries missing from some reports ({} listed) count as 0% there (--missing-as-zero)",
+                    missing
+                );
+            } else {
+                outln!(
+                    "Note: entries missing from some reports ({} listed) are averaged over the reports that contain them (--missing-as-zero counts them as 0%)",
+                    missing
+                );
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// The signatures each entry was merged from by `--merge-overloads`, looked up
+/// before aliases rename the entries; empty for entries that were not merged.
+fn overload_members(
+    entries: &[PerfEntry],
+    overloads: &HashMap<String, Vec<Symbol>>,
+) -> Vec<Vec<Symbol>> {
+    entries
+        .iter()
+        .map(|e| {
+            overloads
+                .get(e.symbol.as_str())
+                .cloned()
+                .unwrap_or_default()
+        })
+        .collect()
+}
+
+/// The value `--bars` draws per entry: Children%, or Overhead% (self time) for
+/// `--no-children` reports.
+fn bar_values(entries: &[PerfEntry], layout: ReportLayout) -> Vec<f64> {
+    entries
+        .iter()
+        .map(|e| match layout {
+            ReportLayout::ChildrenSelf => e.children_pct,
+            ReportLayout::Overhead => e.self_pct,
+        })
+        .collect()
+}
+
+/// The `file:line` `--show-srcline` lists per entry (empty without a Source:Line column).
+fn srclines(entries: &[PerfEntry]) -> Vec<String> {
+    entries.iter().map(|e| e.srcline.clone()).collect()
+}
+
+/// Narrow entries to what `pperf top` lists: `--dso`, targets, exclusions, target
+/// groups and sections, `--merge-overloads` or `--group-by`, then the
+/// `--min-children`/`--min-self` thresholds. Names cut by a threshold are added to
+/// `hidden_callees`, the signatures of merged overloads to `overloads`. `NoMatches` if nothing is left.
+fn select_entries(
+    entries: &[PerfEntry],
+    args: &TopArgs,
+    targets: &[String],
+    match_options: &MatchOptions,
+    file_targets: &[Target],
+    hidden_callees: &mut HashSet<String>,
+    overloads: &mut HashMap<String, Vec<Symbol>>,
+) -> Result<Vec<PerfEntry>, PperfError> {
+    let mut entries = filter_entries_by_dso(entries, &args.dsos);
+    let mut suggestions = Vec::new();
+    if !targets.is_empty() || !match_options.target_specs.is_empty() {
+        let filtered = filter_entries_with(&entries, targets, match_options);
+        if filtered.is_empty() {
+            let mut hints = targets.to_vec();
+            hints.extend(
+                match_options
+                    .target_specs
+                    .iter()
+                    .filter_map(|spec| match spec {
+                        TargetSpec::Exact { simplified, .. } => Some(simplified.clone()),
+                        TargetSpec::Regex { .. } => None,
+                    }),
+            );
+            suggestions = suggest_symbols(&entries, &hints, 3);
+        }
+        entries = filtered;
+    }
+    entries = exclude_entries(&entries, match_options);
+    if entries.is_empty() {
+        return Err(PperfError::NoMatches(suggestions));
+    }
+    if !targets.is_empty() {
+        entries = merge_target_groups(&entries, targets, match_options);
+    }
+    if args.group {
+        entries = merge_target_sections(&entries, file_targets, args.ignore_case);
+    }
+    if args.merge_overloads {
+        let (merged, signatures) = merge_overloads(&entries);
+        entries = merged;
+        overloads.extend(signatures);
+    }
+    if let Some(group_by) = args.group_by {
+        entries = merge_scopes(&entries, group_by);
+    }
+
+    // Hierarchy callees whose own entry falls below a threshold are cut off too
+    let kept = filter_entries_by_min_pct(&entries, args.min_children, args.min_self);
+    let kept_names: HashSet<String> = kept.iter().map(|e| simplify_symbol(&e.symbol)).collect();
+    hidden_callees.extend(
+        entries
+            .iter()
+            .map(|e| simplify_symbol(&e.symbol))
+            .filter(|name| !kept_names.contains(name)),
+    );
+    if kept.is_empty() {
+        return Err(PperfError::NoMatches(Vec::new()));
+    }
+    Ok(kept)
+}
+
+/// Target matching settings from the command line.
+fn match_options(args: &TopArgs, file_targets: &[Target]) -> Result<MatchOptions, PperfError> {
+    let mut excludes = args.exclude.clone();
+    if let Some(ref path) = args.ignore_file {
+        excludes.extend(read_pattern_file(path)?);
+    }
+    Ok(MatchOptions {
+        excludes,
+        ignore_case: args.ignore_case,
+        target_specs: file_targets.iter().map(|t| t.spec.clone()).collect(),
+        max_depth: args.max_depth,
+        assume_callee_trees: args.assume_callee_trees,
+        contribution_mode: args.contribution_mode,
+    })
+}
+
+/// `--user-only` / `--kernel-only`, if either was given.
+fn symbol_space(args: &TopArgs) -> Option<SymbolSpace> {
+    if args.user_only {
+        Some(SymbolSpace::User)
+    } else if args.kernel_only {
+        Some(SymbolSpace::Kernel)
+    } else {
+        None
+    }
+}
+
+/// Describe what `run_top` would do: inputs, options and target resolution.
+fn format_dry_run(
+    args: &TopArgs,
+    set: &ReportSet,
+    warning_counts: &[usize],
+    entries: &[PerfEntry],
+    options: &MatchOptions,
+    file_targets: &[Target],
+) -> String {
+    let yes_no = |flag: bool| if flag { "yes" } else { "no" };
+    let mut output = String::new();
+
+    output.push_str(&format!("Files ({}):\n", set.len()));
+    for ((path, report), warnings) in set.reports.iter().zip(warning_counts) {
+        let layout = match report.layout {
+            ReportLayout::ChildrenSelf => "children/self",
+            ReportLayout::Overhead => "overhead",
+        };
+        output.push_str(&format!(
+            "  {}: {} layout, call graph: {}, {} percentages, {} entries, {} warnings\n",
+            path.display(),
+            layout,
+            yes_no(report.has_call_graph),
+            report.metadata.percent_type.unwrap_or_default(),
+            report.entries.len(),
+            warnings
+        ));
+    }
+    if set.len() > 1 {
+        output.push_str(&format!("  averaged: {} distinct entries\n", entries.len()));
+    }
+
+    output.push_str("Options:\n");
+    output.push_str(&format!(
+        "  sort: {}\n",
+        if args.sort_self { "self" } else { "children" }
+    ));
+    output.push_str(&format!("  number: {}\n", args.number));
+    output.push_str(&format!("  hierarchy: {}\n", yes_no(args.hierarchy)));
+    output.push_str(&format!("  debug: {}\n", yes_no(args.debug)));
+    if let Some(ref context) = args.context {
+        output.push_str(&format!("  context: {}\n", context));
+    }
+    if let Some(min_total) = args.min_total {
+        output.push_str(&format!("  min-total: {:.2}%\n", min_total));
+    }
+    if let Some(min_pct) = args.min_pct {
+        output.push_str(&format!("  min-pct: {:.2}%\n", min_pct));
+    }
+    if let Some(tree_order) = args.tree_order {
+        output.push_str(&format!("  tree-order: {}\n", tree_order));
+    }
+    if args.contribution_mode != ContributionMode::default() {
+        output.push_str(&format!(
+            "  contribution-mode: {}\n",
+            args.contribution_mode
+        ));
+    }
+    if let Some(ref path) = args.ignore_file {
+        output.push_str(&format!(
+            "  ignore-file: {} ({} patterns with --exclude)\n",
+            path.display(),
+            options.excludes.len()
+        ));
+    }
+    if let Some(space) = symbol_space(args) {
+        output.push_str(&format!("  space: {}\n", space));
+    }
+    if !args.comms.is_empty() {
+        output.push_str(&format!("  comm: {}\n", args.comms.join(", ")));
+    }
+    if !args.dsos.is_empty() {
+        output.push_str(&format!("  dso: {}\n", args.dsos.join(", ")));
+    }
+    if let Some(min_children) = args.min_children {
+        output.push_str(&format!("  min-children: {:.2}%\n", min_children));
+    }
+    if let Some(min_self) = args.min_self {
+        output.push_str(&format!("  min-self: {:.2}%\n", min_self));
+    }
+    if let Some(exe) = SYMBOL_FILTER.get() {
+        output.push_str(&format!("  symbol-filter: {}\n", exe.display()));
+    }
+    output.push_str(&format!("  strict: {}\n", yes_no(args.strict)));
+    output.push_str(&format!("  ignore-case: {}\n", yes_no(args.ignore_case)));
+
+    let mut resolved: Vec<(&str, String, usize)> = args
+        .targets
+        .iter()
+        .filter(|t| !is_mangled(t))
+        .map(|t| {
+            let matches = entries
+                .iter()
+                .filter(|e| options.matches(&e.symbol, t))
+                .count();
+            ("substring", t.clone(), matches)
+        })
+        .collect();
+    resolved.extend(file_targets.iter().map(|target| {
+        let matches = entries
+            .iter()
+            .filter(|e| target.spec.matches(&e.symbol, options.ignore_case))
+            .count();
+        let mut label = target.spec.to_string();
+        if let Some(ref group) = target.group {
+            label = format!("[{}] {}", group, label);
+        }
+        if let Some(ref alias) = target.alias {
+            label.push_str(&format!(" (as {})", alias));
+        }
+        if let Some(ref notes) = target.notes {
+            label.push_str(&format!(" [{}]", notes));
+        }
+        (target.spec.kind(), label, matches)
+    }));
+    let unmatched = resolved
+        .iter()
+        .filter(|(_, _, matches)| *matches == 0)
+        .count();
+    output.push_str(&format!(
+        "Targets ({}, {} unmatched):\n",
+        resolved.len(),
+        unmatched
+    ));
+    for (kind, target, matches) in resolved {
+        output.push_str(&format!(
+            "  {:<9}  {}: {} match{}{}\n",
+            kind,
+            target,
+            matches,
+            if matches == 1 { "" } else { "es" },
+            if matches == 0 { " (unmatched)" } else { "" }
+        ));
+    }
+
+    output
+}
+
+fn run_set(args: SetArgs) -> Result<(), PperfError> {
+    let mut files = args.files;
+    let mode = if args.intersect {
+        SetMode::Intersect
+    } else if args.union {
+        SetMode::Union
+    } else {
+        let only = args.only_in.expect("clap requires one mode");
+        let position = match files.iter().position(|f| *f == only) {
+            Some(position) => position,
+            None => {
+                files.insert(0, only);
+                0
+            }
+        };
+        SetMode::OnlyIn(position)
+    };
+
+    let set = parse_reports(&files)?;
+    let layout = set.reports[0].1.layout;
+    let reports: Vec<Vec<PerfEntry>> = set.reports.into_iter().map(|(_, r)| r.entries).collect();
+    let rows = symbol_set_rows(&reports, mode);
+    if rows.is_empty() {
+        return Err(PperfError::NoMatches(Vec::new()));
+    }
+
+    let names: Vec<String> = files.iter().map(|f| f.display().to_string()).collect();
+    let display_rows: Vec<_> = rows.into_iter().take(args.number).collect();
+    out!(
+        "{}",
+        format_set_table(&display_rows, &names, layout, color_enabled(args.no_color))
+    );
+    Ok(())
+}
+
+fn run_diff(args: DiffArgs) -> Result<(), PperfError> {
+    if args.hierarchy && args.targets.is_empty() {
+        return Err(PperfError::HierarchyRequiresTargets);
+    }
+    let options = MatchOptions {
+        ignore_case: args.ignore_case,
+        ..Default::default()
+    };
+    let set = parse_reports(&[args.old.clone(), args.new.clone()])?;
+    let use_color = color_enabled(args.no_color);
+
+    if args.hierarchy {
+        let mut guessed = Vec::new();
+        let mut per_report = Vec::new();
+        for (_, mut report) in set.reports {
+            if !report.has_call_graph {
+                return Err(PperfError::NoCallGraph);
+            }
+            let mut trees = std::mem::take(&mut report.trees);
+            if report.metadata.percent_type == Some(PercentType::Absolute) {
+                convert_absolute_percentages(&mut trees);
+            }
+            per_report.push(report_relations(
+                &mut trees,
+                report.metadata.tree_order,
+                &args.targets,
+                &options,
+                &mut guessed,
+            ));
+        }
+        warn_guessed_tree_order(&guessed);
+
+        let mut rows = diff_relations(&per_report[0], &per_report[1]);
+        rows.retain(|r| r.change(args.threshold) != Change::Unchanged);
+        if let Some(n) = args.top_diff {
+            rows = biggest_movers(&rows, n, RelationDiff::delta);
+        }
+        out!(
+            "{}",
+            format_relation_diff(&rows, args.threshold, args.neutral_band, use_color)
+        );
+        return Ok(());
+    }
+
+    let entries: Vec<Vec<PerfEntry>> = set
+        .reports
+        .iter()
+        .map(|(_, report)| filter_entries_with(&report.entries, &args.targets, &options))
+        .collect();
+    if entries.iter().all(Vec::is_empty) {
+        return Err(PperfError::NoMatches(suggest_symbols(
+            &set.reports[0].1.entries,
+            &args.targets,
+            3,
+        )));
+    }
+    let all_rows = diff_entries(&entries[0], &entries[1]);
+    let changed: Vec<EntryDiff> = all_rows
+        .iter()
+        .filter(|r| r.change(args.threshold) != Change::Unchanged)
+        .cloned()
+        .collect();
+    let rows = match args.top_diff {
+        Some(n) => biggest_movers(&changed, n, EntryDiff::delta),
+        None => changed.into_iter().take(args.number).collect(),
+    };
+    out!(
+        "{}",
+        format_entry_diff(
+            &rows,
+            &args.old.display().to_string(),
+            &args.new.display().to_string(),
+            args.neutral_band,
+            use_color
+        )
+    );
+
+    // Every compared function counts, not only the rows shown
+    if let Some(limit) = args.fail_above {
+        let regressions: Vec<&EntryDiff> = all_rows.iter().filter(|r| r.regressed(limit)).collect();
+        outln!();
+        out!("{}", format_regression_summary(&regressions, limit));
+        if !regressions.is_empty() {
+            return Err(PperfError::RegressionFound(regressions.len()));
+        }
+    }
+    Ok(())
+}
+
+fn run_trend(args: TrendArgs) -> Result<(), PperfError> {
+    let mut files = Vec::new();
+    for path in &args.files {
+        if path.is_dir() {
+            let read_err = |_| PperfError::FileNotFound(path.display().to_string());
+            let mut listed: Vec<PathBuf> = fs::read_dir(path)
+                .map_err(read_err)?
+                .map(|entry| entry.map(|e| e.path()).map_err(read_err))
+                .collect::<Result<_, _>>()?;
+            listed.retain(|p| p.is_file());
+            listed.sort();
+            files.extend(listed);
+        } else {
+            files.push(path.clone());
+        }
+    }
+
+    let options = MatchOptions {
+        ignore_case: args.ignore_case,
+        ..Default::default()
+    };
+    let set = parse_reports(&files)?;
+    let reports: Vec<Vec<PerfEntry>> = set
+        .reports
+        .iter()
+        .map(|(_, report)| filter_entries_with(&report.entries, &args.targets, &options))
+        .collect();
+    if reports.iter().all(Vec::is_empty) {
+        return Err(PperfError::NoMatches(match set.reports.first() {
+            Some((_, report)) => suggest_symbols(&report.entries, &args.targets, 3),
+            None => Vec::new(),
+        }));
+    }
+
+    let rows = trend_rows(&reports);
+    let rows: Vec<_> = match args.top_diff {
+        Some(n) => biggest_movers(&rows, n, |r| r.slope),
+        None => rows.into_iter().take(args.number).collect(),
+    };
+    let names: Vec<String> = files.iter().map(|f| f.display().to_string()).collect();
+    out!(
+        "{}",
+        format_trend_table(
+            &rows,
+            &names,
+            set.reports[0].1.layout,
+            color_enabled(args.no_color)
+        )
+    );
+    Ok(())
+}
+
+fn run_targets_generate(args: GenerateArgs) -> Result<(), PperfError> {
+    let set = parse_reports(std::slice::from_ref(&args.file))?;
+    let report = &set.reports[0].1;
+    let mut entries = filter_entries(&report.entries, &args.targets);
+    if entries.is_empty() {
+        return Err(PperfError::NoMatches(suggest_symbols(
+            &report.entries,
+            &args.targets,
+            3,
+        )));
+    }
+    let sort_order = if args.sort_self {
+        SortOrder::Self_
     } else {
-        let display_entries: Vec<_> = entries.into_iter().take(count).collect();
-        let output = format_table(&display_entries, use_color);
-        print!("{}", output);
+        SortOrder::Children
+    };
+    sort_entries(&mut entries, sort_order);
+
+    entries.truncate(args.number);
+
+    let mut content = format!(
+        "# Top {} by {} from {}\n",
+        entries.len(),
+        if args.sort_self { "Self%" } else { "Children%" },
+        args.file.display()
+    );
+    for entry in &entries {
+        content.push_str(&format!(
+            "# {:.2}% children, {:.2}% self\n{}\n",
+            entry.children_pct,
+            entry.self_pct,
+            format_target_line(&entry.symbol)
+        ));
+    }
+
+    out!("{}", content);
+    Ok(())
+}
+
+fn run_targets_check(args: CheckArgs) -> Result<(), PperfError> {
+    let targets = parse_target_file(&args.target_file)?;
+    let set = parse_reports(std::slice::from_ref(&args.file))?;
+    let entries = &set.reports[0].1.entries;
+
+    let mut output = String::new();
+    let mut unmatched = 0;
+    for target in &targets {
+        let matches: Vec<&PerfEntry> = entries
+            .iter()
+            .filter(|e| target.spec.matches(&e.symbol, args.ignore_case))
+            .collect();
+        match matches.len() {
+            0 => {
+                unmatched += 1;
+                output.push_str(&format!("{}: no match", target.spec));
+                if let TargetSpec::Exact { ref signature, .. } = target.spec
+                    && let Some(closest) = closest_signature(entries, signature)
+                {
+                    output.push_str(&format!("; closest: {}", closest));
+                }
+                output.push('\n');
+            }
+            n => {
+                let ambiguous = n > 1 && matches!(target.spec, TargetSpec::Exact { .. });
+                output.push_str(&format!(
+                    "{}: {} match{}{}\n",
+                    target.spec,
+                    n,
+                    if n == 1 { "" } else { "es" },
+                    if ambiguous { " (ambiguous)" } else { "" }
+                ));
+                for entry in matches {
+                    output.push_str(&format!(
+                        "  {:>6.2}  {:>6.2}  {}\n",
+                        entry.children_pct, entry.self_pct, entry.symbol
+                    ));
+                }
+            }
+        }
+    }
+    out!("{}", output);
+
+    if unmatched > 0 {
+        return Err(PperfError::NoMatches(Vec::new()));
     }
+    Ok(())
+}
+
+/// Relations between the targets in one report's trees, read in `tree_order`. Without
+/// a known order each caller's trees are guessed and the guessed symbols added to `guessed`.
+fn report_relations(
+    trees: &mut [(PerfEntry, Vec<CallTreeNode>)],
+    tree_order: Option<TreeOrder>,
+    targets: &[String],
+    options: &MatchOptions,
+    guessed: &mut Vec<String>,
+) -> Vec<CallRelation> {
+    relabel_target_groups(trees, targets, options);
+    let relations = match tree_order {
+        Some(TreeOrder::Callee) => compute_callee_order_relations(trees, targets, options),
+        Some(TreeOrder::Caller) => {
+            let options = MatchOptions {
+                assume_callee_trees: true,
+                ..options.clone()
+            };
+            compute_call_relations_with(trees, targets, &options)
+        }
+        None => {
+            add_guessed(guessed, guessed_caller_trees(trees, targets, options));
+            compute_call_relations_with(trees, targets, options)
+        }
+    };
+    pperf::log!(
+        Debug,
+        "hierarchy",
+        "relations computed",
+        trees = trees.len(),
+        targets = targets.len(),
+        tree_order = tree_order.map_or("guessed".to_string(), |o| o.to_string()),
+        relations = relations.len(),
+    );
+    relations
+}
+
+fn warn_guessed_tree_order(guessed: &[String]) {
+    if guessed.is_empty() {
+        return;
+    }
+    let mut names = guessed[..guessed.len().min(GUESSED_NAMES_SHOWN)].join(", ");
+    if guessed.len() > GUESSED_NAMES_SHOWN {
+        names.push_str(&format!(
+            " and {} more",
+            guessed.len() - GUESSED_NAMES_SHOWN
+        ));
+    }
+    warnln!(
+        "Warning: call-graph order not stated in the report header; guessed from Self%/Children% that the trees of {} list callers (use --assume-callee-trees or --tree-order to decide)",
+        names
+    );
+}
+
+/// Guessed tree names listed in the warning before the rest are only counted.
+const GUESSED_NAMES_SHOWN: usize = 5;
+
+/// Add newly guessed symbols to `guessed`, each once.
+fn add_guessed(guessed: &mut Vec<String>, symbols: Vec<String>) {
+    for symbol in symbols {
+        if !guessed.contains(&symbol) {
+            guessed.push(symbol);
+        }
+    }
+}
+
+/// The guessed caller-list trees that a lookup of `symbol` reads: those holding it.
+fn guessed_symbol_trees(
+    trees: &[(PerfEntry, Vec<CallTreeNode>)],
+    symbol: &str,
+    tree_order: Option<TreeOrder>,
+    options: &MatchOptions,
+) -> Vec<String> {
+    guessed_caller_entries(
+        trees
+            .iter()
+            .filter(|(_, roots)| tree_contains(roots, symbol))
+            .map(|(entry, _)| entry),
+        tree_order,
+        options,
+    )
+}
+
+/// Each entry of one report with its call tree roots.
+type ReportTrees = Vec<(PerfEntry, Vec<CallTreeNode>)>;
+
+/// Parse one report's call trees for the single-function subcommands, with the
+/// call-graph order to read them in: `tree_order` if given, else the header's.
+fn single_report_trees(
+    file: &PathBuf,
+    tree_order: Option<TreeOrder>,
+) -> Result<(ReportTrees, Option<TreeOrder>), PperfError> {
+    let set = parse_reports(std::slice::from_ref(file))?;
+    let report = set.reports.into_iter().next().unwrap().1;
+    if !report.has_call_graph {
+        return Err(PperfError::NoCallGraph);
+    }
+    let mut trees = report.trees;
+    if report.metadata.percent_type == Some(PercentType::Absolute) {
+        convert_absolute_percentages(&mut trees);
+    }
+    Ok((trees, tree_order.or(report.metadata.tree_order)))
+}
+
+/// The tree of the hottest entry matching `name`, or `NoMatches` with suggestions.
+fn hottest_tree<'a>(
+    trees: &'a [(PerfEntry, Vec<CallTreeNode>)],
+    name: &str,
+    options: &MatchOptions,
+) -> Result<&'a (PerfEntry, Vec<CallTreeNode>), PperfError> {
+    trees
+        .iter()
+        .filter(|(entry, _)| options.matches(&entry.symbol, name))
+        .max_by(|a, b| a.0.children_pct.total_cmp(&b.0.children_pct))
+        .ok_or_else(|| {
+            let entries: Vec<PerfEntry> = trees.iter().map(|(entry, _)| entry.clone()).collect();
+            PperfError::NoMatches(suggest_symbols(&entries, &[name.to_string()], 3))
+        })
+}
+
+fn run_butterfly(args: ButterflyArgs) -> Result<(), PperfError> {
+    let options = MatchOptions {
+        ignore_case: args.ignore_case,
+        assume_callee_trees: args.assume_callee_trees,
+        ..Default::default()
+    };
+    let (trees, tree_order) = single_report_trees(&args.file, args.tree_order)?;
+    let (entry, roots) = hottest_tree(&trees, &args.target, &options)?;
+
+    let symbol = simplify_symbol(&entry.symbol);
+    warn_guessed_tree_order(&guessed_symbol_trees(&trees, &symbol, tree_order, &options));
+    let callers = find_callers(&trees, &symbol, tree_order, &options);
+    let callees = find_callees(&trees, entry, roots, tree_order, &options);
+    let use_color = color_enabled(args.no_color);
+    out!("{}", format_butterfly(entry, &callers, &callees, use_color));
+    Ok(())
+}
+
+fn run_paths(args: PathsArgs) -> Result<(), PperfError> {
+    let options = MatchOptions {
+        ignore_case: args.ignore_case,
+        assume_callee_trees: args.assume_callee_trees,
+        ..Default::default()
+    };
+    let (trees, tree_order) = single_report_trees(&args.file, args.tree_order)?;
+    if tree_order == Some(TreeOrder::Callee) {
+        return Err(PperfError::CalleeOrderedCallGraph("paths".to_string()));
+    }
+    let (entry, roots) = hottest_tree(&trees, &args.from, &options)?;
+
+    warn_guessed_tree_order(&guessed_caller_entries([entry], tree_order, &options));
+    let paths = find_call_paths(entry, roots, &args.to, tree_order, &options);
+    let use_color = color_enabled(args.no_color);
+    out!("{}", format_call_paths(entry, &args.to, &paths, use_color));
+    Ok(())
+}
+
+fn run_hotpath(args: HotpathArgs) -> Result<(), PperfError> {
+    let options = MatchOptions {
+        assume_callee_trees: args.assume_callee_trees,
+        ..Default::default()
+    };
+    let (trees, tree_order) = single_report_trees(&args.file, args.tree_order)?;
+    if tree_order == Some(TreeOrder::Callee) {
+        return Err(PperfError::CalleeOrderedCallGraph("hotpath".to_string()));
+    }
+    let path = find_hot_path(&trees, tree_order, &options).ok_or(PperfError::NoCallGraph)?;
+    // Only a skipped tree hotter than the found leaf could have held a heavier chain
+    warn_guessed_tree_order(&guessed_caller_entries(
+        trees
+            .iter()
+            .map(|(entry, _)| entry)
+            .filter(|entry| entry.children_pct > path.leaf_pct()),
+        tree_order,
+        &options,
+    ));
+    let use_color = color_enabled(args.no_color);
+    out!("{}", format_hot_path(&path, use_color));
+    Ok(())
+}
+
+fn run_stats(args: StatsArgs) -> Result<(), PperfError> {
+    let set = parse_reports(std::slice::from_ref(&args.file))?;
+    out!(
+        "{}",
+        format_distribution(&distribution(&set.reports[0].1.entries))
+    );
+    Ok(())
+}
+
+fn run_synth(args: SynthArgs) -> Result<(), PperfError> {
+    let config = SynthConfig {
+        entries: args.entries,
+        depth: args.depth,
+        seed: args.seed,
+    };
+    let report = generate_report(&config);
 
+    out!("{}", report);
     Ok(())
 }
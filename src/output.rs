@@ -1,34 +1,1374 @@
-use crate::hierarchy::{CallRelation, HierarchyEntry};
-use crate::parser::PerfEntry;
-use crate::symbol::{format_colored_symbol, simplify_symbol};
+use crate::averaging::{EdgeAgreement, ValueStats};
+use crate::budget::BudgetResult;
+use crate::diff::{Change, EntryDiff, RelationDiff};
+use crate::distribution::Distribution;
+use crate::filter::{Target, TargetSpec};
+use crate::hierarchy::{
+    CallPath, CallRelation, CallTreeNode, CalleeRelation, CallerRelation, ContributionMode,
+    HierarchyEntry, HotPath,
+};
+use crate::parser::{PerfEntry, ReportLayout, ReportMetadata};
+use crate::setops::SetRow;
+use crate::symbol::{
+    GREEN, RED, RESET, Symbol, display_alias, format_colored_symbol, format_heat_pct,
+    simplify_symbol,
+};
+use crate::trend::TrendRow;
 use std::collections::{HashMap, HashSet};
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+
+/// Symbol length kept by the tables when the terminal width is unknown.
+pub const DEFAULT_SYMBOL_WIDTH: usize = 100;
+
+/// Shortest symbol length kept however narrow the terminal.
+const MIN_SYMBOL_WIDTH: usize = 20;
+
+/// Columns in front of the Function column of a Children%/Self% table at the
+/// default precision; tables with other columns pass their own to `symbol_width_after`.
+const PCT_COLUMNS_WIDTH: usize = 18;
+
+/// Terminals narrower than this indent nested rows by two spaces instead of four.
+const NARROW_TERMINAL_WIDTH: usize = 80;
+
+/// Width set by `set_terminal_width`; 0 while unknown.
+static TERMINAL_WIDTH: AtomicUsize = AtomicUsize::new(0);
+/// Columns added to every row after a table is formatted (`reserve_columns`).
+static RESERVED_WIDTH: AtomicUsize = AtomicUsize::new(0);
+static INDENT_WIDTH: AtomicUsize = AtomicUsize::new(4);
+static PRECISION: AtomicUsize = AtomicUsize::new(2);
+static HEAT: AtomicBool = AtomicBool::new(false);
+static TRUNCATE_MIDDLE: AtomicBool = AtomicBool::new(false);
+
+/// Fit every table to a `width`-column terminal (`--width`, or the detected width):
+/// symbols keep what the percentage columns leave, and narrow terminals indent
+/// nested rows by two spaces per level. Without a call, symbols are cut at 100.
+pub fn set_terminal_width(width: usize) {
+    TERMINAL_WIDTH.store(width, Ordering::Relaxed);
+    let indent = if width < NARROW_TERMINAL_WIDTH { 2 } else { 4 };
+    INDENT_WIDTH.store(indent, Ordering::Relaxed);
+}
+
+/// Leave `width` columns of every row for what is added once a table is formatted
+/// (`--rank`, `--bars`, `--show-srcline`, trailing markers; see `decoration_width`).
+pub fn reserve_columns(width: usize) {
+    RESERVED_WIDTH.store(width, Ordering::Relaxed);
+}
+
+/// Symbol length kept in a Function column behind `prefix` columns: what the
+/// terminal leaves of its width, or `DEFAULT_SYMBOL_WIDTH` when that is unknown.
+fn symbol_width_after(prefix: usize) -> usize {
+    match TERMINAL_WIDTH.load(Ordering::Relaxed) {
+        0 => DEFAULT_SYMBOL_WIDTH,
+        width => width
+            .saturating_sub(prefix + RESERVED_WIDTH.load(Ordering::Relaxed))
+            .max(MIN_SYMBOL_WIDTH),
+    }
+}
+
+/// Symbol length kept in the Function column of a table with two percentage columns.
+fn symbol_width() -> usize {
+    symbol_width_after(PCT_COLUMNS_WIDTH)
+}
+
+/// Symbol length kept for a row nested `level` indents deep.
+fn nested_symbol_width(level: usize) -> usize {
+    symbol_width()
+        .saturating_sub(level * INDENT_WIDTH.load(Ordering::Relaxed))
+        .max(MIN_SYMBOL_WIDTH)
+}
+
+/// Leading whitespace of a row nested `level` indents deep.
+fn indentation(level: usize) -> String {
+    " ".repeat(level * INDENT_WIDTH.load(Ordering::Relaxed))
+}
+
+/// Where `truncate_symbol` cuts a symbol longer than its column (`--truncate`).
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum TruncateMode {
+    /// Keep the start: `Namespace::Class::met...`
+    #[default]
+    End,
+    /// Keep the start and the end: `Namespace::Cl...::method`
+    Middle,
+}
+
+impl TruncateMode {
+    /// Parse an `end`/`middle` mode name.
+    pub fn from_setting(value: &str) -> Option<TruncateMode> {
+        match value.trim() {
+            "end" => Some(TruncateMode::End),
+            "middle" => Some(TruncateMode::Middle),
+            _ => None,
+        }
+    }
+}
+
+pub fn set_truncate_mode(mode: TruncateMode) {
+    TRUNCATE_MIDDLE.store(mode == TruncateMode::Middle, Ordering::Relaxed);
+}
+
+fn truncate_mode() -> TruncateMode {
+    if TRUNCATE_MIDDLE.load(Ordering::Relaxed) {
+        TruncateMode::Middle
+    } else {
+        TruncateMode::End
+    }
+}
+
+/// Decimal places of every percentage shown (`--precision`); 2 unless set.
+pub fn set_precision(places: usize) {
+    PRECISION.store(places, Ordering::Relaxed);
+}
+
+fn precision() -> usize {
+    PRECISION.load(Ordering::Relaxed)
+}
+
+/// Color the Children%/Self% (and Overhead%) numbers by magnitude (`--heat`);
+/// only set it when colors are on.
+pub fn set_heat(enabled: bool) {
+    HEAT.store(enabled, Ordering::Relaxed);
+}
+
+fn heat() -> bool {
+    HEAT.load(Ordering::Relaxed)
+}
+
+/// Width of a `{:.prec$}` percentage column: the widest value, but at least `min`.
+fn pct_width(values: impl Iterator<Item = f64>, min: usize) -> usize {
+    let prec = precision();
+    values
+        .map(|v| format!("{:.prec$}", v).len())
+        .fold(min, usize::max)
+}
+
+/// How nested hierarchy rows show their level (`--tree-style`).
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum TreeStyle {
+    /// `├──`/`└──` connectors with `│` continuing open levels
+    Unicode,
+    /// The same drawn with `|--`, `` `-- `` and `|`
+    Ascii,
+    /// Indentation only
+    #[default]
+    Plain,
+}
+
+impl TreeStyle {
+    /// Parse a `unicode`/`ascii`/`plain` style name.
+    pub fn from_setting(value: &str) -> Option<TreeStyle> {
+        match value.trim() {
+            "unicode" => Some(TreeStyle::Unicode),
+            "ascii" => Some(TreeStyle::Ascii),
+            "plain" => Some(TreeStyle::Plain),
+            _ => None,
+        }
+    }
+
+    /// One level of a line's prefix, one indent wide: the row's own `connector`, or
+    /// an enclosing level, barred unless it is on its `last` sibling.
+    fn segment(self, connector: bool, last: bool) -> String {
+        let width = INDENT_WIDTH.load(Ordering::Relaxed).max(2);
+        let (branch, corner, bar, line) = match self {
+            TreeStyle::Unicode => ("├", "└", "│", "─"),
+            TreeStyle::Ascii => ("|", "`", "|", "-"),
+            TreeStyle::Plain => return " ".repeat(width),
+        };
+        match (connector, last) {
+            (true, false) => format!("{}{} ", branch, line.repeat(width - 2)),
+            (true, true) => format!("{}{} ", corner, line.repeat(width - 2)),
+            (false, false) => format!("{}{}", bar, " ".repeat(width - 1)),
+            (false, true) => " ".repeat(width),
+        }
+    }
+}
+
+enum PctLine {
+    /// Values and text of a row nested `depth` levels deep
+    Row(f64, f64, usize, String),
+    /// Text shown under the Function column (debug annotations), below a row
+    /// `depth` levels deep
+    Note(usize, String),
+}
+
+/// Children%/Self% table rows, rendered once all values are known so the
+/// percentage columns fit the widest value and never shift the Function column.
+#[derive(Default)]
+struct PctTable {
+    lines: Vec<PctLine>,
+    style: TreeStyle,
+}
+
+impl PctTable {
+    fn row(&mut self, children_pct: f64, self_pct: f64, text: String) {
+        self.nested_row(0, children_pct, self_pct, text);
+    }
+
+    fn nested_row(&mut self, depth: usize, children_pct: f64, self_pct: f64, text: String) {
+        self.lines
+            .push(PctLine::Row(children_pct, self_pct, depth, text));
+    }
+
+    fn note(&mut self, text: String) {
+        self.nested_note(0, text);
+    }
+
+    fn nested_note(&mut self, depth: usize, text: String) {
+        self.lines.push(PctLine::Note(depth, text));
+    }
+
+    /// True when no row `level` deep follows line `i` before the rows go back
+    /// above that level, i.e. line `i` belongs to the last sibling at `level`.
+    fn last_at(&self, i: usize, level: usize) -> bool {
+        for line in &self.lines[i + 1..] {
+            if let PctLine::Row(_, _, depth, _) = line {
+                if *depth == level {
+                    return false;
+                }
+                if *depth < level {
+                    return true;
+                }
+            }
+        }
+        true
+    }
+
+    /// Tree prefix of line `i`, nested `depth` levels deep.
+    fn prefix(&self, i: usize, depth: usize, is_row: bool) -> String {
+        (1..=depth)
+            .map(|level| {
+                self.style
+                    .segment(is_row && level == depth, self.last_at(i, level))
+            })
+            .collect()
+    }
+
+    fn render(&self) -> String {
+        let prec = precision();
+        let rows = || {
+            self.lines.iter().filter_map(|line| match line {
+                PctLine::Row(c, s, _, _) => Some((*c, *s)),
+                PctLine::Note(..) => None,
+            })
+        };
+        let children_width = pct_width(rows().map(|(c, _)| c), 8);
+        let self_width = pct_width(rows().map(|(_, s)| s), 6);
+
+        // "Children%" is one character wider than the default column
+        let mut output = format!(
+            "{:>cw$}{:>sw$}  Function\n",
+            "Children%",
+            "Self%",
+            cw = children_width + 1,
+            sw = self_width + 2
+        );
+        let note_pad = " ".repeat(children_width + 2 + self_width + 2);
+        for (i, line) in self.lines.iter().enumerate() {
+            match line {
+                PctLine::Row(c, s, depth, text) => output.push_str(&format!(
+                    "{}  {}  {}{}\n",
+                    format_heat_pct(&format!("{:>children_width$.prec$}", c), *c, heat()),
+                    format_heat_pct(&format!("{:>self_width$.prec$}", s), *s, heat()),
+                    self.prefix(i, *depth, true),
+                    text
+                )),
+                PctLine::Note(depth, text) => output.push_str(&format!(
+                    "{}{}{}\n",
+                    note_pad,
+                    self.prefix(i, *depth, false),
+                    text
+                )),
+            }
+        }
+        output
+    }
+}
+
+/// Display names from target-file aliases. Symbols are resolved with the targets'
+/// own matching, so full signatures (entries) and simplified names (call trees) both
+/// pick up the alias. Apply after all matching is done, right before formatting.
+#[derive(Debug, Clone, Default)]
+pub struct SymbolAliases {
+    aliases: Vec<(TargetSpec, Symbol)>,
+    ignore_case: bool,
+}
+
+impl SymbolAliases {
+    pub fn new(targets: &[Target], ignore_case: bool) -> SymbolAliases {
+        SymbolAliases {
+            aliases: targets
+                .iter()
+                .filter_map(|t| Some((t.spec.clone(), Symbol::from(t.alias.as_deref()?))))
+                .collect(),
+            ignore_case,
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.aliases.is_empty()
+    }
+
+    pub fn resolve(&self, symbol: &str) -> Option<&str> {
+        self.resolve_symbol(symbol).map(Symbol::as_str)
+    }
+
+    fn resolve_symbol(&self, symbol: &str) -> Option<&Symbol> {
+        self.aliases
+            .iter()
+            .find(|(spec, _)| spec.matches(symbol, self.ignore_case))
+            .map(|(_, alias)| alias)
+    }
+
+    fn rename(&self, symbol: &mut Symbol) {
+        if let Some(alias) = self.resolve_symbol(symbol) {
+            *symbol = alias.clone();
+        }
+    }
+
+    fn rename_relation(&self, relation: &mut CallRelation) {
+        self.rename(&mut relation.caller);
+        self.rename(&mut relation.callee);
+        if let Some(ref mut root) = relation.context_root {
+            self.rename(root);
+        }
+    }
+
+    pub fn apply_to_entries(&self, entries: &mut [PerfEntry]) {
+        for entry in entries {
+            self.rename(&mut entry.symbol);
+        }
+    }
+
+    pub fn apply_to_hierarchy(
+        &self,
+        entries: &mut [HierarchyEntry],
+        relations: &mut [CallRelation],
+    ) {
+        for entry in entries {
+            self.rename(&mut entry.symbol);
+            for callee in &mut entry.callees {
+                self.rename_relation(callee);
+            }
+            for contribution in &mut entry.contributions {
+                self.rename(&mut contribution.caller);
+            }
+        }
+        for relation in relations {
+            self.rename_relation(relation);
+        }
+    }
+
+    pub fn apply_to_agreements(&self, agreements: &mut [EdgeAgreement]) {
+        for agreement in agreements {
+            self.rename(&mut agreement.caller);
+            self.rename(&mut agreement.callee);
+            if let Some(ref mut root) = agreement.context_root {
+                self.rename(root);
+            }
+        }
+    }
+}
+
+/// T021: Format table with optional color support
+pub fn format_table(entries: &[PerfEntry], use_color: bool) -> String {
+    let mut table = PctTable::default();
+    // The widths `PctTable::render` gives the two percentage columns
+    let prefix = pct_width(entries.iter().map(|e| e.children_pct), 8)
+        + pct_width(entries.iter().map(|e| e.self_pct), 6)
+        + 4;
+
+    for entry in entries {
+        let symbol = truncate_symbol(&entry.symbol, symbol_width_after(prefix));
+        // T022: Apply colors to each entry's symbol
+        let colored_symbol = format_colored_symbol(&symbol, use_color);
+        table.row(entry.children_pct, entry.self_pct, colored_symbol);
+    }
+
+    table.render()
+}
+
+/// Format a `perf report --no-children` table, which only has an Overhead column.
+pub fn format_overhead_table(entries: &[PerfEntry], use_color: bool) -> String {
+    format_self_table(entries, "Overhead%", use_color)
+}
+
+/// Format `--group-by` scopes: their summed Self% only, since summing the inclusive
+/// Children% of functions that call each other counts the same time twice.
+pub fn format_scope_table(entries: &[PerfEntry], use_color: bool) -> String {
+    format_self_table(entries, "Self%", use_color)
+}
+
+fn format_self_table(entries: &[PerfEntry], title: &str, use_color: bool) -> String {
+    let prec = precision();
+    let width = pct_width(entries.iter().map(|e| e.self_pct), 9);
+    let mut output = format!("{:>width$}  Function\n", title);
+
+    for entry in entries {
+        let symbol = truncate_symbol(&entry.symbol, symbol_width_after(width + 2));
+        let colored_symbol = format_colored_symbol(&symbol, use_color);
+        let pct = format!("{:>width$.prec$}", entry.self_pct);
+        output.push_str(&format!(
+            "{}  {}\n",
+            format_heat_pct(&pct, entry.self_pct, heat()),
+            colored_symbol
+        ));
+    }
+
+    output
+}
+
+/// Format `--stats` rows: each percentage followed by its standard deviation, min and
+/// max across reports (`-` where an entry has no per-report values, e.g. a merged
+/// group). `--no-children` reports only have the Overhead% (self) columns.
+pub fn format_stats_table(
+    entries: &[PerfEntry],
+    stats: &[Option<(ValueStats, ValueStats)>],
+    layout: ReportLayout,
+    use_color: bool,
+) -> String {
+    fn cells(value: f64, stats: Option<ValueStats>, width: usize) -> String {
+        let prec = precision();
+        let spread = match stats {
+            Some(s) => format!(
+                "{:>6.prec$} {:>6.prec$} {:>6.prec$}",
+                s.stddev, s.min, s.max
+            ),
+            None => format!("{:>6} {:>6} {:>6}", "-", "-", "-"),
+        };
+        format!("{:>width$.prec$} {}", value, spread)
+    }
+
+    let mut output = match layout {
+        ReportLayout::ChildrenSelf => format!(
+            "{:>9} {:>6} {:>6} {:>6}  {:>6} {:>6} {:>6} {:>6}  Function\n",
+            "Children%", "SD", "Min", "Max", "Self%", "SD", "Min", "Max"
+        ),
+        ReportLayout::Overhead => format!(
+            "{:>9} {:>6} {:>6} {:>6}  Function\n",
+            "Overhead%", "SD", "Min", "Max"
+        ),
+    };
+    // Each percentage column is followed by its SD, Min and Max
+    let prefix = match layout {
+        ReportLayout::ChildrenSelf => 9 + 3 * 7 + 2 + 6 + 3 * 7 + 2,
+        ReportLayout::Overhead => 9 + 3 * 7 + 2,
+    };
+    for (entry, stats) in entries.iter().zip(stats) {
+        let symbol = truncate_symbol(&entry.symbol, symbol_width_after(prefix));
+        let colored_symbol = format_colored_symbol(&symbol, use_color);
+        let line = match layout {
+            ReportLayout::ChildrenSelf => format!(
+                "{}  {}",
+                cells(entry.children_pct, stats.map(|(c, _)| c), 9),
+                cells(entry.self_pct, stats.map(|(_, s)| s), 6)
+            ),
+            ReportLayout::Overhead => cells(entry.self_pct, stats.map(|(_, s)| s), 9),
+        };
+        output.push_str(&format!("{}  {}\n", line, colored_symbol));
+    }
+
+    output
+}
+
+/// Append `! CV x%` to the rows of a flat table (one line per entry below the
+/// header) whose coefficient of variation across reports exceeds `threshold`.
+pub fn mark_high_variance(
+    table: &str,
+    variances: &[Option<f64>],
+    threshold: f64,
+    use_color: bool,
+) -> String {
+    let mut output = String::new();
+    for (i, line) in table.lines().enumerate() {
+        output.push_str(line);
+        if let Some(Some(cv)) = i.checked_sub(1).and_then(|row| variances.get(row))
+            && *cv > threshold
+        {
+            let marker = format!("! CV {:.1}%", cv);
+            if use_color {
+                output.push_str(&format!("  {}{}{}", RED, marker, RESET));
+            } else {
+                output.push_str(&format!("  {}", marker));
+            }
+        }
+        output.push('\n');
+    }
+    output
+}
+
+/// Append `(N overloads)` to the rows of a flat table (one line per entry below
+/// the header) that `--merge-overloads` merged, given each entry's member
+/// signatures (empty for unmerged entries); with `debug`, list the signatures
+/// below the row.
+pub fn mark_overloads(table: &str, signatures: &[Vec<Symbol>], debug: bool) -> String {
+    let columns = table.lines().next().map_or(0, |header| {
+        header
+            .split_whitespace()
+            .take_while(|title| *title != "Function")
+            .count()
+    });
+    let mut output = String::new();
+    for (i, line) in table.lines().enumerate() {
+        output.push_str(line);
+        let members = i.checked_sub(1).and_then(|row| signatures.get(row));
+        if let Some(members) = members.filter(|m| m.len() > 1) {
+            output.push_str(&format!("  ({} overloads)", members.len()));
+            if debug {
+                // Under the symbol: past the titled columns and any `--bars` bar
+                let mut end = after_columns(line, columns);
+                if line[end..].trim_start().starts_with(['█', '░']) {
+                    end = after_columns(line, columns + 1);
+                }
+                let column = line[..end].chars().count() + 2;
+                for member in members {
+                    output.push_str(&format!("\n{:column$}(overload: {})", "", member));
+                }
+            }
+        }
+        output.push('\n');
+    }
+    output
+}
+
+/// Cells of a `--bars` bar; a full bar is 100%.
+const BAR_WIDTH: usize = 10;
+
+/// A `BAR_WIDTH`-cell bar showing `pct` of 100%, e.g. `███░░░░░░░` for 30%.
+pub fn format_bar(pct: f64) -> String {
+    let filled = ((pct / 100.0 * BAR_WIDTH as f64).round() as usize).min(BAR_WIDTH);
+    format!("{}{}", "█".repeat(filled), "░".repeat(BAR_WIDTH - filled))
+}
+
+/// Insert a bar of each entry's value after the percentage columns of a flat table
+/// (one line per entry below the header), like `perf report --tui` shows.
+pub fn add_bar_column(table: &str, values: &[f64]) -> String {
+    let mut lines = table.lines();
+    let Some(header) = lines.next() else {
+        return String::new();
+    };
+    let columns = header
+        .split_whitespace()
+        .take_while(|title| *title != "Function")
+        .count();
+    let (head, title) = header.split_at(after_columns(header, columns));
+    let mut output = format!("{}  {:w$}{}\n", head, "", title, w = BAR_WIDTH);
+    for (line, value) in lines.zip(values) {
+        let (pcts, symbol) = line.split_at(after_columns(line, columns));
+        output.push_str(&format!("{}  {}{}\n", pcts, format_bar(*value), symbol));
+    }
+    output
+}
+
+/// Insert a `Source` column with each entry's `file:line` after the percentage columns
+/// of a flat table (one line per entry below the header); `-` where it is unknown.
+/// Right-aligned like the percentages, so later columns find where each row's ends.
+pub fn add_srcline_column(table: &str, srclines: &[String]) -> String {
+    let mut lines = table.lines();
+    let Some(header) = lines.next() else {
+        return String::new();
+    };
+    let columns = header
+        .split_whitespace()
+        .take_while(|title| *title != "Function")
+        .count();
+    let cells: Vec<&str> = srclines
+        .iter()
+        .map(|srcline| if srcline.is_empty() { "-" } else { srcline })
+        .collect();
+    let width = srcline_width(srclines);
+    let (head, title) = header.split_at(after_columns(header, columns));
+    let mut output = format!("{}  {:>w$}{}\n", head, "Source", title, w = width);
+    for (line, cell) in lines.zip(cells) {
+        let (pcts, symbol) = line.split_at(after_columns(line, columns));
+        output.push_str(&format!("{}  {:>w$}{}\n", pcts, cell, symbol, w = width));
+    }
+    output
+}
+
+/// Width of the `--show-srcline` column: the longest `file:line`, at least `Source`.
+fn srcline_width(srclines: &[String]) -> usize {
+    srclines
+        .iter()
+        .map(|srcline| srcline.chars().count())
+        .fold(6, usize::max)
+}
+
+/// Longest `mark_high_variance` marker, `  ! CV 100.0%`.
+const VARIANCE_MARKER_WIDTH: usize = 13;
+
+/// Longest `mark_overloads` marker counted on, `  (99 overloads)`.
+const OVERLOAD_MARKER_WIDTH: usize = 16;
+
+/// Columns the flat-table decorations add to each row, for `reserve_columns`:
+/// `add_rank_column` over `rows` rows, `add_bar_column`, `add_srcline_column` with
+/// `srclines`, and the `mark_high_variance` and `mark_overloads` markers.
+pub fn decoration_width(
+    rank_rows: Option<usize>,
+    bars: bool,
+    srclines: Option<&[String]>,
+    variance_marker: bool,
+    overload_marker: bool,
+) -> usize {
+    rank_rows.map_or(0, |rows| rows.to_string().len() + 2)
+        + if bars { BAR_WIDTH + 2 } else { 0 }
+        + srclines.map_or(0, |s| srcline_width(s) + 2)
+        + if variance_marker {
+            VARIANCE_MARKER_WIDTH
+        } else {
+            0
+        }
+        + if overload_marker {
+            OVERLOAD_MARKER_WIDTH
+        } else {
+            0
+        }
+}
+
+/// Byte offset just past the first `columns` whitespace-separated words of `line`.
+fn after_columns(line: &str, columns: usize) -> usize {
+    let mut end = 0;
+    for _ in 0..columns {
+        let start = end + line[end..].len() - line[end..].trim_start().len();
+        end = line[start..]
+            .find(char::is_whitespace)
+            .map_or(line.len(), |i| start + i);
+    }
+    end
+}
+
+/// Prefix a flat table (one line per entry below the header) with a `#` column
+/// numbering the entries from 1, so rows can be referred to by rank.
+pub fn add_rank_column(table: &str) -> String {
+    let width = table.lines().count().saturating_sub(1).to_string().len();
+    let mut output = String::new();
+    for (i, line) in table.lines().enumerate() {
+        let rank = if i == 0 {
+            "#".to_string()
+        } else {
+            i.to_string()
+        };
+        output.push_str(&format!("{:>width$}  {}\n", rank, line));
+    }
+    output
+}
+
+/// Titles of the name column that ends every table's column header line.
+const NAME_COLUMN_TITLES: &[&str] = &[
+    "Function",
+    "Caller",
+    "Callee",
+    "Path",
+    "Edge",
+    "Threshold",
+    "Coverage",
+    "Range",
+];
+
+/// True for a table's column header line such as `Children%   Self%  Function`: it
+/// ends in a name-column title and, unlike every row, holds no number.
+pub fn is_column_header(line: &str) -> bool {
+    let words: Vec<&str> = line.split_whitespace().collect();
+    words
+        .last()
+        .is_some_and(|last| NAME_COLUMN_TITLES.contains(last))
+        && !words
+            .iter()
+            .any(|word| word.trim_matches(['+', '%']).parse::<f64>().is_ok())
+}
+
+/// Drop the column header lines of printed tables (`--no-header`), so every line of
+/// a table is a row. With `legends` (`--quiet`) the titles and report legends printed
+/// above each header and the blank lines between tables go too; lines inside a
+/// table are kept.
+pub fn strip_table_header(text: &str, legends: bool) -> String {
+    let mut output = String::new();
+    let mut pending = String::new();
+    let mut in_table = false;
+    for line in text.split_inclusive('\n') {
+        if is_column_header(line) {
+            if !legends {
+                output.push_str(&pending);
+            }
+            pending.clear();
+            in_table = true;
+        } else if line.trim().is_empty() {
+            in_table = false;
+            if !legends {
+                pending.push_str(line);
+            }
+        } else if in_table {
+            output.push_str(line);
+        } else {
+            pending.push_str(line);
+        }
+    }
+    output.push_str(&pending);
+    output
+}
+
+/// Title of the percentage `pperf set` and `pperf trend` list per report: the
+/// inclusive Children%, or the Overhead% of `--no-children` reports.
+fn per_report_title(layout: ReportLayout) -> &'static str {
+    match layout {
+        ReportLayout::ChildrenSelf => "Children%",
+        ReportLayout::Overhead => "Overhead%",
+    }
+}
+
+/// Format `pperf set` rows: a numbered legend of report names, then one Children%
+/// (Overhead% for `layout` `Overhead`) column per report (`-` where the symbol is absent).
+pub fn format_set_table(
+    rows: &[SetRow],
+    names: &[String],
+    layout: ReportLayout,
+    use_color: bool,
+) -> String {
+    let prec = precision();
+    let mut output = String::new();
+    for (i, name) in names.iter().enumerate() {
+        output.push_str(&format!("[{}] {}\n", i + 1, name));
+    }
+    let width = pct_width(
+        rows.iter()
+            .flat_map(|r| r.children.iter().flatten().copied()),
+        8,
+    );
+    output.push_str(&format!("\n{} per report:\n", per_report_title(layout)));
+    for i in 0..names.len() {
+        output.push_str(&format!("{:>width$}  ", format!("[{}]", i + 1)));
+    }
+    output.push_str("Function\n");
+
+    for row in rows {
+        for value in &row.children {
+            match value {
+                Some(v) => output.push_str(&format!("{:>width$.prec$}  ", v)),
+                None => output.push_str(&format!("{:>width$}  ", "-")),
+            }
+        }
+        let symbol = truncate_symbol(&row.symbol, symbol_width_after(names.len() * (width + 2)));
+        output.push_str(&format_colored_symbol(&symbol, use_color));
+        output.push('\n');
+    }
+
+    output
+}
+
+/// Color `text` red when `delta` grows by more than `band` points (a regression) and
+/// green when it drops by more; changes within the band stay uncolored.
+fn color_delta(text: String, delta: f64, band: f64, use_color: bool) -> String {
+    if !use_color || delta.abs() <= band {
+        text
+    } else if delta > 0.0 {
+        format!("{}{}{}", RED, text, RESET)
+    } else {
+        format!("{}{}{}", GREEN, text, RESET)
+    }
+}
+
+/// Format `pperf diff` entry rows: Children% in each report (`-` where absent) and
+/// the change in points, colored outside the `neutral_band` (see `color_delta`).
+pub fn format_entry_diff(
+    rows: &[EntryDiff],
+    old_name: &str,
+    new_name: &str,
+    neutral_band: f64,
+    use_color: bool,
+) -> String {
+    let prec = precision();
+    let mut output = format!("Old: {}\nNew: {}\n\n", old_name, new_name);
+    let width = pct_width(
+        rows.iter()
+            .flat_map(|r| [r.old_children, r.new_children])
+            .flatten(),
+        8,
+    );
+    output.push_str(&format!(
+        "{:>width$}  {:>width$}  {:>width$}  Function\n",
+        "Old", "New", "Delta"
+    ));
+
+    let cell = |value: Option<f64>| match value {
+        Some(v) => format!("{:>width$.prec$}", v),
+        None => format!("{:>width$}", "-"),
+    };
+    for row in rows {
+        let symbol = truncate_symbol(&row.symbol, symbol_width_after(3 * (width + 2)));
+        let delta = format!("{:>+width$.prec$}", row.delta());
+        output.push_str(&format!(
+            "{}  {}  {}  {}\n",
+            cell(row.old_children),
+            cell(row.new_children),
+            color_delta(delta, row.delta(), neutral_band, use_color),
+            format_colored_symbol(&symbol, use_color)
+        ));
+    }
+
+    output
+}
+
+/// Format the `diff --fail-above` summary as tab-separated lines: one
+/// `regression<TAB>delta<TAB>old<TAB>new<TAB>symbol` per regressed entry (`-` where
+/// absent), then `result<TAB>PASS|FAIL<TAB>count<TAB>limit`.
+pub fn format_regression_summary(regressions: &[&EntryDiff], limit: f64) -> String {
+    let prec = precision();
+    let cell = |value: Option<f64>| match value {
+        Some(v) => format!("{:.prec$}", v),
+        None => "-".to_string(),
+    };
+    let mut output = String::new();
+    for row in regressions {
+        output.push_str(&format!(
+            "regression\t{:+.prec$}\t{}\t{}\t{}\n",
+            row.delta(),
+            cell(row.old_children),
+            cell(row.new_children),
+            row.symbol
+        ));
+    }
+    output.push_str(&format!(
+        "result\t{}\t{}\t{:.prec$}\n",
+        if regressions.is_empty() {
+            "PASS"
+        } else {
+            "FAIL"
+        },
+        regressions.len(),
+        limit
+    ));
+    output
+}
+
+/// Format `pperf diff --hierarchy` rows as `caller → callee: old% → new%`, with the
+/// context root of path-specific relations and the kind of change in front. The
+/// values are colored outside the `neutral_band` (see `color_delta`).
+pub fn format_relation_diff(
+    rows: &[RelationDiff],
+    threshold: f64,
+    neutral_band: f64,
+    use_color: bool,
+) -> String {
+    let prec = precision();
+    let mut output = format!(
+        "Relations (absolute %, changes above {:.prec$} points):\n",
+        threshold
+    );
+    let pct = |value: Option<f64>| match value {
+        Some(v) => format!("{:.prec$}%", v),
+        None => "-".to_string(),
+    };
+    for row in rows {
+        let change = row.change(threshold);
+        let values = match change {
+            Change::Appeared => pct(row.new_pct),
+            Change::Disappeared => pct(row.old_pct),
+            _ => format!("{} → {}", pct(row.old_pct), pct(row.new_pct)),
+        };
+        let context = match row.context_root {
+            Some(ref root) => format!(" (in {})", format_colored_symbol(root, use_color)),
+            None => String::new(),
+        };
+        output.push_str(&format!(
+            "  {:<11}  {} → {}{}: {}\n",
+            change.to_string(),
+            format_colored_symbol(&row.caller, use_color),
+            format_colored_symbol(&row.callee, use_color),
+            context,
+            color_delta(values, row.delta(), neutral_band, use_color)
+        ));
+    }
+
+    output
+}
+
+/// Marker after the symbols of `pperf trend` that rose in every report.
+const GROWING_MARKER: &str = "! growing";
+
+/// Format `pperf trend` rows like `format_set_table`, with the slope (points per
+/// report) and `! growing` after symbols that rose in every report.
+pub fn format_trend_table(
+    rows: &[TrendRow],
+    names: &[String],
+    layout: ReportLayout,
+    use_color: bool,
+) -> String {
+    let prec = precision();
+    let mut output = String::new();
+    for (i, name) in names.iter().enumerate() {
+        output.push_str(&format!("[{}] {}\n", i + 1, name));
+    }
+    let width = pct_width(
+        rows.iter().flat_map(|r| r.values.iter().flatten().copied()),
+        8,
+    );
+    output.push_str(&format!("\n{} per report:\n", per_report_title(layout)));
+    for i in 0..names.len() {
+        output.push_str(&format!("{:>width$}  ", format!("[{}]", i + 1)));
+    }
+    output.push_str(&format!("{:>width$}  Function\n", "Slope"));
+
+    for row in rows {
+        for value in &row.values {
+            match value {
+                Some(v) => output.push_str(&format!("{:>width$.prec$}  ", v)),
+                None => output.push_str(&format!("{:>width$}  ", "-")),
+            }
+        }
+        output.push_str(&format!("{:>+width$.prec$}  ", row.slope));
+        // A column per report and the slope; `! growing` follows some symbols
+        let prefix = (names.len() + 1) * (width + 2);
+        let symbol = truncate_symbol(
+            &row.symbol,
+            symbol_width_after(prefix + GROWING_MARKER.len() + 2),
+        );
+        output.push_str(&format_colored_symbol(&symbol, use_color));
+        if row.growing {
+            if use_color {
+                output.push_str(&format!("  {}{}{}", RED, GROWING_MARKER, RESET));
+            } else {
+                output.push_str(&format!("  {}", GROWING_MARKER));
+            }
+        }
+        output.push('\n');
+    }
+
+    output
+}
+
+/// Format `--budget` results: PASS/FAIL (or MISSING when nothing matched) with the
+/// measured value next to each maximum (`-` where a budget sets no limit).
+pub fn format_budget_table(results: &[BudgetResult], use_color: bool) -> String {
+    let prec = precision();
+    let cell = |value: Option<f64>| match value {
+        Some(v) => format!("{:>9.prec$}", v),
+        None => format!("{:>9}", "-"),
+    };
+    let mut output = format!(
+        "{:<7}  {:>9}  {:>9}  {:>9}  {:>9}  Function\n",
+        "Status", "Children%", "Max", "Self%", "Max"
+    );
+    for result in results {
+        let status = if result.exceeded() {
+            "FAIL"
+        } else if result.children_pct.is_none() {
+            "MISSING"
+        } else {
+            "PASS"
+        };
+        let status = if use_color && result.exceeded() {
+            format!("{}{:<7}{}", RED, status, RESET)
+        } else {
+            format!("{:<7}", status)
+        };
+        output.push_str(&format!(
+            "{}  {}  {}  {}  {}  {}\n",
+            status,
+            cell(result.children_pct),
+            cell(result.budget.max_children),
+            cell(result.self_pct),
+            cell(result.budget.max_self),
+            truncate_symbol(&result.symbol, symbol_width())
+        ));
+    }
+    output
+}
+
+/// Format one `--callers` block: the function with its Children%, then each direct
+/// caller's share of total runtime (Total%) and of the function's time (Share%).
+pub fn format_callers(entry: &PerfEntry, callers: &[CallerRelation], use_color: bool) -> String {
+    let prec = precision();
+    let name = truncate_symbol(&simplify_symbol(&entry.symbol), symbol_width());
+    let mut output = format!(
+        "{} ({:.prec$}% children)\n",
+        format_colored_symbol(&name, use_color),
+        entry.children_pct
+    );
+    if callers.is_empty() {
+        output.push_str("  (no callers in call graph)\n");
+        return output;
+    }
+    output.push_str("   Total%   Share%  Caller\n");
+    for caller in callers {
+        let share = if entry.children_pct > 0.0 {
+            caller.absolute_pct / entry.children_pct * 100.0
+        } else {
+            0.0
+        };
+        let symbol = truncate_symbol(&caller.caller, symbol_width());
+        output.push_str(&format!(
+            "{:>9.prec$}{:>9.prec$}  {}\n",
+            caller.absolute_pct,
+            share,
+            format_colored_symbol(&symbol, use_color)
+        ));
+    }
+    output
+}
+
+/// Format `pperf butterfly`: the function's callers, the function itself, then its
+/// callees, each with Total% (of runtime) and Share% (of the function's time).
+pub fn format_butterfly(
+    entry: &PerfEntry,
+    callers: &[CallerRelation],
+    callees: &[CalleeRelation],
+    use_color: bool,
+) -> String {
+    let prec = precision();
+    let row = |total: f64, share: f64, symbol: &str| {
+        format!(
+            "{:>9.prec$}{:>9.prec$}  {}\n",
+            total,
+            share,
+            format_colored_symbol(&truncate_symbol(symbol, symbol_width()), use_color)
+        )
+    };
+
+    let mut output = String::from("   Total%   Share%  Caller\n");
+    if callers.is_empty() {
+        output.push_str("  (no callers in call graph)\n");
+    }
+    for caller in callers {
+        let share = if entry.children_pct > 0.0 {
+            caller.absolute_pct / entry.children_pct * 100.0
+        } else {
+            0.0
+        };
+        output.push_str(&row(caller.absolute_pct, share, &caller.caller));
+    }
+    let name = truncate_symbol(&simplify_symbol(&entry.symbol), symbol_width());
+    output.push_str(&format!(
+        "-> {} ({:.prec$}% children, {:.prec$}% self)\n",
+        format_colored_symbol(&name, use_color),
+        entry.children_pct,
+        entry.self_pct
+    ));
+    output.push_str("   Total%   Share%  Callee\n");
+    if callees.is_empty() {
+        output.push_str("  (no callees in call graph)\n");
+    }
+    for callee in callees {
+        output.push_str(&row(
+            callee.absolute_pct,
+            callee.relative_pct,
+            &callee.callee,
+        ));
+    }
+    output
+}
+
+/// Format `pperf paths`: one row per call chain with its % of the caller's time
+/// (Relative%) and of runtime (Total%), the chain written as in `--debug`, then the sum.
+pub fn format_call_paths(
+    entry: &PerfEntry,
+    to: &str,
+    paths: &[CallPath],
+    use_color: bool,
+) -> String {
+    let prec = precision();
+    let name = truncate_symbol(&simplify_symbol(&entry.symbol), symbol_width());
+    let mut output = format!(
+        "Paths from {} ({:.prec$}% children) to {}:\n",
+        format_colored_symbol(&name, use_color),
+        entry.children_pct,
+        to
+    );
+    if paths.is_empty() {
+        output.push_str("  (no paths in call graph)\n");
+        return output;
+    }
+    output.push_str("Relative%   Total%  Path\n");
+    for path in paths {
+        let chain: Vec<String> = path
+            .steps
+            .iter()
+            .map(|step| {
+                format!(
+                    "{} {:.prec$}%",
+                    format_colored_symbol(
+                        &truncate_symbol(&step.symbol, symbol_width()),
+                        use_color
+                    ),
+                    step.percentage
+                )
+            })
+            .collect();
+        output.push_str(&format!(
+            "{:>9.prec$}{:>9.prec$}  {}\n",
+            path.relative_pct,
+            path.absolute_pct,
+            chain.join(" × ")
+        ));
+    }
+    let relative: f64 = paths.iter().map(|p| p.relative_pct).sum();
+    let absolute: f64 = paths.iter().map(|p| p.absolute_pct).sum();
+    output.push_str(&format!(
+        "{:>9.prec$}{:>9.prec$}  Total ({} path{})\n",
+        relative,
+        absolute,
+        paths.len(),
+        if paths.len() == 1 { "" } else { "s" }
+    ));
+    output
+}
+
+/// Format `pperf hotpath`: the chain from tree root to leaf, one function per line
+/// with its % of runtime (Total%) and of the function above (Relative%).
+pub fn format_hot_path(path: &HotPath, use_color: bool) -> String {
+    let prec = precision();
+    let mut output = format!(
+        "Hot path ({:.prec$}% of runtime at the leaf):\n",
+        path.leaf_pct()
+    );
+    output.push_str("   Total% Relative%  Function\n");
+    let mut pct = path.root_pct;
+    for step in &path.steps {
+        pct = pct * step.percentage / 100.0;
+        output.push_str(&format!(
+            "{:>9.prec$}{:>10.prec$}  {}\n",
+            pct,
+            step.percentage,
+            format_colored_symbol(&truncate_symbol(&step.symbol, symbol_width()), use_color)
+        ));
+    }
+    output
+}
+
+/// Cells of the widest `pperf stats` histogram bar.
+const HISTOGRAM_BAR_WIDTH: usize = 20;
+
+/// Format `pperf stats`: entries above each threshold, the entries covering each
+/// share of the self time, and the Self% histogram with a bar per bucket (the
+/// fullest bucket drawn `HISTOGRAM_BAR_WIDTH` cells wide).
+pub fn format_distribution(stats: &Distribution) -> String {
+    let prec = precision();
+    let width = pct_width(
+        stats
+            .histogram
+            .iter()
+            .map(|b| b.self_pct)
+            .chain([stats.self_total]),
+        ">Children".len(),
+    );
+    let mut output = format!(
+        "Entries: {} (Self% total {:.prec$}%)\n",
+        stats.entries, stats.self_total
+    );
+
+    output.push_str("\nEntries above a share of runtime:\n");
+    output.push_str(&format!(
+        "{:>width$}  {:>width$}  Threshold\n",
+        ">Children", ">Self"
+    ));
+    for count in &stats.above {
+        output.push_str(&format!(
+            "{:>width$}  {:>width$}  {}%\n",
+            count.children, count.self_, count.threshold
+        ));
+    }
+
+    output.push_str("\nFewest entries covering a share of the Self% total:\n");
+    output.push_str(&format!(
+        "{:>width$}  {:>width$}  Coverage\n",
+        "Entries", "Self%"
+    ));
+    for coverage in &stats.coverage {
+        let functions = coverage
+            .functions
+            .map_or("-".to_string(), |n| n.to_string());
+        output.push_str(&format!(
+            "{:>width$}  {:>width$.prec$}  {}%\n",
+            functions, coverage.self_pct, coverage.level
+        ));
+    }
+
+    output.push_str("\nSelf% histogram:\n");
+    output.push_str(&format!(
+        "{:>width$}  {:>width$}  {:bar$}  Range\n",
+        "Entries",
+        "Self%",
+        "",
+        bar = HISTOGRAM_BAR_WIDTH
+    ));
+    let fullest = stats
+        .histogram
+        .iter()
+        .map(|b| b.functions)
+        .max()
+        .unwrap_or(0);
+    for bucket in &stats.histogram {
+        let cells = if fullest == 0 {
+            0
+        } else {
+            (bucket.functions * HISTOGRAM_BAR_WIDTH).div_ceil(fullest)
+        };
+        let range = match (bucket.min, bucket.max) {
+            (Some(min), None) => format!(">{}%", min),
+            (Some(min), Some(max)) => format!("{}-{}%", min, max),
+            (None, Some(max)) => format!("<={}%", max),
+            (None, None) => "all".to_string(),
+        };
+        output.push_str(&format!(
+            "{:>width$}  {:>width$.prec$}  {:bar$}  {}\n",
+            bucket.functions,
+            bucket.self_pct,
+            "█".repeat(cells),
+            range,
+            bar = HISTOGRAM_BAR_WIDTH
+        ));
+    }
+    output
+}
 
-/// T021: Format table with optional color support
-pub fn format_table(entries: &[PerfEntry], use_color: bool) -> String {
+/// Format `--tree`: every node of a function's parsed call tree, indented one
+/// level (four spaces, two on narrow terminals) per depth, with its percentage relative to its parent.
+pub fn format_call_tree(entry: &PerfEntry, roots: &[CallTreeNode], use_color: bool) -> String {
+    fn render(node: &CallTreeNode, depth: usize, use_color: bool, output: &mut String) {
+        let prec = precision();
+        let symbol = truncate_symbol(&node.symbol, symbol_width());
+        output.push_str(&format!(
+            "{:>9.prec$}  {}{}{}\n",
+            node.relative_pct,
+            indentation(depth),
+            format_colored_symbol(&symbol, use_color),
+            if node.inlined { " (inlined)" } else { "" }
+        ));
+        for child in &node.children {
+            render(child, depth + 1, use_color, output);
+        }
+    }
+
+    let prec = precision();
+    let mut output = format!(
+        "Call tree of {} ({:.prec$}% children):\n",
+        entry.symbol, entry.children_pct
+    );
+    output.push_str("Relative%  Function\n");
+    for root in roots {
+        render(root, 0, use_color, &mut output);
+    }
+    output
+}
+
+/// Format report header metadata as `Label: value` lines, skipping absent fields.
+pub fn format_metadata(metadata: &ReportMetadata) -> String {
     let mut output = String::new();
-    output.push_str("Children%   Self%  Function\n");
+    if let Some(samples) = metadata.samples {
+        output.push_str(&format!("Samples:      {}\n", samples));
+    }
+    if let Some(ref event) = metadata.event {
+        output.push_str(&format!("Event:        {}\n", event));
+    }
+    if let Some(event_count) = metadata.event_count {
+        output.push_str(&format!("Event count:  {}\n", event_count));
+    }
+    if let Some(ref cmdline) = metadata.cmdline {
+        output.push_str(&format!("Command line: {}\n", cmdline));
+    }
+    if let Some(percent_type) = metadata.percent_type {
+        output.push_str(&format!("Percent type: {}\n", percent_type));
+    }
+    if let Some(tree_order) = metadata.tree_order {
+        output.push_str(&format!("Tree order:   {}\n", tree_order));
+    }
+    output
+}
 
-    for entry in entries {
-        let symbol = truncate_symbol(&entry.symbol, 100);
-        // T022: Apply colors to each entry's symbol
-        let colored_symbol = format_colored_symbol(&symbol, use_color);
+/// Render values as a unicode sparkline (`▁` lowest to `█` highest present value,
+/// a space where a value is absent); equal values all get the middle bar.
+pub fn sparkline(values: &[Option<f64>]) -> String {
+    const BARS: [char; 8] = ['▁', '▂', '▃', '▄', '▅', '▆', '▇', '█'];
+    let present = values.iter().flatten();
+    let min = present.clone().copied().fold(f64::INFINITY, f64::min);
+    let max = present.copied().fold(f64::NEG_INFINITY, f64::max);
+    values
+        .iter()
+        .map(|v| match v {
+            None => ' ',
+            Some(_) if max - min < 1e-9 => BARS[3],
+            Some(v) => BARS[(((v - min) / (max - min)) * 7.0).round() as usize],
+        })
+        .collect()
+}
+
+/// Format per-edge agreement for multi-report hierarchy runs: mean relative%,
+/// coefficient of variation, the per-report values, and a `!` on disagreeing edges.
+/// With `debug`, a `sparkline` of the values follows the list.
+pub fn format_edge_agreement(agreements: &[EdgeAgreement], debug: bool) -> String {
+    let prec = precision();
+    let mut output = String::new();
+    output.push_str("Edge agreement (relative% across reports):\n");
+    output.push_str("   Mean%    CV%  Edge\n");
+
+    for agreement in agreements {
+        let values: Vec<String> = agreement
+            .relative_values
+            .iter()
+            .map(|v| match v {
+                Some(v) => format!("{:.prec$}", v),
+                None => "-".to_string(),
+            })
+            .collect();
+        let context = match agreement.context_root {
+            Some(ref root) => format!(" (in {})", truncate_symbol(root, 40)),
+            None => String::new(),
+        };
         output.push_str(&format!(
-            "{:>8.2}  {:>6.2}  {}\n",
-            entry.children_pct, entry.self_pct, colored_symbol
+            "{} {:>6.prec$} {:>6.1}  {} -> {}{}  [{}]",
+            if agreement.disagrees() { "!" } else { " " },
+            agreement.mean_relative_pct,
+            agreement.cv_pct,
+            truncate_symbol(&agreement.caller, 60),
+            truncate_symbol(&agreement.callee, 60),
+            context,
+            values.join(", ")
         ));
+        if debug {
+            output.push_str(&format!(" {}", sparkline(&agreement.relative_values)));
+        }
+        output.push('\n');
     }
 
     output
 }
 
 pub fn truncate_symbol(symbol: &str, max_len: usize) -> String {
-    if symbol.len() <= max_len {
-        symbol.to_string()
-    } else {
-        format!("{}...", &symbol[..max_len - 3])
+    // Kept whole, so the alias is still found when the name is shown
+    if symbol.chars().count() <= max_len || display_alias(symbol).is_some() {
+        return symbol.to_string();
+    }
+    match truncate_mode() {
+        TruncateMode::End => {
+            let head: String = symbol.chars().take(max_len.saturating_sub(3)).collect();
+            format!("{}...", head)
+        }
+        // Cut the shown name, so the end kept is the method and not its arguments
+        TruncateMode::Middle => {
+            let simplified = simplify_symbol(symbol);
+            if simplified.chars().count() <= max_len {
+                simplified
+            } else {
+                truncate_middle(&simplified, max_len)
+            }
+        }
     }
 }
 
+/// Cut the middle out of `symbol` to keep `max_len` characters: the start (the
+/// namespace and class) and the method name from its last `::`, if that leaves a
+/// quarter of the room to the start; otherwise the start and end halves.
+fn truncate_middle(symbol: &str, max_len: usize) -> String {
+    let chars: Vec<char> = symbol.chars().collect();
+    let budget = max_len.saturating_sub(3);
+    let method_len = symbol
+        .rfind("::")
+        .map(|pos| symbol[pos..].chars().count())
+        .filter(|&len| len <= budget - budget / 4);
+    let tail_len = method_len.unwrap_or(budget / 2);
+    let head: String = chars[..budget - tail_len].iter().collect();
+    let tail: String = chars[chars.len() - tail_len..].iter().collect();
+    format!("{}...{}", head, tail)
+}
+
 /// Format hierarchy table with multi-level nested callees.
 /// Uses context-specific relations for accurate path percentages.
 /// Calculates remainder contributions for standalone entries.
@@ -38,13 +1378,16 @@ pub fn format_hierarchy_table(
     all_relations: &[CallRelation],
     use_color: bool,
     debug: bool,
+    tree_style: TreeStyle,
 ) -> String {
-    let mut output = String::new();
-    output.push_str("Children%   Self%  Function\n");
+    let mut table = PctTable {
+        style: tree_style,
+        ..Default::default()
+    };
 
     // Build context-specific callee map: (root_caller, caller) → callees
     // For root caller A's tree, when B→C has context_root = Some(A), store under (A, B)
-    let mut context_callee_map: HashMap<(String, String), Vec<&CallRelation>> = HashMap::new();
+    let mut context_callee_map: HashMap<(Symbol, Symbol), Vec<&CallRelation>> = HashMap::new();
     for r in all_relations {
         if let Some(ref root) = r.context_root {
             context_callee_map
@@ -55,7 +1398,7 @@ pub fn format_hierarchy_table(
     }
 
     // Build direct callee map for root callers (context_root = None)
-    let mut direct_callee_map: HashMap<String, Vec<&CallRelation>> = HashMap::new();
+    let mut direct_callee_map: HashMap<Symbol, Vec<&CallRelation>> = HashMap::new();
     for r in all_relations {
         if r.context_root.is_none() {
             direct_callee_map
@@ -73,7 +1416,7 @@ pub fn format_hierarchy_table(
     }
 
     // Collect all callees from overall relations (to identify root vs intermediate callers)
-    let all_callees: HashSet<String> = entries
+    let all_callees: HashSet<Symbol> = entries
         .iter()
         .flat_map(|e| e.callees.iter().map(|c| c.callee.clone()))
         .collect();
@@ -89,17 +1432,18 @@ pub fn format_hierarchy_table(
         }
 
         let simplified = simplify_symbol(&entry.symbol);
-        if all_callees.contains(&simplified) {
+        if all_callees.contains(simplified.as_str()) {
             continue; // Not a root caller
         }
 
         // Display root caller with original percentage
-        let symbol = truncate_symbol(&entry.symbol, 100);
+        let symbol = truncate_symbol(&entry.symbol, symbol_width());
         let colored_symbol = format_colored_symbol(&symbol, use_color);
-        output.push_str(&format!(
-            "{:>8.2}  {:>6.2}  {}\n",
-            entry.original_children_pct, entry.original_self_pct, colored_symbol
-        ));
+        table.row(
+            entry.original_children_pct,
+            entry.original_self_pct,
+            colored_symbol,
+        );
 
         // Display direct callees of this root, using context-specific relations for deeper levels
         let mut visited: HashSet<String> = HashSet::new();
@@ -113,7 +1457,7 @@ pub fn format_hierarchy_table(
             &entry_by_simplified,
             &mut consumed_absolute,
             &mut visited,
-            &mut output,
+            &mut table,
             1,
             use_color,
             debug,
@@ -123,18 +1467,19 @@ pub fn format_hierarchy_table(
     // Second pass: display standalone entries with remainder callees
     for entry in entries {
         let simplified = simplify_symbol(&entry.symbol);
-        let is_root_caller = entry.is_caller && !all_callees.contains(&simplified);
+        let is_root_caller = entry.is_caller && !all_callees.contains(simplified.as_str());
         if is_root_caller {
             continue; // Already shown
         }
 
         // Show entry with adjusted percentage
-        let symbol = truncate_symbol(&entry.symbol, 100);
+        let symbol = truncate_symbol(&entry.symbol, symbol_width());
         let colored_symbol = format_colored_symbol(&symbol, use_color);
-        output.push_str(&format!(
-            "{:>8.2}  {:>6.2}  {}\n",
-            entry.adjusted_children_pct, entry.original_self_pct, colored_symbol
-        ));
+        table.row(
+            entry.adjusted_children_pct,
+            entry.original_self_pct,
+            colored_symbol,
+        );
 
         // Output standalone debug annotation showing the subtraction breakdown
         let standalone_annotation = format_standalone_debug_annotation(
@@ -145,11 +1490,12 @@ pub fn format_hierarchy_table(
             debug,
         );
         if !standalone_annotation.is_empty() {
-            output.push_str(&format!("                  {}\n", standalone_annotation));
+            table.note(standalone_annotation);
         }
 
         // If this entry has callees, show remainder callees (overall - consumed)
         if entry.is_caller {
+            let mut shown_pct = None;
             for callee in &entry.callees {
                 let callee_simplified = simplify_symbol(&callee.callee);
                 let consumed = consumed_absolute
@@ -169,19 +1515,40 @@ pub fn format_hierarchy_table(
                     };
 
                     // Display the remainder
-                    let indent = "    ";
-                    let callee_symbol = truncate_symbol(&callee.callee, 96);
+                    let callee_symbol = truncate_symbol(&callee.callee, nested_symbol_width(1));
                     let colored_callee = format_colored_symbol(&callee_symbol, use_color);
-                    output.push_str(&format!(
-                        "{:>8.2}  {:>6.2}  {}{}\n",
-                        relative_to_standalone, 0.0, indent, colored_callee
-                    ));
+                    table.nested_row(
+                        1,
+                        relative_to_standalone,
+                        callee_self_pct(&entry_by_simplified, &callee_simplified),
+                        colored_callee,
+                    );
+                    *shown_pct.get_or_insert(0.0) += relative_to_standalone;
                 }
             }
+            if let Some(shown_pct) = shown_pct {
+                push_other_row(&mut table, shown_pct, 1, use_color);
+            }
         }
     }
 
-    output
+    table.render()
+}
+
+/// Add the synthetic `(other)` row below a caller's listed callees: the part of its
+/// time (100% minus the callees' Children%) spent in itself or in unlisted callees.
+fn push_other_row(table: &mut PctTable, shown_pct: f64, indent_level: usize, use_color: bool) {
+    let remainder = (100.0 - shown_pct).max(0.0);
+    if remainder < 0.005 {
+        return;
+    }
+    let label = if use_color {
+        use crate::symbol::{DIM, RESET};
+        format!("{}(other){}", DIM, RESET)
+    } else {
+        "(other)".to_string()
+    };
+    table.nested_row(indent_level, remainder, 0.0, label);
 }
 
 /// Display callees recursively using context-specific relations.
@@ -190,12 +1557,12 @@ pub fn format_hierarchy_table(
 fn display_callees_with_context(
     caller_simplified: &str,
     root_caller_simplified: &str,
-    direct_callee_map: &HashMap<String, Vec<&CallRelation>>,
-    context_callee_map: &HashMap<(String, String), Vec<&CallRelation>>,
-    _entry_by_simplified: &HashMap<String, &HierarchyEntry>,
+    direct_callee_map: &HashMap<Symbol, Vec<&CallRelation>>,
+    context_callee_map: &HashMap<(Symbol, Symbol), Vec<&CallRelation>>,
+    entry_by_simplified: &HashMap<String, &HierarchyEntry>,
     consumed_absolute: &mut HashMap<String, f64>,
     visited: &mut HashSet<String>,
-    output: &mut String,
+    table: &mut PctTable,
     indent_level: usize,
     use_color: bool,
     debug: bool,
@@ -206,6 +1573,7 @@ fn display_callees_with_context(
         None => return,
     };
 
+    let mut shown_pct = None;
     for callee_rel in callees {
         let callee_simplified = simplify_symbol(&callee_rel.callee);
 
@@ -214,15 +1582,17 @@ fn display_callees_with_context(
             continue;
         }
         visited.insert(callee_simplified.clone());
+        *shown_pct.get_or_insert(0.0) += callee_rel.relative_pct;
 
         // Display this callee
-        let indent = "    ".repeat(indent_level);
-        let callee_symbol = truncate_symbol(&callee_rel.callee, 100 - indent_level * 4);
+        let callee_symbol = truncate_symbol(&callee_rel.callee, nested_symbol_width(indent_level));
         let colored_callee = format_colored_symbol(&callee_symbol, use_color);
-        output.push_str(&format!(
-            "{:>8.2}  {:>6.2}  {}{}\n",
-            callee_rel.relative_pct, 0.0, indent, colored_callee
-        ));
+        table.nested_row(
+            indent_level,
+            callee_rel.relative_pct,
+            callee_self_pct(entry_by_simplified, &callee_simplified),
+            colored_callee,
+        );
 
         // T013: Output debug annotation on separate line below
         let annotation = format_debug_annotation(
@@ -232,7 +1602,7 @@ fn display_callees_with_context(
             debug,
         );
         if !annotation.is_empty() {
-            output.push_str(&format!("                  {}{}\n", indent, annotation));
+            table.nested_note(indent_level, annotation);
         }
 
         // Track consumed absolute contribution
@@ -243,26 +1613,29 @@ fn display_callees_with_context(
         // Check if this callee has context-specific nested callees
         // Look for relations with context_root = root_caller and caller = this callee
         let context_key = (
-            root_caller_simplified.to_string(),
+            Symbol::from(root_caller_simplified),
             callee_rel.callee.clone(),
         );
         if let Some(nested) = context_callee_map.get(&context_key) {
+            let mut nested_shown_pct = None;
             for nested_rel in nested {
                 let nested_simplified = simplify_symbol(&nested_rel.callee);
                 if visited.contains(&nested_simplified) {
                     continue;
                 }
                 visited.insert(nested_simplified.clone());
+                *nested_shown_pct.get_or_insert(0.0) += nested_rel.relative_pct;
 
                 // Display nested callee with context-specific percentage
-                let nested_indent = "    ".repeat(indent_level + 1);
                 let nested_symbol =
-                    truncate_symbol(&nested_rel.callee, 100 - (indent_level + 1) * 4);
+                    truncate_symbol(&nested_rel.callee, nested_symbol_width(indent_level + 1));
                 let colored_nested = format_colored_symbol(&nested_symbol, use_color);
-                output.push_str(&format!(
-                    "{:>8.2}  {:>6.2}  {}{}\n",
-                    nested_rel.relative_pct, 0.0, nested_indent, colored_nested
-                ));
+                table.nested_row(
+                    indent_level + 1,
+                    nested_rel.relative_pct,
+                    callee_self_pct(entry_by_simplified, &nested_simplified),
+                    colored_nested,
+                );
 
                 // T013: Output debug annotation for nested callee
                 let nested_annotation = format_debug_annotation(
@@ -272,10 +1645,7 @@ fn display_callees_with_context(
                     debug,
                 );
                 if !nested_annotation.is_empty() {
-                    output.push_str(&format!(
-                        "                  {}{}\n",
-                        nested_indent, nested_annotation
-                    ));
+                    table.nested_note(indent_level + 1, nested_annotation);
                 }
 
                 // Track consumed absolute contribution
@@ -285,7 +1655,7 @@ fn display_callees_with_context(
 
                 // Continue recursively if this nested callee has its own nested callees
                 let deeper_key = (
-                    root_caller_simplified.to_string(),
+                    Symbol::from(root_caller_simplified),
                     nested_rel.callee.clone(),
                 );
                 if context_callee_map.contains_key(&deeper_key) {
@@ -293,17 +1663,32 @@ fn display_callees_with_context(
                         &nested_rel.callee,
                         root_caller_simplified,
                         context_callee_map,
+                        entry_by_simplified,
                         consumed_absolute,
                         visited,
-                        output,
+                        table,
                         indent_level + 2,
                         use_color,
                         debug,
                     );
                 }
             }
+            if let Some(nested_shown_pct) = nested_shown_pct {
+                push_other_row(table, nested_shown_pct, indent_level + 1, use_color);
+            }
         }
     }
+    if let Some(shown_pct) = shown_pct {
+        push_other_row(table, shown_pct, indent_level, use_color);
+    }
+}
+
+/// Self% of a callee row: the function's own Self% from its hierarchy entry, since
+/// the Children% column of a callee row is a share of its caller.
+fn callee_self_pct(entry_by_simplified: &HashMap<String, &HierarchyEntry>, callee: &str) -> f64 {
+    entry_by_simplified
+        .get(callee)
+        .map_or(0.0, |entry| entry.original_self_pct)
 }
 
 /// Display nested callees from context-specific map.
@@ -312,34 +1697,38 @@ fn display_callees_with_context(
 fn display_nested_context(
     caller: &str,
     root_caller_simplified: &str,
-    context_callee_map: &HashMap<(String, String), Vec<&CallRelation>>,
+    context_callee_map: &HashMap<(Symbol, Symbol), Vec<&CallRelation>>,
+    entry_by_simplified: &HashMap<String, &HierarchyEntry>,
     consumed_absolute: &mut HashMap<String, f64>,
     visited: &mut HashSet<String>,
-    output: &mut String,
+    table: &mut PctTable,
     indent_level: usize,
     use_color: bool,
     debug: bool,
 ) {
-    let context_key = (root_caller_simplified.to_string(), caller.to_string());
+    let context_key = (Symbol::from(root_caller_simplified), Symbol::from(caller));
     let callees = match context_callee_map.get(&context_key) {
         Some(c) => c,
         None => return,
     };
 
+    let mut shown_pct = None;
     for callee_rel in callees {
         let callee_simplified = simplify_symbol(&callee_rel.callee);
         if visited.contains(&callee_simplified) {
             continue;
         }
         visited.insert(callee_simplified.clone());
+        *shown_pct.get_or_insert(0.0) += callee_rel.relative_pct;
 
-        let indent = "    ".repeat(indent_level);
-        let callee_symbol = truncate_symbol(&callee_rel.callee, 100 - indent_level * 4);
+        let callee_symbol = truncate_symbol(&callee_rel.callee, nested_symbol_width(indent_level));
         let colored_callee = format_colored_symbol(&callee_symbol, use_color);
-        output.push_str(&format!(
-            "{:>8.2}  {:>6.2}  {}{}\n",
-            callee_rel.relative_pct, 0.0, indent, colored_callee
-        ));
+        table.nested_row(
+            indent_level,
+            callee_rel.relative_pct,
+            callee_self_pct(entry_by_simplified, &callee_simplified),
+            colored_callee,
+        );
 
         // T013: Output debug annotation
         let annotation = format_debug_annotation(
@@ -349,14 +1738,14 @@ fn display_nested_context(
             debug,
         );
         if !annotation.is_empty() {
-            output.push_str(&format!("                  {}{}\n", indent, annotation));
+            table.nested_note(indent_level, annotation);
         }
 
         *consumed_absolute.entry(callee_simplified).or_default() += callee_rel.absolute_pct;
 
         // Continue recursively
         let deeper_key = (
-            root_caller_simplified.to_string(),
+            Symbol::from(root_caller_simplified),
             callee_rel.callee.clone(),
         );
         if context_callee_map.contains_key(&deeper_key) {
@@ -364,15 +1753,19 @@ fn display_nested_context(
                 &callee_rel.callee,
                 root_caller_simplified,
                 context_callee_map,
+                entry_by_simplified,
                 consumed_absolute,
                 visited,
-                output,
+                table,
                 indent_level + 1,
                 use_color,
                 debug,
             );
         }
     }
+    if let Some(shown_pct) = shown_pct {
+        push_other_row(table, shown_pct, indent_level, use_color);
+    }
 }
 
 /// T012: Format debug annotation for calculation path.
@@ -385,6 +1778,7 @@ pub fn format_debug_annotation(
     use_color: bool,
     debug: bool,
 ) -> String {
+    let prec = precision();
     // Return empty if debug mode is not enabled
     if !debug {
         return String::new();
@@ -394,15 +1788,15 @@ pub fn format_debug_annotation(
 
     let content = if intermediary_path.is_empty() {
         // T017: Direct call - no intermediaries
-        format!("(direct: {:.2}%)", final_pct)
+        format!("(direct: {:.prec$}%)", final_pct)
     } else {
         // Indirect call - show multiplication chain
         let steps: Vec<String> = intermediary_path
             .iter()
-            .map(|step| format!("{} {:.2}%", step.symbol, step.percentage))
+            .map(|step| format!("{} {:.prec$}%", step.symbol, step.percentage))
             .collect();
         let chain = steps.join(" × ");
-        format!("(via {} = {:.2}%)", chain, final_pct)
+        format!("(via {} = {:.prec$}%)", chain, final_pct)
     };
 
     // T014: Apply DIM color when use_color is true
@@ -416,6 +1810,8 @@ pub fn format_debug_annotation(
 /// Format debug annotation for standalone entries.
 /// Returns empty string if debug is false or no contributions to show.
 /// Format: "(standalone: X.XX% - Y.YY% (CallerA) - Z.ZZ% (CallerB) = W.WW%)"
+/// A caller reaching the entry over several paths shows how they were combined:
+/// "max(Y.YY%, V.VV%) (CallerA)" or "(Y.YY% + V.VV%) (CallerA)".
 pub fn format_standalone_debug_annotation(
     original_pct: f64,
     contributions: &[crate::hierarchy::CallerContribution],
@@ -423,6 +1819,7 @@ pub fn format_standalone_debug_annotation(
     use_color: bool,
     debug: bool,
 ) -> String {
+    let prec = precision();
     // Return empty if debug mode is not enabled
     if !debug {
         return String::new();
@@ -438,12 +1835,24 @@ pub fn format_standalone_debug_annotation(
     // Build subtraction chain: "- X.XX% (CallerA) - Y.YY% (CallerB)"
     let subtractions: Vec<String> = contributions
         .iter()
-        .map(|c| format!("{:.2}% ({})", c.absolute_pct, c.caller))
+        .map(|c| {
+            let paths: Vec<String> = c
+                .path_pcts
+                .iter()
+                .map(|p| format!("{:.prec$}%", p))
+                .collect();
+            let amount = match c.mode {
+                _ if paths.len() < 2 => format!("{:.prec$}%", c.absolute_pct),
+                ContributionMode::Max => format!("max({})", paths.join(", ")),
+                ContributionMode::Sum => format!("({})", paths.join(" + ")),
+            };
+            format!("{} ({})", amount, c.caller)
+        })
         .collect();
     let chain = subtractions.join(" - ");
 
     let content = format!(
-        "(standalone: {:.2}% - {} = {:.2}%)",
+        "(standalone: {:.prec$}% - {} = {:.prec$}%)",
         original_pct, chain, adjusted_pct
     );
 
@@ -457,8 +1866,9 @@ pub fn format_standalone_debug_annotation(
 
 #[cfg(test)]
 mod tests {
+    use crate::averaging::EdgeAgreement;
     use crate::hierarchy::IntermediaryStep;
-    use crate::parser::PerfEntry;
+    use crate::parser::{PerfEntry, ReportLayout};
 
     #[test]
     fn test_format_table_aligned_output() {
@@ -466,17 +1876,20 @@ mod tests {
             PerfEntry {
                 children_pct: 90.74,
                 self_pct: 0.00,
-                symbol: "parallel_for_with_progress".to_string(),
+                symbol: "parallel_for_with_progress".into(),
+                ..Default::default()
             },
             PerfEntry {
                 children_pct: 71.80,
                 self_pct: 11.94,
-                symbol: "get_mSubbandLF_significance".to_string(),
+                symbol: "get_mSubbandLF_significance".into(),
+                ..Default::default()
             },
             PerfEntry {
                 children_pct: 7.45,
                 self_pct: 7.45,
-                symbol: "std::inner_product".to_string(),
+                symbol: "std::inner_product".into(),
+                ..Default::default()
             },
         ];
 
@@ -531,6 +1944,164 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_format_overhead_table() {
+        let entries = vec![PerfEntry {
+            children_pct: 11.94,
+            self_pct: 11.94,
+            symbol: "get_mSubbandLF_significance".into(),
+            ..Default::default()
+        }];
+
+        let output = super::format_overhead_table(&entries, false);
+        let lines: Vec<&str> = output.lines().collect();
+        assert_eq!(lines[0], "Overhead%  Function");
+        assert!(!lines[0].contains("Children%"));
+        assert_eq!(lines[1], "    11.94  get_mSubbandLF_significance");
+    }
+
+    #[test]
+    fn test_add_bar_column() {
+        assert_eq!(super::format_bar(30.0), "███░░░░░░░");
+        assert_eq!(super::format_bar(0.0), "░░░░░░░░░░");
+        assert_eq!(super::format_bar(120.0), "██████████");
+
+        let table = "Children%   Self%  Function\n   71.80    0.00  main\n    7.47    7.45  0000000000000000\n";
+        let output = super::add_bar_column(table, &[71.80, 7.47]);
+        let lines: Vec<&str> = output.lines().collect();
+        assert_eq!(
+            lines[0],
+            format!("Children%   Self%{}Function", " ".repeat(14))
+        );
+        assert_eq!(lines[1], "   71.80    0.00  ███████░░░  main");
+        // A symbol that looks like a number stays whole
+        assert_eq!(lines[2], "    7.47    7.45  █░░░░░░░░░  0000000000000000");
+    }
+
+    #[test]
+    fn test_add_srcline_column() {
+        let table = "Children%   Self%  Function\n   21.72   11.94  get_mSubbandLF_significance\n    1.79    1.79  main\n";
+        let srclines = vec!["hierarchical_4d_encoder.cpp:412".to_string(), String::new()];
+        let output = super::add_srcline_column(table, &srclines);
+        let lines: Vec<&str> = output.lines().collect();
+        assert_eq!(
+            lines[0],
+            format!("Children%   Self%  {}Source  Function", " ".repeat(25))
+        );
+        assert_eq!(
+            lines[1],
+            "   21.72   11.94  hierarchical_4d_encoder.cpp:412  get_mSubbandLF_significance"
+        );
+        assert_eq!(
+            lines[2],
+            format!("    1.79    1.79  {}-  main", " ".repeat(30))
+        );
+    }
+
+    #[test]
+    fn test_add_rank_column() {
+        let rows: Vec<String> = (1..=10)
+            .map(|i| format!("   {:>2}.00  f{}", i, i))
+            .collect();
+        let table = format!("Children%  Function\n{}\n", rows.join("\n"));
+        let ranked = super::add_rank_column(&table);
+        let lines: Vec<&str> = ranked.lines().collect();
+        assert_eq!(lines.len(), 11);
+        assert_eq!(lines[0], " #  Children%  Function");
+        assert_eq!(lines[1], " 1      1.00  f1");
+        assert_eq!(lines[10], "10     10.00  f10");
+    }
+
+    #[test]
+    fn test_is_column_header() {
+        assert!(super::is_column_header("Children%   Self%  Function"));
+        assert!(super::is_column_header("   Total%   Share%  Caller"));
+        assert!(super::is_column_header(
+            "     [1]       [2]     Slope  Function"
+        ));
+        assert!(!super::is_column_header("   71.80    0.00  Function"));
+        assert!(!super::is_column_header("Children% per report:"));
+    }
+
+    #[test]
+    fn test_strip_table_header() {
+        let text = "[1] a.txt\n[2] b.txt\n\n     [1]       [2]  Function\n   71.80     69.80  f\n";
+        assert_eq!(
+            super::strip_table_header(text, false),
+            "[1] a.txt\n[2] b.txt\n\n   71.80     69.80  f\n"
+        );
+        assert_eq!(
+            super::strip_table_header(text, true),
+            "   71.80     69.80  f\n"
+        );
+        // Lines between two tables stay unless they precede the second header
+        let butterfly = "   Total%  Caller\n    1.00  g\n-> f (2.00% children)\n   Total%  Callee\n    0.50  h\n";
+        assert_eq!(
+            super::strip_table_header(butterfly, true),
+            "    1.00  g\n-> f (2.00% children)\n    0.50  h\n"
+        );
+        // Quiet output has no blank separators
+        let sections = "a.txt:\n   Total%  Callee\n    1.00  g\n\nCombined:\n   Total%  Callee\n    0.50  h\n\n";
+        assert_eq!(
+            super::strip_table_header(sections, true),
+            "    1.00  g\n    0.50  h\n"
+        );
+        assert_eq!(
+            super::strip_table_header(sections, false),
+            "a.txt:\n    1.00  g\n\nCombined:\n    0.50  h\n\n"
+        );
+    }
+
+    #[test]
+    fn test_mark_high_variance() {
+        let table = "Children%   Self%  Function\n   65.57    0.00  a\n   33.87    0.00  b\n    6.61    6.59  c\n";
+        let output = super::mark_high_variance(table, &[Some(11.4), Some(2.0), None], 10.0, false);
+        let lines: Vec<&str> = output.lines().collect();
+        assert_eq!(lines[0], "Children%   Self%  Function");
+        assert_eq!(lines[1], "   65.57    0.00  a  ! CV 11.4%");
+        assert_eq!(lines[2], "   33.87    0.00  b");
+        assert_eq!(lines[3], "    6.61    6.59  c");
+    }
+
+    #[test]
+    fn test_format_metadata() {
+        use crate::parser::{PercentType, ReportMetadata, TreeOrder};
+
+        let metadata = ReportMetadata {
+            samples: Some(5000),
+            event: Some("cycles".to_string()),
+            event_count: Some(274024838576),
+            cmdline: None,
+            percent_type: Some(PercentType::Absolute),
+            tree_order: Some(TreeOrder::Callee),
+        };
+        let output = super::format_metadata(&metadata);
+        assert!(output.contains("Samples:      5000"));
+        assert!(output.contains("Event:        cycles"));
+        assert!(output.contains("Event count:  274024838576"));
+        assert!(!output.contains("Command line"));
+        assert!(output.contains("Percent type: absolute"));
+        assert!(output.contains("Tree order:   callee"));
+
+        let empty = super::format_metadata(&ReportMetadata::default());
+        assert!(empty.is_empty());
+    }
+
+    #[test]
+    fn test_mark_overloads() {
+        let table = "Children%   Self%  Function\n   92.30    2.00  A::f\n   38.30    1.00  B::g\n";
+        let signatures = vec![vec!["A::f(int)".into(), "A::f(double)".into()], Vec::new()];
+        assert_eq!(
+            super::mark_overloads(table, &signatures, false),
+            "Children%   Self%  Function\n   92.30    2.00  A::f  (2 overloads)\n   38.30    1.00  B::g\n"
+        );
+        let debug = super::mark_overloads(table, &signatures, true);
+        let lines: Vec<&str> = debug.lines().collect();
+        assert_eq!(lines[2], "                  (overload: A::f(int))");
+        assert_eq!(lines[3], "                  (overload: A::f(double))");
+        assert_eq!(lines[4], "   38.30    1.00  B::g");
+    }
+
     #[test]
     fn test_truncate_symbol_short() {
         let short = "short_name";
@@ -545,11 +2116,31 @@ mod tests {
         assert!(truncated.ends_with("..."));
     }
 
+    #[test]
+    fn test_truncate_symbol_multibyte() {
+        // Cut by characters, never inside one
+        let symbol = format!("Ünïcödé::{}", "ä".repeat(40));
+        let truncated = super::truncate_symbol(&symbol, 20);
+        assert_eq!(truncated.chars().count(), 20);
+        assert_eq!(truncated, format!("Ünïcödé::{}...", "ä".repeat(8)));
+    }
+
+    #[test]
+    fn test_truncate_middle() {
+        let symbol = "Hierarchical4DEncoder::Subband::rd_optimize_hexadecatree";
+        let truncated = super::truncate_middle(symbol, 40);
+        assert_eq!(truncated, "Hierarchica...::rd_optimize_hexadecatree");
+        assert!(truncated.chars().count() <= 40);
+
+        // No `::` to keep: an even split
+        assert_eq!(super::truncate_middle(&"x".repeat(30), 10), "xxxx...xxx");
+    }
+
     // T008: Unit test for format_debug_annotation with single intermediary
     #[test]
     fn test_format_debug_annotation_single_intermediary() {
         let path = vec![IntermediaryStep {
-            symbol: "do_4d_transform".to_string(),
+            symbol: "do_4d_transform".into(),
             percentage: 42.0,
         }];
 
@@ -575,11 +2166,11 @@ mod tests {
     fn test_format_debug_annotation_multiple_intermediaries() {
         let path = vec![
             IntermediaryStep {
-                symbol: "do_4d_transform".to_string(),
+                symbol: "do_4d_transform".into(),
                 percentage: 50.0,
             },
             IntermediaryStep {
-                symbol: "compute_dct".to_string(),
+                symbol: "compute_dct".into(),
                 percentage: 80.0,
             },
         ];
@@ -638,11 +2229,13 @@ mod tests {
     // Unit test for format_standalone_debug_annotation with single caller
     #[test]
     fn test_format_standalone_debug_annotation_single_caller() {
-        use crate::hierarchy::CallerContribution;
+        use crate::hierarchy::{CallerContribution, ContributionMode};
 
         let contributions = vec![CallerContribution {
-            caller: "rd_optimize_transform".to_string(),
+            caller: "rd_optimize_transform".into(),
             absolute_pct: 12.37,
+            path_pcts: vec![12.37],
+            mode: ContributionMode::Max,
         }];
 
         // original 38.00% - 12.37% = 25.63%
@@ -673,16 +2266,20 @@ mod tests {
     // Unit test for format_standalone_debug_annotation with multiple callers
     #[test]
     fn test_format_standalone_debug_annotation_multiple_callers() {
-        use crate::hierarchy::CallerContribution;
+        use crate::hierarchy::{CallerContribution, ContributionMode};
 
         let contributions = vec![
             CallerContribution {
-                caller: "CallerA".to_string(),
+                caller: "CallerA".into(),
                 absolute_pct: 20.0,
+                path_pcts: vec![20.0],
+                mode: ContributionMode::Max,
             },
             CallerContribution {
-                caller: "CallerB".to_string(),
+                caller: "CallerB".into(),
                 absolute_pct: 15.0,
+                path_pcts: vec![15.0],
+                mode: ContributionMode::Max,
             },
         ];
 
@@ -712,6 +2309,42 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_format_standalone_debug_annotation_combined_paths() {
+        use crate::hierarchy::{CallerContribution, ContributionMode};
+
+        let contribution = |mode, absolute_pct| CallerContribution {
+            caller: "rd_optimize_transform".into(),
+            absolute_pct,
+            path_pcts: vec![0.85, 0.19],
+            mode,
+        };
+
+        let annotation = super::format_standalone_debug_annotation(
+            20.12,
+            &[contribution(ContributionMode::Sum, 1.04)],
+            19.08,
+            false,
+            true,
+        );
+        assert_eq!(
+            annotation,
+            "(standalone: 20.12% - (0.85% + 0.19%) (rd_optimize_transform) = 19.08%)"
+        );
+
+        let annotation = super::format_standalone_debug_annotation(
+            20.12,
+            &[contribution(ContributionMode::Max, 0.85)],
+            19.27,
+            false,
+            true,
+        );
+        assert_eq!(
+            annotation,
+            "(standalone: 20.12% - max(0.85%, 0.19%) (rd_optimize_transform) = 19.27%)"
+        );
+    }
+
     // Unit test for format_standalone_debug_annotation with empty contributions
     #[test]
     fn test_format_standalone_debug_annotation_no_contributions() {
@@ -731,11 +2364,13 @@ mod tests {
     // Unit test for format_standalone_debug_annotation with debug disabled
     #[test]
     fn test_format_standalone_debug_annotation_debug_disabled() {
-        use crate::hierarchy::CallerContribution;
+        use crate::hierarchy::{CallerContribution, ContributionMode};
 
         let contributions = vec![CallerContribution {
-            caller: "SomeCaller".to_string(),
+            caller: "SomeCaller".into(),
             absolute_pct: 10.0,
+            path_pcts: vec![10.0],
+            mode: ContributionMode::Max,
         }];
 
         // Debug disabled - should return empty
@@ -743,4 +2378,428 @@ mod tests {
             super::format_standalone_debug_annotation(50.00, &contributions, 40.00, false, false);
         assert!(annotation.is_empty(), "Should be empty when debug is false");
     }
+
+    #[test]
+    fn test_format_edge_agreement() {
+        let agreements = vec![
+            EdgeAgreement {
+                caller: "A".into(),
+                callee: "B".into(),
+                context_root: None,
+                relative_values: vec![Some(20.0), Some(22.0)],
+                mean_relative_pct: 21.0,
+                cv_pct: 4.76,
+            },
+            EdgeAgreement {
+                caller: "B".into(),
+                callee: "C".into(),
+                context_root: Some("A".into()),
+                relative_values: vec![Some(10.0), None],
+                mean_relative_pct: 10.0,
+                cv_pct: 0.0,
+            },
+        ];
+        let output = super::format_edge_agreement(&agreements, false);
+        let lines: Vec<&str> = output.lines().collect();
+
+        assert_eq!(lines[0], "Edge agreement (relative% across reports):");
+        assert_eq!(lines[2], "   21.00    4.8  A -> B  [20.00, 22.00]");
+        assert_eq!(lines[3], "!  10.00    0.0  B -> C (in A)  [10.00, -]");
+
+        let output = super::format_edge_agreement(&agreements, true);
+        let lines: Vec<&str> = output.lines().collect();
+        assert_eq!(lines[2], "   21.00    4.8  A -> B  [20.00, 22.00] ▁█");
+        assert_eq!(lines[3], "!  10.00    0.0  B -> C (in A)  [10.00, -] ▄ ");
+    }
+
+    #[test]
+    fn test_sparkline() {
+        assert_eq!(super::sparkline(&[Some(1.0), Some(4.5), Some(8.0)]), "▁▅█");
+        assert_eq!(super::sparkline(&[Some(3.0), None, Some(3.0)]), "▄ ▄");
+        assert_eq!(super::sparkline(&[]), "");
+    }
+
+    #[test]
+    fn test_format_entry_diff_colors_deltas() {
+        use crate::symbol::{GREEN, RED, RESET};
+
+        let row = |symbol: &str, old: f64, new: f64| crate::diff::EntryDiff {
+            symbol: symbol.into(),
+            old_children: Some(old),
+            new_children: Some(new),
+            old_self: None,
+            new_self: None,
+        };
+        let rows = vec![
+            row("slower", 10.0, 13.0),
+            row("faster", 10.0, 7.5),
+            row("noise", 10.0, 10.8),
+        ];
+        let output = super::format_entry_diff(&rows, "a.txt", "b.txt", 1.0, true);
+        let lines: Vec<&str> = output.lines().collect();
+
+        assert!(lines[4].contains(&format!("{}   +3.00{}", RED, RESET)));
+        assert!(lines[5].contains(&format!("{}   -2.50{}", GREEN, RESET)));
+        assert!(lines[6].starts_with("   10.00     10.80     +0.80  "));
+
+        let plain = super::format_entry_diff(&rows, "a.txt", "b.txt", 1.0, false);
+        assert!(!plain.contains('\x1b'));
+    }
+
+    #[test]
+    fn test_format_set_table() {
+        let rows = vec![crate::setops::SetRow {
+            symbol: "new_path(int)".into(),
+            children: vec![None, Some(30.0)],
+            self_pct: vec![None, Some(15.0)],
+        }];
+        let names = vec!["a.txt".to_string(), "b.txt".to_string()];
+        let output = super::format_set_table(&rows, &names, ReportLayout::ChildrenSelf, false);
+        let lines: Vec<&str> = output.lines().collect();
+
+        assert_eq!(lines[0], "[1] a.txt");
+        assert_eq!(lines[1], "[2] b.txt");
+        assert_eq!(lines[3], "Children% per report:");
+        assert_eq!(lines[4], "     [1]       [2]  Function");
+        assert_eq!(lines[5], "       -     30.00  new_path");
+
+        // `--no-children` reports list their Overhead%
+        let output = super::format_set_table(&rows, &names, ReportLayout::Overhead, false);
+        assert_eq!(output.lines().nth(3), Some("Overhead% per report:"));
+    }
+
+    #[test]
+    fn test_format_table_widens_for_large_values() {
+        let entries = vec![
+            PerfEntry {
+                children_pct: 123456.78,
+                self_pct: 1234.5,
+                symbol: "wide".into(),
+                ..Default::default()
+            },
+            PerfEntry {
+                children_pct: 1.0,
+                self_pct: 0.5,
+                symbol: "narrow".into(),
+                ..Default::default()
+            },
+        ];
+        let output = super::format_table(&entries, false);
+        let lines: Vec<&str> = output.lines().collect();
+
+        // Every row starts its Function column at the same offset
+        let offset = lines[1].find("wide").unwrap();
+        assert_eq!(lines[2].find("narrow").unwrap(), offset);
+        assert_eq!(lines[0].find("Function").unwrap(), offset + 1);
+        assert_eq!(lines[1], "123456.78  1234.50  wide");
+    }
+
+    #[test]
+    fn test_format_table_default_widths_unchanged() {
+        let entries = vec![PerfEntry {
+            children_pct: 100.0,
+            self_pct: 100.0,
+            symbol: "main".into(),
+            ..Default::default()
+        }];
+        let output = super::format_table(&entries, false);
+        assert_eq!(
+            output,
+            "Children%   Self%  Function\n  100.00  100.00  main\n"
+        );
+    }
+
+    #[test]
+    fn test_format_overhead_table_widens() {
+        let entries = vec![PerfEntry {
+            children_pct: 0.0,
+            self_pct: 1234567.0,
+            symbol: "main".into(),
+            ..Default::default()
+        }];
+        let output = super::format_overhead_table(&entries, false);
+        assert_eq!(output, " Overhead%  Function\n1234567.00  main\n");
+    }
+
+    #[test]
+    fn test_symbol_aliases() {
+        use crate::filter::{Target, TargetSpec};
+
+        let mut aliased = Target::new(TargetSpec::exact(
+            "DCT4DBlock::DCT4DBlock(Block4D const&, double)",
+        ));
+        aliased.alias = Some("dct".to_string());
+        let targets = vec![
+            aliased,
+            Target::new(TargetSpec::exact("std::inner_product")),
+        ];
+        let aliases = super::SymbolAliases::new(&targets, false);
+
+        // Full signature (entries) and simplified name (call trees) both resolve
+        assert_eq!(
+            aliases.resolve("DCT4DBlock::DCT4DBlock(Block4D const&, double)"),
+            Some("dct")
+        );
+        assert_eq!(aliases.resolve("DCT4DBlock::DCT4DBlock"), Some("dct"));
+        assert_eq!(aliases.resolve("std::inner_product"), None);
+
+        let mut entries = vec![PerfEntry {
+            symbol: "DCT4DBlock::DCT4DBlock(Block4D const&, double)".into(),
+            ..Default::default()
+        }];
+        aliases.apply_to_entries(&mut entries);
+        assert_eq!(entries[0].symbol, "dct");
+
+        let mut agreements = vec![EdgeAgreement {
+            caller: "DCT4DBlock::DCT4DBlock".into(),
+            callee: "std::inner_product".into(),
+            context_root: Some("DCT4DBlock::DCT4DBlock".into()),
+            relative_values: vec![],
+            mean_relative_pct: 0.0,
+            cv_pct: 0.0,
+        }];
+        aliases.apply_to_agreements(&mut agreements);
+        assert_eq!(agreements[0].caller, "dct");
+        assert_eq!(agreements[0].callee, "std::inner_product");
+        assert_eq!(agreements[0].context_root.as_deref(), Some("dct"));
+    }
+
+    #[test]
+    fn test_format_budget_table() {
+        use crate::budget::{Budget, BudgetResult};
+        use crate::filter::TargetSpec;
+
+        let result = |children: Option<f64>, max_children: Option<f64>| BudgetResult {
+            budget: Budget {
+                spec: TargetSpec::exact("main"),
+                max_children,
+                max_self: None,
+            },
+            symbol: "main".into(),
+            children_pct: children,
+            self_pct: children.map(|_| 1.0),
+        };
+        let output = super::format_budget_table(
+            &[
+                result(Some(38.29), Some(40.0)),
+                result(Some(71.8), Some(70.0)),
+                result(None, Some(1.0)),
+            ],
+            false,
+        );
+        let lines: Vec<&str> = output.lines().collect();
+
+        assert_eq!(
+            lines[0],
+            "Status   Children%        Max      Self%        Max  Function"
+        );
+        assert_eq!(
+            lines[1],
+            "PASS         38.29      40.00       1.00          -  main"
+        );
+        assert_eq!(
+            lines[2],
+            "FAIL         71.80      70.00       1.00          -  main"
+        );
+        assert_eq!(
+            lines[3],
+            "MISSING          -       1.00          -          -  main"
+        );
+    }
+
+    #[test]
+    fn test_format_callers() {
+        use crate::hierarchy::CallerRelation;
+
+        let entry = PerfEntry {
+            children_pct: 38.29,
+            symbol: "DCT4DBlock::DCT4DBlock(Block4D const&, double)".into(),
+            ..Default::default()
+        };
+        let callers = vec![CallerRelation {
+            caller: "TransformPartition::rd_optimize_transform".into(),
+            absolute_pct: 17.23,
+        }];
+        let output = super::format_callers(&entry, &callers, false);
+        let lines: Vec<&str> = output.lines().collect();
+
+        assert_eq!(lines[0], "DCT4DBlock::DCT4DBlock (38.29% children)");
+        assert_eq!(lines[1], "   Total%   Share%  Caller");
+        assert_eq!(
+            lines[2],
+            "    17.23    45.00  TransformPartition::rd_optimize_transform"
+        );
+        assert_eq!(
+            super::format_callers(&entry, &[], false).lines().nth(1),
+            Some("  (no callers in call graph)")
+        );
+    }
+
+    #[test]
+    fn test_format_butterfly() {
+        use crate::hierarchy::{CalleeRelation, CallerRelation};
+
+        let entry = PerfEntry {
+            children_pct: 38.29,
+            self_pct: 0.0,
+            symbol: "DCT4DBlock::DCT4DBlock(Block4D const&, double)".into(),
+            ..Default::default()
+        };
+        let callers = vec![CallerRelation {
+            caller: "TransformPartition::rd_optimize_transform".into(),
+            absolute_pct: 17.23,
+        }];
+        let callees = vec![CalleeRelation {
+            callee: "Transformed4DBlock::do_4d_transform".into(),
+            relative_pct: 20.12,
+            absolute_pct: 7.70,
+        }];
+        let output = super::format_butterfly(&entry, &callers, &callees, false);
+        let lines: Vec<&str> = output.lines().collect();
+
+        assert_eq!(lines[0], "   Total%   Share%  Caller");
+        assert_eq!(
+            lines[1],
+            "    17.23    45.00  TransformPartition::rd_optimize_transform"
+        );
+        assert_eq!(
+            lines[2],
+            "-> DCT4DBlock::DCT4DBlock (38.29% children, 0.00% self)"
+        );
+        assert_eq!(lines[3], "   Total%   Share%  Callee");
+        assert_eq!(
+            lines[4],
+            "     7.70    20.12  Transformed4DBlock::do_4d_transform"
+        );
+
+        let empty = super::format_butterfly(&entry, &[], &[], false);
+        assert!(empty.contains("  (no callers in call graph)\n"));
+        assert!(empty.contains("  (no callees in call graph)\n"));
+    }
+
+    #[test]
+    fn test_format_call_paths() {
+        use crate::hierarchy::{CallPath, IntermediaryStep};
+
+        let entry = PerfEntry {
+            children_pct: 50.0,
+            symbol: "encode(Block const&)".into(),
+            ..Default::default()
+        };
+        let step = |symbol: &str, percentage| IntermediaryStep {
+            symbol: symbol.into(),
+            percentage,
+        };
+        let paths = vec![
+            CallPath {
+                steps: vec![step("split", 60.0), step("dct", 50.0)],
+                relative_pct: 30.0,
+                absolute_pct: 15.0,
+            },
+            CallPath {
+                steps: vec![step("dct", 20.0)],
+                relative_pct: 20.0,
+                absolute_pct: 10.0,
+            },
+        ];
+        let output = super::format_call_paths(&entry, "dct", &paths, false);
+        let lines: Vec<&str> = output.lines().collect();
+
+        assert_eq!(lines[0], "Paths from encode (50.00% children) to dct:");
+        assert_eq!(lines[1], "Relative%   Total%  Path");
+        assert_eq!(lines[2], "    30.00    15.00  split 60.00% × dct 50.00%");
+        assert_eq!(lines[3], "    20.00    10.00  dct 20.00%");
+        assert_eq!(lines[4], "    50.00    25.00  Total (2 paths)");
+        assert_eq!(
+            super::format_call_paths(&entry, "dct", &[], false)
+                .lines()
+                .nth(1),
+            Some("  (no paths in call graph)")
+        );
+    }
+
+    #[test]
+    fn test_format_hot_path() {
+        use crate::hierarchy::{HotPath, IntermediaryStep};
+
+        let step = |symbol: &str, percentage| IntermediaryStep {
+            symbol: symbol.into(),
+            percentage,
+        };
+        let path = HotPath {
+            root_pct: 90.0,
+            steps: vec![step("main", 100.0), step("encode", 60.0), step("dct", 40.0)],
+        };
+        let output = super::format_hot_path(&path, false);
+        let lines: Vec<&str> = output.lines().collect();
+
+        assert_eq!(lines[0], "Hot path (21.60% of runtime at the leaf):");
+        assert_eq!(lines[1], "   Total% Relative%  Function");
+        assert_eq!(lines[2], "    90.00    100.00  main");
+        assert_eq!(lines[3], "    54.00     60.00  encode");
+        assert_eq!(lines[4], "    21.60     40.00  dct");
+    }
+
+    #[test]
+    fn test_format_distribution() {
+        let entry = |children: f64, self_: f64| PerfEntry {
+            children_pct: children,
+            self_pct: self_,
+            symbol: "f".into(),
+            ..Default::default()
+        };
+        let stats = crate::distribution::distribution(&[entry(80.0, 60.0), entry(40.0, 40.0)]);
+        let output = super::format_distribution(&stats);
+        let lines: Vec<&str> = output.lines().collect();
+
+        assert_eq!(lines[0], "Entries: 2 (Self% total 100.00%)");
+        assert_eq!(lines[3], ">Children      >Self  Threshold");
+        assert_eq!(lines[4], "        2          2  10%");
+        assert_eq!(lines[10], "        1      60.00  50%");
+        assert_eq!(lines[11], "        2     100.00  80%");
+        assert_eq!(
+            lines[17],
+            format!("        2     100.00  {}  >10%", "█".repeat(20))
+        );
+        assert_eq!(
+            lines[21],
+            format!("        0       0.00  {}  <=0.01%", " ".repeat(20))
+        );
+    }
+
+    #[test]
+    fn test_format_call_tree() {
+        use crate::hierarchy::CallTreeNode;
+
+        let node = |symbol: &str, relative_pct: f64, children| CallTreeNode {
+            symbol: symbol.into(),
+            relative_pct,
+            inlined: false,
+            children,
+        };
+        let entry = PerfEntry {
+            children_pct: 38.29,
+            symbol: "DCT4DBlock::DCT4DBlock".into(),
+            ..Default::default()
+        };
+        let roots = vec![node(
+            "DCT4DBlock::DCT4DBlock",
+            100.0,
+            vec![node(
+                "Transformed4DBlock::do_4d_transform",
+                98.5,
+                vec![node("std::inner_product", 12.25, vec![])],
+            )],
+        )];
+        let output = super::format_call_tree(&entry, &roots, false);
+
+        assert_eq!(
+            output,
+            "Call tree of DCT4DBlock::DCT4DBlock (38.29% children):\n\
+             Relative%  Function\n\
+             \x20  100.00  DCT4DBlock::DCT4DBlock\n\
+             \x20   98.50      Transformed4DBlock::do_4d_transform\n\
+             \x20   12.25          std::inner_product\n"
+        );
+    }
 }
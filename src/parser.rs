@@ -1,13 +1,202 @@
 use std::fs;
+use std::io::Read;
 use std::path::Path;
 
 use crate::PperfError;
+use crate::demangle::demangle;
+use crate::hierarchy::{CallTreeCollector, CallTreeNode};
+use crate::symbol::{
+    DemangleMode, Symbol, SymbolTransform, demangle_mode, jvm_symbol_name, simplify_symbol,
+};
 
-#[derive(Debug, Clone, PartialEq)]
+#[derive(Debug, Clone, Default, PartialEq)]
 pub struct PerfEntry {
     pub children_pct: f64,
     pub self_pct: f64,
-    pub symbol: String,
+    pub symbol: Symbol,
+    /// Command column (process name); empty when the report omits it
+    pub comm: String,
+    /// Shared Object column, e.g. `libc.so.6` or `[unknown]`; empty when omitted
+    pub dso: String,
+    /// Symbol carried the `[k]` (kernel) privilege marker rather than `[.]`
+    pub kernel: bool,
+    /// Source:Line column (`--sort ...,srcline`), e.g. `block_4d.h:88`; empty when omitted
+    pub srcline: String,
+}
+
+/// Report-level information from the `#` header comments.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ReportMetadata {
+    /// Sample count from `# Samples: 5K of event 'cycles'` (K/M suffixes expanded)
+    pub samples: Option<u64>,
+    /// Event name from the samples line, e.g. `cycles`
+    pub event: Option<String>,
+    /// Value of `# Event count (approx.): N`
+    pub event_count: Option<u64>,
+    /// Recorded command line from `# cmdline : ...` (only present with `--header`)
+    pub cmdline: Option<String>,
+    /// `--percent-type`/`--percentage` setting named in the header; None when not stated
+    pub percent_type: Option<PercentType>,
+    /// Call-graph order (`-g ...,caller`/`-g ...,callee`, `-G`) named in the header
+    pub tree_order: Option<TreeOrder>,
+}
+
+/// What call-graph percentages are relative to.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum PercentType {
+    /// Each call-graph line is a share of its parent (pperf's default reading)
+    #[default]
+    Relative,
+    /// Each call-graph line is a share of all samples
+    Absolute,
+}
+
+impl PercentType {
+    /// Parse a `--percent-type`/`--percentage` value: `relative`/`absolute`, or
+    /// perf's `local-*`/`global-*` names.
+    pub fn from_setting(value: &str) -> Option<PercentType> {
+        let value = value.trim().trim_matches(|c| c == '\'' || c == '"');
+        if value == "relative" || value.starts_with("local-") {
+            Some(PercentType::Relative)
+        } else if value == "absolute" || value.starts_with("global-") {
+            Some(PercentType::Absolute)
+        } else {
+            None
+        }
+    }
+}
+
+impl std::fmt::Display for PercentType {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            PercentType::Relative => write!(f, "relative"),
+            PercentType::Absolute => write!(f, "absolute"),
+        }
+    }
+}
+
+/// Direction of the call-graph trees, using perf's `--call-graph` order names.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum TreeOrder {
+    /// Caller-based: each node's children are the functions it calls (perf's default
+    /// with `--children`)
+    #[default]
+    Caller,
+    /// Callee-based: each node's children are the functions that call it
+    Callee,
+}
+
+impl TreeOrder {
+    /// Parse a `caller`/`callee` order name.
+    pub fn from_setting(value: &str) -> Option<TreeOrder> {
+        match value.trim().trim_matches(|c| c == '\'' || c == '"') {
+            "caller" => Some(TreeOrder::Caller),
+            "callee" => Some(TreeOrder::Callee),
+            _ => None,
+        }
+    }
+}
+
+impl std::fmt::Display for TreeOrder {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            TreeOrder::Caller => write!(f, "caller"),
+            TreeOrder::Callee => write!(f, "callee"),
+        }
+    }
+}
+
+/// Percentage columns present in a report.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub enum ReportLayout {
+    /// Default `perf report` output: Children% and Self%
+    #[default]
+    ChildrenSelf,
+    /// `perf report --no-children`: a single Overhead column (self time)
+    Overhead,
+}
+
+impl std::fmt::Display for ReportLayout {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ReportLayout::ChildrenSelf => write!(f, "children-self"),
+            ReportLayout::Overhead => write!(f, "overhead"),
+        }
+    }
+}
+
+/// Entries, call trees and metadata produced by parsing one report.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ParsedReport {
+    pub entries: Vec<PerfEntry>,
+    /// Each entry (simplified symbol) with its call tree, parsed in the same pass as
+    /// `entries`; empty without a call graph
+    pub trees: Vec<(PerfEntry, Vec<CallTreeNode>)>,
+    pub metadata: ReportMetadata,
+    pub layout: ReportLayout,
+    /// False when the report was recorded without `-g` (no call tree lines)
+    pub has_call_graph: bool,
+    /// Lines that looked like report data but could not be parsed
+    pub warnings: Vec<ParseWarning>,
+    /// Malformed call-graph lines, reported only by the views that use `trees`
+    pub tree_warnings: Vec<ParseWarning>,
+}
+
+/// A skipped input line, reported by `--verbose` and rejected by `--strict`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ParseWarning {
+    /// 1-based line number in the report
+    pub line: usize,
+    pub reason: String,
+}
+
+impl std::fmt::Display for ParseWarning {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "line {}: {}", self.line, self.reason)
+    }
+}
+
+/// A column named in the report's `# Children  Self  Command ...` header line.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Column {
+    Children,
+    Self_,
+    Overhead,
+    Command,
+    SharedObject,
+    Symbol,
+    /// `Source:Line`, from the `srcline` sort key
+    Srcline,
+    /// Any other sort key (cpu, pid, ...), kept by header name
+    Other(String),
+}
+
+impl Column {
+    fn from_header_name(name: &str) -> Column {
+        match name {
+            "Children" => Column::Children,
+            "Self" => Column::Self_,
+            "Overhead" => Column::Overhead,
+            "Command" => Column::Command,
+            "Shared Object" => Column::SharedObject,
+            "Symbol" => Column::Symbol,
+            "Source:Line" => Column::Srcline,
+            other => Column::Other(other.to_string()),
+        }
+    }
+}
+
+/// Column layout derived from the header and its `# ........  ....` underline.
+/// Spans are byte ranges into data lines; the last column extends to end of line.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ColumnMap {
+    pub columns: Vec<(Column, std::ops::Range<usize>)>,
+}
+
+impl ColumnMap {
+    pub fn position(&self, column: &Column) -> Option<usize> {
+        self.columns.iter().position(|(c, _)| c == column)
+    }
 }
 
 #[derive(Debug, Clone, Copy, PartialEq)]
@@ -16,6 +205,17 @@ pub enum SortOrder {
     Self_,
 }
 
+/// Parse a percentage value, accepting a comma decimal separator (`73,86`) as
+/// written by perf under non-English locales.
+pub fn parse_percentage(s: &str) -> Option<f64> {
+    let s = s.trim();
+    if s.contains(',') {
+        s.replacen(',', ".", 1).parse().ok()
+    } else {
+        s.parse().ok()
+    }
+}
+
 pub fn parse_line(line: &str) -> Option<PerfEntry> {
     let trimmed = line.trim_start();
 
@@ -33,46 +233,495 @@ pub fn parse_line(line: &str) -> Option<PerfEntry> {
 
     let pct_end = trimmed.find('%')?;
     let children_str = &trimmed[..pct_end];
-    let children_pct: f64 = children_str.trim().parse().ok()?;
+    let children_pct = parse_percentage(children_str)?;
 
     let rest = &trimmed[pct_end + 1..].trim_start();
-    let pct_end2 = rest.find('%')?;
-    let self_str = &rest[..pct_end2];
-    let self_pct: f64 = self_str.trim().parse().ok()?;
 
-    let after_self = &rest[pct_end2 + 1..].trim_start();
+    // `--no-children` reports have a single Overhead column: the next token is the
+    // Command, not a second percentage. Overhead is self time, so it fills both fields.
+    let next_token = rest.split_whitespace().next().unwrap_or("");
+    let second_pct = next_token.strip_suffix('%').and_then(parse_percentage);
+    let (self_pct, after_self) = match second_pct {
+        Some(self_pct) => (self_pct, rest[next_token.len()..].trim_start()),
+        None => (children_pct, *rest),
+    };
 
-    let symbol = if let Some(marker_pos) = after_self.find("[.] ") {
-        after_self[marker_pos + 4..].to_string()
-    } else if let Some(marker_pos) = after_self.find("[k] ") {
-        after_self[marker_pos + 4..].to_string()
+    let marker_pos = after_self.find("[.] ").or_else(|| after_self.find("[k] "));
+    let kernel = marker_pos.is_some_and(|pos| after_self[pos..].starts_with("[k]"));
+
+    let (symbol, columns) = if let Some(marker_pos) = marker_pos {
+        let columns: Vec<&str> = after_self[..marker_pos].split_whitespace().collect();
+        (after_self[marker_pos + 4..].to_string(), columns)
     } else {
         let parts: Vec<&str> = after_self.split_whitespace().collect();
         if parts.len() >= 2 {
-            parts[parts.len() - 1].to_string()
+            (
+                parts[parts.len() - 1].to_string(),
+                parts[..parts.len() - 1].to_vec(),
+            )
         } else {
             return None;
         }
     };
 
+    // Command names may contain spaces ("C2 CompilerThread"); the DSO never does
+    let (comm, dso) = match columns.split_last() {
+        Some((dso, comm)) if !comm.is_empty() => (comm.join(" "), dso.to_string()),
+        Some((comm, _)) => (comm.to_string(), String::new()),
+        None => (String::new(), String::new()),
+    };
+
     Some(PerfEntry {
         children_pct,
         self_pct,
-        symbol,
+        symbol: symbol.into(),
+        comm,
+        dso,
+        kernel,
+        srcline: String::new(),
     })
 }
 
-pub fn parse_file(path: &Path) -> Result<Vec<PerfEntry>, PperfError> {
-    let content = fs::read_to_string(path)
-        .map_err(|_| PperfError::FileNotFound(path.display().to_string()))?;
+fn is_underline(comment: &str) -> bool {
+    comment.contains('.') && comment.chars().all(|c| c == '.' || c == ' ')
+}
 
-    let entries: Vec<PerfEntry> = content.lines().filter_map(parse_line).collect();
+/// Build a column map from the header line directly above the dotted underline.
+/// Returns None when the report has no recognizable header.
+pub fn parse_header_columns(content: &str) -> Option<ColumnMap> {
+    let mut previous: Option<&str> = None;
+
+    for line in content.lines() {
+        if !line.starts_with('#') {
+            previous = None;
+            continue;
+        }
+        if is_underline(&line[1..]) {
+            let header = previous?;
+            let mut spans: Vec<std::ops::Range<usize>> = Vec::new();
+            let mut start: Option<usize> = None;
+            for (i, c) in line.char_indices() {
+                match (c, start) {
+                    ('.', None) => start = Some(i),
+                    (' ', Some(s)) => {
+                        spans.push(s..i);
+                        start = None;
+                    }
+                    _ => {}
+                }
+            }
+            if let Some(s) = start {
+                spans.push(s..line.len());
+            }
+
+            // Percentage columns are right-aligned: their values start right after the
+            // previous column's separator, so widen each span back to that point.
+            let mut columns = Vec::new();
+            let mut column_start = 0;
+            for (i, span) in spans.iter().enumerate() {
+                let is_last = i + 1 == spans.len();
+                let name_end = if is_last {
+                    header.len()
+                } else {
+                    span.end.min(header.len())
+                };
+                let name = header.get(column_start..name_end)?.trim();
+                let name = name.trim_start_matches('#').trim();
+                let end = if is_last { usize::MAX } else { span.end };
+                columns.push((Column::from_header_name(name), column_start..end));
+                column_start = span.end;
+            }
 
+            if !columns.iter().any(|(c, _)| *c == Column::Symbol) {
+                return None;
+            }
+            return Some(ColumnMap { columns });
+        }
+        previous = Some(line);
+    }
+    None
+}
+
+fn parse_pct_field(field: &str) -> Option<f64> {
+    parse_percentage(field.trim().strip_suffix('%')?)
+}
+
+/// Parse an entry line using the column spans from the report header.
+pub fn parse_line_with_columns(line: &str, columns: &ColumnMap) -> Option<PerfEntry> {
+    let trimmed = line.trim_start();
+    if trimmed.is_empty() || trimmed.starts_with('#') {
+        return None;
+    }
+    if trimmed.starts_with('|') || trimmed.starts_with('-') {
+        return None;
+    }
+
+    let mut entry = PerfEntry::default();
+    let mut children_pct = None;
+    let mut self_pct = None;
+    let mut has_symbol = false;
+
+    for (column, span) in &columns.columns {
+        let end = span.end.min(line.len());
+        let field = line.get(span.start.min(end)..end)?;
+        match column {
+            Column::Children => children_pct = Some(parse_pct_field(field)?),
+            Column::Self_ | Column::Overhead => self_pct = Some(parse_pct_field(field)?),
+            Column::Command => entry.comm = field.trim().to_string(),
+            Column::SharedObject => entry.dso = field.trim().to_string(),
+            Column::Symbol => {
+                let field = field.trim();
+                // Drop the `[.] ` / `[k] ` privilege marker
+                let symbol = match field.as_bytes() {
+                    [b'[', marker, b']', b' ', ..] => {
+                        entry.kernel = *marker == b'k';
+                        field[4..].trim_start()
+                    }
+                    _ => field,
+                };
+                entry.symbol = symbol.into();
+                has_symbol = !symbol.is_empty();
+            }
+            Column::Srcline => entry.srcline = field.trim().to_string(),
+            Column::Other(_) => {}
+        }
+    }
+
+    if !has_symbol {
+        return None;
+    }
+    entry.self_pct = self_pct.or(children_pct)?;
+    entry.children_pct = children_pct.unwrap_or(entry.self_pct);
+    Some(entry)
+}
+
+/// Parse an entry line with the header's column map when available, falling back to
+/// the default `Children Self Command DSO Symbol` layout heuristics.
+/// Mangled symbols (`perf report --no-demangle`, or a binary perf could not
+/// demangle) are demangled, so targets and filters see C++ names either way,
+/// unless `--demangle none` keeps them.
+pub fn parse_entry_line(line: &str, columns: Option<&ColumnMap>) -> Option<PerfEntry> {
+    let mut entry = match columns {
+        Some(columns) => parse_line_with_columns(line, columns).or_else(|| parse_line(line)),
+        None => parse_line(line),
+    }?;
+    if demangle_mode() != DemangleMode::None
+        && let Some(demangled) = demangle(&entry.symbol).or_else(|| jvm_symbol_name(&entry.symbol))
+    {
+        entry.symbol = demangled.into();
+    }
+    Some(entry)
+}
+
+/// Parse a sample count such as `5K`, `1M` or `812`.
+fn parse_sample_count(s: &str) -> Option<u64> {
+    let (digits, multiplier) = match s.chars().last()? {
+        'K' => (&s[..s.len() - 1], 1_000),
+        'M' => (&s[..s.len() - 1], 1_000_000),
+        'G' => (&s[..s.len() - 1], 1_000_000_000),
+        _ => (s, 1),
+    };
+    let value: f64 = digits.parse().ok()?;
+    Some((value * multiplier as f64).round() as u64)
+}
+
+pub fn parse_metadata(content: &str) -> ReportMetadata {
+    let mut metadata = ReportMetadata::default();
+
+    for line in content.lines() {
+        let Some(comment) = line.strip_prefix('#') else {
+            continue;
+        };
+        let comment = comment.trim();
+
+        if let Some(rest) = comment.strip_prefix("Samples:") {
+            let mut parts = rest.split_whitespace();
+            metadata.samples = parts.next().and_then(parse_sample_count);
+            if let Some(pos) = rest.find("of event") {
+                let event = rest[pos + "of event".len()..].trim().trim_matches('\'');
+                if !event.is_empty() {
+                    metadata.event = Some(event.to_string());
+                }
+            }
+        } else if let Some(rest) = comment.strip_prefix("Event count")
+            && let Some((_, value)) = rest.split_once(':')
+        {
+            metadata.event_count = value.trim().parse().ok();
+        } else if let Some(rest) = comment.strip_prefix("cmdline")
+            && let Some((_, value)) = rest.split_once(':')
+        {
+            metadata.cmdline = Some(value.trim().to_string());
+        }
+        if let Some(percent_type) = find_percent_type(comment) {
+            metadata.percent_type = Some(percent_type);
+        }
+        if let Some(tree_order) = find_tree_order(comment) {
+            metadata.tree_order = Some(tree_order);
+        }
+    }
+
+    metadata
+}
+
+/// Find `--percent-type X`, `--percentage X` (or the `=X` forms) in a header line.
+fn find_percent_type(comment: &str) -> Option<PercentType> {
+    let mut words = comment.split_whitespace();
+    while let Some(word) = words.next() {
+        for flag in ["--percent-type", "--percentage"] {
+            let Some(rest) = word.strip_prefix(flag) else {
+                continue;
+            };
+            let value = match rest.strip_prefix('=') {
+                Some(value) => Some(value),
+                None if rest.is_empty() => words.clone().next(),
+                None => None,
+            };
+            if let Some(percent_type) = value.and_then(PercentType::from_setting) {
+                return Some(percent_type);
+            }
+        }
+    }
+    None
+}
+
+/// Find the call-graph order in a header line: an `order` field of `-g`/`--call-graph`
+/// (`-g graph,0.5,callee`, `--call-graph=caller`), or `-G`/`--inverted` (caller-based).
+fn find_tree_order(comment: &str) -> Option<TreeOrder> {
+    let mut words = comment.split_whitespace();
+    while let Some(word) = words.next() {
+        if word == "-G" || word == "--inverted" {
+            return Some(TreeOrder::Caller);
+        }
+        for flag in ["--call-graph", "-g"] {
+            let Some(rest) = word.strip_prefix(flag) else {
+                continue;
+            };
+            let value = match rest.strip_prefix('=') {
+                Some(value) => Some(value),
+                None if rest.is_empty() => words.clone().next(),
+                None => None,
+            };
+            if let Some(tree_order) =
+                value.and_then(|v| v.split(',').find_map(TreeOrder::from_setting))
+            {
+                return Some(tree_order);
+            }
+        }
+    }
+    None
+}
+
+/// Detect the percentage columns from the `# Children  Self ...` / `# Overhead ...` header.
+pub fn detect_layout(content: &str) -> ReportLayout {
+    for line in content.lines() {
+        let Some(comment) = line.strip_prefix('#') else {
+            continue;
+        };
+        let comment = comment.trim_start();
+        if comment.starts_with("Children") {
+            return ReportLayout::ChildrenSelf;
+        }
+        if comment.starts_with("Overhead") {
+            return ReportLayout::Overhead;
+        }
+    }
+    ReportLayout::ChildrenSelf
+}
+
+/// True if any line belongs to a call tree (`|`, `--XX%--` or `---` markers).
+pub fn has_call_graph(content: &str) -> bool {
+    content.lines().any(|line| {
+        let trimmed = line.trim_start();
+        trimmed.starts_with('|') || trimmed.starts_with("--")
+    })
+}
+
+/// Decode raw report bytes: invalid UTF-8 becomes U+FFFD, a leading BOM is dropped
+/// and CRLF (or bare CR) line endings become LF.
+pub fn normalize_content(bytes: &[u8]) -> String {
+    let text = String::from_utf8_lossy(bytes);
+    let text = text.strip_prefix('\u{feff}').unwrap_or(&text);
+    text.replace("\r\n", "\n").replace('\r', "\n")
+}
+
+const GZIP_MAGIC: &[u8] = &[0x1f, 0x8b];
+const ZSTD_MAGIC: &[u8] = &[0x28, 0xb5, 0x2f, 0xfd];
+
+/// Decompress gzip or zstd data, detected by magic bytes; other data is returned as is.
+pub fn decompress(bytes: Vec<u8>) -> Result<Vec<u8>, PperfError> {
+    let mut output = Vec::new();
+    if bytes.starts_with(GZIP_MAGIC) {
+        flate2::read::MultiGzDecoder::new(bytes.as_slice())
+            .read_to_end(&mut output)
+            .map_err(|_| PperfError::InvalidFormat)?;
+    } else if bytes.starts_with(ZSTD_MAGIC) {
+        ruzstd::decoding::StreamingDecoder::new(bytes.as_slice())
+            .map_err(|_| PperfError::InvalidFormat)?
+            .read_to_end(&mut output)
+            .map_err(|_| PperfError::InvalidFormat)?;
+    } else {
+        return Ok(bytes);
+    }
+    Ok(output)
+}
+
+/// Path argument that reads the report from standard input.
+pub const STDIN_PATH: &str = "-";
+
+/// Read a report file (or stdin for `-`), tolerating gzip/zstd compression,
+/// Windows line endings, BOMs and non-UTF-8 bytes.
+pub fn read_report(path: &Path) -> Result<String, PperfError> {
+    let bytes = if path == Path::new(STDIN_PATH) {
+        let mut bytes = Vec::new();
+        std::io::stdin()
+            .lock()
+            .read_to_end(&mut bytes)
+            .map_err(|_| PperfError::FileNotFound("<stdin>".to_string()))?;
+        bytes
+    } else {
+        fs::read(path).map_err(|_| PperfError::FileNotFound(path.display().to_string()))?
+    };
+    let compression = if bytes.starts_with(GZIP_MAGIC) {
+        "gzip"
+    } else if bytes.starts_with(ZSTD_MAGIC) {
+        "zstd"
+    } else {
+        "none"
+    };
+    crate::log!(
+        Info,
+        "parser",
+        "reading report",
+        path = path.display(),
+        bytes = bytes.len(),
+        compression = compression,
+    );
+    Ok(normalize_content(&decompress(bytes)?))
+}
+
+pub fn parse_report(path: &Path) -> Result<ParsedReport, PperfError> {
+    parse_report_content(&read_report(path)?)
+}
+
+/// Parse already-read report text; see `read_report`.
+pub fn parse_report_content(content: &str) -> Result<ParsedReport, PperfError> {
+    let columns = parse_header_columns(content);
+    let has_call_graph = has_call_graph(content);
+    let mut entries: Vec<PerfEntry> = Vec::new();
+    let mut warnings: Vec<ParseWarning> = Vec::new();
+    let mut trees = CallTreeCollector::default();
+    let mut tree_warnings: Vec<ParseWarning> = Vec::new();
+    for (index, line) in content.lines().enumerate() {
+        let entry = parse_entry_line(line, columns.as_ref());
+        if has_call_graph {
+            trees.push_line(index, line, entry.as_ref(), &mut tree_warnings);
+        }
+        if let Some(entry) = entry {
+            entries.push(entry);
+        } else if line.trim_start().starts_with(|c: char| c.is_ascii_digit()) && line.contains('%')
+        {
+            // Call chains may hold bare hex addresses, but only entries start with a digit and carry a `%`
+            warnings.push(ParseWarning {
+                line: index + 1,
+                reason: "unparseable entry line".to_string(),
+            });
+        }
+    }
+
+    let mut skipped: Vec<&ParseWarning> = warnings.iter().chain(&tree_warnings).collect();
+    skipped.sort_by_key(|warning| warning.line);
+    for warning in skipped {
+        crate::log!(
+            Trace,
+            "parser",
+            "line skipped",
+            line = warning.line,
+            reason = warning.reason
+        );
+    }
     if entries.is_empty() {
+        crate::log!(
+            Info,
+            "parser",
+            "no entry lines found",
+            lines = content.lines().count()
+        );
         return Err(PperfError::InvalidFormat);
     }
 
-    Ok(entries)
+    let report = ParsedReport {
+        entries,
+        trees: trees.finish(),
+        metadata: parse_metadata(content),
+        layout: detect_layout(content),
+        has_call_graph,
+        warnings,
+        tree_warnings,
+    };
+    log_report(&report, columns.is_some());
+    Ok(report)
+}
+
+/// Log what was detected in a parsed report: its format at `-v`, the size of its
+/// call trees at `-vv`.
+fn log_report(report: &ParsedReport, column_header: bool) {
+    let unstated = |value: Option<String>| value.unwrap_or_else(|| "unstated".to_string());
+    crate::log!(
+        Info,
+        "parser",
+        "report parsed",
+        entries = report.entries.len(),
+        skipped = report.warnings.len(),
+        layout = report.layout,
+        column_header = column_header,
+        call_graph = report.has_call_graph,
+        percent_type = unstated(report.metadata.percent_type.map(|t| t.to_string())),
+        tree_order = unstated(report.metadata.tree_order.map(|o| o.to_string())),
+    );
+    if report.has_call_graph {
+        fn count(nodes: &[CallTreeNode], depth: usize, stats: &mut (usize, usize)) {
+            for node in nodes {
+                stats.0 += 1;
+                stats.1 = stats.1.max(depth);
+                count(&node.children, depth + 1, stats);
+            }
+        }
+        let mut stats = (0, 0);
+        for (_, roots) in &report.trees {
+            count(roots, 1, &mut stats);
+        }
+        crate::log!(
+            Debug,
+            "parser",
+            "call trees built",
+            trees = report.trees.len(),
+            nodes = stats.0,
+            max_depth = stats.1,
+            malformed = report.tree_warnings.len(),
+        );
+    }
+}
+
+pub fn parse_file(path: &Path) -> Result<Vec<PerfEntry>, PperfError> {
+    parse_report(path).map(|report| report.entries)
+}
+
+/// Apply a user symbol transform to every entry. The transform sees the simplified
+/// name; its result replaces that name inside the full signature, or the whole
+/// signature when simplification did more than cut off the parameters.
+pub fn transform_entry_symbols(entries: &mut [PerfEntry], transform: &dyn SymbolTransform) {
+    for entry in entries {
+        let simplified = simplify_symbol(&entry.symbol);
+        let rewritten = transform.transform(&simplified);
+        if rewritten != simplified {
+            entry.symbol = if entry.symbol.contains(simplified.as_str()) {
+                entry.symbol.replacen(simplified.as_str(), &rewritten, 1)
+            } else {
+                rewritten
+            }
+            .into();
+        }
+    }
 }
 
 pub fn sort_entries(entries: &mut [PerfEntry], order: SortOrder) {
@@ -111,7 +760,8 @@ mod tests {
         let entry = PerfEntry {
             children_pct: 90.74,
             self_pct: 0.00,
-            symbol: "test_function".to_string(),
+            symbol: "test_function".into(),
+            ..Default::default()
         };
         assert_eq!(entry.children_pct, 90.74);
         assert_eq!(entry.self_pct, 0.00);
@@ -151,6 +801,91 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_parse_metadata_real_data() {
+        let path =
+            std::path::Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures/perf-report.txt");
+
+        let report = parse_report(&path).expect("Failed to parse perf report file");
+        assert_eq!(report.metadata.samples, Some(5_000));
+        assert_eq!(report.metadata.event.as_deref(), Some("cycles"));
+        assert_eq!(report.metadata.event_count, Some(274_024_838_576));
+        assert_eq!(report.metadata.cmdline, None);
+    }
+
+    #[test]
+    fn test_parse_report_trees_in_same_pass() {
+        let path =
+            std::path::Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures/perf-report.txt");
+        let content = read_report(&path).unwrap();
+
+        let report = parse_report_content(&content).unwrap();
+        let trees = crate::hierarchy::parse_file_call_trees(&content, &report.entries);
+        assert!(!report.trees.is_empty());
+        assert_eq!(report.trees, trees);
+        assert_eq!(report.trees.len(), report.entries.len());
+    }
+
+    #[test]
+    fn test_parse_metadata_cmdline() {
+        let content =
+            "# cmdline : /usr/bin/perf record -g ./encoder\n# Samples: 812  of event 'cpu-clock'\n";
+        let metadata = parse_metadata(content);
+        assert_eq!(
+            metadata.cmdline.as_deref(),
+            Some("/usr/bin/perf record -g ./encoder")
+        );
+        assert_eq!(metadata.samples, Some(812));
+        assert_eq!(metadata.event.as_deref(), Some("cpu-clock"));
+        assert_eq!(metadata.event_count, None);
+        assert_eq!(metadata.percent_type, None);
+    }
+
+    #[test]
+    fn test_parse_metadata_percent_type() {
+        let content = "# cmdline : /usr/bin/perf report --percent-type global-period -g\n";
+        assert_eq!(
+            parse_metadata(content).percent_type,
+            Some(PercentType::Absolute)
+        );
+
+        let content = "# perf report --percentage=relative --stdio\n";
+        assert_eq!(
+            parse_metadata(content).percent_type,
+            Some(PercentType::Relative)
+        );
+
+        // An unknown value is not a setting
+        assert_eq!(parse_metadata("# --percentage bogus\n").percent_type, None);
+    }
+
+    #[test]
+    fn test_parse_metadata_tree_order() {
+        let content = "# cmdline : /usr/bin/perf report --stdio -g graph,0.5,callee\n";
+        assert_eq!(parse_metadata(content).tree_order, Some(TreeOrder::Callee));
+
+        let content = "# perf report --call-graph=fractal,caller --stdio\n";
+        assert_eq!(parse_metadata(content).tree_order, Some(TreeOrder::Caller));
+
+        let content = "# cmdline : /usr/bin/perf report -G --no-children\n";
+        assert_eq!(parse_metadata(content).tree_order, Some(TreeOrder::Caller));
+
+        // `-g` without an order, or a word merely starting with `-g`, says nothing
+        assert_eq!(
+            parse_metadata("# perf record -g ./encoder\n").tree_order,
+            None
+        );
+        assert_eq!(parse_metadata("# perf report -gfoo\n").tree_order, None);
+    }
+
+    #[test]
+    fn test_parse_sample_count_suffixes() {
+        assert_eq!(parse_sample_count("5K"), Some(5_000));
+        assert_eq!(parse_sample_count("1M"), Some(1_000_000));
+        assert_eq!(parse_sample_count("42"), Some(42));
+        assert_eq!(parse_sample_count("abc"), None);
+    }
+
     #[test]
     fn test_parse_line_valid_data() {
         let line = "    90.74%     0.00%  jpl-encoder-bin  jpl-encoder-bin      [.] parallel_for_with_progress";
@@ -160,6 +895,239 @@ mod tests {
         assert_eq!(entry.children_pct, 90.74);
         assert_eq!(entry.self_pct, 0.00);
         assert_eq!(entry.symbol, "parallel_for_with_progress");
+        assert_eq!(entry.comm, "jpl-encoder-bin");
+        assert_eq!(entry.dso, "jpl-encoder-bin");
+    }
+
+    #[test]
+    fn test_parse_line_comm_and_dso_columns() {
+        let line =
+            "    79.08%     0.00%  jpl-encoder-bin  [unknown]            [k] 0000000000000000";
+        let entry = parse_line(line).unwrap();
+        assert_eq!(entry.comm, "jpl-encoder-bin");
+        assert_eq!(entry.dso, "[unknown]");
+        assert_eq!(entry.symbol, "0000000000000000");
+        assert!(entry.kernel);
+
+        let line =
+            "    78.97%     0.00%  jpl-encoder-bin  libgomp.so.1.0.0     [.] 0x00007d4c47221c0e";
+        let entry = parse_line(line).unwrap();
+        assert_eq!(entry.dso, "libgomp.so.1.0.0");
+        assert!(!entry.kernel);
+
+        let line = "     2.00%     1.00%  C2 CompilerThread  libjvm.so  [.] Compile::Optimize";
+        let entry = parse_line(line).unwrap();
+        assert_eq!(entry.comm, "C2 CompilerThread");
+        assert_eq!(entry.dso, "libjvm.so");
+    }
+
+    #[test]
+    fn test_parse_entry_line_demangles() {
+        let line = "    38.29%     0.00%  jpl-encoder-bin  jpl-encoder-bin      [.] _ZN10DCT4DBlockC1ERK7Block4Dd";
+        let entry = parse_entry_line(line, None).unwrap();
+        assert_eq!(
+            entry.symbol,
+            "DCT4DBlock::DCT4DBlock(Block4D const&, double)"
+        );
+
+        // Outside the supported grammar the name is kept as perf printed it
+        let line = "     1.00%     1.00%  a.out  a.out  [.] _Zunsupported";
+        assert_eq!(
+            parse_entry_line(line, None).unwrap().symbol,
+            "_Zunsupported"
+        );
+    }
+
+    #[test]
+    fn test_parse_file_real_data_dso_values() {
+        let path =
+            std::path::Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures/perf-report.txt");
+        let entries = parse_file(&path).expect("Failed to parse perf report file");
+
+        assert!(entries.iter().all(|e| !e.comm.is_empty()));
+        assert!(entries.iter().any(|e| e.dso == "libgomp.so.1.0.0"));
+        assert!(entries.iter().any(|e| e.comm == "perf-exec"));
+    }
+
+    #[test]
+    fn test_parse_line_overhead_only() {
+        let line = "    11.94%  jpl-encoder-bin  jpl-encoder-bin      [.] Hierarchical4DEncoder::get_mSubbandLF_significance";
+        let entry = parse_line(line).expect("Overhead-only line should parse");
+        assert_eq!(entry.self_pct, 11.94);
+        assert_eq!(entry.children_pct, 11.94);
+        assert_eq!(entry.comm, "jpl-encoder-bin");
+        assert_eq!(
+            entry.symbol,
+            "Hierarchical4DEncoder::get_mSubbandLF_significance"
+        );
+
+        // A '%' inside the symbol must not be mistaken for a Self% column
+        let line = "     1.50%  encoder  libfoo.so  [.] Modulo::operator%(int)";
+        let entry = parse_line(line).unwrap();
+        assert_eq!(entry.self_pct, 1.50);
+        assert_eq!(entry.symbol, "Modulo::operator%(int)");
+    }
+
+    #[test]
+    fn test_detect_layout() {
+        assert_eq!(
+            detect_layout("# Children      Self  Command  Shared Object  Symbol\n"),
+            ReportLayout::ChildrenSelf
+        );
+        assert_eq!(
+            detect_layout("# Overhead  Command          Shared Object        Symbol\n"),
+            ReportLayout::Overhead
+        );
+        assert_eq!(detect_layout("no header"), ReportLayout::ChildrenSelf);
+    }
+
+    #[test]
+    fn test_parse_report_no_children_fixture() {
+        let path = std::path::Path::new(env!("CARGO_MANIFEST_DIR"))
+            .join("tests/fixtures/perf-report-no-children.txt");
+        let report = parse_report(&path).expect("Failed to parse --no-children report");
+
+        assert_eq!(report.layout, ReportLayout::Overhead);
+        assert_eq!(report.entries.len(), 8);
+        let first = &report.entries[0];
+        assert_eq!(first.self_pct, 16.30);
+        assert_eq!(first.children_pct, first.self_pct);
+    }
+
+    #[test]
+    fn test_parse_report_real_data_layout() {
+        let path =
+            std::path::Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures/perf-report.txt");
+        let report = parse_report(&path).expect("Failed to parse perf report file");
+        assert_eq!(report.layout, ReportLayout::ChildrenSelf);
+    }
+
+    #[test]
+    fn test_parse_header_columns_real_data() {
+        let content = std::fs::read_to_string(
+            std::path::Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures/perf-report.txt"),
+        )
+        .unwrap();
+        let columns = parse_header_columns(&content).expect("Real report has a header");
+        let names: Vec<&Column> = columns.columns.iter().map(|(c, _)| c).collect();
+        assert_eq!(
+            names,
+            vec![
+                &Column::Children,
+                &Column::Self_,
+                &Column::Command,
+                &Column::SharedObject,
+                &Column::Symbol
+            ]
+        );
+
+        // The column-aware parser must agree with the default heuristics on real data
+        for line in content.lines() {
+            assert_eq!(
+                parse_line_with_columns(line, &columns),
+                parse_line(line),
+                "Mismatch on line: {}",
+                line
+            );
+        }
+    }
+
+    #[test]
+    fn test_parse_header_columns_custom_sort_order() {
+        let content = "\
+# Children      Self  Symbol                                    Shared Object     CPU
+# ........  ........  ........................................  ................  ...
+#
+    21.72%    11.94%  [.] Hierarchical4DEncoder::get_mSubband   jpl-encoder-bin   003
+     7.47%     7.45%  [k] double std::inner_product<double*>    libfoo.so         001
+";
+        let columns = parse_header_columns(content).unwrap();
+        assert_eq!(columns.position(&Column::Symbol), Some(2));
+        assert_eq!(columns.columns[4].0, Column::Other("CPU".to_string()));
+
+        let entries: Vec<PerfEntry> = content
+            .lines()
+            .filter_map(|l| parse_line_with_columns(l, &columns))
+            .collect();
+        assert_eq!(entries.len(), 2);
+        assert_eq!(entries[0].children_pct, 21.72);
+        assert_eq!(entries[0].self_pct, 11.94);
+        assert_eq!(entries[0].symbol, "Hierarchical4DEncoder::get_mSubband");
+        assert_eq!(entries[0].dso, "jpl-encoder-bin");
+        assert_eq!(entries[1].symbol, "double std::inner_product<double*>");
+        assert!(!entries[0].kernel);
+        assert!(entries[1].kernel);
+        assert_eq!(entries[1].dso, "libfoo.so");
+    }
+
+    #[test]
+    fn test_parse_header_columns_missing_header() {
+        assert!(parse_header_columns("    10.00%     5.00%  cmd  dso  [.] f\n").is_none());
+    }
+
+    #[test]
+    fn test_parse_report_custom_columns_fixture() {
+        let path = std::path::Path::new(env!("CARGO_MANIFEST_DIR"))
+            .join("tests/fixtures/perf-report-sort-sym-dso-srcline.txt");
+        let report = parse_report(&path).expect("Failed to parse custom-column report");
+
+        assert_eq!(report.entries.len(), 6);
+        let first = &report.entries[0];
+        assert_eq!(first.children_pct, 21.72);
+        assert_eq!(first.self_pct, 11.94);
+        assert!(
+            first
+                .symbol
+                .starts_with("Hierarchical4DEncoder::get_mSubbandLF_significance")
+        );
+        assert_eq!(first.dso, "jpl-encoder-bin");
+        assert_eq!(first.srcline, "hierarchical_4d_encoder.cpp:412");
+        // A symbol wide enough to touch the Shared Object column
+        assert_eq!(report.entries[4].srcline, "stl_algo.h:4342");
+        assert_eq!(report.entries[4].dso, "jpl-encoder-bin");
+    }
+
+    #[test]
+    fn test_has_call_graph() {
+        let with_tree = "    71.80%     0.00%  bin  bin  [.] f\n            |\n            ---f\n";
+        assert!(has_call_graph(with_tree));
+
+        let flat = "    71.80%     0.00%  bin  bin  [.] f\n    10.00%    10.00%  bin  bin  [.] g\n";
+        assert!(!has_call_graph(flat));
+    }
+
+    #[test]
+    fn test_parse_report_call_graph_detection() {
+        let dir = std::path::Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures");
+        let with_graph = parse_report(&dir.join("perf-report.txt")).unwrap();
+        assert!(with_graph.has_call_graph);
+
+        let flat = parse_report(&dir.join("perf-report-no-children.txt")).unwrap();
+        assert!(!flat.has_call_graph);
+    }
+
+    #[test]
+    fn test_parse_percentage_locales() {
+        assert_eq!(parse_percentage("73.86"), Some(73.86));
+        assert_eq!(parse_percentage("73,86"), Some(73.86));
+        assert_eq!(parse_percentage(" 0,00 "), Some(0.0));
+        assert_eq!(parse_percentage("7,3,8"), None);
+        assert_eq!(parse_percentage("abc"), None);
+    }
+
+    #[test]
+    fn test_parse_line_comma_decimal_separator() {
+        let line = "    73,86%     1,50%  jpl-encoder-bin  jpl-encoder-bin      [.] DCT4DBlock::DCT4DBlock(Block4D const&, double)";
+        let entry = parse_line(line).expect("Comma-decimal line should parse");
+        assert_eq!(entry.children_pct, 73.86);
+        assert_eq!(entry.self_pct, 1.50);
+        assert_eq!(
+            entry.symbol,
+            "DCT4DBlock::DCT4DBlock(Block4D const&, double)"
+        );
+
+        let overhead_only = "    11,94%  jpl-encoder-bin  jpl-encoder-bin      [.] f";
+        assert_eq!(parse_line(overhead_only).unwrap().self_pct, 11.94);
     }
 
     #[test]
@@ -200,17 +1168,20 @@ mod tests {
             PerfEntry {
                 children_pct: 90.0,
                 self_pct: 1.0,
-                symbol: "a".to_string(),
+                symbol: "a".into(),
+                ..Default::default()
             },
             PerfEntry {
                 children_pct: 50.0,
                 self_pct: 10.0,
-                symbol: "b".to_string(),
+                symbol: "b".into(),
+                ..Default::default()
             },
             PerfEntry {
                 children_pct: 30.0,
                 self_pct: 5.0,
-                symbol: "c".to_string(),
+                symbol: "c".into(),
+                ..Default::default()
             },
         ];
         sort_entries(&mut entries, SortOrder::Self_);
@@ -225,17 +1196,20 @@ mod tests {
             PerfEntry {
                 children_pct: 30.0,
                 self_pct: 5.0,
-                symbol: "a".to_string(),
+                symbol: "a".into(),
+                ..Default::default()
             },
             PerfEntry {
                 children_pct: 90.0,
                 self_pct: 5.0,
-                symbol: "b".to_string(),
+                symbol: "b".into(),
+                ..Default::default()
             },
             PerfEntry {
                 children_pct: 50.0,
                 self_pct: 5.0,
-                symbol: "c".to_string(),
+                symbol: "c".into(),
+                ..Default::default()
             },
         ];
         sort_entries(&mut entries, SortOrder::Self_);
@@ -243,4 +1217,121 @@ mod tests {
         assert_eq!(entries[1].children_pct, 50.0);
         assert_eq!(entries[2].children_pct, 30.0);
     }
+
+    #[test]
+    fn test_normalize_content_strips_bom_and_cr() {
+        let raw = b"\xEF\xBB\xBF# Samples: 5K of event 'cycles'\r\n    38.00%     0.00%  app  [.] main\r\n";
+        let content = normalize_content(raw);
+        assert!(!content.contains('\r'));
+        assert!(content.starts_with("# Samples"));
+        assert_eq!(content.lines().count(), 2);
+    }
+
+    #[test]
+    fn test_normalize_content_bare_cr() {
+        let content = normalize_content(b"a\rb\rc");
+        assert_eq!(content, "a\nb\nc");
+    }
+
+    #[test]
+    fn test_normalize_content_invalid_utf8() {
+        let raw = b"    38.00%    10.00%  app  [.] caf\xE9_encode\n";
+        let content = normalize_content(raw);
+        let entry = parse_line(content.lines().next().unwrap()).unwrap();
+        assert_eq!(entry.symbol, "caf\u{fffd}_encode");
+        assert!((entry.children_pct - 38.0).abs() < 0.01);
+    }
+
+    #[test]
+    fn test_parse_report_windows_file() {
+        let raw = "\u{feff}#\r\n# Children      Self  Command  Shared Object  Symbol\r\n\
+                   #\r\n    38.00%    10.00%  app  app  [.] main\r\n            |\r\n\
+                   \x20           ---main\r\n\r\n";
+        let path = std::env::temp_dir().join(format!("pperf-crlf-{}.txt", std::process::id()));
+        std::fs::write(&path, raw.as_bytes()).unwrap();
+        let report = parse_report(&path);
+        std::fs::remove_file(&path).unwrap();
+
+        let report = report.unwrap();
+        assert_eq!(report.entries.len(), 1);
+        assert_eq!(report.entries[0].symbol, "main");
+        assert!(report.has_call_graph);
+    }
+
+    #[test]
+    fn test_parse_report_collects_warnings() {
+        let raw = "# Children      Self  Command  Shared Object  Symbol\n\
+                   #\n    38.00%    10.00%  app  app  [.] main\n\
+                   \x20   12.5x%  app  [.] broken\n\
+                   \x20   20.00%     5.00%  app  app  [.] helper\n";
+        let path = std::env::temp_dir().join(format!("pperf-warn-{}.txt", std::process::id()));
+        std::fs::write(&path, raw).unwrap();
+        let report = parse_report(&path);
+        std::fs::remove_file(&path).unwrap();
+
+        let report = report.unwrap();
+        assert_eq!(report.entries.len(), 2);
+        assert_eq!(
+            report.warnings,
+            vec![ParseWarning {
+                line: 4,
+                reason: "unparseable entry line".to_string(),
+            }]
+        );
+        assert_eq!(
+            report.warnings[0].to_string(),
+            "line 4: unparseable entry line"
+        );
+    }
+
+    #[test]
+    fn test_parse_report_real_file_has_no_warnings() {
+        let report = parse_report(Path::new("perf-report.txt")).unwrap();
+        assert!(report.warnings.is_empty(), "{:?}", report.warnings);
+    }
+
+    #[test]
+    fn test_transform_entry_symbols_sees_simplified_names() {
+        let mut entries = vec![
+            PerfEntry {
+                symbol: "Acme::DCT4DBlock::DCT4DBlock(Block4D const&, double)".into(),
+                ..Default::default()
+            },
+            PerfEntry {
+                symbol: "std::vector<int>::size() const".into(),
+                ..Default::default()
+            },
+        ];
+        let strip = |symbol: &str| symbol.replace("Acme::", "").replace("std::", "");
+        transform_entry_symbols(&mut entries, &strip);
+        assert_eq!(
+            entries[0].symbol,
+            "DCT4DBlock::DCT4DBlock(Block4D const&, double)"
+        );
+        // Simplification dropped the template, so the rewritten name replaces it all
+        assert_eq!(entries[1].symbol, "vector::size");
+    }
+
+    #[test]
+    fn test_decompress_passes_plain_text_through() {
+        let text = b"    38.00%    10.00%  app  [.] main\n".to_vec();
+        assert_eq!(decompress(text.clone()).unwrap(), text);
+    }
+
+    #[test]
+    fn test_decompress_rejects_truncated_gzip() {
+        let result = decompress(vec![0x1f, 0x8b, 0x08, 0x00]);
+        assert_eq!(result, Err(PperfError::InvalidFormat));
+    }
+
+    #[test]
+    fn test_parse_report_compressed_fixtures() {
+        let plain = parse_report(Path::new("tests/fixtures/perf-report-de-locale.txt")).unwrap();
+        for suffix in ["gz", "zst"] {
+            let path = format!("tests/fixtures/perf-report-de-locale.txt.{}", suffix);
+            let report = parse_report(Path::new(&path)).unwrap();
+            assert_eq!(report.entries, plain.entries, "{}", path);
+            assert_eq!(report.metadata, plain.metadata, "{}", path);
+        }
+    }
 }
@@ -0,0 +1,502 @@
+//! Names for symbols perf could only print as addresses: JIT-compiled code
+//! listed in perf map files (`/tmp/perf-<pid>.map`), code in binaries with
+//! debug information, looked up through `addr2line`, and kernel code listed in
+//! `/proc/kallsyms`.
+
+use std::collections::{BTreeSet, HashMap};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+use crate::PperfError;
+use crate::parser::PerfEntry;
+use crate::symbol::{SymbolTransform, simplify_symbol};
+
+/// Directory JIT runtimes write their perf map files to.
+pub const PERF_MAP_DIR: &str = "/tmp";
+
+/// The address a symbol stands for when perf printed it as one: `0x7f3a...` or
+/// bare hex digits such as `00007f3a...`.
+pub fn parse_address(symbol: &str) -> Option<u64> {
+    let digits = symbol.strip_prefix("0x").unwrap_or(symbol);
+    if digits.is_empty() || !digits.chars().all(|c| c.is_ascii_hexdigit()) {
+        return None;
+    }
+    u64::from_str_radix(digits, 16).ok()
+}
+
+/// Function ranges from perf map files: `START SIZE name` per line, both numbers
+/// in hex (with or without `0x`). Resolves an address to the function whose range
+/// contains it.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct PerfMap {
+    /// `(start, end, name)`, sorted by start; `end` is exclusive
+    ranges: Vec<(u64, u64, String)>,
+}
+
+impl PerfMap {
+    /// Parse a perf map file's content. Lines that are not `START SIZE name` are
+    /// skipped, as perf itself does.
+    pub fn parse(content: &str) -> PerfMap {
+        let mut map = PerfMap::default();
+        map.extend(content);
+        map
+    }
+
+    /// Read perf map files into one map.
+    pub fn load(paths: &[PathBuf]) -> Result<PerfMap, PperfError> {
+        let mut map = PerfMap::default();
+        for path in paths {
+            let content = fs::read_to_string(path)
+                .map_err(|_| PperfError::FileNotFound(path.display().to_string()))?;
+            map.extend(&content);
+        }
+        Ok(map)
+    }
+
+    /// Add the ranges of another map file's content.
+    pub fn extend(&mut self, content: &str) {
+        for line in content.lines() {
+            let mut fields = line.trim().splitn(3, char::is_whitespace);
+            let (Some(start), Some(size), Some(name)) =
+                (fields.next(), fields.next(), fields.next())
+            else {
+                continue;
+            };
+            let (Some(start), Some(size)) = (parse_address(start), parse_address(size)) else {
+                continue;
+            };
+            let name = name.trim();
+            if size == 0 || name.is_empty() {
+                continue;
+            }
+            self.ranges
+                .push((start, start.saturating_add(size), name.to_string()));
+        }
+        self.ranges.sort_by_key(|&(start, _, _)| start);
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.ranges.is_empty()
+    }
+
+    /// The function whose range contains `address`; of overlapping ranges (code
+    /// recompiled at the same place) the one starting last wins.
+    pub fn resolve(&self, address: u64) -> Option<&str> {
+        let after = self
+            .ranges
+            .partition_point(|&(start, _, _)| start <= address);
+        self.ranges[..after]
+            .iter()
+            .rev()
+            .find(|&&(_, end, _)| address < end)
+            .map(|(_, _, name)| name.as_str())
+    }
+}
+
+impl SymbolTransform for PerfMap {
+    fn transform(&self, symbol: &str) -> String {
+        parse_address(symbol)
+            .and_then(|address| self.resolve(address))
+            .unwrap_or(symbol)
+            .to_string()
+    }
+}
+
+/// The pid of a JIT shared object as perf names it: `perf-<pid>.map` (also with a
+/// directory in front) or `[JIT] tid <pid>`.
+pub fn jit_pid(dso: &str) -> Option<u32> {
+    let name = dso.rsplit('/').next().unwrap_or(dso);
+    let pid = match name.strip_prefix("perf-") {
+        Some(rest) => rest.strip_suffix(".map")?,
+        None => dso.strip_prefix("[JIT] tid ")?,
+    };
+    pid.trim().parse().ok()
+}
+
+/// Perf map files for the JIT processes of address-only entries, found in
+/// `PERF_MAP_DIR`; processes whose file is not there are left out.
+pub fn find_perf_maps(entries: &[PerfEntry]) -> Vec<PathBuf> {
+    let pids: BTreeSet<u32> = entries
+        .iter()
+        .filter(|e| parse_address(&e.symbol).is_some())
+        .filter_map(|e| jit_pid(&e.dso))
+        .collect();
+    pids.into_iter()
+        .map(|pid| Path::new(PERF_MAP_DIR).join(format!("perf-{}.map", pid)))
+        .filter(|path| path.is_file())
+        .collect()
+}
+
+/// Program run to look addresses up in a `--binary`.
+pub const ADDR2LINE: &str = "addr2line";
+
+/// What `addr2line` knows about an address; either part may be missing.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct SourceLocation {
+    /// Demangled function name
+    pub function: Option<String>,
+    /// `file:line`
+    pub srcline: Option<String>,
+}
+
+impl SourceLocation {
+    /// The name an address-only symbol gets: the function, else its `file:line`.
+    pub fn name(&self) -> Option<&str> {
+        self.function.as_deref().or(self.srcline.as_deref())
+    }
+}
+
+/// Parse `addr2line -f` output: a function line and a `file:line` line per
+/// address, `??` (and `??:0`/`??:?`) where unknown.
+pub fn parse_addr2line_output(output: &str) -> Vec<SourceLocation> {
+    let known = |text: &str| {
+        let text = text.trim();
+        (!text.is_empty() && !text.starts_with("??")).then(|| text.to_string())
+    };
+    let lines: Vec<&str> = output.lines().collect();
+    lines
+        .chunks(2)
+        .map(|pair| SourceLocation {
+            function: known(pair[0]),
+            // A discriminator suffix says nothing about the line
+            srcline: pair
+                .get(1)
+                .map(|line| line.split(" (discriminator").next().unwrap_or(line))
+                .and_then(known)
+                .filter(|line| !line.ends_with(":0") && !line.ends_with(":?")),
+        })
+        .collect()
+}
+
+/// Look addresses up in `binary` with one `addr2line -f -C` run. Addresses are
+/// offsets into the file, as perf prints them for a binary it found no symbol in.
+pub fn run_addr2line(
+    binary: &Path,
+    addresses: &[u64],
+) -> Result<HashMap<u64, SourceLocation>, PperfError> {
+    if !binary.is_file() {
+        return Err(PperfError::FileNotFound(binary.display().to_string()));
+    }
+    if addresses.is_empty() {
+        return Ok(HashMap::new());
+    }
+    let failed = |reason: &str| {
+        PperfError::AddressResolutionFailed(format!("{}: {}", binary.display(), reason))
+    };
+    let output = Command::new(ADDR2LINE)
+        .args(["-f", "-C", "-e"])
+        .arg(binary)
+        .args(addresses.iter().map(|a| format!("{:#x}", a)))
+        .output()
+        .map_err(|e| failed(&format!("cannot run {}: {}", ADDR2LINE, e)))?;
+    if !output.status.success() {
+        return Err(failed(&format!(
+            "{} exited with {}",
+            ADDR2LINE, output.status
+        )));
+    }
+    let locations = parse_addr2line_output(&String::from_utf8_lossy(&output.stdout));
+    if locations.len() != addresses.len() {
+        return Err(failed(&format!(
+            "expected {} locations from {}, got {}",
+            addresses.len(),
+            ADDR2LINE,
+            locations.len()
+        )));
+    }
+    Ok(addresses.iter().copied().zip(locations).collect())
+}
+
+/// What `--binary` found out about the report's addresses.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ResolvedAddresses {
+    /// Names for addresses, per binary file name in `--binary` order
+    pub names: Vec<(String, HashMap<u64, String>)>,
+    /// `file:line` of the named functions, by simplified name (for `--hyperlinks`)
+    pub source_locations: HashMap<String, String>,
+}
+
+impl ResolvedAddresses {
+    pub fn is_empty(&self) -> bool {
+        self.names.iter().all(|(_, names)| names.is_empty())
+    }
+
+    /// Name the address-only entries of every resolved binary, by their Shared
+    /// Object's file name.
+    pub fn apply_to_entries(&self, entries: &mut [PerfEntry]) {
+        for entry in entries {
+            let dso = entry.dso.rsplit('/').next().unwrap_or("");
+            let name = parse_address(&entry.symbol).and_then(|address| {
+                self.names
+                    .iter()
+                    .find(|(binary, _)| binary == dso)
+                    .and_then(|(_, names)| names.get(&address))
+            });
+            if let Some(name) = name {
+                entry.symbol = name.as_str().into();
+            }
+        }
+    }
+}
+
+/// Call-tree frames carry no Shared Object, so a frame takes the name of the
+/// first binary knowing its address (entries print `0x00007d4c...`, call trees
+/// `0x7d4c...`).
+impl SymbolTransform for ResolvedAddresses {
+    fn transform(&self, symbol: &str) -> String {
+        parse_address(symbol)
+            .and_then(|address| self.names.iter().find_map(|(_, names)| names.get(&address)))
+            .cloned()
+            .unwrap_or_else(|| symbol.to_string())
+    }
+}
+
+/// `--binary`: name the address-only entries whose Shared Object is one of
+/// `binaries` (compared by file name) through `addr2line`, noting the source
+/// locations of the functions found. Addresses it knows nothing about are left out.
+pub fn resolve_binary_addresses(
+    entries: &[PerfEntry],
+    binaries: &[PathBuf],
+) -> Result<ResolvedAddresses, PperfError> {
+    let mut resolved = ResolvedAddresses::default();
+    for binary in binaries {
+        let file_name = binary
+            .file_name()
+            .and_then(|n| n.to_str())
+            .unwrap_or("")
+            .to_string();
+        let addresses: BTreeSet<u64> = entries
+            .iter()
+            .filter(|e| e.dso.rsplit('/').next() == Some(file_name.as_str()))
+            .filter_map(|e| parse_address(&e.symbol))
+            .collect();
+        let addresses: Vec<u64> = addresses.into_iter().collect();
+        let mut names = HashMap::new();
+        for (address, location) in run_addr2line(binary, &addresses)? {
+            let Some(name) = location.name() else {
+                continue;
+            };
+            if let (Some(function), Some(srcline)) = (&location.function, &location.srcline) {
+                resolved
+                    .source_locations
+                    .insert(simplify_symbol(function), srcline.clone());
+            }
+            names.insert(address, name.to_string());
+        }
+        resolved.names.push((file_name, names));
+    }
+    Ok(resolved)
+}
+
+/// Where the running kernel lists its symbols; `--kallsyms` without a file reads it.
+pub const PROC_KALLSYMS: &str = "/proc/kallsyms";
+
+/// Kernel function addresses from a kallsyms listing: `ADDRESS TYPE name
+/// [module]` per line. A function runs from its address to the next one's.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Kallsyms {
+    /// `(address, name)` of the text symbols, sorted by address
+    symbols: Vec<(u64, String)>,
+}
+
+impl Kallsyms {
+    /// Parse a kallsyms listing. Only text symbols (types `t`/`T`/`w`/`W`) are kept,
+    /// and zero addresses, which `/proc/kallsyms` shows to unprivileged readers
+    /// (`kernel.kptr_restrict`), are skipped.
+    pub fn parse(content: &str) -> Kallsyms {
+        let mut symbols: Vec<(u64, String)> = content
+            .lines()
+            .filter_map(|line| {
+                let mut fields = line.split_whitespace();
+                let address = u64::from_str_radix(fields.next()?, 16).ok()?;
+                let kind = fields.next()?;
+                let name = fields.next()?;
+                (address != 0 && matches!(kind, "t" | "T" | "w" | "W"))
+                    .then(|| (address, name.to_string()))
+            })
+            .collect();
+        symbols.sort_by_key(|&(address, _)| address);
+        Kallsyms { symbols }
+    }
+
+    /// Read a kallsyms listing (`/proc/kallsyms` or a saved copy).
+    pub fn load(path: &Path) -> Result<Kallsyms, PperfError> {
+        let content = fs::read_to_string(path)
+            .map_err(|_| PperfError::FileNotFound(path.display().to_string()))?;
+        Ok(Kallsyms::parse(&content))
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.symbols.is_empty()
+    }
+
+    /// The function containing `address`: the last one starting at or below it.
+    /// Addresses below the first or past the last function are not kernel text.
+    pub fn resolve(&self, address: u64) -> Option<&str> {
+        let after = self.symbols.partition_point(|&(start, _)| start <= address);
+        if after == 0 || after == self.symbols.len() {
+            return None;
+        }
+        Some(self.symbols[after - 1].1.as_str())
+    }
+
+    /// Name the `[k]` entries shown as addresses.
+    pub fn apply_to_entries(&self, entries: &mut [PerfEntry]) {
+        for entry in entries.iter_mut().filter(|e| e.kernel) {
+            if let Some(name) = parse_address(&entry.symbol).and_then(|a| self.resolve(a)) {
+                entry.symbol = name.into();
+            }
+        }
+    }
+}
+
+/// Call-tree frames carry no `[k]` marker; kernel text addresses lie far from user
+/// space, so any frame inside the kernel's range is named.
+impl SymbolTransform for Kallsyms {
+    fn transform(&self, symbol: &str) -> String {
+        parse_address(symbol)
+            .and_then(|address| self.resolve(address))
+            .unwrap_or(symbol)
+            .to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_address() {
+        assert_eq!(parse_address("0x00007f3a10"), Some(0x7f3a10));
+        assert_eq!(parse_address("00005666eb8572c0"), Some(0x5666eb8572c0));
+        assert_eq!(parse_address("0x"), None);
+        assert_eq!(parse_address("DCT4DBlock::DCT4DBlock"), None);
+    }
+
+    #[test]
+    fn test_perf_map_resolve() {
+        let map = PerfMap::parse(
+            "7f3a1000 40 LazyCompile:*hot_loop app.js:12\n\
+             not a map line\n\
+             0x7f3a1040 0x20 Interpreter\n\
+             7f3a1000 10 recompiled\n",
+        );
+        assert_eq!(map.resolve(0x7f3a1004), Some("recompiled"));
+        assert_eq!(
+            map.resolve(0x7f3a1010),
+            Some("LazyCompile:*hot_loop app.js:12")
+        );
+        assert_eq!(map.resolve(0x7f3a1050), Some("Interpreter"));
+        assert_eq!(map.resolve(0x7f3a1060), None);
+        assert_eq!(map.resolve(0x10), None);
+        assert_eq!(map.transform("0x00007f3a1041"), "Interpreter");
+        assert_eq!(map.transform("main"), "main");
+    }
+
+    #[test]
+    fn test_jit_pid() {
+        assert_eq!(jit_pid("perf-4242.map"), Some(4242));
+        assert_eq!(jit_pid("/tmp/perf-4242.map"), Some(4242));
+        assert_eq!(jit_pid("[JIT] tid 17"), Some(17));
+        assert_eq!(jit_pid("libc.so.6"), None);
+    }
+
+    #[test]
+    fn test_parse_addr2line_output() {
+        let locations = parse_addr2line_output(
+            "DCT4DBlock::DCT4DBlock(Block4D const&, double)\n\
+             /src/DCT4DBlock.cpp:42\n\
+             ??\n\
+             ??:0\n\
+             gomp_thread_start\n\
+             ??:?\n\
+             ??\n\
+             /src/team.c:120 (discriminator 3)\n",
+        );
+        assert_eq!(locations.len(), 4);
+        assert_eq!(
+            locations[0].name(),
+            Some("DCT4DBlock::DCT4DBlock(Block4D const&, double)")
+        );
+        assert_eq!(
+            locations[0].srcline.as_deref(),
+            Some("/src/DCT4DBlock.cpp:42")
+        );
+        assert_eq!(locations[1], SourceLocation::default());
+        assert_eq!(locations[2].srcline, None);
+        assert_eq!(locations[3].name(), Some("/src/team.c:120"));
+    }
+
+    #[test]
+    fn test_resolved_addresses_match_any_spelling() {
+        let resolved = ResolvedAddresses {
+            names: vec![(
+                "libgomp.so.1.0.0".to_string(),
+                HashMap::from([(0x1fefe, "gomp_barrier_wait".to_string())]),
+            )],
+            ..Default::default()
+        };
+        assert_eq!(resolved.transform("0x1fefe"), "gomp_barrier_wait");
+        assert_eq!(resolved.transform("0x1feff"), "0x1feff");
+
+        // Entries only take names from their own binary
+        let entry = |dso: &str| PerfEntry {
+            symbol: "0x000000000001fefe".into(),
+            dso: dso.to_string(),
+            ..Default::default()
+        };
+        let mut entries = vec![entry("/usr/lib/libgomp.so.1.0.0"), entry("libc.so.6")];
+        resolved.apply_to_entries(&mut entries);
+        assert_eq!(entries[0].symbol, "gomp_barrier_wait");
+        assert_eq!(entries[1].symbol, "0x000000000001fefe");
+    }
+
+    #[test]
+    fn test_resolve_binary_addresses_missing_binary() {
+        let result = resolve_binary_addresses(&[], &[PathBuf::from("/nonexistent/binary")]);
+        assert!(matches!(result, Err(PperfError::FileNotFound(_))));
+    }
+
+    #[test]
+    fn test_kallsyms_resolve() {
+        let kallsyms = Kallsyms::parse(
+            "ffffffffb1000000 T _stext\n\
+             ffffffffb12c6300 T do_sys_openat2\n\
+             ffffffffb1500000 d some_data\n\
+             ffffffffb1f19c00 t do_syscall_64\n\
+             ffffffffb2000000 T entry_SYSCALL_64\n\
+             ffffffffc0a01000 t nvme_irq\t[nvme]\n\
+             ffffffffc0a02000 T _end_of_text\n",
+        );
+        assert_eq!(kallsyms.resolve(0xffffffffb12c637a), Some("do_sys_openat2"));
+        // Data symbols do not end a function
+        assert_eq!(kallsyms.resolve(0xffffffffb1600000), Some("do_sys_openat2"));
+        assert_eq!(kallsyms.resolve(0xffffffffc0a01010), Some("nvme_irq"));
+        assert_eq!(kallsyms.resolve(0x2f6f747461707261), None);
+        assert_eq!(kallsyms.resolve(0xffffffffc0a03000), None);
+        assert_eq!(kallsyms.transform("0xffffffffb20000e6"), "entry_SYSCALL_64");
+
+        let mut entries = vec![
+            PerfEntry {
+                symbol: "0xffffffffb1f19ceb".into(),
+                kernel: true,
+                ..Default::default()
+            },
+            PerfEntry {
+                symbol: "0xffffffffb1f19ceb".into(),
+                ..Default::default()
+            },
+        ];
+        kallsyms.apply_to_entries(&mut entries);
+        assert_eq!(entries[0].symbol, "do_syscall_64");
+        assert_eq!(entries[1].symbol, "0xffffffffb1f19ceb");
+    }
+
+    #[test]
+    fn test_kallsyms_restricted() {
+        let kallsyms =
+            Kallsyms::parse("0000000000000000 T _stext\n0000000000000000 T do_sys_openat2\n");
+        assert!(kallsyms.is_empty());
+    }
+}
@@ -0,0 +1,142 @@
+//! Set operations over the symbols of several reports (`pperf set`).
+//!
+//! Symbols are compared exactly; each row carries the symbol's percentages
+//! from every report, with `None` where a report does not contain it.
+
+use std::collections::HashMap;
+
+use crate::parser::PerfEntry;
+use crate::symbol::Symbol;
+
+/// Which symbols `symbol_set_rows` keeps.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum SetMode {
+    /// Symbols present in every report
+    Intersect,
+    /// Symbols present in any report
+    Union,
+    /// Symbols present in the report at this index and in no other
+    OnlyIn(usize),
+}
+
+/// One symbol with its per-report values.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SetRow {
+    pub symbol: Symbol,
+    /// Children% per report, in report order
+    pub children: Vec<Option<f64>>,
+    /// Self% per report, in report order
+    pub self_pct: Vec<Option<f64>>,
+}
+
+impl SetRow {
+    /// Highest Children% in any report, used for ordering.
+    pub fn max_children(&self) -> f64 {
+        self.children.iter().flatten().fold(0.0, |a, &b| a.max(b))
+    }
+}
+
+/// Combine report symbol sets according to `mode`, hottest symbols first.
+/// A symbol listed more than once in a report uses its first line.
+pub fn symbol_set_rows(reports: &[Vec<PerfEntry>], mode: SetMode) -> Vec<SetRow> {
+    let count = reports.len();
+    let mut index: HashMap<&str, usize> = HashMap::new();
+    let mut rows: Vec<SetRow> = Vec::new();
+
+    for (i, entries) in reports.iter().enumerate() {
+        for entry in entries {
+            let slot = *index.entry(entry.symbol.as_str()).or_insert_with(|| {
+                rows.push(SetRow {
+                    symbol: entry.symbol.clone(),
+                    children: vec![None; count],
+                    self_pct: vec![None; count],
+                });
+                rows.len() - 1
+            });
+            if rows[slot].children[i].is_none() {
+                rows[slot].children[i] = Some(entry.children_pct);
+                rows[slot].self_pct[i] = Some(entry.self_pct);
+            }
+        }
+    }
+
+    rows.retain(|row| match mode {
+        SetMode::Intersect => row.children.iter().all(Option::is_some),
+        SetMode::Union => true,
+        SetMode::OnlyIn(only) => row
+            .children
+            .iter()
+            .enumerate()
+            .all(|(i, v)| v.is_some() == (i == only)),
+    });
+    rows.sort_by(|a, b| {
+        b.max_children()
+            .partial_cmp(&a.max_children())
+            .unwrap_or(std::cmp::Ordering::Equal)
+    });
+    rows
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry(symbol: &str, children: f64) -> PerfEntry {
+        PerfEntry {
+            children_pct: children,
+            self_pct: children / 2.0,
+            symbol: symbol.into(),
+            ..Default::default()
+        }
+    }
+
+    fn reports() -> Vec<Vec<PerfEntry>> {
+        vec![
+            vec![
+                entry("main", 90.0),
+                entry("old_path", 20.0),
+                entry("shared", 10.0),
+            ],
+            vec![
+                entry("main", 92.0),
+                entry("new_path", 30.0),
+                entry("shared", 12.0),
+            ],
+        ]
+    }
+
+    #[test]
+    fn test_intersect() {
+        let rows = symbol_set_rows(&reports(), SetMode::Intersect);
+        let symbols: Vec<&str> = rows.iter().map(|r| r.symbol.as_str()).collect();
+        assert_eq!(symbols, vec!["main", "shared"]);
+        assert_eq!(rows[1].children, vec![Some(10.0), Some(12.0)]);
+        assert_eq!(rows[1].self_pct, vec![Some(5.0), Some(6.0)]);
+    }
+
+    #[test]
+    fn test_union_sorted_by_max_children() {
+        let rows = symbol_set_rows(&reports(), SetMode::Union);
+        let symbols: Vec<&str> = rows.iter().map(|r| r.symbol.as_str()).collect();
+        assert_eq!(symbols, vec!["main", "new_path", "old_path", "shared"]);
+        assert_eq!(rows[1].children, vec![None, Some(30.0)]);
+    }
+
+    #[test]
+    fn test_only_in() {
+        let rows = symbol_set_rows(&reports(), SetMode::OnlyIn(1));
+        assert_eq!(rows.len(), 1);
+        assert_eq!(rows[0].symbol, "new_path");
+
+        let rows = symbol_set_rows(&reports(), SetMode::OnlyIn(0));
+        assert_eq!(rows[0].symbol, "old_path");
+    }
+
+    #[test]
+    fn test_duplicate_symbol_uses_first_line() {
+        let reports = vec![vec![entry("f", 40.0), entry("f", 10.0)]];
+        let rows = symbol_set_rows(&reports, SetMode::Union);
+        assert_eq!(rows.len(), 1);
+        assert_eq!(rows[0].children, vec![Some(40.0)]);
+    }
+}
@@ -2,16 +2,34 @@
 //!
 //! This module provides:
 //! - ANSI color codes for terminal output
-//! - Symbol type classification (User, Library, Unresolved)
+//! - Symbol type classification (User, Library, Managed, Unresolved)
 //! - Symbol name simplification (strip return types, templates, arguments)
+//! - User-supplied symbol rewriting (`SymbolTransform`, `--symbol-filter`, config `[[rewrite]]` rules)
+//! - Display-name aliases (config `[[alias]]` tables)
+//! - Shared, interned symbol names (`Symbol`, `SymbolInterner`)
+//! - Terminal hyperlinks from symbols to their source (`--hyperlinks`)
 
-use std::io::{IsTerminal, stdout};
+use std::borrow::Borrow;
+use std::collections::{HashMap, HashSet};
+use std::fmt;
+use std::io::{IsTerminal, Read, Write, stdout};
+use std::path::Path;
+use std::process::{Command, Stdio};
+use std::sync::atomic::{AtomicU8, Ordering};
+use std::sync::{Arc, OnceLock};
+
+use regex::Regex;
+
+use crate::PperfError;
+use crate::filter::TargetSpec;
 
 // ANSI color codes
 pub const RESET: &str = "\x1b[0m";
 pub const BLUE: &str = "\x1b[34m"; // User functions
 pub const YELLOW: &str = "\x1b[33m"; // Library/system functions
 pub const RED: &str = "\x1b[31m"; // Unresolved symbols
+pub const MAGENTA: &str = "\x1b[35m"; // Managed (JVM) frames
+pub const GREEN: &str = "\x1b[32m"; // Improvements in diff output
 pub const DIM: &str = "\x1b[2m"; // Dim/gray text for debug annotations
 
 /// Classification of a symbol's origin for color coding
@@ -23,6 +41,10 @@ pub enum SymbolType {
     Library,
     /// Unresolved symbols like hex addresses (displayed in red)
     Unresolved,
+    /// Managed-runtime frames: JIT-compiled Java methods and JVM stubs, Python
+    /// functions and the CPython evaluator, V8 functions and builtins (displayed in
+    /// magenta)
+    Managed,
 }
 
 /// Determine whether to use colored output
@@ -36,12 +58,33 @@ pub fn should_use_color(no_color_flag: bool) -> bool {
     stdout().is_terminal()
 }
 
+/// Width of the terminal stdout writes to: `COLUMNS` if set, else `stty size` on
+/// the controlling terminal. None when stdout is not a terminal (piped output keeps
+/// the default layout) or the width cannot be read.
+pub fn terminal_width() -> Option<usize> {
+    if !stdout().is_terminal() {
+        return None;
+    }
+    if let Some(columns) = std::env::var("COLUMNS")
+        .ok()
+        .and_then(|c| c.trim().parse().ok())
+    {
+        return Some(columns);
+    }
+    let tty = std::fs::File::open("/dev/tty").ok()?;
+    let output = Command::new("stty").arg("size").stdin(tty).output().ok()?;
+    // `rows columns`
+    let size = String::from_utf8(output.stdout).ok()?;
+    size.split_whitespace().nth(1)?.parse().ok()
+}
+
 /// Get the ANSI color code for a symbol type
 pub fn color_for_type(symbol_type: SymbolType) -> &'static str {
     match symbol_type {
         SymbolType::User => BLUE,
         SymbolType::Library => YELLOW,
         SymbolType::Unresolved => RED,
+        SymbolType::Managed => MAGENTA,
     }
 }
 
@@ -86,17 +129,248 @@ fn is_library_symbol(symbol: &str) -> bool {
     false
 }
 
+/// JVM frames perf names without a method: the bytecode interpreter, call stubs and
+/// (from `comm`-sorted or thread-rooted chains) the JIT compiler threads, whose
+/// 15-character names perf truncates.
+const JVM_FRAMES: &[&str] = &["Interpreter", "I2C/C2I adapters"];
+const JVM_FRAME_PREFIXES: &[&str] = &["C1 CompilerThre", "C2 CompilerThre", "StubRoutines"];
+
+/// Check if a symbol is a managed-runtime frame: a Java method as perf-map-agent
+/// writes it (`Lcom/foo/Bar;::method`), the same in dotted form
+/// (`com.foo.Bar::method`), a JVM stub, a Python frame or a V8 frame.
+fn is_managed_symbol(symbol: &str) -> bool {
+    is_v8_symbol(symbol)
+        || JVM_FRAMES.contains(&symbol)
+        || JVM_FRAME_PREFIXES.iter().any(|p| symbol.starts_with(p))
+        || jvm_symbol_name(symbol).is_some()
+        || is_java_method(symbol)
+        || is_python_symbol(symbol)
+}
+
+/// A `package.Class::method` name: a dotted package path is never a C++ scope.
+fn is_java_method(symbol: &str) -> bool {
+    symbol.split_once("::").is_some_and(|(class, _)| {
+        class.contains('.')
+            && class
+                .chars()
+                .all(|c| c.is_alphanumeric() || c == '_' || c == '$' || c == '.')
+    })
+}
+
+/// `Lcom/foo/Bar$Inner;::method(I)V` -> `com.foo.Bar$Inner::method(I)V`: the JVM
+/// type descriptor of a perf-map-agent frame turned into a dotted class name, so
+/// targets are written the way Java code names the class. `None` for other symbols.
+pub fn jvm_symbol_name(symbol: &str) -> Option<String> {
+    let (class, method) = symbol.strip_prefix('L')?.split_once(";::")?;
+    if class.is_empty() || class.contains(' ') {
+        return None;
+    }
+    Some(format!("{}::{}", class.replace('/', "."), method))
+}
+
+/// `com.foo.Bar::method(I)V` (or its descriptor form) -> `com.foo.Bar::method`.
+fn simplify_jvm_symbol(symbol: &str) -> Option<String> {
+    let name = jvm_symbol_name(symbol).unwrap_or_else(|| symbol.to_string());
+    if !is_java_method(&name) {
+        return None;
+    }
+    Some(name.split('(').next().unwrap_or(&name).to_string())
+}
+
+/// Prefix of the Python functions CPython's perf trampolines (`python -X perf`) and
+/// py-perf name.
+const PYTHON_PREFIX: &str = "py::";
+
+/// CPython's bytecode evaluator frames, which every Python call runs through.
+const PYTHON_EVALUATOR_PREFIXES: &[&str] = &["_PyEval_", "PyEval_"];
+
+/// Check if a symbol is a Python frame: `py::module:function` (or CPython's
+/// `py::function:/path/module.py`), its simplified `module:function`, or the evaluator.
+fn is_python_symbol(symbol: &str) -> bool {
+    if symbol.starts_with(PYTHON_PREFIX)
+        || PYTHON_EVALUATOR_PREFIXES
+            .iter()
+            .any(|p| symbol.starts_with(p))
+    {
+        return true;
+    }
+    // `module:function`: a single colon never appears in a C++ name outside `auto:1`
+    symbol.split_once(':').is_some_and(|(module, function)| {
+        !module.is_empty()
+            && !function.is_empty()
+            && module
+                .chars()
+                .all(|c| c.is_alphanumeric() || c == '_' || c == '.')
+            && function
+                .chars()
+                .all(|c| c.is_alphanumeric() || c == '_' || c == '<' || c == '>')
+    })
+}
+
+/// `py::encoder:encode_frame` -> `encoder:encode_frame`; CPython's
+/// `py::encode_frame:/app/codec/encoder.py` -> `encoder:encode_frame`, the file stem
+/// standing in for the module. Already simplified names are kept (`<module>` is no
+/// template). `None` for other symbols.
+fn simplify_python_symbol(symbol: &str) -> Option<String> {
+    let Some(rest) = symbol.strip_prefix(PYTHON_PREFIX) else {
+        return is_python_symbol(symbol).then(|| symbol.to_string());
+    };
+    if let Some((function, path)) = rest.rsplit_once(':')
+        && let Some(file) = path.strip_suffix(".py")
+    {
+        let module = file.rsplit('/').next().unwrap_or(file);
+        return Some(format!("{}:{}", module, function));
+    }
+    Some(rest.to_string())
+}
+
+/// Kinds of code V8 names in `--perf-basic-prof` maps whose symbol is a JavaScript
+/// function and its location: `LazyCompile:*encodeFrame /srv/app/encoder.js:42`.
+const V8_FUNCTION_KINDS: &[&str] = &[
+    "LazyCompile:",
+    "Function:",
+    "JS:",
+    "InterpretedFunction:",
+    "Script:",
+    "Eval:",
+];
+
+/// Kinds of V8-generated code, named without a location: `Builtin:ArrayPrototypePush`.
+const V8_CODE_KINDS: &[&str] = &[
+    "Builtin:",
+    "BytecodeHandler:",
+    "Stub:",
+    "Handler:",
+    "RegExp:",
+];
+
+/// Source file extensions of a simplified V8 function's location.
+const JS_EXTENSIONS: &[&str] = &[".js", ".mjs", ".cjs", ".ts"];
+
+/// Check if a symbol is a V8 frame: a function or generated code as V8's perf map
+/// names it, or a simplified `encodeFrame encoder.js:42`.
+fn is_v8_symbol(symbol: &str) -> bool {
+    V8_FUNCTION_KINDS
+        .iter()
+        .chain(V8_CODE_KINDS)
+        .any(|kind| symbol.starts_with(kind))
+        || is_js_function(symbol)
+}
+
+/// A simplified V8 function: `name file.js:line`.
+fn is_js_function(symbol: &str) -> bool {
+    symbol.rsplit_once(' ').is_some_and(|(name, location)| {
+        let mut parts = location.split(':');
+        let file = parts.next().unwrap_or_default();
+        !name.is_empty()
+            && !name.contains(' ')
+            && !file.contains('/')
+            && JS_EXTENSIONS.iter().any(|ext| file.ends_with(ext))
+            && parts.all(|n| !n.is_empty() && n.chars().all(|c| c.is_ascii_digit()))
+    })
+}
+
+/// `LazyCompile:*encodeFrame /srv/app/encoder.js:42` -> `encodeFrame encoder.js:42`:
+/// the code kind and tier marker (`*` optimized, `~` interpreted, `^` baseline) go,
+/// as does the directory; unnamed functions become `<anonymous>`. Generated code
+/// keeps its kind (`Builtin:ArrayPrototypePush`). `None` for other symbols.
+fn simplify_v8_symbol(symbol: &str) -> Option<String> {
+    if is_js_function(symbol) || V8_CODE_KINDS.iter().any(|kind| symbol.starts_with(kind)) {
+        return Some(symbol.to_string());
+    }
+    let rest = V8_FUNCTION_KINDS
+        .iter()
+        .find_map(|kind| symbol.strip_prefix(kind))?;
+    let rest = rest.trim_start_matches(['*', '~', '^']);
+    let (name, location) = match rest.rsplit_once(' ') {
+        Some((name, location)) => (name.trim(), Some(location)),
+        None => (rest, None),
+    };
+    let name = if name.is_empty() { "<anonymous>" } else { name };
+    Some(match location {
+        Some(location) => format!(
+            "{} {}",
+            name,
+            location.rsplit('/').next().unwrap_or(location)
+        ),
+        None => name.to_string(),
+    })
+}
+
+/// Which symbols a config `[[library]]`/`[[user]]` table selects, by their
+/// simplified name.
+#[derive(Debug, Clone)]
+pub enum SymbolPattern {
+    Prefix(String),
+    Regex(Regex),
+}
+
+impl SymbolPattern {
+    pub fn matches(&self, simplified: &str) -> bool {
+        match self {
+            SymbolPattern::Prefix(prefix) => simplified.starts_with(prefix.as_str()),
+            SymbolPattern::Regex(re) => re.is_match(simplified),
+        }
+    }
+}
+
+/// A config classification table: symbols matching `pattern` are `symbol_type`
+/// (`Library` or `User`).
+#[derive(Debug, Clone)]
+pub struct ClassificationRule {
+    pub pattern: SymbolPattern,
+    pub symbol_type: SymbolType,
+}
+
+static CLASSIFICATION_RULES: OnceLock<Vec<ClassificationRule>> = OnceLock::new();
+
+/// Install the config file's classification rules for the rest of the run (first
+/// call wins).
+pub fn set_classification_rules(rules: Vec<ClassificationRule>) {
+    let _ = CLASSIFICATION_RULES.set(rules);
+}
+
+/// The type the installed config rules assign, if any.
+fn configured_type(symbol: &str) -> Option<SymbolType> {
+    let rules = CLASSIFICATION_RULES
+        .get()
+        .filter(|rules| !rules.is_empty())?;
+    classify_with_rules(symbol, rules)
+}
+
+/// The type `rules` assign: `User` rules win over `Library` ones, which win over
+/// the built-in library list.
+fn classify_with_rules(symbol: &str, rules: &[ClassificationRule]) -> Option<SymbolType> {
+    let simplified = simplify_symbol(symbol);
+    let matching = |symbol_type: SymbolType| {
+        rules
+            .iter()
+            .any(|r| r.symbol_type == symbol_type && r.pattern.matches(&simplified))
+    };
+    [SymbolType::User, SymbolType::Library]
+        .into_iter()
+        .find(|t| matching(*t))
+}
+
 /// T019: Classify a symbol by its type for color coding
 pub fn classify_symbol(symbol: &str) -> SymbolType {
     // Priority 1: Unresolved hex addresses
     if is_hex_address(symbol) {
         return SymbolType::Unresolved;
     }
-    // Priority 2: Library/system functions
+    // Priority 2: Config `[[user]]` / `[[library]]` tables
+    if let Some(symbol_type) = configured_type(symbol) {
+        return symbol_type;
+    }
+    // Priority 3: JVM frames
+    if is_managed_symbol(symbol) {
+        return SymbolType::Managed;
+    }
+    // Priority 4: Library/system functions
     if is_library_symbol(symbol) {
         return SymbolType::Library;
     }
-    // Priority 3: Everything else is user code
+    // Priority 5: Everything else is user code
     SymbolType::User
 }
 
@@ -136,16 +410,12 @@ fn strip_return_type(symbol: &str) -> &str {
 /// e.g., "std::vector<int>" -> "std::vector"
 fn strip_template_params(symbol: &str) -> String {
     let mut result = String::new();
-    let mut depth = 0;
+    let mut depth: usize = 0;
 
     for c in symbol.chars() {
         match c {
             '<' => depth += 1,
-            '>' => {
-                if depth > 0 {
-                    depth -= 1;
-                }
-            }
+            '>' => depth = depth.saturating_sub(1),
             _ if depth == 0 => result.push(c),
             _ => {} // Skip chars inside templates
         }
@@ -227,12 +497,65 @@ fn collapse_lambda(symbol: &str) -> String {
     result
 }
 
-/// T038: Simplify a symbol by stripping return types, templates, arguments, and clone suffixes
+/// How report symbols are shown and compared (`--demangle`).
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum DemangleMode {
+    /// As perf printed them: mangled names stay mangled, nothing is simplified
+    None,
+    /// Demangled and simplified to `Class::function`
+    #[default]
+    Simplified,
+    /// Demangled, keeping return types, template arguments and argument lists
+    Full,
+}
+
+impl DemangleMode {
+    /// Parse a `none`/`simplified`/`full` mode name.
+    pub fn from_setting(value: &str) -> Option<DemangleMode> {
+        match value.trim() {
+            "none" => Some(DemangleMode::None),
+            "simplified" => Some(DemangleMode::Simplified),
+            "full" => Some(DemangleMode::Full),
+            _ => None,
+        }
+    }
+}
+
+static DEMANGLE_MODE: AtomicU8 = AtomicU8::new(DemangleMode::Simplified as u8);
+
+/// Set the symbol form for the whole run; call it before any report is parsed,
+/// since call trees store their names already simplified.
+pub fn set_demangle_mode(mode: DemangleMode) {
+    DEMANGLE_MODE.store(mode as u8, Ordering::Relaxed);
+}
+
+pub fn demangle_mode() -> DemangleMode {
+    match DEMANGLE_MODE.load(Ordering::Relaxed) {
+        0 => DemangleMode::None,
+        2 => DemangleMode::Full,
+        _ => DemangleMode::Simplified,
+    }
+}
+
+/// T038: Simplify a symbol by stripping return types, templates, arguments, and clone suffixes.
+/// Outside `--demangle simplified` the symbol is kept whole.
 pub fn simplify_symbol(symbol: &str) -> String {
+    if demangle_mode() != DemangleMode::Simplified {
+        return symbol.to_string();
+    }
     // Preserve hex addresses unchanged (T031)
     if is_hex_address(symbol) {
         return symbol.to_string();
     }
+    if let Some(js) = simplify_v8_symbol(symbol) {
+        return js;
+    }
+    if let Some(java) = simplify_jvm_symbol(symbol) {
+        return java;
+    }
+    if let Some(python) = simplify_python_symbol(symbol) {
+        return python;
+    }
 
     // Apply transformations in order
     // 0. Strip "auto " prefix (C++ return type deduction keyword)
@@ -251,11 +574,115 @@ pub fn simplify_symbol(symbol: &str) -> String {
     s.to_string()
 }
 
+/// One `[[rewrite]]` rule of the config file: every match of `pattern` in a shown
+/// symbol is replaced by `replacement` (`$1` refers to a capture group).
+#[derive(Debug, Clone)]
+pub struct RewriteRule {
+    pub pattern: Regex,
+    pub replacement: String,
+}
+
+impl RewriteRule {
+    pub fn apply(&self, symbol: &str) -> String {
+        self.pattern
+            .replace_all(symbol, self.replacement.as_str())
+            .into_owned()
+    }
+}
+
+static REWRITE_RULES: OnceLock<Vec<RewriteRule>> = OnceLock::new();
+
+/// Install the config file's rewrite rules for the rest of the run (first call wins).
+pub fn set_rewrite_rules(rules: Vec<RewriteRule>) {
+    let _ = REWRITE_RULES.set(rules);
+}
+
+/// Apply the rewrite rules, in order, to a simplified symbol about to be shown.
+/// Matching and grouping keep using the unrewritten names.
+pub fn rewrite_symbol(symbol: &str) -> String {
+    let rules = REWRITE_RULES.get().map(Vec::as_slice).unwrap_or_default();
+    let mut symbol = symbol.to_string();
+    for rule in rules {
+        symbol = rule.apply(&symbol);
+    }
+    symbol
+}
+
+/// One `[[alias]]` table of the config file: the symbols `spec` matches (a full
+/// signature, which also matches its simplified name, or a regex) are shown as `label`.
+#[derive(Debug, Clone)]
+pub struct DisplayAlias {
+    pub spec: TargetSpec,
+    pub label: String,
+}
+
+static DISPLAY_ALIASES: OnceLock<Vec<DisplayAlias>> = OnceLock::new();
+
+/// Install the config file's aliases for the rest of the run (first call wins).
+pub fn set_display_aliases(aliases: Vec<DisplayAlias>) {
+    let _ = DISPLAY_ALIASES.set(aliases);
+}
+
+/// The label the first matching `[[alias]]` table gives a symbol about to be shown.
+pub fn display_alias(symbol: &str) -> Option<&'static str> {
+    DISPLAY_ALIASES
+        .get()?
+        .iter()
+        .find(|alias| alias.spec.matches(symbol, false))
+        .map(|alias| alias.label.as_str())
+}
+
+/// `--hyperlinks` template used without a value: the source file itself.
+pub const FILE_LINK_TEMPLATE: &str = "file://{file}";
+
+static LINK_TEMPLATE: OnceLock<String> = OnceLock::new();
+static SOURCE_LOCATIONS: OnceLock<HashMap<String, String>> = OnceLock::new();
+
+/// Turn on `--hyperlinks` for the rest of the run: symbols with a known source
+/// location link to `template`, with `{file}` and `{line}` filled in (first call
+/// wins).
+pub fn set_hyperlink_template(template: String) {
+    let _ = LINK_TEMPLATE.set(template);
+}
+
+/// Install the known source locations, `file:line` by simplified symbol name
+/// (first call wins).
+pub fn set_source_locations(locations: HashMap<String, String>) {
+    let _ = SOURCE_LOCATIONS.set(locations);
+}
+
+/// The link target of a symbol, when `--hyperlinks` is on and its source
+/// location is known.
+pub fn hyperlink_target(symbol: &str) -> Option<String> {
+    let template = LINK_TEMPLATE.get()?;
+    let location = SOURCE_LOCATIONS.get()?.get(&simplify_symbol(symbol))?;
+    Some(fill_link_template(template, location))
+}
+
+/// Fill `{file}` and `{line}` of a link template from a `file:line` location
+/// (`{line}` is empty without a line number).
+fn fill_link_template(template: &str, location: &str) -> String {
+    let (file, line) = match location.rsplit_once(':') {
+        Some((file, line)) if line.chars().all(|c| c.is_ascii_digit()) => (file, line),
+        _ => (location, ""),
+    };
+    template.replace("{file}", file).replace("{line}", line)
+}
+
+/// Wrap `text` in an OSC 8 terminal hyperlink to `target`.
+pub fn format_hyperlink(text: &str, target: &str) -> String {
+    format!("\x1b]8;;{}\x1b\\{}\x1b]8;;\x1b\\", target, text)
+}
+
 /// T020/T039: Format a symbol with optional ANSI color codes
-/// T039: Now calls simplify_symbol() before applying color
+/// T039: Now calls simplify_symbol() before applying color, then the config's rewrite rules.
+/// With colors on, `--hyperlinks` also links it to its source.
 pub fn format_colored_symbol(symbol: &str, use_color: bool) -> String {
-    // T039: Simplify symbol before formatting
-    let simplified = simplify_symbol(symbol);
+    // T039: Simplify symbol before formatting; a config alias replaces the name
+    let simplified = match display_alias(symbol) {
+        Some(label) => label.to_string(),
+        None => rewrite_symbol(&simplify_symbol(symbol)),
+    };
 
     if !use_color {
         return simplified;
@@ -263,7 +690,242 @@ pub fn format_colored_symbol(symbol: &str, use_color: bool) -> String {
     // Classify based on original symbol for correct color detection
     let symbol_type = classify_symbol(symbol);
     let color = color_for_type(symbol_type);
-    format!("{}{}{}", color, simplified, RESET)
+    let colored = format!("{}{}{}", color, simplified, RESET);
+    match hyperlink_target(symbol) {
+        Some(target) => format_hyperlink(&colored, &target),
+        None => colored,
+    }
+}
+
+/// `--heat` color of a percentage: red above 50%, yellow above 10%, none below.
+pub fn heat_color(pct: f64) -> Option<&'static str> {
+    if pct > 50.0 {
+        Some(RED)
+    } else if pct > 10.0 {
+        Some(YELLOW)
+    } else {
+        None
+    }
+}
+
+/// Color an already padded percentage `text` by the magnitude of `pct`.
+pub fn format_heat_pct(text: &str, pct: f64, use_color: bool) -> String {
+    match heat_color(pct) {
+        Some(color) if use_color => format!("{}{}{}", color, text, RESET),
+        _ => text.to_string(),
+    }
+}
+
+/// An immutable function name whose clones share one allocation.
+///
+/// Reports repeat the same names across tens of thousands of call-tree lines;
+/// entries, tree nodes and relations hold a `Symbol` so copying a name between
+/// them is a reference-count increment. Symbols made through a `SymbolInterner`
+/// also share storage with every other occurrence of the same name.
+#[derive(Clone, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Symbol(Arc<str>);
+
+impl Symbol {
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+}
+
+impl std::ops::Deref for Symbol {
+    type Target = str;
+
+    fn deref(&self) -> &str {
+        &self.0
+    }
+}
+
+impl AsRef<str> for Symbol {
+    fn as_ref(&self) -> &str {
+        &self.0
+    }
+}
+
+// Hash and Eq agree with `str`, so `HashMap<Symbol, _>` can be queried with `&str`
+impl Borrow<str> for Symbol {
+    fn borrow(&self) -> &str {
+        &self.0
+    }
+}
+
+impl fmt::Debug for Symbol {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Debug::fmt(&*self.0, f)
+    }
+}
+
+impl fmt::Display for Symbol {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Display::fmt(&*self.0, f)
+    }
+}
+
+impl From<&str> for Symbol {
+    fn from(name: &str) -> Self {
+        Symbol(Arc::from(name))
+    }
+}
+
+impl From<String> for Symbol {
+    fn from(name: String) -> Self {
+        Symbol(Arc::from(name))
+    }
+}
+
+impl From<&String> for Symbol {
+    fn from(name: &String) -> Self {
+        Symbol(Arc::from(name.as_str()))
+    }
+}
+
+impl From<Symbol> for String {
+    fn from(symbol: Symbol) -> Self {
+        symbol.0.to_string()
+    }
+}
+
+impl PartialEq<str> for Symbol {
+    fn eq(&self, other: &str) -> bool {
+        &*self.0 == other
+    }
+}
+
+impl PartialEq<&str> for Symbol {
+    fn eq(&self, other: &&str) -> bool {
+        &*self.0 == *other
+    }
+}
+
+impl PartialEq<String> for Symbol {
+    fn eq(&self, other: &String) -> bool {
+        &*self.0 == other.as_str()
+    }
+}
+
+impl PartialEq<Symbol> for str {
+    fn eq(&self, other: &Symbol) -> bool {
+        self == &*other.0
+    }
+}
+
+impl PartialEq<Symbol> for &str {
+    fn eq(&self, other: &Symbol) -> bool {
+        *self == &*other.0
+    }
+}
+
+impl PartialEq<Symbol> for String {
+    fn eq(&self, other: &Symbol) -> bool {
+        self.as_str() == &*other.0
+    }
+}
+
+/// Hands out one shared `Symbol` per distinct name, for parsing a report's
+/// entries and call trees.
+#[derive(Debug, Default)]
+pub struct SymbolInterner {
+    symbols: HashSet<Symbol>,
+}
+
+impl SymbolInterner {
+    pub fn intern(&mut self, name: &str) -> Symbol {
+        if let Some(symbol) = self.symbols.get(name) {
+            return symbol.clone();
+        }
+        let symbol = Symbol::from(name);
+        self.symbols.insert(symbol.clone());
+        symbol
+    }
+
+    /// Number of distinct names interned so far
+    pub fn len(&self) -> usize {
+        self.symbols.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.symbols.is_empty()
+    }
+}
+
+/// Rewrites parsed symbols, e.g. to strip company-specific namespaces.
+///
+/// Applied once to every symbol right after parsing, to its simplified name
+/// (`transform_entry_symbols` puts the result back into the entry's full
+/// signature), so matching, hierarchy and output all see the rewritten names.
+pub trait SymbolTransform {
+    fn transform(&self, symbol: &str) -> String;
+}
+
+impl<F: Fn(&str) -> String> SymbolTransform for F {
+    fn transform(&self, symbol: &str) -> String {
+        self(symbol)
+    }
+}
+
+/// A precomputed symbol mapping; symbols without an entry are kept unchanged.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct MappedSymbols(pub HashMap<String, String>);
+
+impl SymbolTransform for MappedSymbols {
+    fn transform(&self, symbol: &str) -> String {
+        self.0
+            .get(symbol)
+            .cloned()
+            .unwrap_or_else(|| symbol.to_string())
+    }
+}
+
+/// Run an external filter (like `c++filt`) once over all symbols: one symbol per
+/// line on stdin, the rewritten symbol on the same line of stdout.
+pub fn run_symbol_filter(exe: &Path, symbols: &[String]) -> Result<MappedSymbols, PperfError> {
+    let failed =
+        |reason: &str| PperfError::SymbolFilterFailed(format!("{}: {}", exe.display(), reason));
+
+    let mut child = Command::new(exe)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .spawn()
+        .map_err(|e| failed(&e.to_string()))?;
+
+    // Feed stdin from a thread so a filter that streams output cannot deadlock on full pipes
+    let mut stdin = child.stdin.take().expect("stdin is piped");
+    let input: String = symbols.iter().map(|s| format!("{}\n", s)).collect();
+    let writer = std::thread::spawn(move || stdin.write_all(input.as_bytes()));
+
+    let mut output = String::new();
+    child
+        .stdout
+        .take()
+        .expect("stdout is piped")
+        .read_to_string(&mut output)
+        .map_err(|e| failed(&e.to_string()))?;
+    let status = child.wait().map_err(|e| failed(&e.to_string()))?;
+    // A filter may exit without reading everything; its output is what counts
+    let _ = writer.join();
+
+    if !status.success() {
+        return Err(failed(&format!("exited with {}", status)));
+    }
+    let lines: Vec<&str> = output.lines().collect();
+    if lines.len() != symbols.len() {
+        return Err(failed(&format!(
+            "expected {} output lines, got {}",
+            symbols.len(),
+            lines.len()
+        )));
+    }
+
+    Ok(MappedSymbols(
+        symbols
+            .iter()
+            .zip(lines)
+            .map(|(from, to)| (from.clone(), to.to_string()))
+            .collect(),
+    ))
 }
 
 #[cfg(test)]
@@ -308,6 +970,136 @@ mod tests {
         assert_eq!(color_for_type(SymbolType::User), BLUE);
         assert_eq!(color_for_type(SymbolType::Library), YELLOW);
         assert_eq!(color_for_type(SymbolType::Unresolved), RED);
+        assert_eq!(color_for_type(SymbolType::Managed), MAGENTA);
+    }
+
+    #[test]
+    fn test_classify_symbol_jvm_frames() {
+        assert_eq!(
+            classify_symbol("Lcom/acme/codec/Encoder;::encodeBlock"),
+            SymbolType::Managed
+        );
+        assert_eq!(
+            classify_symbol("com.acme.codec.Encoder::encodeBlock"),
+            SymbolType::Managed
+        );
+        assert_eq!(classify_symbol("Interpreter"), SymbolType::Managed);
+        assert_eq!(classify_symbol("C2 CompilerThread0"), SymbolType::Managed);
+        assert_eq!(classify_symbol("C1 CompilerThre"), SymbolType::Managed);
+        assert_eq!(classify_symbol("StubRoutines (1)"), SymbolType::Managed);
+        // C++ scopes and clone suffixes are not package paths
+        assert_eq!(
+            classify_symbol("Block4D::get_linear_position"),
+            SymbolType::User
+        );
+        assert_eq!(
+            classify_symbol("std::inner_product<double*> [clone .constprop.0]"),
+            SymbolType::Library
+        );
+        assert_eq!(classify_symbol("InterpreterLoop::run"), SymbolType::User);
+    }
+
+    #[test]
+    fn test_classify_symbol_python_frames() {
+        assert_eq!(
+            classify_symbol("py::encoder:encode_frame"),
+            SymbolType::Managed
+        );
+        assert_eq!(classify_symbol("encoder:encode_frame"), SymbolType::Managed);
+        assert_eq!(classify_symbol("codec.dct:forward"), SymbolType::Managed);
+        assert_eq!(classify_symbol("main:<module>"), SymbolType::Managed);
+        assert_eq!(
+            classify_symbol("_PyEval_EvalFrameDefault"),
+            SymbolType::Managed
+        );
+        assert_eq!(classify_symbol("PyEval_EvalCode"), SymbolType::Managed);
+        // C++ scopes and lambdas keep their types
+        assert_eq!(
+            classify_symbol("Block4D::get_linear_position"),
+            SymbolType::User
+        );
+        assert_eq!(
+            classify_symbol("Transformed4DBlock::{lambda(auto:1 const&)#1}"),
+            SymbolType::User
+        );
+    }
+
+    #[test]
+    fn test_simplify_python_symbol() {
+        assert_eq!(
+            simplify_symbol("py::encoder:encode_frame"),
+            "encoder:encode_frame"
+        );
+        assert_eq!(
+            simplify_symbol("py::encode_frame:/srv/app/codec/encoder.py"),
+            "encoder:encode_frame"
+        );
+        assert_eq!(
+            simplify_symbol("py::<module>:/srv/app/main.py"),
+            "main:<module>"
+        );
+        assert_eq!(
+            simplify_symbol("_PyEval_EvalFrameDefault"),
+            "_PyEval_EvalFrameDefault"
+        );
+    }
+
+    #[test]
+    fn test_classify_symbol_v8_frames() {
+        assert_eq!(
+            classify_symbol("LazyCompile:*encodeFrame /srv/app/encoder.js:42"),
+            SymbolType::Managed
+        );
+        assert_eq!(
+            classify_symbol("encodeFrame encoder.js:42"),
+            SymbolType::Managed
+        );
+        assert_eq!(
+            classify_symbol("Builtin:ArrayPrototypePush"),
+            SymbolType::Managed
+        );
+        assert_eq!(classify_symbol("BytecodeHandler:Ldar"), SymbolType::Managed);
+        // A C++ conversion operator is not a location
+        assert_eq!(classify_symbol("Block4D::operator bool"), SymbolType::User);
+    }
+
+    #[test]
+    fn test_simplify_v8_symbol() {
+        assert_eq!(
+            simplify_symbol("LazyCompile:*encodeFrame /srv/app/encoder.js:42"),
+            "encodeFrame encoder.js:42"
+        );
+        assert_eq!(
+            simplify_symbol("JS:~quantize file:///srv/app/quant.mjs:7:12"),
+            "quantize quant.mjs:7:12"
+        );
+        assert_eq!(
+            simplify_symbol("LazyCompile:* /srv/app/main.js:3"),
+            "<anonymous> main.js:3"
+        );
+        assert_eq!(
+            simplify_symbol("Builtin:ArrayPrototypePush"),
+            "Builtin:ArrayPrototypePush"
+        );
+        // Simplifying again keeps the name
+        assert_eq!(
+            simplify_symbol("<anonymous> main.js:3"),
+            "<anonymous> main.js:3"
+        );
+    }
+
+    #[test]
+    fn test_simplify_jvm_symbol() {
+        assert_eq!(
+            simplify_symbol("Lcom/acme/codec/Encoder;::encodeBlock"),
+            "com.acme.codec.Encoder::encodeBlock"
+        );
+        assert_eq!(
+            simplify_symbol("Ljava/util/HashMap$TreeNode;::putTreeVal(Ljava/util/HashMap;I)V"),
+            "java.util.HashMap$TreeNode::putTreeVal"
+        );
+        assert_eq!(simplify_symbol("Interpreter"), "Interpreter");
+        assert_eq!(simplify_symbol("C2 CompilerThread0"), "C2 CompilerThread0");
     }
 
     // T010: Unit test for classify_symbol with hex addresses
@@ -361,6 +1153,44 @@ mod tests {
     }
 
     // T015: Unit test for format_colored_symbol
+    #[test]
+    fn test_classify_with_rules() {
+        let rules = vec![
+            ClassificationRule {
+                pattern: SymbolPattern::Prefix("boost::".to_string()),
+                symbol_type: SymbolType::Library,
+            },
+            ClassificationRule {
+                pattern: SymbolPattern::Regex(Regex::new("^std::acme").unwrap()),
+                symbol_type: SymbolType::User,
+            },
+        ];
+        assert_eq!(
+            classify_with_rules("void boost::asio::run(int)", &rules),
+            Some(SymbolType::Library)
+        );
+        // A [[user]] table overrides the built-in std:: rule
+        assert_eq!(
+            classify_with_rules("std::acme::encode", &rules),
+            Some(SymbolType::User)
+        );
+        assert_eq!(classify_with_rules("std::sort", &rules), None);
+    }
+
+    #[test]
+    fn test_format_heat_pct() {
+        assert_eq!(
+            format_heat_pct(" 71.80", 71.80, true),
+            format!("{} 71.80{}", RED, RESET)
+        );
+        assert_eq!(
+            format_heat_pct(" 38.29", 38.29, true),
+            format!("{} 38.29{}", YELLOW, RESET)
+        );
+        assert_eq!(format_heat_pct("  7.47", 7.47, true), "  7.47");
+        assert_eq!(format_heat_pct(" 71.80", 71.80, false), " 71.80");
+    }
+
     #[test]
     fn test_format_colored_symbol() {
         // With colors enabled
@@ -498,4 +1328,87 @@ mod tests {
             "DCT4DBlock::DCT4DBlock"
         );
     }
+
+    #[test]
+    fn test_closure_symbol_transform() {
+        let strip = |s: &str| s.replace("Acme::", "");
+        assert_eq!(strip.transform("Acme::Codec::encode"), "Codec::encode");
+    }
+
+    #[test]
+    fn test_mapped_symbols_fallback() {
+        let mut map = HashMap::new();
+        map.insert("a".to_string(), "b".to_string());
+        let mapped = MappedSymbols(map);
+        assert_eq!(mapped.transform("a"), "b");
+        assert_eq!(mapped.transform("c"), "c");
+    }
+
+    #[test]
+    fn test_run_symbol_filter() {
+        let symbols = vec!["Acme::encode".to_string(), "main".to_string()];
+        let mapped = run_symbol_filter(Path::new("rev"), &symbols).unwrap();
+        assert_eq!(mapped.transform("Acme::encode"), "edocne::emcA");
+        assert_eq!(mapped.transform("main"), "niam");
+    }
+
+    #[test]
+    fn test_run_symbol_filter_missing_exe() {
+        let result = run_symbol_filter(Path::new("/nonexistent/filter"), &["a".to_string()]);
+        assert!(matches!(result, Err(PperfError::SymbolFilterFailed(_))));
+    }
+
+    #[test]
+    fn test_run_symbol_filter_line_count_mismatch() {
+        let result = run_symbol_filter(Path::new("true"), &["a".to_string()]);
+        assert!(matches!(result, Err(PperfError::SymbolFilterFailed(_))));
+    }
+
+    #[test]
+    fn test_symbol_interner_shares_storage() {
+        let mut interner = SymbolInterner::default();
+        let a = interner.intern("DCT4DBlock::DCT4DBlock");
+        let b = interner.intern("DCT4DBlock::DCT4DBlock");
+        let c = interner.intern("main");
+        assert_eq!(a, b);
+        assert_eq!(a.as_str().as_ptr(), b.as_str().as_ptr());
+        assert_ne!(a, c);
+        assert_eq!(interner.len(), 2);
+    }
+
+    #[test]
+    fn test_symbol_compares_with_str() {
+        let symbol = Symbol::from("main");
+        assert_eq!(symbol, "main");
+        assert_eq!("main", symbol);
+        assert_eq!(symbol.to_string(), "main");
+        assert!(symbol.contains("ai"));
+    }
+
+    #[test]
+    fn test_fill_link_template() {
+        assert_eq!(
+            fill_link_template(FILE_LINK_TEMPLATE, "/src/DCT4DBlock.cpp:42"),
+            "file:///src/DCT4DBlock.cpp"
+        );
+        assert_eq!(
+            fill_link_template(
+                "https://github.com/acme/codec/blob/main{file}#L{line}",
+                "/src/DCT4DBlock.cpp:42"
+            ),
+            "https://github.com/acme/codec/blob/main/src/DCT4DBlock.cpp#L42"
+        );
+        assert_eq!(
+            fill_link_template("{file}#L{line}", "/src/DCT4DBlock.cpp"),
+            "/src/DCT4DBlock.cpp#L"
+        );
+    }
+
+    #[test]
+    fn test_format_hyperlink() {
+        assert_eq!(
+            format_hyperlink("main", "file:///src/main.cpp"),
+            "\x1b]8;;file:///src/main.cpp\x1b\\main\x1b]8;;\x1b\\"
+        );
+    }
 }
@@ -0,0 +1,275 @@
+//! Synthetic perf report generation.
+//!
+//! Produces structurally valid `perf report --stdio` text (header, top-level
+//! entries and call trees) from a seed, so bug reports can ship reproducible
+//! inputs instead of proprietary profiles.
+
+/// Parameters for a synthetic report.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct SynthConfig {
+    /// Number of top-level entries
+    pub entries: usize,
+    /// Maximum call tree depth below each entry
+    pub depth: usize,
+    /// Seed for the deterministic generator
+    pub seed: u64,
+}
+
+/// Xorshift64 generator: tiny, deterministic, and good enough for fixture data.
+struct Rng(u64);
+
+impl Rng {
+    fn new(seed: u64) -> Self {
+        // Xorshift must never be seeded with zero
+        Rng(seed.max(1))
+    }
+
+    fn next_u64(&mut self) -> u64 {
+        let mut x = self.0;
+        x ^= x << 13;
+        x ^= x >> 7;
+        x ^= x << 17;
+        self.0 = x;
+        x
+    }
+
+    /// Uniform value in [low, high)
+    fn range(&mut self, low: f64, high: f64) -> f64 {
+        let unit = (self.next_u64() >> 11) as f64 / (1u64 << 53) as f64;
+        low + unit * (high - low)
+    }
+
+    fn below(&mut self, n: usize) -> usize {
+        (self.next_u64() % n as u64) as usize
+    }
+}
+
+fn function_name(index: usize) -> String {
+    format!("synth::Stage{}::process_{}(int, double)", index % 7, index)
+}
+
+/// Two-decimal rounding, so the written values are exactly what the parser reads back.
+fn round2(value: f64) -> f64 {
+    (value * 100.0).round() / 100.0
+}
+
+/// Write the children of a call tree node, recursing until `remaining_depth` is exhausted.
+/// Child percentages never exceed the parent's and their sum stays below it.
+fn write_children(
+    output: &mut String,
+    rng: &mut Rng,
+    prefix: &str,
+    parent_index: usize,
+    parent_pct: f64,
+    function_count: usize,
+    remaining_depth: usize,
+) {
+    if remaining_depth == 0 || parent_index + 1 >= function_count || parent_pct < 0.02 {
+        return;
+    }
+
+    let candidates = function_count - parent_index - 1;
+    let child_count = (1 + rng.below(3)).min(candidates);
+    let mut budget = parent_pct * rng.range(0.5, 0.95);
+
+    // Choose distinct callees first so the last sibling gets the ` --` marker
+    let mut children: Vec<(usize, f64)> = Vec::new();
+    for i in 0..child_count {
+        let pct = round2(if i + 1 == child_count {
+            budget
+        } else {
+            budget * rng.range(0.3, 0.7)
+        });
+        if pct < 0.01 {
+            break;
+        }
+        budget -= pct;
+
+        let mut callee = parent_index + 1 + rng.below(candidates);
+        while children.iter().any(|&(c, _)| c == callee) {
+            callee = parent_index + 1 + (callee - parent_index) % candidates;
+        }
+        children.push((callee, pct));
+    }
+
+    for (i, &(callee, pct)) in children.iter().enumerate() {
+        let is_last = i + 1 == children.len();
+
+        output.push_str(&format!("{}|          \n", prefix));
+        let marker = if is_last { " --" } else { "|--" };
+        output.push_str(&format!(
+            "{}{}{:.2}%--{}\n",
+            prefix,
+            marker,
+            pct,
+            function_name(callee)
+        ));
+
+        let child_prefix = if is_last {
+            format!("{}           ", prefix)
+        } else {
+            format!("{}|          ", prefix)
+        };
+        write_children(
+            output,
+            rng,
+            &child_prefix,
+            callee,
+            pct,
+            function_count,
+            remaining_depth - 1,
+        );
+    }
+}
+
+/// Generate a synthetic perf report.
+pub fn generate_report(config: &SynthConfig) -> String {
+    let mut rng = Rng::new(config.seed);
+    let mut output = String::new();
+
+    output.push_str(
+        "# To display the perf.data header info, please use --header/--header-only options.\n",
+    );
+    output.push_str("#\n#\n# Total Lost Samples: 0\n#\n");
+    output.push_str(&format!(
+        "# Samples: {}K of event 'cycles'\n",
+        config.entries.max(1)
+    ));
+    output.push_str(&format!(
+        "# Event count (approx.): {}\n#\n",
+        1_000_000 * config.entries.max(1) as u64
+    ));
+    output.push_str(
+        "# Children      Self  Command          Shared Object        Symbol\n\
+         # ........  ........  ...............  ...................  ......\n#\n",
+    );
+
+    let mut children_pct = rng.range(85.0, 99.0);
+    for index in 0..config.entries {
+        let children = round2(children_pct);
+        let self_pct = round2(children * rng.range(0.0, 0.3));
+
+        output.push_str(&format!(
+            "{:>9.2}%{:>9.2}%  {:<15}  {:<19}  [.] {}\n",
+            children,
+            self_pct,
+            "synth",
+            "synth",
+            function_name(index)
+        ));
+
+        if config.depth > 0 {
+            output.push_str("            |\n");
+            output.push_str(&format!("            ---{}\n", function_name(index)));
+            write_children(
+                &mut output,
+                &mut rng,
+                "               ",
+                index,
+                children,
+                config.entries,
+                config.depth,
+            );
+        }
+        output.push('\n');
+
+        children_pct *= rng.range(0.6, 0.95);
+    }
+
+    output.push_str("\n#\n# (Generated by pperf synth)\n#\n");
+    output
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::hierarchy::{CallTreeNode, parse_file_call_trees};
+    use crate::parser::{parse_line, parse_metadata};
+
+    fn tree_depth(node: &CallTreeNode) -> usize {
+        1 + node.children.iter().map(tree_depth).max().unwrap_or(0)
+    }
+
+    fn check_child_pcts(node: &CallTreeNode) {
+        let sum: f64 = node.children.iter().map(|c| c.relative_pct).sum();
+        assert!(
+            sum <= node.relative_pct + 0.01,
+            "children of {} sum to {} > {}",
+            node.symbol,
+            sum,
+            node.relative_pct
+        );
+        node.children.iter().for_each(check_child_pcts);
+    }
+
+    #[test]
+    fn test_generate_report_is_deterministic() {
+        let config = SynthConfig {
+            entries: 10,
+            depth: 3,
+            seed: 7,
+        };
+        assert_eq!(generate_report(&config), generate_report(&config));
+
+        let other = SynthConfig { seed: 8, ..config };
+        assert_ne!(generate_report(&config), generate_report(&other));
+    }
+
+    #[test]
+    fn test_generate_report_round_trips_entries() {
+        let config = SynthConfig {
+            entries: 25,
+            depth: 4,
+            seed: 42,
+        };
+        let content = generate_report(&config);
+
+        let entries: Vec<_> = content.lines().filter_map(parse_line).collect();
+        assert_eq!(entries.len(), 25);
+        for pair in entries.windows(2) {
+            assert!(pair[0].children_pct >= pair[1].children_pct);
+        }
+        for entry in &entries {
+            assert!(entry.self_pct <= entry.children_pct);
+        }
+
+        let metadata = parse_metadata(&content);
+        assert_eq!(metadata.samples, Some(25_000));
+        assert_eq!(metadata.event.as_deref(), Some("cycles"));
+    }
+
+    #[test]
+    fn test_generate_report_round_trips_call_trees() {
+        let config = SynthConfig {
+            entries: 25,
+            depth: 4,
+            seed: 42,
+        };
+        let content = generate_report(&config);
+        let entries: Vec<_> = content.lines().filter_map(parse_line).collect();
+        let trees = parse_file_call_trees(&content, &entries);
+
+        assert_eq!(trees.len(), 25);
+        for (entry, roots) in &trees {
+            assert_eq!(roots.len(), 1, "{} should have one root", entry.symbol);
+            let root = &roots[0];
+            assert_eq!(root.symbol, entry.symbol);
+            // Root line plus at most `depth` levels of callees
+            assert!(tree_depth(root) <= config.depth + 1);
+            check_child_pcts(root);
+        }
+        assert!(trees.iter().any(|(_, roots)| tree_depth(&roots[0]) > 2));
+    }
+
+    #[test]
+    fn test_generate_report_depth_zero_has_no_trees() {
+        let config = SynthConfig {
+            entries: 5,
+            depth: 0,
+            seed: 1,
+        };
+        let content = generate_report(&config);
+        assert!(!content.contains("---"));
+        assert_eq!(content.lines().filter_map(parse_line).count(), 5);
+    }
+}
@@ -0,0 +1,103 @@
+//! Trends over an ordered series of reports (`pperf trend`).
+//!
+//! Symbols are matched exactly, as in `pperf set`; each row carries the
+//! symbol's Children% per report, the least-squares slope over the reports
+//! that contain it, and whether it grew from every report to the next.
+
+use crate::parser::PerfEntry;
+use crate::setops::{SetMode, symbol_set_rows};
+use crate::symbol::Symbol;
+
+/// One symbol across the series.
+#[derive(Debug, Clone, PartialEq)]
+pub struct TrendRow {
+    pub symbol: Symbol,
+    /// Children% per report, in series order; None where the symbol is absent
+    pub values: Vec<Option<f64>>,
+    /// Least-squares slope in Children% points per report (0.0 with fewer than two values)
+    pub slope: f64,
+    /// Present in every report and strictly higher in each than in the one before
+    pub growing: bool,
+}
+
+/// Trend of every symbol in `reports` (oldest first), steepest growth first.
+/// A symbol listed more than once in a report uses its first line.
+pub fn trend_rows(reports: &[Vec<PerfEntry>]) -> Vec<TrendRow> {
+    let mut rows: Vec<TrendRow> = symbol_set_rows(reports, SetMode::Union)
+        .into_iter()
+        .map(|row| TrendRow {
+            slope: slope(&row.children),
+            growing: growing(&row.children),
+            symbol: row.symbol,
+            values: row.children,
+        })
+        .collect();
+    rows.sort_by(|a, b| b.slope.total_cmp(&a.slope));
+    rows
+}
+
+/// Least-squares slope of the present values against their report index.
+fn slope(values: &[Option<f64>]) -> f64 {
+    let points: Vec<(f64, f64)> = values
+        .iter()
+        .enumerate()
+        .filter_map(|(i, v)| v.map(|v| (i as f64, v)))
+        .collect();
+    if points.len() < 2 {
+        return 0.0;
+    }
+    let n = points.len() as f64;
+    let mean_x = points.iter().map(|p| p.0).sum::<f64>() / n;
+    let mean_y = points.iter().map(|p| p.1).sum::<f64>() / n;
+    let covariance: f64 = points.iter().map(|p| (p.0 - mean_x) * (p.1 - mean_y)).sum();
+    let variance: f64 = points.iter().map(|p| (p.0 - mean_x).powi(2)).sum();
+    covariance / variance
+}
+
+fn growing(values: &[Option<f64>]) -> bool {
+    values.len() > 1
+        && values.iter().all(Option::is_some)
+        && values.windows(2).all(|pair| pair[1] > pair[0])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry(symbol: &str, children: f64) -> PerfEntry {
+        PerfEntry {
+            children_pct: children,
+            self_pct: children,
+            symbol: symbol.into(),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_trend_rows() {
+        let reports = vec![
+            vec![entry("creep", 1.0), entry("flat", 5.0), entry("late", 9.0)],
+            vec![entry("creep", 2.0), entry("flat", 5.0)],
+            vec![entry("creep", 4.0), entry("flat", 5.0), entry("late", 3.0)],
+        ];
+        let rows = trend_rows(&reports);
+
+        let symbols: Vec<&str> = rows.iter().map(|r| r.symbol.as_str()).collect();
+        assert_eq!(symbols, vec!["creep", "flat", "late"]);
+        assert!((rows[0].slope - 1.5).abs() < 1e-9);
+        assert!(rows[0].growing);
+        assert_eq!(rows[1].slope, 0.0);
+        assert!(!rows[1].growing);
+        // Absent from the middle report: sloped over the two it appears in
+        assert!((rows[2].slope + 3.0).abs() < 1e-9);
+        assert_eq!(rows[2].values, vec![Some(9.0), None, Some(3.0)]);
+    }
+
+    #[test]
+    fn test_growing_needs_every_report() {
+        assert!(!growing(&[Some(1.0), None, Some(3.0)]));
+        assert!(!growing(&[Some(1.0), Some(1.0)]));
+        assert!(!growing(&[Some(1.0)]));
+        assert!(growing(&[Some(1.0), Some(1.5)]));
+    }
+}
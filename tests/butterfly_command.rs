@@ -0,0 +1,103 @@
+use std::process::Command;
+
+#[test]
+fn test_butterfly_command() {
+    let output = Command::new("cargo")
+        .args([
+            "run",
+            "--",
+            "butterfly",
+            "-t",
+            "DCT4DBlock",
+            "--no-color",
+            "perf-report.txt",
+        ])
+        .output()
+        .expect("Failed to execute command");
+
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(output.status.success(), "Command failed: {}", stderr);
+
+    let lines: Vec<&str> = stdout.lines().collect();
+    assert_eq!(lines[0], "   Total%   Share%  Caller");
+    assert_eq!(
+        lines[1],
+        "    10.42    27.20  TransformPartition::rd_optimize_transform"
+    );
+    assert!(stdout.contains("\n-> DCT4DBlock::DCT4DBlock (38.29% children, 0.00% self)\n"));
+    assert!(stdout.contains("\n     7.70    20.12  Transformed4DBlock::do_4d_transform\n"));
+}
+
+#[test]
+fn test_butterfly_command_no_match() {
+    let output = Command::new("cargo")
+        .args([
+            "run",
+            "--",
+            "butterfly",
+            "-t",
+            "NoSuchSymbol",
+            "perf-report.txt",
+        ])
+        .output()
+        .expect("Failed to execute command");
+
+    assert_eq!(output.status.code(), Some(4), "Expected exit code 4");
+}
+
+#[test]
+fn test_butterfly_command_tree_order() {
+    let run = |extra: &[&str]| {
+        let mut args = vec!["run", "--", "butterfly", "-t", "get_mSubbandLF"];
+        args.extend_from_slice(extra);
+        args.extend(["--no-color", "perf-report.txt"]);
+        Command::new("cargo")
+            .args(&args)
+            .output()
+            .expect("Failed to execute command")
+    };
+
+    // Self% is over half of Children%, so the tree is guessed to list callers
+    let output = run(&[]);
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(output.status.success(), "Command failed: {}", stderr);
+    assert!(stderr.contains("Warning: call-graph order not stated"));
+    assert!(stdout.contains("  (no callees in call graph)"));
+
+    let output = run(&["--assume-callee-trees"]);
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(output.status.success(), "Command failed: {}", stderr);
+    assert!(!stderr.contains("Warning:"));
+    assert!(stdout.ends_with("     0.10     0.46  Block4D::get_linear_position\n     0.06     0.28  LightfieldCoordinate::LightfieldCoordinate\n"));
+}
+
+#[test]
+fn test_butterfly_command_symbol_filter() {
+    let output = Command::new("cargo")
+        .args([
+            "run",
+            "--",
+            "--symbol-filter",
+            "tests/fixtures/strip-namespace.sh",
+            "butterfly",
+            "-t",
+            "DCT4DBlock",
+            "--no-color",
+            "perf-report.txt",
+        ])
+        .output()
+        .expect("Failed to execute command");
+
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(output.status.success(), "Command failed: {}", stderr);
+    // Callers come from the other entries' trees, rewritten like the target's own
+    assert_eq!(
+        stdout.lines().nth(1),
+        Some("    10.42    27.20  rd_optimize_transform")
+    );
+    assert!(!stdout.contains("TransformPartition::"));
+}
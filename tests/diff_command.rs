@@ -0,0 +1,133 @@
+use std::process::Command;
+
+const DE_LOCALE: &str = "tests/fixtures/perf-report-de-locale.txt";
+const DISTURBED: &str = "tests/fixtures/perf-report-de-locale-disturbed.txt";
+const EXTRA: &str = "tests/fixtures/perf-report-de-locale-extra.txt";
+
+#[test]
+fn test_diff_command_entries() {
+    let output = Command::new("cargo")
+        .args(["run", "--", "diff", "--no-color", DE_LOCALE, EXTRA])
+        .output()
+        .expect("Failed to execute command");
+
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(output.status.success(), "Command failed: {}", stderr);
+
+    let lines: Vec<&str> = stdout.lines().collect();
+    assert_eq!(lines[0], format!("Old: {}", DE_LOCALE));
+    assert_eq!(lines[3], "     Old       New     Delta  Function");
+    assert_eq!(
+        lines[4],
+        "       -      3.00     +3.00  __memmove_avx_unaligned_erms"
+    );
+    assert_eq!(
+        lines[5],
+        "   71.80     69.80     -2.00  TransformPartition::rd_optimize_transform"
+    );
+    // inner_product moved by 0.00 points, under the threshold
+    assert_eq!(lines.len(), 6);
+}
+
+#[test]
+fn test_diff_command_hierarchy() {
+    let output = Command::new("cargo")
+        .args([
+            "run",
+            "--",
+            "diff",
+            "-H",
+            "-t",
+            "rd_optimize_transform",
+            "-t",
+            "DCT4DBlock",
+            "--no-color",
+            DE_LOCALE,
+            DISTURBED,
+        ])
+        .output()
+        .expect("Failed to execute command");
+
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(output.status.success(), "Command failed: {}", stderr);
+
+    assert_eq!(
+        stdout,
+        "Relations (absolute %, changes above 0.50 points):\n  \
+         changed      TransformPartition::rd_optimize_transform → DCT4DBlock::DCT4DBlock: 12.37% → 9.49%\n"
+    );
+}
+
+#[test]
+fn test_diff_command_hierarchy_requires_targets() {
+    let output = Command::new("cargo")
+        .args(["run", "--", "diff", "-H", DE_LOCALE, DISTURBED])
+        .output()
+        .expect("Failed to execute command");
+
+    assert_eq!(output.status.code(), Some(3));
+}
+
+#[test]
+fn test_diff_command_fail_above() {
+    let run = |old: &str, new: &str| {
+        Command::new("cargo")
+            .args([
+                "run",
+                "--",
+                "diff",
+                "--no-color",
+                "--fail-above",
+                "2",
+                old,
+                new,
+            ])
+            .output()
+            .expect("Failed to execute command")
+    };
+
+    // memmove is new in EXTRA, up 3 points from nothing
+    let output = run(DE_LOCALE, EXTRA);
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert_eq!(output.status.code(), Some(6));
+    let summary: Vec<&str> = stdout.lines().rev().take(2).collect();
+    assert_eq!(summary[0], "result\tFAIL\t1\t2.00");
+    assert_eq!(
+        summary[1],
+        "regression\t+3.00\t-\t3.00\t__memmove_avx_unaligned_erms"
+    );
+
+    // rd_optimize_transform grows by exactly 2.00, which is allowed
+    let output = run(EXTRA, DE_LOCALE);
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(output.status.success());
+    assert_eq!(stdout.lines().last(), Some("result\tPASS\t0\t2.00"));
+}
+
+#[test]
+fn test_diff_command_top_diff() {
+    let output = Command::new("cargo")
+        .args([
+            "run",
+            "--",
+            "diff",
+            "--no-color",
+            "--top-diff",
+            "1",
+            DE_LOCALE,
+            DISTURBED,
+        ])
+        .output()
+        .expect("Failed to execute command");
+
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(output.status.success());
+    // Everything dropped, so only the largest drop is left
+    let rows: Vec<&str> = stdout.lines().skip(4).collect();
+    assert_eq!(
+        rows,
+        vec!["   71.80     55.10    -16.70  TransformPartition::rd_optimize_transform"]
+    );
+}
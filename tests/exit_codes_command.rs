@@ -0,0 +1,59 @@
+use std::process::Command;
+
+#[test]
+fn test_exit_codes_command_lists_table() {
+    let output = Command::new("cargo")
+        .args(["run", "--", "exit-codes"])
+        .output()
+        .expect("Failed to execute command");
+
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(output.status.success());
+
+    let rows: Vec<Vec<&str>> = stdout.lines().map(|l| l.split('\t').collect()).collect();
+    assert!(
+        rows.iter().all(|r| r.len() == 3),
+        "Rows must be code\\tname\\tdescription"
+    );
+    assert!(rows.contains(&vec!["0", "Success", "Analysis completed"]));
+    assert!(rows.iter().any(|r| r[0] == "1" && r[1] == "FileNotFound"));
+    assert!(rows.iter().any(|r| r[0] == "2" && r[1] == "InvalidFormat"));
+    assert!(rows.iter().any(|r| r[0] == "3" && r[1] == "Usage"));
+    assert!(rows.iter().any(|r| r[0] == "4" && r[1] == "NoMatches"));
+}
+
+#[test]
+fn test_exit_codes_match_actual_exit_status() {
+    let listing = Command::new("cargo")
+        .args(["run", "--", "exit-codes"])
+        .output()
+        .expect("Failed to execute command");
+    let stdout = String::from_utf8_lossy(&listing.stdout);
+    let code_for = |name: &str| -> i32 {
+        stdout
+            .lines()
+            .map(|l| l.split('\t').collect::<Vec<_>>())
+            .find(|r| r[1] == name)
+            .map(|r| r[0].parse().unwrap())
+            .unwrap()
+    };
+
+    let missing = Command::new("cargo")
+        .args(["run", "--", "top", "nonexistent.txt"])
+        .output()
+        .expect("Failed to execute command");
+    assert_eq!(missing.status.code(), Some(code_for("FileNotFound")));
+
+    let no_match = Command::new("cargo")
+        .args([
+            "run",
+            "--",
+            "top",
+            "-t",
+            "NoSuchFunction",
+            "perf-report.txt",
+        ])
+        .output()
+        .expect("Failed to execute command");
+    assert_eq!(no_match.status.code(), Some(code_for("NoMatches")));
+}
@@ -0,0 +1,3 @@
+#!/bin/sh
+# Symbol filter used by the --symbol-filter tests: drops one namespace
+exec sed "s/TransformPartition:://g"
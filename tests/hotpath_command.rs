@@ -0,0 +1,44 @@
+use std::process::Command;
+
+#[test]
+fn test_hotpath_command() {
+    let output = Command::new("cargo")
+        .args(["run", "--", "hotpath", "--no-color", "perf-report.txt"])
+        .output()
+        .expect("Failed to execute command");
+
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(output.status.success(), "Command failed: {}", stderr);
+
+    let lines: Vec<&str> = stdout.lines().collect();
+    assert!(lines[0].starts_with("Hot path ("), "{}", stdout);
+    assert_eq!(lines[1], "   Total% Relative%  Function");
+    // The chain whose leaf holds the most runtime, not the heaviest child at each step
+    assert_eq!(lines[2], "    38.29    100.00  DCT4DBlock::DCT4DBlock");
+    assert_eq!(lines[3], "     5.43     14.17  0x7d4c47223efe");
+    assert_eq!(lines.len(), 4, "{}", stdout);
+}
+
+#[test]
+fn test_hotpath_command_callee_order() {
+    let output = Command::new("cargo")
+        .args([
+            "run",
+            "--",
+            "hotpath",
+            "tests/fixtures/perf-report-callee-order.txt",
+        ])
+        .output()
+        .expect("Failed to execute command");
+
+    // Every tree lists callers, so there is no chain to follow down
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert_eq!(output.status.code(), Some(2));
+    assert!(
+        stderr.contains("Call graph is callee-ordered"),
+        "{}",
+        stderr
+    );
+    assert!(!stderr.contains("--hierarchy"), "{}", stderr);
+}
@@ -0,0 +1,80 @@
+use std::process::Command;
+
+#[test]
+fn test_paths_command() {
+    let output = Command::new("cargo")
+        .args([
+            "run",
+            "--",
+            "paths",
+            "--from",
+            "rd_optimize_transform",
+            "--to",
+            "DCT4DBlock",
+            "--no-color",
+            "perf-report.txt",
+        ])
+        .output()
+        .expect("Failed to execute command");
+
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(output.status.success(), "Command failed: {}", stderr);
+
+    let lines: Vec<&str> = stdout.lines().collect();
+    assert_eq!(
+        lines[0],
+        "Paths from TransformPartition::rd_optimize_transform (71.80% children) to DCT4DBlock:"
+    );
+    assert_eq!(
+        lines[2],
+        "     8.53     6.13  TransformPartition::rd_optimize_transform 71.78% × DCT4DBlock::DCT4DBlock 11.89%"
+    );
+    assert!(lines[3].ends_with(" × DCT4DBlock::DCT4DBlock 9.17%"));
+    assert_eq!(lines.last(), Some(&"    10.42     7.48  Total (3 paths)"));
+}
+
+#[test]
+fn test_paths_command_unknown_caller() {
+    let output = Command::new("cargo")
+        .args([
+            "run",
+            "--",
+            "paths",
+            "--from",
+            "NoSuchSymbol",
+            "--to",
+            "DCT4DBlock",
+            "perf-report.txt",
+        ])
+        .output()
+        .expect("Failed to execute command");
+
+    assert_eq!(output.status.code(), Some(4), "Expected exit code 4");
+}
+
+#[test]
+fn test_paths_command_callee_order() {
+    let output = Command::new("cargo")
+        .args([
+            "run",
+            "--",
+            "paths",
+            "--from",
+            "main",
+            "--to",
+            "DCT4DBlock",
+            "tests/fixtures/perf-report-callee-order.txt",
+        ])
+        .output()
+        .expect("Failed to execute command");
+
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert_eq!(output.status.code(), Some(2));
+    assert!(
+        stderr.contains("Call graph is callee-ordered"),
+        "{}",
+        stderr
+    );
+    assert!(stderr.contains("-g caller"), "{}", stderr);
+}
@@ -0,0 +1,105 @@
+use std::process::Command;
+
+const DE_LOCALE: &str = "tests/fixtures/perf-report-de-locale.txt";
+const SORT_SYM: &str = "tests/fixtures/perf-report-sort-sym-dso-srcline.txt";
+
+#[test]
+fn test_set_command_intersect() {
+    let output = Command::new("cargo")
+        .args([
+            "run",
+            "--",
+            "set",
+            "--intersect",
+            "--no-color",
+            "perf-report.txt",
+            DE_LOCALE,
+        ])
+        .output()
+        .expect("Failed to execute command");
+
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(output.status.success(), "Command failed: {}", stderr);
+
+    assert!(stdout.starts_with("[1] perf-report.txt\n"));
+    assert!(stdout.contains("   71.80     71.80  TransformPartition::rd_optimize_transform"));
+    assert_eq!(stdout.lines().filter(|l| l.contains("  -  ")).count(), 0);
+}
+
+#[test]
+fn test_set_command_only_in() {
+    let output = Command::new("cargo")
+        .args([
+            "run",
+            "--",
+            "set",
+            "--only-in",
+            DE_LOCALE,
+            SORT_SYM,
+            "--no-color",
+        ])
+        .output()
+        .expect("Failed to execute command");
+
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(output.status.success(), "Command failed: {}", stderr);
+    assert!(stdout.contains(&format!("[1] {}", DE_LOCALE)));
+    for line in stdout.lines().skip(5) {
+        assert!(
+            line.contains("-  "),
+            "Row should be absent from [2]: {}",
+            line
+        );
+    }
+}
+
+#[test]
+fn test_set_command_requires_mode() {
+    let output = Command::new("cargo")
+        .args(["run", "--", "set", "perf-report.txt", DE_LOCALE])
+        .output()
+        .expect("Failed to execute command");
+
+    assert_eq!(output.status.code(), Some(3), "Expected exit code 3");
+}
+
+#[test]
+fn test_set_command_empty_result() {
+    // Every symbol of the de-locale fixture also appears in the full report
+    let output = Command::new("cargo")
+        .args([
+            "run",
+            "--",
+            "set",
+            "--only-in",
+            DE_LOCALE,
+            "perf-report.txt",
+        ])
+        .output()
+        .expect("Failed to execute command");
+
+    assert_eq!(output.status.code(), Some(4), "Expected exit code 4");
+}
+
+#[test]
+fn test_set_command_no_children_title() {
+    let output = Command::new("cargo")
+        .args([
+            "run",
+            "--",
+            "set",
+            "--union",
+            "--no-color",
+            "tests/fixtures/perf-report-no-children.txt",
+            "perf-report.txt",
+        ])
+        .output()
+        .expect("Failed to execute command");
+
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(output.status.success());
+    // `--no-children` reports list their Overhead column
+    assert_eq!(stdout.lines().nth(3), Some("Overhead% per report:"));
+}
@@ -0,0 +1,47 @@
+use std::process::Command;
+
+#[test]
+fn test_stats_command() {
+    let output = Command::new("cargo")
+        .args([
+            "run",
+            "--",
+            "stats",
+            "tests/fixtures/perf-report-no-children.txt",
+        ])
+        .output()
+        .expect("Failed to execute command");
+
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(output.status.success(), "Command failed: {}", stderr);
+
+    let lines: Vec<&str> = stdout.lines().collect();
+    assert_eq!(lines[0], "Entries: 8 (Self% total 55.07%)");
+    assert_eq!(lines[3], ">Children      >Self  Threshold");
+    assert_eq!(lines[4], "        2          2  10%");
+    // Five of the eight entries hold 80% of the listed self time
+    assert!(stdout.contains("        5      46.93  80%\n"), "{}", stdout);
+    assert!(stdout.contains("        6      26.83  ████████████████████  1-10%\n"));
+}
+
+#[test]
+fn test_stats_command_quiet_rows() {
+    let output = Command::new("cargo")
+        .args([
+            "run",
+            "--",
+            "--quiet",
+            "stats",
+            "tests/fixtures/perf-report-no-children.txt",
+        ])
+        .output()
+        .expect("Failed to execute command");
+
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(output.status.success());
+    // 3 thresholds, 4 coverage levels and 5 histogram buckets, nothing between them
+    let rows: Vec<&str> = stdout.lines().collect();
+    assert_eq!(rows.len(), 12, "{}", stdout);
+    assert_eq!(rows[0], "        2          2  10%");
+}
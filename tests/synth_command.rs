@@ -0,0 +1,85 @@
+use std::process::Command;
+
+#[test]
+fn test_synth_command_stdout_is_parseable() {
+    let output = Command::new("cargo")
+        .args(["run", "--", "synth", "--entries", "5", "--depth", "2"])
+        .output()
+        .expect("Failed to execute command");
+
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let stderr = String::from_utf8_lossy(&output.stderr);
+
+    assert!(output.status.success(), "Command failed: {}", stderr);
+    assert!(stdout.contains("# Samples: 5K of event 'cycles'"));
+    assert!(stdout.contains("%--synth::Stage"));
+}
+
+#[test]
+fn test_synth_command_output_file_round_trip() {
+    let path = std::env::temp_dir().join("pperf-synth-round-trip.txt");
+    let path_str = path.to_str().unwrap();
+
+    let output = Command::new("cargo")
+        .args([
+            "run",
+            "--",
+            "synth",
+            "--entries",
+            "12",
+            "--depth",
+            "3",
+            "--seed",
+            "9",
+            "-o",
+            path_str,
+        ])
+        .output()
+        .expect("Failed to execute command");
+    assert!(output.status.success(), "synth -o should succeed");
+
+    let top = Command::new("cargo")
+        .args(["run", "--", "top", "-n", "50", "--no-color", path_str])
+        .output()
+        .expect("Failed to execute command");
+    let stdout = String::from_utf8_lossy(&top.stdout);
+
+    assert!(top.status.success(), "Generated report should parse");
+    assert_eq!(
+        stdout.lines().skip(1).count(),
+        12,
+        "All entries should parse"
+    );
+
+    let hierarchy = Command::new("cargo")
+        .args([
+            "run",
+            "--",
+            "top",
+            "-H",
+            "-t",
+            "process_0",
+            "-t",
+            "Stage",
+            "--no-color",
+            path_str,
+        ])
+        .output()
+        .expect("Failed to execute command");
+    assert!(
+        hierarchy.status.success(),
+        "Generated report should support --hierarchy"
+    );
+
+    let _ = std::fs::remove_file(&path);
+}
+
+#[test]
+fn test_synth_command_zero_entries_error() {
+    let output = Command::new("cargo")
+        .args(["run", "--", "synth", "--entries", "0"])
+        .output()
+        .expect("Failed to execute command");
+
+    assert_eq!(output.status.code(), Some(3), "Exit code should be 3");
+}
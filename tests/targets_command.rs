@@ -0,0 +1,94 @@
+use std::process::Command;
+
+#[test]
+fn test_targets_generate_round_trip() {
+    let path = std::env::temp_dir().join("pperf-targets-generate.txt");
+    let generate = Command::new("cargo")
+        .args([
+            "run", "--", "targets", "generate", "-n", "3", "-t", "DCT", "-o",
+        ])
+        .arg(&path)
+        .arg("perf-report.txt")
+        .output()
+        .expect("Failed to execute command");
+    let stderr = String::from_utf8_lossy(&generate.stderr);
+    assert!(generate.status.success(), "Command failed: {}", stderr);
+
+    let content = std::fs::read_to_string(&path).unwrap();
+    assert!(content.starts_with("# Top 2 by Children% from perf-report.txt\n"));
+    assert!(content.contains("\nDCT4DBlock::DCT4DBlock(Block4D const&, double)\n"));
+
+    let top = Command::new("cargo")
+        .args(["run", "--", "top", "--no-color", "--target-file"])
+        .arg(&path)
+        .arg("perf-report.txt")
+        .output()
+        .expect("Failed to execute command");
+    let stdout = String::from_utf8_lossy(&top.stdout);
+    assert!(top.status.success());
+    assert!(
+        stdout.contains("   38.29    0.00  DCT4DBlock::DCT4DBlock"),
+        "{}",
+        stdout
+    );
+    assert_eq!(stdout.lines().count(), 3, "{}", stdout);
+
+    let no_match = Command::new("cargo")
+        .args([
+            "run",
+            "--",
+            "targets",
+            "generate",
+            "-t",
+            "NoSuchSymbol",
+            "perf-report.txt",
+        ])
+        .output()
+        .expect("Failed to execute command");
+    assert_eq!(no_match.status.code(), Some(4), "Expected exit code 4");
+}
+
+#[test]
+fn test_targets_check() {
+    let output = Command::new("cargo")
+        .args([
+            "run",
+            "--",
+            "targets",
+            "check",
+            "--target-file",
+            "tests/fixtures/targets-check.txt",
+            "perf-report.txt",
+        ])
+        .output()
+        .expect("Failed to execute command");
+
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    // Unmatched lines make the check fail like an unmatched -t target
+    assert_eq!(output.status.code(), Some(4), "Expected exit code 4");
+    assert!(stdout.contains(
+        "TransformPartition::rd_optimize_transform(Block4D const&): 1 match\n   71.80    0.00  TransformPartition::rd_optimize_transform(Block4D const&)\n"
+    ));
+    assert!(stdout.contains(
+        "DCT4DBlock::DCT4DBlock(Block4D const&, int): no match; closest: DCT4DBlock::DCT4DBlock(Block4D const&, double)\n"
+    ));
+    assert!(
+        stdout.contains("NoSuch::thing(int): no match\n"),
+        "{}",
+        stdout
+    );
+
+    let clean = Command::new("cargo")
+        .args([
+            "run",
+            "--",
+            "targets",
+            "check",
+            "--target-file",
+            "tests/fixtures/targets.txt",
+            "perf-report.txt",
+        ])
+        .output()
+        .expect("Failed to execute command");
+    assert!(clean.status.success());
+}
@@ -709,3 +709,3099 @@ fn test_top_command_debug_standalone_annotations() {
         "Standalone annotation should show subtraction"
     );
 }
+
+// Integration test for --context restricting hierarchy to one root's tree
+#[test]
+fn test_top_command_context_filter() {
+    let output = Command::new("cargo")
+        .args([
+            "run",
+            "--",
+            "top",
+            "--hierarchy",
+            "--context",
+            "rd_optimize_transform",
+            "-t",
+            "rd_optimize_transform",
+            "-t",
+            "DCT4DBlock",
+            "--no-color",
+            "perf-report.txt",
+        ])
+        .output()
+        .expect("Failed to execute command");
+
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let stderr = String::from_utf8_lossy(&output.stderr);
+
+    assert!(
+        output.status.success(),
+        "--context should succeed: {}",
+        stderr
+    );
+    assert!(
+        stdout.contains("rd_optimize_transform"),
+        "Output should contain the context caller"
+    );
+}
+
+// --context is only meaningful in hierarchy mode
+#[test]
+fn test_top_command_context_requires_hierarchy() {
+    let output = Command::new("cargo")
+        .args([
+            "run",
+            "--",
+            "top",
+            "--context",
+            "rd_optimize",
+            "perf-report.txt",
+        ])
+        .output()
+        .expect("Failed to execute command");
+
+    assert_eq!(
+        output.status.code(),
+        Some(3),
+        "Exit code should be 3 for --context without --hierarchy"
+    );
+}
+
+// Integration test for --show-meta printing report header metadata
+#[test]
+fn test_top_command_show_meta() {
+    let output = Command::new("cargo")
+        .args([
+            "run",
+            "--",
+            "top",
+            "--show-meta",
+            "-n",
+            "3",
+            "perf-report.txt",
+        ])
+        .output()
+        .expect("Failed to execute command");
+
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let stderr = String::from_utf8_lossy(&output.stderr);
+
+    assert!(
+        output.status.success(),
+        "--show-meta should succeed: {}",
+        stderr
+    );
+    assert!(
+        stdout.contains("Samples:      5000"),
+        "Should show sample count"
+    );
+    assert!(
+        stdout.contains("Event:        cycles"),
+        "Should show event name"
+    );
+    assert!(
+        stdout.contains("Event count:  274024838576"),
+        "Should show event count"
+    );
+    assert!(stdout.contains("Children%"), "Table should still follow");
+}
+
+// perf report --no-children reports only have an Overhead column
+#[test]
+fn test_top_command_no_children_report() {
+    let output = Command::new("cargo")
+        .args([
+            "run",
+            "--",
+            "top",
+            "--no-color",
+            "tests/fixtures/perf-report-no-children.txt",
+        ])
+        .output()
+        .expect("Failed to execute command");
+
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let stderr = String::from_utf8_lossy(&output.stderr);
+
+    assert!(output.status.success(), "Command failed: {}", stderr);
+    let lines: Vec<&str> = stdout.lines().collect();
+    assert_eq!(
+        lines[0], "Overhead%  Function",
+        "Header should use Overhead"
+    );
+    assert_eq!(lines.len(), 9, "Header + 8 entries");
+    assert!(lines[2].contains("11.94"));
+    assert!(lines[2].contains("get_mSubbandLF_significance"));
+}
+
+// --hierarchy on a report without call graphs fails early with guidance
+#[test]
+fn test_top_command_hierarchy_without_call_graph() {
+    let output = Command::new("cargo")
+        .args([
+            "run",
+            "--",
+            "top",
+            "-H",
+            "-t",
+            "get_mSubband",
+            "tests/fixtures/perf-report-no-children.txt",
+        ])
+        .output()
+        .expect("Failed to execute command");
+
+    assert_eq!(
+        output.status.code(),
+        Some(2),
+        "Exit code should be 2 for missing call graph"
+    );
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(
+        stderr.contains("no call graph") && stderr.contains("perf record -g"),
+        "Error should explain how to record call graphs: {}",
+        stderr
+    );
+}
+
+// Reports from non-English locales use a comma decimal separator (71,80%)
+#[test]
+fn test_top_command_comma_decimal_locale() {
+    let output = Command::new("cargo")
+        .args([
+            "run",
+            "--",
+            "top",
+            "-H",
+            "-t",
+            "rd_optimize_transform",
+            "-t",
+            "DCT4DBlock",
+            "--no-color",
+            "tests/fixtures/perf-report-de-locale.txt",
+        ])
+        .output()
+        .expect("Failed to execute command");
+
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let stderr = String::from_utf8_lossy(&output.stderr);
+
+    assert!(output.status.success(), "Command failed: {}", stderr);
+    assert!(stdout.contains("   71.80    0.00  TransformPartition::rd_optimize_transform"));
+    assert!(stdout.contains("   17.23    0.00      DCT4DBlock::DCT4DBlock"));
+    assert!(stdout.contains("   25.92    0.00  DCT4DBlock::DCT4DBlock"));
+}
+
+// OR-group targets collapse their members into one logical row
+#[test]
+fn test_top_command_target_group() {
+    let output = Command::new("cargo")
+        .args([
+            "run",
+            "--",
+            "top",
+            "-t",
+            "DCT4DBlock::|inner_product",
+            "--no-color",
+            "perf-report.txt",
+        ])
+        .output()
+        .expect("Failed to execute command");
+
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let stderr = String::from_utf8_lossy(&output.stderr);
+
+    assert!(output.status.success(), "Command failed: {}", stderr);
+    let data_lines: Vec<&str> = stdout.lines().skip(1).collect();
+    assert_eq!(data_lines.len(), 1, "Group should produce a single row");
+    // DCT4DBlock::DCT4DBlock (38.29%) + std::inner_product (7.47%)
+    assert!(data_lines[0].contains("45.76"), "got: {}", data_lines[0]);
+    assert!(data_lines[0].contains("DCT4DBlock::|inner_product"));
+}
+
+// OR-group targets act as a single callee in hierarchy mode
+#[test]
+fn test_top_command_target_group_hierarchy() {
+    let output = Command::new("cargo")
+        .args([
+            "run",
+            "--",
+            "top",
+            "-H",
+            "-t",
+            "rd_optimize_transform",
+            "-t",
+            "DCT4DBlock|inner_product",
+            "--no-color",
+            "perf-report.txt",
+        ])
+        .output()
+        .expect("Failed to execute command");
+
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(output.status.success());
+    assert!(stdout.contains("      DCT4DBlock|inner_product"));
+}
+
+#[test]
+fn test_top_command_malformed_lines_skipped_silently() {
+    let output = Command::new("cargo")
+        .args([
+            "run",
+            "--",
+            "top",
+            "tests/fixtures/perf-report-malformed.txt",
+        ])
+        .output()
+        .expect("Failed to execute command");
+
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(output.status.success(), "Command failed: {}", stderr);
+    assert!(!stderr.contains("Warning:"));
+}
+
+#[test]
+fn test_top_command_verbose_prints_warnings() {
+    let output = Command::new("cargo")
+        .args([
+            "run",
+            "--",
+            "top",
+            "-H",
+            "-t",
+            "rd_optimize_transform",
+            "-t",
+            "DCT4DBlock",
+            "--verbose",
+            "tests/fixtures/perf-report-malformed.txt",
+        ])
+        .output()
+        .expect("Failed to execute command");
+
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(output.status.success(), "Command failed: {}", stderr);
+    assert!(stderr.contains("Warning: line 266: unparseable entry line"));
+    assert!(stderr.contains("Warning: line 28: malformed call-graph percentage"));
+    assert!(stdout.contains("rd_optimize_transform"));
+}
+
+#[test]
+fn test_verbosity_levels_log_parsing_decisions() {
+    let run = |flag: &str| {
+        Command::new("cargo")
+            .args([
+                "run",
+                "--",
+                flag,
+                "top",
+                "-H",
+                "-t",
+                "rd_optimize_transform",
+                "-t",
+                "DCT4DBlock",
+                "tests/fixtures/perf-report-malformed.txt",
+            ])
+            .output()
+            .expect("Failed to execute command")
+    };
+
+    let output = run("-v");
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(output.status.success(), "Command failed: {}", stderr);
+    assert!(
+        stderr.contains(" INFO parser: report parsed entries=3 skipped=1 layout=children-self")
+    );
+    assert!(stderr.contains(" INFO hierarchy: hierarchy built reports=1"));
+    assert!(!stderr.contains("DEBUG"));
+
+    let output = run("-vv");
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(stderr.contains("DEBUG parser: call trees built trees=3"));
+    assert!(stderr.contains("DEBUG hierarchy: relations computed"));
+    assert!(!stderr.contains("TRACE"));
+
+    let output = run("-vvv");
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(
+        stderr.contains(
+            "TRACE parser: line skipped line=28 reason=\"malformed call-graph percentage\""
+        )
+    );
+}
+
+#[test]
+fn test_no_header_prints_only_rows() {
+    let output = Command::new("cargo")
+        .args([
+            "run",
+            "--",
+            "top",
+            "--no-header",
+            "-n",
+            "3",
+            "tests/fixtures/perf-report.txt",
+        ])
+        .output()
+        .expect("Failed to execute command");
+
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(output.status.success());
+    let lines: Vec<&str> = stdout.lines().collect();
+    assert_eq!(lines.len(), 3);
+    assert!(lines[0].contains("90.74") && lines[0].contains("parallel_for_with_progress"));
+}
+
+#[test]
+fn test_quiet_drops_legends_and_warnings() {
+    let output = Command::new("cargo")
+        .args([
+            "run",
+            "--",
+            "-q",
+            "trend",
+            "-n",
+            "2",
+            "tests/fixtures/perf-report-de-locale.txt",
+            "tests/fixtures/perf-report-de-locale-rep2.txt",
+        ])
+        .output()
+        .expect("Failed to execute command");
+
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(output.status.success());
+    assert_eq!(stdout.lines().count(), 2);
+    assert!(!stdout.contains("[1]") && !stdout.contains("Function"));
+
+    // The mixed percent types warning is silenced; the table rows are not
+    let output = Command::new("cargo")
+        .args([
+            "run",
+            "--",
+            "top",
+            "--quiet",
+            "-n",
+            "2",
+            "tests/fixtures/perf-report-de-locale.txt",
+            "tests/fixtures/perf-report-de-locale-absolute.txt",
+        ])
+        .output()
+        .expect("Failed to execute command");
+
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(output.status.success());
+    assert!(!stderr.contains("Warning:"), "{}", stderr);
+    assert_eq!(stdout.lines().count(), 2);
+    assert!(
+        stdout
+            .lines()
+            .next()
+            .unwrap()
+            .contains("rd_optimize_transform")
+    );
+}
+
+#[test]
+fn test_quiet_per_file_rows_only() {
+    let output = Command::new("cargo")
+        .args([
+            "run",
+            "--",
+            "top",
+            "-q",
+            "--per-file",
+            "-n",
+            "2",
+            "perf-report.txt",
+            "tests/fixtures/perf-report-de-locale.txt",
+        ])
+        .output()
+        .expect("Failed to execute command");
+
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(output.status.success());
+    // Two rows per report and two combined, without file names, titles or blank lines
+    let lines: Vec<&str> = stdout.lines().collect();
+    assert_eq!(lines.len(), 6, "{}", stdout);
+    assert!(lines.iter().all(|l| l.contains('.') && !l.ends_with(':')));
+    assert_eq!(
+        lines[2],
+        "   71.80    0.00  TransformPartition::rd_optimize_transform"
+    );
+}
+
+#[test]
+fn test_top_command_strict_fails_on_warnings() {
+    let output = Command::new("cargo")
+        .args([
+            "run",
+            "--",
+            "top",
+            "--strict",
+            "tests/fixtures/perf-report-malformed.txt",
+        ])
+        .output()
+        .expect("Failed to execute command");
+
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert_eq!(output.status.code(), Some(2), "Expected exit code 2");
+    assert!(stdout.is_empty(), "Strict failure must not print a table");
+    assert!(stderr.contains("unparseable line(s)"));
+}
+
+#[test]
+fn test_top_command_strict_accepts_clean_report() {
+    let output = Command::new("cargo")
+        .args([
+            "run",
+            "--",
+            "top",
+            "--strict",
+            "-H",
+            "-t",
+            "rd_optimize",
+            "-t",
+            "DCT4DBlock",
+            "perf-report.txt",
+        ])
+        .output()
+        .expect("Failed to execute command");
+
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(output.status.success(), "Command failed: {}", stderr);
+}
+
+#[test]
+fn test_top_command_multiple_files_averaged() {
+    let output = Command::new("cargo")
+        .args([
+            "run",
+            "--",
+            "top",
+            "--no-color",
+            "tests/fixtures/perf-report-de-locale.txt",
+            "tests/fixtures/perf-report-de-locale-rep2.txt",
+        ])
+        .output()
+        .expect("Failed to execute command");
+
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(output.status.success(), "Command failed: {}", stderr);
+
+    // 71.80 and 69.80 average to 70.80; unchanged entries keep their value
+    let first = stdout.lines().nth(1).unwrap();
+    assert!(
+        first.contains("70.80"),
+        "Expected averaged value: {}",
+        first
+    );
+    assert!(stdout.contains("38.29"));
+}
+
+#[test]
+fn test_top_command_outlier_run() {
+    let run = |extra: &[&str]| {
+        let mut args = vec!["run", "--", "top", "-n", "1", "--no-color"];
+        args.extend_from_slice(extra);
+        args.extend([
+            "tests/fixtures/perf-report-de-locale.txt",
+            "tests/fixtures/perf-report-de-locale-rep2.txt",
+            "tests/fixtures/perf-report-de-locale-disturbed.txt",
+        ]);
+        Command::new("cargo")
+            .args(args)
+            .output()
+            .expect("Failed to execute command")
+    };
+
+    let output = run(&[]);
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(output.status.success(), "Command failed: {}", stderr);
+    assert!(stderr.contains(
+        "Warning: tests/fixtures/perf-report-de-locale-disturbed.txt looks like an outlier run"
+    ));
+    assert!(stdout.lines().nth(1).unwrap().starts_with("   65.57"));
+
+    // Dropped, the other two runs average as before
+    let output = run(&["--drop-outliers"]);
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(stderr.contains("; dropped from the average"));
+    assert!(stdout.lines().nth(1).unwrap().starts_with("   70.80"));
+}
+
+#[test]
+fn test_top_command_flag_variance() {
+    let output = Command::new("cargo")
+        .args([
+            "run",
+            "--",
+            "top",
+            "--flag-variance",
+            "1",
+            "--no-color",
+            "tests/fixtures/perf-report-de-locale.txt",
+            "tests/fixtures/perf-report-de-locale-rep2.txt",
+        ])
+        .output()
+        .expect("Failed to execute command");
+
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(output.status.success(), "Command failed: {}", stderr);
+
+    // Only rd_optimize_transform differs between the runs (71.80 vs 69.80)
+    let lines: Vec<&str> = stdout.lines().collect();
+    assert_eq!(
+        lines[1],
+        "   70.80    0.00  TransformPartition::rd_optimize_transform  ! CV 1.4%"
+    );
+    assert_eq!(lines[2], "   38.29    0.00  DCT4DBlock::DCT4DBlock");
+}
+
+#[test]
+fn test_top_command_per_file() {
+    let output = Command::new("cargo")
+        .args([
+            "run",
+            "--",
+            "top",
+            "--per-file",
+            "-n",
+            "1",
+            "--no-color",
+            "tests/fixtures/perf-report-de-locale.txt",
+            "tests/fixtures/perf-report-de-locale-rep2.txt",
+        ])
+        .output()
+        .expect("Failed to execute command");
+
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(output.status.success(), "Command failed: {}", stderr);
+
+    let lines: Vec<&str> = stdout.lines().collect();
+    assert_eq!(lines[0], "tests/fixtures/perf-report-de-locale.txt:");
+    assert_eq!(
+        lines[2],
+        "   71.80    0.00  TransformPartition::rd_optimize_transform"
+    );
+    assert_eq!(lines[4], "tests/fixtures/perf-report-de-locale-rep2.txt:");
+    assert_eq!(
+        lines[6],
+        "   69.80    0.00  TransformPartition::rd_optimize_transform"
+    );
+    assert_eq!(lines[8], "Combined (mean) over 2 reports:");
+    assert_eq!(
+        lines[10],
+        "   70.80    0.00  TransformPartition::rd_optimize_transform"
+    );
+}
+
+#[test]
+fn test_top_command_per_file_single_report() {
+    let output = Command::new("cargo")
+        .args([
+            "run",
+            "--",
+            "top",
+            "--per-file",
+            "-n",
+            "1",
+            "--no-color",
+            "tests/fixtures/perf-report-de-locale.txt",
+        ])
+        .output()
+        .expect("Failed to execute command");
+
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(output.status.success(), "Command failed: {}", stderr);
+
+    // The report's table is the combined one, so it is printed once
+    let lines: Vec<&str> = stdout.lines().collect();
+    assert_eq!(lines.len(), 3, "{}", stdout);
+    assert_eq!(lines[0], "tests/fixtures/perf-report-de-locale.txt:");
+    assert_eq!(
+        lines[2],
+        "   71.80    0.00  TransformPartition::rd_optimize_transform"
+    );
+}
+
+#[test]
+fn test_top_command_missing_as_zero() {
+    let run = |extra: &[&str]| {
+        let mut args = vec!["run", "--", "top", "--no-color"];
+        args.extend_from_slice(extra);
+        args.extend([
+            "tests/fixtures/perf-report-de-locale.txt",
+            "tests/fixtures/perf-report-de-locale-extra.txt",
+        ]);
+        let output = Command::new("cargo")
+            .args(args)
+            .output()
+            .expect("Failed to execute command");
+        assert!(output.status.success());
+        String::from_utf8_lossy(&output.stdout).into_owned()
+    };
+
+    // Only the second report has the memmove entry
+    let stdout = run(&[]);
+    assert!(stdout.contains("    3.00    3.00  __memmove_avx_unaligned_erms\n"));
+    assert!(
+        stdout
+            .lines()
+            .last()
+            .unwrap()
+            .contains("(1 listed) are averaged over the reports that contain them")
+    );
+
+    let stdout = run(&["--missing-as-zero"]);
+    assert!(stdout.contains("    1.50    1.50  __memmove_avx_unaligned_erms\n"));
+    // Present in both, so unchanged
+    assert!(stdout.contains("   70.80    0.00  TransformPartition::rd_optimize_transform\n"));
+    assert_eq!(
+        stdout.lines().last(),
+        Some(
+            "Note: entries missing from some reports (1 listed) count as 0% there (--missing-as-zero)"
+        )
+    );
+}
+
+#[test]
+fn test_top_command_output_file() {
+    let path = std::env::temp_dir().join(format!("pperf-output-{}.txt", std::process::id()));
+    let output = Command::new("cargo")
+        .args(["run", "--", "top", "-n", "1", "perf-report.txt", "-o"])
+        .arg(&path)
+        .output()
+        .expect("Failed to execute command");
+
+    assert!(output.status.success());
+    assert!(output.stdout.is_empty());
+    let written = std::fs::read_to_string(&path).unwrap();
+    std::fs::remove_file(&path).unwrap();
+    assert_eq!(
+        written,
+        "Children%   Self%  Function\n   90.74    0.00  parallel_for_with_progress\n"
+    );
+
+    let output = Command::new("cargo")
+        .args([
+            "run",
+            "--",
+            "--output",
+            "no-such-dir/out.txt",
+            "top",
+            "perf-report.txt",
+        ])
+        .output()
+        .expect("Failed to execute command");
+    assert_eq!(output.status.code(), Some(1));
+}
+
+#[test]
+fn test_top_command_width() {
+    let output = Command::new("cargo")
+        .args([
+            "run",
+            "--",
+            "--width",
+            "60",
+            "top",
+            "-H",
+            "-t",
+            "rd_optimize",
+            "-t",
+            "DCT4DBlock",
+            "--no-color",
+            "perf-report.txt",
+        ])
+        .output()
+        .expect("Failed to execute command");
+
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(output.status.success());
+    let lines: Vec<&str> = stdout.lines().collect();
+    // 60 columns leave 42 for symbols; nested rows indent by two on narrow terminals
+    assert_eq!(
+        lines[1],
+        "   71.80    0.00  TransformPartition::rd_optimize_transfo..."
+    );
+    assert_eq!(lines[2], "   17.23    0.00    DCT4DBlock::DCT4DBlock");
+    assert!(lines.iter().all(|l| l.chars().count() <= 60));
+}
+
+#[test]
+fn test_top_command_truncate_middle() {
+    let output = Command::new("cargo")
+        .args([
+            "run",
+            "--",
+            "--width",
+            "55",
+            "--truncate",
+            "middle",
+            "top",
+            "-t",
+            "rd_optimize_transform",
+            "--no-color",
+            "perf-report.txt",
+        ])
+        .output()
+        .expect("Failed to execute command");
+
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(output.status.success());
+    let lines: Vec<&str> = stdout.lines().collect();
+    // The class prefix and the whole method name survive the cut of 37 columns
+    assert_eq!(
+        lines[1],
+        "   71.80    0.00  TransformPa...::rd_optimize_transform"
+    );
+}
+
+#[test]
+fn test_top_command_weighted_reports() {
+    let run = |args: &[&str]| {
+        let mut full = vec!["run", "--", "top", "-n", "1", "--no-color"];
+        full.extend_from_slice(args);
+        Command::new("cargo")
+            .args(full)
+            .output()
+            .expect("Failed to execute command")
+    };
+    let expected = "   71.30    0.00  TransformPartition::rd_optimize_transform";
+
+    // (3 × 71.80 + 69.80) / 4
+    let output = run(&[
+        "--weight",
+        "3",
+        "tests/fixtures/perf-report-de-locale.txt",
+        "--weight",
+        "1",
+        "tests/fixtures/perf-report-de-locale-rep2.txt",
+    ]);
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(output.status.success());
+    assert_eq!(stdout.lines().nth(1), Some(expected));
+
+    let output = run(&[
+        "tests/fixtures/perf-report-de-locale.txt:3",
+        "tests/fixtures/perf-report-de-locale-rep2.txt",
+    ]);
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(output.status.success());
+    assert_eq!(stdout.lines().nth(1), Some(expected));
+
+    // One weight for two files
+    let output = run(&[
+        "--weight",
+        "3",
+        "tests/fixtures/perf-report-de-locale.txt",
+        "tests/fixtures/perf-report-de-locale-rep2.txt",
+    ]);
+    assert_eq!(output.status.code(), Some(3));
+    assert!(String::from_utf8_lossy(&output.stderr).contains("Invalid report weights"));
+}
+
+#[test]
+fn test_top_command_multiple_files_median() {
+    let output = Command::new("cargo")
+        .args([
+            "run",
+            "--",
+            "top",
+            "--agg",
+            "median",
+            "-n",
+            "1",
+            "--no-color",
+            "tests/fixtures/perf-report-de-locale.txt",
+            "tests/fixtures/perf-report-de-locale-rep2.txt",
+            "tests/fixtures/perf-report-de-locale-rep2.txt",
+        ])
+        .output()
+        .expect("Failed to execute command");
+
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(output.status.success(), "Command failed: {}", stderr);
+
+    // Median of 71.80, 69.80, 69.80; the mean would be 70.47
+    assert_eq!(
+        stdout.lines().nth(1),
+        Some("   69.80    0.00  TransformPartition::rd_optimize_transform")
+    );
+}
+
+#[test]
+fn test_top_command_multiple_files_stats() {
+    let output = Command::new("cargo")
+        .args([
+            "run",
+            "--",
+            "top",
+            "--stats",
+            "-n",
+            "2",
+            "--no-color",
+            "tests/fixtures/perf-report-de-locale.txt",
+            "tests/fixtures/perf-report-de-locale-rep2.txt",
+        ])
+        .output()
+        .expect("Failed to execute command");
+
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(output.status.success(), "Command failed: {}", stderr);
+
+    let lines: Vec<&str> = stdout.lines().collect();
+    assert_eq!(
+        lines[0],
+        "Children%     SD    Min    Max   Self%     SD    Min    Max  Function"
+    );
+    assert_eq!(
+        lines[1],
+        "    70.80   1.00  69.80  71.80    0.00   0.00   0.00   0.00  TransformPartition::rd_optimize_transform"
+    );
+}
+
+#[test]
+fn test_top_command_width_counts_shown_columns() {
+    let run = |extra: &[&str]| {
+        let mut args = vec!["run", "--", "--width", "80", "top", "--no-color"];
+        args.extend_from_slice(extra);
+        args.extend_from_slice(&[
+            "tests/fixtures/perf-report-de-locale.txt",
+            "tests/fixtures/perf-report-de-locale-rep2.txt",
+        ]);
+        Command::new("cargo")
+            .args(args)
+            .output()
+            .expect("Failed to execute command")
+    };
+
+    // Eight number columns leave 19 of the 80, so the 20-column minimum applies
+    let output = run(&["--stats", "-n", "2"]);
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(output.status.success());
+    assert_eq!(
+        stdout.lines().nth(1),
+        Some("    70.80   1.00  69.80  71.80    0.00   0.00   0.00   0.00  TransformPartitio...")
+    );
+
+    // Rank, bars and the variance marker are counted too
+    let output = run(&["--rank", "--bars", "--flag-variance", "0"]);
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(output.status.success());
+    assert!(
+        stdout.lines().all(|l| l.chars().count() <= 80),
+        "{}",
+        stdout
+    );
+}
+
+#[test]
+fn test_top_command_multiple_files_hierarchy_agreement() {
+    let output = Command::new("cargo")
+        .args([
+            "run",
+            "--",
+            "top",
+            "-H",
+            "-t",
+            "rd_optimize_transform",
+            "-t",
+            "DCT4DBlock",
+            "--no-color",
+            "tests/fixtures/perf-report-de-locale.txt",
+            "tests/fixtures/perf-report-de-locale-rep2.txt",
+        ])
+        .output()
+        .expect("Failed to execute command");
+
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(output.status.success(), "Command failed: {}", stderr);
+
+    assert!(stdout.contains("Edge agreement"));
+    // Relative% 17.23 vs 9.10 is well above the disagreement threshold
+    let edge = stdout
+        .lines()
+        .find(|l| l.contains("rd_optimize_transform -> DCT4DBlock"))
+        .expect("Edge row missing");
+    assert!(edge.starts_with('!'), "Edge should be flagged: {}", edge);
+    assert!(edge.contains("[17.23, 9.10]"));
+}
+
+#[test]
+fn test_top_command_multiple_files_debug_sparkline() {
+    let output = Command::new("cargo")
+        .args([
+            "run",
+            "--",
+            "top",
+            "-H",
+            "-D",
+            "-t",
+            "rd_optimize_transform",
+            "-t",
+            "DCT4DBlock",
+            "--no-color",
+            "tests/fixtures/perf-report-de-locale.txt",
+            "tests/fixtures/perf-report-de-locale-rep2.txt",
+        ])
+        .output()
+        .expect("Failed to execute command");
+
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(output.status.success());
+    let edge = stdout
+        .lines()
+        .find(|l| l.contains("rd_optimize_transform -> DCT4DBlock"))
+        .expect("Edge row missing");
+    assert!(edge.ends_with("[17.23, 9.10] █▁"), "No sparkline: {}", edge);
+}
+
+#[test]
+fn test_top_command_single_file_has_no_agreement_section() {
+    let output = Command::new("cargo")
+        .args([
+            "run",
+            "--",
+            "top",
+            "-H",
+            "-t",
+            "rd_optimize",
+            "-t",
+            "DCT4DBlock",
+            "perf-report.txt",
+        ])
+        .output()
+        .expect("Failed to execute command");
+
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(output.status.success());
+    assert!(!stdout.contains("Edge agreement"));
+}
+
+#[test]
+fn test_top_command_min_total_hides_small_relations() {
+    let output = Command::new("cargo")
+        .args([
+            "run",
+            "--",
+            "top",
+            "-H",
+            "-t",
+            "rd_optimize",
+            "-t",
+            "DCT4DBlock",
+            "-t",
+            "inner_product",
+            "--min-total",
+            "0.5",
+            "--no-color",
+            "perf-report.txt",
+        ])
+        .output()
+        .expect("Failed to execute command");
+
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(output.status.success(), "Command failed: {}", stderr);
+
+    // DCT4DBlock → inner_product inside rd_optimize_transform is only 0.07% of total
+    assert!(
+        !stdout.contains("0.07"),
+        "Small relation should be hidden: {}",
+        stdout
+    );
+    assert!(stdout.contains("      DCT4DBlock::DCT4DBlock"));
+}
+
+#[test]
+fn test_top_command_min_pct_hides_small_relations() {
+    let output = Command::new("cargo")
+        .args([
+            "run",
+            "--",
+            "top",
+            "-H",
+            "-t",
+            "rd_optimize",
+            "-t",
+            "DCT4DBlock",
+            "-t",
+            "inner_product",
+            "--min-pct",
+            "1",
+            "--no-color",
+            "perf-report.txt",
+        ])
+        .output()
+        .expect("Failed to execute command");
+
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(output.status.success(), "Command failed: {}", stderr);
+
+    // DCT4DBlock → inner_product is 2.21% of DCT4DBlock but only 0.58% of total
+    assert!(
+        !stdout.contains("      std::inner_product"),
+        "Small relations should be hidden: {}",
+        stdout
+    );
+    assert!(stdout.contains("   17.23    0.00      DCT4DBlock::DCT4DBlock"));
+    assert!(stdout.contains("      Hierarchical4DEncoder::rd_optimize_hexadecatree"));
+}
+
+#[test]
+fn test_top_command_hierarchy_other_rows() {
+    let output = Command::new("cargo")
+        .args([
+            "run",
+            "--",
+            "top",
+            "-H",
+            "-t",
+            "rd_optimize_transform",
+            "-t",
+            "DCT4DBlock",
+            "--no-color",
+            "perf-report.txt",
+        ])
+        .output()
+        .expect("Failed to execute command");
+
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(output.status.success(), "Command failed: {}", stderr);
+
+    // The remainder row follows the listed callees so each level adds up to 100%
+    let lines: Vec<&str> = stdout.lines().collect();
+    let dct = lines
+        .iter()
+        .position(|l| *l == "   17.23    0.00      DCT4DBlock::DCT4DBlock")
+        .expect(&stdout);
+    assert_eq!(
+        lines[dct + 1],
+        "   82.77    0.00      (other)",
+        "{}",
+        stdout
+    );
+    // Standalone entries without listed callees get no remainder row
+    assert_eq!(stdout.matches("(other)").count(), 1, "{}", stdout);
+}
+
+#[test]
+fn test_top_command_hierarchy_independent_of_tree_width() {
+    // The same trees, re-indented to 7 columns per level instead of perf's usual 11
+    let run = |file: &str| {
+        let output = Command::new("cargo")
+            .args([
+                "run",
+                "--",
+                "top",
+                "-H",
+                "-t",
+                "rd_optimize_transform",
+                "-t",
+                "DCT4DBlock",
+                "-t",
+                "inner_product",
+                "--no-color",
+                file,
+            ])
+            .output()
+            .expect("Failed to execute command");
+        assert!(output.status.success());
+        String::from_utf8_lossy(&output.stdout).to_string()
+    };
+
+    let standard = run("tests/fixtures/perf-report.txt");
+    let narrow = run("tests/fixtures/perf-report-narrow-tree.txt");
+    assert!(standard.contains("    0.07    7.45          std::inner_product\n"));
+    assert_eq!(standard, narrow);
+}
+
+#[test]
+fn test_top_command_min_total_rejects_invalid_value() {
+    let output = Command::new("cargo")
+        .args([
+            "run",
+            "--",
+            "top",
+            "-H",
+            "-t",
+            "rd_optimize",
+            "--min-total",
+            "150",
+            "perf-report.txt",
+        ])
+        .output()
+        .expect("Failed to execute command");
+
+    assert_eq!(output.status.code(), Some(3), "Expected exit code 3");
+}
+
+#[test]
+fn test_top_command_compressed_hierarchy() {
+    let plain = Command::new("cargo")
+        .args([
+            "run",
+            "--",
+            "top",
+            "-H",
+            "-t",
+            "rd_optimize_transform",
+            "-t",
+            "DCT4DBlock",
+            "--no-color",
+            "tests/fixtures/perf-report-de-locale.txt",
+        ])
+        .output()
+        .expect("Failed to execute command");
+
+    for path in [
+        "tests/fixtures/perf-report-de-locale.txt.gz",
+        "tests/fixtures/perf-report-de-locale.txt.zst",
+    ] {
+        let output = Command::new("cargo")
+            .args([
+                "run",
+                "--",
+                "top",
+                "-H",
+                "-t",
+                "rd_optimize_transform",
+                "-t",
+                "DCT4DBlock",
+                "--no-color",
+                path,
+            ])
+            .output()
+            .expect("Failed to execute command");
+
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        assert!(output.status.success(), "{} failed: {}", path, stderr);
+        assert_eq!(output.stdout, plain.stdout, "{} output differs", path);
+    }
+}
+
+#[test]
+fn test_top_command_symbol_filter_rewrites_everywhere() {
+    let output = Command::new("cargo")
+        .args([
+            "run",
+            "--",
+            "top",
+            "-H",
+            "-t",
+            "rd_optimize_transform",
+            "-t",
+            "DCT4DBlock",
+            "--no-color",
+            "--symbol-filter",
+            "tests/fixtures/strip-namespace.sh",
+            "perf-report.txt",
+        ])
+        .output()
+        .expect("Failed to execute command");
+
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(output.status.success(), "Command failed: {}", stderr);
+    assert!(!stdout.contains("TransformPartition::"));
+    assert!(stdout.contains("  rd_optimize_transform"));
+    assert!(stdout.contains("      DCT4DBlock::DCT4DBlock"));
+
+    // Targets are matched against the rewritten names
+    let output = Command::new("cargo")
+        .args([
+            "run",
+            "--",
+            "top",
+            "-t",
+            "TransformPartition",
+            "--symbol-filter",
+            "tests/fixtures/strip-namespace.sh",
+            "perf-report.txt",
+        ])
+        .output()
+        .expect("Failed to execute command");
+    assert_eq!(output.status.code(), Some(4), "Expected exit code 4");
+}
+
+#[test]
+fn test_top_command_symbol_filter_missing_exe() {
+    let output = Command::new("cargo")
+        .args([
+            "run",
+            "--",
+            "top",
+            "--symbol-filter",
+            "/nonexistent/filter",
+            "perf-report.txt",
+        ])
+        .output()
+        .expect("Failed to execute command");
+
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert_eq!(output.status.code(), Some(1), "Expected exit code 1");
+    assert!(stderr.contains("Symbol filter failed"));
+}
+
+#[test]
+fn test_top_command_reads_stdin() {
+    let from_file = Command::new("cargo")
+        .args([
+            "run",
+            "--",
+            "top",
+            "-H",
+            "-t",
+            "rd_optimize",
+            "-t",
+            "DCT4DBlock",
+            "perf-report.txt",
+        ])
+        .output()
+        .expect("Failed to execute command");
+
+    let output = Command::new("cargo")
+        .args([
+            "run",
+            "--",
+            "top",
+            "-H",
+            "-t",
+            "rd_optimize",
+            "-t",
+            "DCT4DBlock",
+            "-",
+        ])
+        .stdin(std::fs::File::open("perf-report.txt").expect("Fixture missing"))
+        .output()
+        .expect("Failed to execute command");
+
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(output.status.success(), "Command failed: {}", stderr);
+    assert_eq!(output.stdout, from_file.stdout);
+}
+
+#[test]
+fn test_top_command_empty_stdin() {
+    let output = Command::new("cargo")
+        .args(["run", "--", "top", "-"])
+        .stdin(std::process::Stdio::null())
+        .output()
+        .expect("Failed to execute command");
+
+    assert_eq!(output.status.code(), Some(2), "Expected exit code 2");
+}
+
+#[test]
+fn test_top_command_dry_run() {
+    let output = Command::new("cargo")
+        .args([
+            "run",
+            "--",
+            "top",
+            "--dry-run",
+            "-H",
+            "-t",
+            "rd_optimize",
+            "-t",
+            "NoSuchFunction",
+            "perf-report.txt",
+        ])
+        .output()
+        .expect("Failed to execute command");
+
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let stderr = String::from_utf8_lossy(&output.stderr);
+
+    // An unmatched target is reported, not an error
+    assert!(output.status.success(), "Command failed: {}", stderr);
+    assert!(stdout.contains("perf-report.txt: children/self layout, call graph: yes"));
+    assert!(stdout.contains("hierarchy: yes"));
+    assert!(stdout.contains("Targets (2, 1 unmatched):"));
+    assert!(stdout.contains("NoSuchFunction: 0 matches (unmatched)"));
+    assert!(
+        !stdout.contains("Children%"),
+        "Dry run must not print the table"
+    );
+}
+
+#[test]
+fn test_top_command_exclude_hides_symbols() {
+    let output = Command::new("cargo")
+        .args([
+            "run",
+            "--",
+            "top",
+            "-n",
+            "50",
+            "--exclude",
+            "std::",
+            "--exclude",
+            "0x",
+            "--no-color",
+            "perf-report.txt",
+        ])
+        .output()
+        .expect("Failed to execute command");
+
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(output.status.success(), "Command failed: {}", stderr);
+    assert!(stdout.contains("parallel_for_with_progress"));
+    for line in stdout.lines().skip(1) {
+        assert!(!line.contains("  std::"), "Excluded symbol shown: {}", line);
+        assert!(!line.contains("0x"), "Excluded symbol shown: {}", line);
+    }
+}
+
+#[test]
+fn test_top_command_exclude_in_hierarchy() {
+    let output = Command::new("cargo")
+        .args([
+            "run",
+            "--",
+            "top",
+            "-H",
+            "-t",
+            "rd_optimize",
+            "-t",
+            "DCT4DBlock",
+            "--exclude",
+            "DCT4DBlock",
+            "--no-color",
+            "perf-report.txt",
+        ])
+        .output()
+        .expect("Failed to execute command");
+
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(output.status.success(), "Command failed: {}", stderr);
+    assert!(
+        !stdout.contains("DCT4DBlock"),
+        "Excluded callee shown: {}",
+        stdout
+    );
+    assert!(stdout.contains("TransformPartition::rd_optimize_transform"));
+}
+
+#[test]
+fn test_top_command_exclude_everything_is_no_match() {
+    let output = Command::new("cargo")
+        .args([
+            "run",
+            "--",
+            "top",
+            "-t",
+            "DCT4DBlock",
+            "--exclude",
+            "DCT4D",
+            "perf-report.txt",
+        ])
+        .output()
+        .expect("Failed to execute command");
+
+    assert_eq!(output.status.code(), Some(4), "Expected exit code 4");
+}
+
+#[test]
+fn test_top_command_ignore_case() {
+    let exact = Command::new("cargo")
+        .args([
+            "run",
+            "--",
+            "top",
+            "-H",
+            "-t",
+            "rd_optimize",
+            "-t",
+            "DCT4DBlock",
+            "--no-color",
+            "perf-report.txt",
+        ])
+        .output()
+        .expect("Failed to execute command");
+
+    let folded = Command::new("cargo")
+        .args([
+            "run",
+            "--",
+            "top",
+            "-i",
+            "-H",
+            "-t",
+            "RD_OPTIMIZE",
+            "-t",
+            "dct4dblock",
+            "--no-color",
+            "perf-report.txt",
+        ])
+        .output()
+        .expect("Failed to execute command");
+
+    let stderr = String::from_utf8_lossy(&folded.stderr);
+    assert!(folded.status.success(), "Command failed: {}", stderr);
+    assert!(exact.status.success());
+    // Upper-case targets still resolve to the original display names
+    assert_eq!(folded.stdout, exact.stdout);
+
+    let without_flag = Command::new("cargo")
+        .args(["run", "--", "top", "-t", "dct4dblock", "perf-report.txt"])
+        .output()
+        .expect("Failed to execute command");
+    assert_eq!(without_flag.status.code(), Some(4), "Expected exit code 4");
+}
+
+#[test]
+fn test_top_command_mixed_percent_types_warn() {
+    let output = Command::new("cargo")
+        .args([
+            "run",
+            "--",
+            "top",
+            "-H",
+            "-t",
+            "rd_optimize",
+            "-t",
+            "DCT4DBlock",
+            "--no-color",
+            "tests/fixtures/perf-report-de-locale.txt",
+            "tests/fixtures/perf-report-de-locale-absolute.txt",
+        ])
+        .output()
+        .expect("Failed to execute command");
+
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(output.status.success(), "Command failed: {}", stderr);
+    assert!(
+        stderr.contains("different percent types")
+            && stderr.contains("perf-report-de-locale-absolute.txt: absolute"),
+        "Expected mixing warning: {}",
+        stderr
+    );
+
+    let single = Command::new("cargo")
+        .args([
+            "run",
+            "--",
+            "top",
+            "--show-meta",
+            "tests/fixtures/perf-report-de-locale-absolute.txt",
+        ])
+        .output()
+        .expect("Failed to execute command");
+    assert!(String::from_utf8_lossy(&single.stdout).contains("Percent type: absolute"));
+    assert!(!String::from_utf8_lossy(&single.stderr).contains("percent types"));
+}
+
+#[test]
+fn test_top_command_no_matches_suggests_symbols() {
+    let output = Command::new("cargo")
+        .args(["run", "--", "top", "-t", "DTC4DBlock", "perf-report.txt"])
+        .output()
+        .expect("Failed to execute command");
+
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert_eq!(output.status.code(), Some(4), "Expected exit code 4");
+    assert!(
+        stderr.contains("did you mean: ") && stderr.contains("DCT4DBlock::DCT4DBlock"),
+        "Expected a suggestion: {}",
+        stderr
+    );
+}
+
+#[test]
+fn test_top_command_min_children_cuts_hierarchy_callees() {
+    let output = Command::new("cargo")
+        .args([
+            "run",
+            "--",
+            "top",
+            "-H",
+            "-t",
+            "rd_optimize",
+            "-t",
+            "DCT4DBlock",
+            "-t",
+            "inner_product",
+            "--min-children",
+            "10",
+            "--no-color",
+            "perf-report.txt",
+        ])
+        .output()
+        .expect("Failed to execute command");
+
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(output.status.success(), "Command failed: {}", stderr);
+
+    // inner_product is 6.89% Children%: gone as an entry and as a callee
+    assert!(
+        !stdout.contains("inner_product"),
+        "Below-threshold callee should be hidden: {}",
+        stdout
+    );
+    assert!(stdout.contains("      DCT4DBlock::DCT4DBlock"));
+}
+
+#[test]
+fn test_top_command_min_self() {
+    let output = Command::new("cargo")
+        .args([
+            "run",
+            "--",
+            "top",
+            "-n",
+            "50",
+            "--min-self",
+            "5%",
+            "--no-color",
+            "perf-report.txt",
+        ])
+        .output()
+        .expect("Failed to execute command");
+
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(output.status.success(), "Command failed: {}", stderr);
+
+    let rows: Vec<&str> = stdout.lines().skip(1).collect();
+    assert!(!rows.is_empty());
+    for row in rows {
+        let self_pct: f64 = row.split_whitespace().nth(1).unwrap().parse().unwrap();
+        assert!(self_pct >= 5.0, "Row below --min-self: {}", row);
+    }
+}
+
+#[test]
+fn test_top_command_dso_filter() {
+    let output = Command::new("cargo")
+        .args([
+            "run",
+            "--",
+            "top",
+            "-n",
+            "50",
+            "--dso",
+            "libc.so",
+            "--dso",
+            "libm",
+            "--no-color",
+            "perf-report.txt",
+        ])
+        .output()
+        .expect("Failed to execute command");
+
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(output.status.success(), "Command failed: {}", stderr);
+    assert!(stdout.contains("malloc"));
+    assert!(
+        !stdout.contains("rd_optimize"),
+        "Encoder symbols should be filtered out: {}",
+        stdout
+    );
+
+    let none = Command::new("cargo")
+        .args(["run", "--", "top", "--dso", "libnope", "perf-report.txt"])
+        .output()
+        .expect("Failed to execute command");
+    assert_eq!(none.status.code(), Some(4), "Expected exit code 4");
+}
+
+#[test]
+fn test_top_command_comm_filter() {
+    let output = Command::new("cargo")
+        .args([
+            "run",
+            "--",
+            "top",
+            "-n",
+            "100",
+            "--comm",
+            "perf-exec",
+            "--no-color",
+            "perf-report.txt",
+        ])
+        .output()
+        .expect("Failed to execute command");
+
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(output.status.success(), "Command failed: {}", stderr);
+    assert!(
+        !stdout.contains("rd_optimize"),
+        "jpl-encoder-bin symbols should be filtered out: {}",
+        stdout
+    );
+
+    // The encoder's call trees are dropped too, so its functions are unknown
+    let hierarchy = Command::new("cargo")
+        .args([
+            "run",
+            "--",
+            "top",
+            "-H",
+            "-t",
+            "rd_optimize",
+            "--comm",
+            "perf-exec",
+            "perf-report.txt",
+        ])
+        .output()
+        .expect("Failed to execute command");
+    assert_eq!(hierarchy.status.code(), Some(4), "Expected exit code 4");
+}
+
+#[test]
+fn test_top_command_user_and_kernel_only() {
+    let user = Command::new("cargo")
+        .args([
+            "run",
+            "--",
+            "top",
+            "-n",
+            "5",
+            "--user-only",
+            "--no-color",
+            "perf-report.txt",
+        ])
+        .output()
+        .expect("Failed to execute command");
+    let stdout = String::from_utf8_lossy(&user.stdout);
+    assert!(user.status.success());
+    assert!(
+        !stdout.contains("0000000000000000"),
+        "Kernel entry should be hidden: {}",
+        stdout
+    );
+    assert!(stdout.contains("parallel_for_with_progress"));
+
+    let kernel = Command::new("cargo")
+        .args([
+            "run",
+            "--",
+            "top",
+            "-n",
+            "5",
+            "--kernel-only",
+            "--no-color",
+            "perf-report.txt",
+        ])
+        .output()
+        .expect("Failed to execute command");
+    let stdout = String::from_utf8_lossy(&kernel.stdout);
+    assert!(kernel.status.success());
+    assert!(stdout.contains("0000000000000000"));
+    assert!(!stdout.contains("parallel_for_with_progress"));
+
+    let both = Command::new("cargo")
+        .args([
+            "run",
+            "--",
+            "top",
+            "--user-only",
+            "--kernel-only",
+            "perf-report.txt",
+        ])
+        .output()
+        .expect("Failed to execute command");
+    assert_eq!(both.status.code(), Some(3), "Expected exit code 3");
+}
+
+#[test]
+fn test_top_command_ignore_file() {
+    let output = Command::new("cargo")
+        .args([
+            "run",
+            "--",
+            "top",
+            "-n",
+            "30",
+            "--ignore-file",
+            "tests/fixtures/ignore-glue.txt",
+            "--no-color",
+            "perf-report.txt",
+        ])
+        .output()
+        .expect("Failed to execute command");
+
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(output.status.success(), "Command failed: {}", stderr);
+    assert!(!stdout.contains("parallel_for_with_progress"));
+    assert!(!stdout.contains("0x"));
+    assert!(stdout.contains("rd_optimize_transform"));
+
+    let missing = Command::new("cargo")
+        .args([
+            "run",
+            "--",
+            "top",
+            "--ignore-file",
+            "tests/fixtures/no-such-ignore-file.txt",
+            "perf-report.txt",
+        ])
+        .output()
+        .expect("Failed to execute command");
+    assert_eq!(missing.status.code(), Some(1), "Expected exit code 1");
+}
+
+#[test]
+fn test_top_command_target_file() {
+    let from_file = Command::new("cargo")
+        .args([
+            "run",
+            "--",
+            "top",
+            "-H",
+            "--target-file",
+            "tests/fixtures/targets.txt",
+            "--no-color",
+            "perf-report.txt",
+        ])
+        .output()
+        .expect("Failed to execute command");
+
+    let stdout = String::from_utf8_lossy(&from_file.stdout);
+    let stderr = String::from_utf8_lossy(&from_file.stderr);
+    assert!(from_file.status.success(), "Command failed: {}", stderr);
+
+    // Exact signatures skip the second rd_optimize_transform overload (71.78%)
+    assert!(!stdout.contains("71.78"), "Unexpected overload: {}", stdout);
+    assert!(stdout.contains("   71.80    0.00  TransformPartition::rd_optimize_transform"));
+    // The re: line reaches inner_product both as an entry and as a callee
+    assert!(stdout.contains("          std::inner_product"));
+    assert!(stdout.contains("    6.89    7.45  std::inner_product"));
+}
+
+#[test]
+fn test_top_command_target_file_errors() {
+    let invalid = Command::new("cargo")
+        .args([
+            "run",
+            "--",
+            "top",
+            "--target-file",
+            "tests/fixtures/targets-invalid-regex.txt",
+            "perf-report.txt",
+        ])
+        .output()
+        .expect("Failed to execute command");
+    let stderr = String::from_utf8_lossy(&invalid.stderr);
+    assert_eq!(invalid.status.code(), Some(3), "Expected exit code 3");
+    assert!(stderr.contains("line 2: invalid regex"), "{}", stderr);
+}
+
+#[test]
+fn test_top_command_target_file_with_targets() {
+    let output = Command::new("cargo")
+        .args([
+            "run",
+            "--",
+            "top",
+            "-H",
+            "-t",
+            "get_mSubband",
+            "--target-file",
+            "tests/fixtures/targets.txt",
+            "--no-color",
+            "perf-report.txt",
+        ])
+        .output()
+        .expect("Failed to execute command");
+
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(output.status.success(), "Command failed: {}", stderr);
+
+    // The -t substring target joins the file's exact and regex targets
+    assert!(stdout.contains("   71.80    0.00  TransformPartition::rd_optimize_transform"));
+    assert!(
+        stdout.contains("    0.00   11.94      Hierarchical4DEncoder::get_mSubbandLF_significance")
+    );
+    assert!(
+        stdout.contains("   21.72   11.94  Hierarchical4DEncoder::get_mSubbandLF_significance")
+    );
+
+    let dry_run = Command::new("cargo")
+        .args([
+            "run",
+            "--",
+            "top",
+            "--dry-run",
+            "-t",
+            "get_mSubband",
+            "--target-file",
+            "tests/fixtures/targets.txt",
+            "perf-report.txt",
+        ])
+        .output()
+        .expect("Failed to execute command");
+    let stdout = String::from_utf8_lossy(&dry_run.stdout);
+    assert!(stdout.contains("Targets (4, 0 unmatched):"), "{}", stdout);
+    assert!(
+        stdout.contains("  substring  get_mSubband: 1 match\n"),
+        "{}",
+        stdout
+    );
+    assert!(stdout.contains("  regex      re:^(double )?std::inner_product: 1 match\n"));
+}
+
+#[test]
+fn test_top_command_target_file_aliases() {
+    let output = Command::new("cargo")
+        .args([
+            "run",
+            "--",
+            "top",
+            "-H",
+            "--target-file",
+            "tests/fixtures/targets.toml",
+            "--no-color",
+            "perf-report.txt",
+        ])
+        .output()
+        .expect("Failed to execute command");
+
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(output.status.success(), "Command failed: {}", stderr);
+
+    // Aliases replace both entry rows and callee rows; unaliased targets keep their name
+    assert!(
+        stdout.contains("   71.80    0.00  rd-transform"),
+        "{}",
+        stdout
+    );
+    assert!(stdout.contains("   17.23    0.00      dct"), "{}", stdout);
+    assert!(
+        stdout.contains("    6.89    7.45  std::inner_product"),
+        "{}",
+        stdout
+    );
+    assert!(!stdout.contains("DCT4DBlock"), "{}", stdout);
+
+    let dry_run = Command::new("cargo")
+        .args([
+            "run",
+            "--",
+            "top",
+            "--dry-run",
+            "--target-file",
+            "tests/fixtures/targets.toml",
+            "perf-report.txt",
+        ])
+        .output()
+        .expect("Failed to execute command");
+    let stdout = String::from_utf8_lossy(&dry_run.stdout);
+    assert!(
+        stdout.contains("(as rd-transform) [top-level partition search]: 1 match"),
+        "{}",
+        stdout
+    );
+}
+
+#[test]
+fn test_top_command_group() {
+    let output = Command::new("cargo")
+        .args([
+            "run",
+            "--",
+            "top",
+            "--group",
+            "--target-file",
+            "tests/fixtures/targets-groups.txt",
+            "--no-color",
+            "perf-report.txt",
+        ])
+        .output()
+        .expect("Failed to execute command");
+
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(output.status.success(), "Command failed: {}", stderr);
+
+    // 71.80 + 38.29; entropy: get_mSubband plus three hexadecatree matches
+    assert!(stdout.contains("  110.09    0.00  transform"), "{}", stdout);
+    assert!(stdout.contains("  101.13   12.02  entropy"), "{}", stdout);
+    assert_eq!(stdout.lines().count(), 3, "{}", stdout);
+
+    let hierarchy = Command::new("cargo")
+        .args([
+            "run",
+            "--",
+            "top",
+            "--group",
+            "-H",
+            "--target-file",
+            "tests/fixtures/targets-groups.txt",
+            "perf-report.txt",
+        ])
+        .output()
+        .expect("Failed to execute command");
+    assert_eq!(hierarchy.status.code(), Some(3), "Expected exit code 3");
+}
+
+#[test]
+fn test_top_command_budget() {
+    let output = Command::new("cargo")
+        .args([
+            "run",
+            "--",
+            "top",
+            "--budget",
+            "tests/fixtures/budgets.toml",
+            "--no-color",
+            "perf-report.txt",
+        ])
+        .output()
+        .expect("Failed to execute command");
+
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert_eq!(output.status.code(), Some(5), "Expected exit code 5");
+    assert!(stderr.contains("1 budget(s) exceeded"), "{}", stderr);
+    assert!(
+        stdout.contains("PASS         71.80      75.00"),
+        "{}",
+        stdout
+    );
+    assert!(
+        stdout.contains("FAIL          7.47          -       7.45       5.00  std::inner_product")
+    );
+
+    let invalid = Command::new("cargo")
+        .args([
+            "run",
+            "--",
+            "top",
+            "--budget",
+            "tests/fixtures/targets.toml",
+            "perf-report.txt",
+        ])
+        .output()
+        .expect("Failed to execute command");
+    let stderr = String::from_utf8_lossy(&invalid.stderr);
+    assert_eq!(invalid.status.code(), Some(3), "Expected exit code 3");
+    assert!(stderr.contains("unknown table [[target]]"), "{}", stderr);
+}
+
+#[test]
+fn test_top_command_mangled_target() {
+    let output = Command::new("cargo")
+        .args([
+            "run",
+            "--",
+            "top",
+            "-H",
+            "-t",
+            "_ZN10DCT4DBlockC1ERK7Block4Dd",
+            "-t",
+            "inner_product",
+            "--no-color",
+            "perf-report.txt",
+        ])
+        .output()
+        .expect("Failed to execute command");
+
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(output.status.success(), "Command failed: {}", stderr);
+    assert!(
+        stdout.contains("   38.29    0.00  DCT4DBlock::DCT4DBlock\n"),
+        "{}",
+        stdout
+    );
+    assert!(
+        stdout.contains("    1.50    7.45      std::inner_product\n"),
+        "{}",
+        stdout
+    );
+}
+
+#[test]
+fn test_top_command_callers() {
+    let output = Command::new("cargo")
+        .args([
+            "run",
+            "--",
+            "top",
+            "--callers",
+            "-t",
+            "DCT4DBlock::DCT4DBlock",
+            "--no-color",
+            "perf-report.txt",
+        ])
+        .output()
+        .expect("Failed to execute command");
+
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(output.status.success(), "Command failed: {}", stderr);
+
+    let lines: Vec<&str> = stdout.lines().collect();
+    assert_eq!(lines[0], "DCT4DBlock::DCT4DBlock (38.29% children)");
+    // Callers come from every tree, including ones not rooted at a target
+    assert_eq!(
+        lines[2],
+        "    10.42    27.20  TransformPartition::rd_optimize_transform"
+    );
+    assert_eq!(lines.len(), 3, "{}", stdout);
+}
+
+#[test]
+fn test_top_command_tree() {
+    let output = Command::new("cargo")
+        .args([
+            "run",
+            "--",
+            "top",
+            "--tree",
+            "DCT4DBlock::DCT4DBlock",
+            "--no-color",
+            "perf-report.txt",
+        ])
+        .output()
+        .expect("Failed to execute command");
+
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(output.status.success(), "Command failed: {}", stderr);
+
+    let lines: Vec<&str> = stdout.lines().collect();
+    assert_eq!(
+        lines[0],
+        "Call tree of DCT4DBlock::DCT4DBlock (38.29% children):"
+    );
+    assert_eq!(lines[2], "   100.00  DCT4DBlock::DCT4DBlock");
+    // Non-target callees are shown too, nested under their caller
+    assert_eq!(
+        lines[3],
+        "    20.12      Transformed4DBlock::do_4d_transform"
+    );
+    assert_eq!(lines[4], "     7.47          std::inner_product");
+
+    let missing = Command::new("cargo")
+        .args([
+            "run",
+            "--",
+            "top",
+            "--tree",
+            "NoSuchFunction",
+            "perf-report.txt",
+        ])
+        .output()
+        .expect("Failed to execute command");
+    assert_eq!(missing.status.code(), Some(4), "Expected exit code 4");
+}
+
+#[test]
+fn test_top_command_max_depth() {
+    let output = Command::new("cargo")
+        .args([
+            "run",
+            "--",
+            "top",
+            "-H",
+            "--max-depth",
+            "2",
+            "-t",
+            "rd_optimize_transform",
+            "-t",
+            "DCT4DBlock::DCT4DBlock",
+            "-t",
+            "inner_product",
+            "--no-color",
+            "perf-report.txt",
+        ])
+        .output()
+        .expect("Failed to execute command");
+
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(output.status.success(), "Command failed: {}", stderr);
+
+    // Only the DCT4DBlock calls within two levels of rd_optimize_transform are found,
+    // and inner_product is too deep to nest under DCT4DBlock there
+    assert!(
+        stdout.contains("   11.89    0.00      DCT4DBlock::DCT4DBlock\n"),
+        "{}",
+        stdout
+    );
+    assert!(
+        stdout.contains("   29.75    0.00  DCT4DBlock::DCT4DBlock\n"),
+        "{}",
+        stdout
+    );
+    assert!(
+        !stdout.contains("          std::inner_product"),
+        "{}",
+        stdout
+    );
+
+    let zero = Command::new("cargo")
+        .args([
+            "run",
+            "--",
+            "top",
+            "-H",
+            "--max-depth",
+            "0",
+            "-t",
+            "DCT",
+            "perf-report.txt",
+        ])
+        .output()
+        .expect("Failed to execute command");
+    assert_eq!(zero.status.code(), Some(3), "Expected exit code 3");
+}
+
+#[test]
+fn test_top_command_hierarchy_callee_order() {
+    let run = |extra: &[&str]| {
+        let mut args = vec![
+            "run",
+            "--",
+            "top",
+            "-H",
+            "-t",
+            "rd_optimize",
+            "-t",
+            "DCT4D",
+            "--no-color",
+        ];
+        args.extend_from_slice(extra);
+        args.push("tests/fixtures/perf-report-callee-order.txt");
+        let output = Command::new("cargo")
+            .args(&args)
+            .output()
+            .expect("Failed to execute command");
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        assert!(output.status.success(), "Command failed: {}", stderr);
+        String::from_utf8_lossy(&output.stdout).to_string()
+    };
+
+    // The header names `-g ...,callee`, so DCT4DBlock's children are its callers:
+    // 90% of its 20% is reached through rd_optimize_transform (45% of its 40%)
+    let stdout = run(&[]);
+    let lines: Vec<&str> = stdout.lines().collect();
+    assert_eq!(
+        lines[1], "   40.00    5.00  TransformPartition::rd_optimize_transform",
+        "{}",
+        stdout
+    );
+    assert_eq!(
+        lines[2], "   45.00   20.00      DCT4DBlock::DCT4DBlock",
+        "{}",
+        stdout
+    );
+    assert!(
+        stdout.contains("    2.00   20.00  DCT4DBlock::DCT4DBlock"),
+        "{}",
+        stdout
+    );
+
+    // Forcing caller order reads the callers as callees, inverting the relation
+    let stdout = run(&["--tree-order", "caller"]);
+    let lines: Vec<&str> = stdout.lines().collect();
+    assert_eq!(
+        lines[1], "   20.00   20.00  DCT4DBlock::DCT4DBlock",
+        "{}",
+        stdout
+    );
+    assert_eq!(
+        lines[2], "   60.00    5.00      TransformPartition::rd_optimize_transform",
+        "{}",
+        stdout
+    );
+}
+
+#[test]
+fn test_top_command_tree_order_invalid() {
+    let output = Command::new("cargo")
+        .args([
+            "run",
+            "--",
+            "top",
+            "-H",
+            "-t",
+            "DCT4D",
+            "--tree-order",
+            "sideways",
+            "perf-report.txt",
+        ])
+        .output()
+        .expect("Failed to execute command");
+
+    assert_eq!(output.status.code(), Some(3), "Expected exit code 3");
+}
+
+#[test]
+fn test_top_command_assume_callee_trees() {
+    let run = |extra: &[&str]| {
+        let mut args = vec![
+            "run",
+            "--",
+            "top",
+            "-H",
+            "-t",
+            "rd_optimize_transform",
+            "-t",
+            "get_mSubband",
+            "--no-color",
+        ];
+        args.extend_from_slice(extra);
+        args.push("perf-report.txt");
+        Command::new("cargo")
+            .args(&args)
+            .output()
+            .expect("Failed to execute command")
+    };
+
+    // perf-report.txt states no call-graph order, so the Self% guess is reported
+    let output = run(&[]);
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(output.status.success(), "Command failed: {}", stderr);
+    assert!(
+        stderr.contains("Warning: call-graph order not stated")
+            && stderr.contains("Hierarchical4DEncoder::get_mSubbandLF_significance list callers"),
+        "{}",
+        stderr
+    );
+
+    // The override reads get_mSubbandLF_significance's tree as callees, without a warning
+    let output = run(&["--assume-callee-trees"]);
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(output.status.success(), "Command failed: {}", stderr);
+    assert!(!stderr.contains("Warning:"), "{}", stderr);
+    let lines: Vec<&str> = stdout.lines().collect();
+    let leaf = lines
+        .iter()
+        .position(|l| *l == "   21.72   11.94  Hierarchical4DEncoder::get_mSubbandLF_significance")
+        .expect(&stdout);
+    assert_eq!(
+        lines[leaf + 1],
+        "    7.66    0.00      TransformPartition::rd_optimize_transform",
+        "{}",
+        stdout
+    );
+
+    // It contradicts an explicit tree order
+    let output = run(&["--assume-callee-trees", "--tree-order", "callee"]);
+    assert_eq!(output.status.code(), Some(3), "Expected exit code 3");
+}
+
+#[test]
+fn test_top_command_contribution_mode_sum() {
+    let run = |mode: &str| {
+        let output = Command::new("cargo")
+            .args([
+                "run",
+                "--",
+                "top",
+                "-H",
+                "-D",
+                "-t",
+                "rd_optimize_transform",
+                "-t",
+                "Transformed4DBlock::do_4d_transform",
+                "--contribution-mode",
+                mode,
+                "--no-color",
+                "perf-report.txt",
+            ])
+            .output()
+            .expect("Failed to execute command");
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        assert!(output.status.success(), "Command failed: {}", stderr);
+        String::from_utf8_lossy(&output.stdout).to_string()
+    };
+
+    // By default only the first path from rd_optimize_transform is subtracted
+    let stdout = run("max");
+    assert!(
+        stdout.contains("   19.93    0.00  Transformed4DBlock::do_4d_transform"),
+        "{}",
+        stdout
+    );
+
+    // do_4d_transform is reached through DCT4DBlock at three recursion levels
+    let stdout = run("sum");
+    assert!(
+        stdout.contains("   18.92    0.00  Transformed4DBlock::do_4d_transform"),
+        "{}",
+        stdout
+    );
+    assert!(
+        stdout.contains(
+            "(standalone: 20.12% - (0.85% + 0.19% + 0.17%) (TransformPartition::rd_optimize_transform) = 18.92%)"
+        ),
+        "{}",
+        stdout
+    );
+}
+
+#[test]
+fn test_top_command_strict_math() {
+    let run = |extra: &[&str]| {
+        let mut args = vec!["run", "--", "top", "-H", "-t", "encode_", "-t", "quantize"];
+        args.extend_from_slice(extra);
+        args.push("tests/fixtures/perf-report-overlapping-callers.txt");
+        Command::new("cargo")
+            .args(&args)
+            .output()
+            .expect("Failed to execute command")
+    };
+    let message = "callers of quantize account for 42.00% but it has 30.00% Children% (encode_frame 32.00%, encode_slice 10.00%)";
+
+    // By default the clamped entry is reported and the table still printed
+    let output = run(&["--no-color"]);
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(output.status.success(), "Command failed: {}", stderr);
+    assert!(
+        stderr.contains(&format!("Warning: {}", message)),
+        "{}",
+        stderr
+    );
+    assert!(stdout.contains("    0.00   30.00  quantize"), "{}", stdout);
+
+    let output = run(&["--strict-math"]);
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert_eq!(output.status.code(), Some(2), "Expected exit code 2");
+    assert!(stderr.contains(message), "{}", stderr);
+    assert!(output.stdout.is_empty());
+}
+
+#[test]
+fn test_top_command_precision() {
+    let output = Command::new("cargo")
+        .args([
+            "run",
+            "--",
+            "--precision",
+            "1",
+            "top",
+            "-n",
+            "2",
+            "--no-color",
+            "tests/fixtures/perf-report-de-locale.txt",
+        ])
+        .output()
+        .expect("Failed to execute command");
+
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(output.status.success());
+    let lines: Vec<&str> = stdout.lines().collect();
+    assert_eq!(lines[0], "Children%   Self%  Function");
+    assert_eq!(
+        lines[1],
+        "    71.8     0.0  TransformPartition::rd_optimize_transform"
+    );
+    assert_eq!(lines[2], "    38.3     0.0  DCT4DBlock::DCT4DBlock");
+
+    let output = Command::new("cargo")
+        .args(["run", "--", "--precision", "7", "top", "perf-report.txt"])
+        .output()
+        .expect("Failed to execute command");
+    assert_eq!(output.status.code(), Some(3));
+}
+
+#[test]
+fn test_top_command_rank() {
+    let output = Command::new("cargo")
+        .args(["run", "--", "top", "-n", "3", "--rank", "perf-report.txt"])
+        .output()
+        .expect("Failed to execute command");
+
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(output.status.success());
+    let lines: Vec<&str> = stdout.lines().collect();
+    assert_eq!(lines[0], "#  Children%   Self%  Function");
+    assert!(lines[1].starts_with("1     90.74"));
+    assert!(lines[3].starts_with("3  "));
+    assert_eq!(lines.len(), 4);
+}
+
+#[test]
+fn test_top_command_merge_overloads() {
+    let output = Command::new("cargo")
+        .args([
+            "run",
+            "--",
+            "top",
+            "--merge-overloads",
+            "-D",
+            "--no-color",
+            "-t",
+            "rd_optimize_transform",
+            "perf-report.txt",
+        ])
+        .output()
+        .expect("Failed to execute command");
+
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(output.status.success());
+    let lines: Vec<&str> = stdout.lines().collect();
+    // 71.80 + 71.78: the two overloads are one row
+    assert_eq!(
+        lines[1],
+        "  143.58    0.00  TransformPartition::rd_optimize_transform  (2 overloads)"
+    );
+    assert_eq!(
+        lines[2],
+        "                  (overload: TransformPartition::rd_optimize_transform(Block4D const&))"
+    );
+    assert!(lines[3].starts_with(
+        "                  (overload: TransformPartition::rd_optimize_transform(Block4D const&, Block4D&"
+    ));
+    assert_eq!(lines.len(), 4);
+}
+
+#[test]
+fn test_top_command_group_by_namespace() {
+    let output = Command::new("cargo")
+        .args([
+            "run",
+            "--",
+            "top",
+            "--group-by",
+            "namespace",
+            "-n",
+            "3",
+            "--no-color",
+            "perf-report.txt",
+        ])
+        .output()
+        .expect("Failed to execute command");
+
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(output.status.success());
+    let lines: Vec<&str> = stdout.lines().collect();
+    // Summed Children% would count nested callers twice, so scopes rank by Self%
+    assert_eq!(lines[0], "    Self%  Function");
+    assert_eq!(lines[1], "    33.36  [global]");
+    assert_eq!(lines[2], "    29.80  std::");
+    assert_eq!(lines[3], "    14.69  Hierarchical4DEncoder::");
+    assert_eq!(lines.len(), 4);
+}
+
+#[test]
+fn test_top_command_group_by_rejects_unknown_scope() {
+    let output = Command::new("cargo")
+        .args(["run", "--", "top", "--group-by", "file", "perf-report.txt"])
+        .output()
+        .expect("Failed to execute command");
+
+    assert_eq!(output.status.code(), Some(3));
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(stderr.contains("'file' is not class or namespace"));
+}
+
+#[test]
+fn test_top_command_perf_map() {
+    let output = Command::new("cargo")
+        .args([
+            "run",
+            "--",
+            "top",
+            "--perf-map",
+            "tests/fixtures/perf-jit.map",
+            "-t",
+            "hot_loop",
+            "-t",
+            "Interpreter",
+            "--no-color",
+            "perf-report.txt",
+        ])
+        .output()
+        .expect("Failed to execute command");
+
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(output.status.success());
+    // 0x00007d4c46bf0000 and 0x00005666eb8572c0 fall inside the mapped ranges
+    let lines: Vec<&str> = stdout.lines().collect();
+    assert_eq!(lines[1], "   11.77    0.00  Interpreter");
+    assert_eq!(lines[2], "   11.77    0.00  jit::hot_loop");
+    assert_eq!(lines.len(), 3);
+}
+
+#[test]
+fn test_top_command_perf_map_missing() {
+    let output = Command::new("cargo")
+        .args([
+            "run",
+            "--",
+            "top",
+            "--perf-map",
+            "tests/fixtures/nonexistent.map",
+            "perf-report.txt",
+        ])
+        .output()
+        .expect("Failed to execute command");
+
+    assert_eq!(output.status.code(), Some(1));
+}
+
+#[test]
+fn test_top_command_binary_missing() {
+    let output = Command::new("cargo")
+        .args([
+            "run",
+            "--",
+            "top",
+            "--binary",
+            "tests/fixtures/nonexistent-binary",
+            "perf-report.txt",
+        ])
+        .output()
+        .expect("Failed to execute command");
+
+    assert_eq!(output.status.code(), Some(1));
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(stderr.contains("File not found: tests/fixtures/nonexistent-binary"));
+}
+
+#[test]
+fn test_top_command_kallsyms() {
+    let output = Command::new("cargo")
+        .args([
+            "run",
+            "--",
+            "top",
+            "--kallsyms=tests/fixtures/kallsyms",
+            "--kernel-only",
+            "-n",
+            "5",
+            "--no-color",
+            "perf-report.txt",
+        ])
+        .output()
+        .expect("Failed to execute command");
+
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(output.status.success());
+    let lines: Vec<&str> = stdout.lines().collect();
+    assert_eq!(lines[2], "    0.88    0.00  entry_SYSCALL_64");
+    assert_eq!(lines[3], "    0.88    0.00  do_syscall_64");
+    assert_eq!(lines[4], "    0.68    0.68  path_openat");
+    // Outside kernel text: left as it is
+    assert_eq!(lines[5], "    0.68    0.00  0x2f6f747461707261");
+}
+
+#[test]
+fn test_top_command_show_srcline() {
+    let output = Command::new("cargo")
+        .args([
+            "run",
+            "--",
+            "top",
+            "-n",
+            "3",
+            "--show-srcline",
+            "--no-color",
+            "tests/fixtures/perf-report-sort-sym-dso-srcline.txt",
+        ])
+        .output()
+        .expect("Failed to execute command");
+
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(output.status.success());
+    let lines: Vec<&str> = stdout.lines().collect();
+    assert_eq!(
+        lines[0],
+        format!("Children%   Self%  {}Source  Function", " ".repeat(25))
+    );
+    assert_eq!(
+        lines[1],
+        "   21.72   11.94  hierarchical_4d_encoder.cpp:412  Hierarchical4DEncoder::get_mSubbandLF_significance"
+    );
+    assert_eq!(
+        lines[3],
+        "    6.83    4.65                    block_4d.h:88  Block4D::get_linear_position"
+    );
+}
+
+#[test]
+fn test_top_command_collapse_inlined() {
+    let run = |extra: &[&str]| {
+        let output = Command::new("cargo")
+            .args(["run", "--", "top", "--no-color", "--tree", "encode_block"])
+            .args(extra)
+            .arg("tests/fixtures/perf-report-inline.txt")
+            .output()
+            .expect("Failed to execute command");
+        assert!(output.status.success());
+        String::from_utf8_lossy(&output.stdout).to_string()
+    };
+
+    // Inlined frames are tagged in the tree
+    let tree = run(&[]);
+    assert!(tree.contains("    45.00      Block4D::get_linear_position (inlined)\n"));
+    assert!(tree.contains("    30.00      DCT4DBlock::DCT4DBlock\n"));
+
+    // Folded, the DCT4DBlock time reached through the inlined frame joins the direct call
+    let collapsed = run(&["--collapse-inlined"]);
+    assert!(!collapsed.contains("(inlined)"));
+    assert!(collapsed.contains("    57.00      DCT4DBlock::DCT4DBlock\n"));
+    assert!(collapsed.contains("    18.00      Hierarchical4DEncoder::quantize\n"));
+}
+
+#[test]
+fn test_top_command_java_frames() {
+    let output = Command::new("cargo")
+        .args([
+            "run",
+            "--",
+            "top",
+            "--no-color",
+            "-t",
+            "com.acme.codec",
+            "-t",
+            "HashMap::getNode",
+            "tests/fixtures/perf-report-java.txt",
+        ])
+        .output()
+        .expect("Failed to execute command");
+
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(output.status.success());
+    // JVM type descriptors read as dotted class names, signatures dropped
+    assert_eq!(
+        stdout,
+        "Children%   Self%  Function\n   64.20    0.00  com.acme.codec.Encoder::encodeFrame\n   41.90   38.70  com.acme.codec.Dct::forward\n    4.80    4.80  java.util.HashMap::getNode\n"
+    );
+}
+
+#[test]
+fn test_top_command_python_frames() {
+    let output = Command::new("cargo")
+        .args([
+            "run",
+            "--",
+            "top",
+            "--no-color",
+            "--tree",
+            "main",
+            "tests/fixtures/perf-report-python.txt",
+        ])
+        .output()
+        .expect("Failed to execute command");
+
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(output.status.success());
+    // module:function survives simplification, `<module>` included
+    assert!(stdout.starts_with("Call tree of main:<module> (88.10% children):\n"));
+    assert!(stdout.contains("   100.00          encoder:encode_frame\n"));
+    assert!(stdout.contains("   100.00                  dct:forward\n"));
+    assert!(stdout.contains("    25.00              encoder:quantize\n"));
+}
+
+#[test]
+fn test_top_command_v8_frames() {
+    let output = Command::new("cargo")
+        .args([
+            "run",
+            "--",
+            "top",
+            "--no-color",
+            "--tree",
+            "main.js",
+            "tests/fixtures/perf-report-node.txt",
+        ])
+        .output()
+        .expect("Failed to execute command");
+
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(output.status.success());
+    assert_eq!(
+        stdout,
+        "Call tree of main main.js:1 (81.40% children):
+Relative%  Function
+   100.00  main main.js:1
+    85.00      encodeFrame encoder.js:42
+    60.00          dct dct.js:9
+    20.00          Builtin:ArrayPrototypePush
+    15.00      <anonymous> main.js:30
+"
+    );
+}
+
+#[test]
+fn test_top_command_bars() {
+    let output = Command::new("cargo")
+        .args([
+            "run",
+            "--",
+            "top",
+            "-n",
+            "2",
+            "--bars",
+            "--no-color",
+            "-t",
+            "DCT4DBlock",
+            "perf-report.txt",
+        ])
+        .output()
+        .expect("Failed to execute command");
+
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(output.status.success());
+    let lines: Vec<&str> = stdout.lines().collect();
+    assert_eq!(
+        lines[1],
+        "   38.29    0.00  ████░░░░░░  DCT4DBlock::DCT4DBlock"
+    );
+}
+
+#[test]
+fn test_top_command_hierarchy_tree_style() {
+    let output = Command::new("cargo")
+        .args([
+            "run",
+            "--",
+            "top",
+            "-H",
+            "-t",
+            "rd_optimize",
+            "-t",
+            "DCT4DBlock",
+            "-t",
+            "inner_product",
+            "--tree-style",
+            "unicode",
+            "--no-color",
+            "perf-report.txt",
+        ])
+        .output()
+        .expect("Failed to execute command");
+
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(output.status.success());
+    let lines: Vec<&str> = stdout.lines().collect();
+    assert_eq!(lines[2], "   17.23    0.00  ├── DCT4DBlock::DCT4DBlock");
+    assert_eq!(lines[3], "    0.07    7.45  │   ├── std::inner_product");
+    assert_eq!(lines[4], "   99.93    0.00  │   └── (other)");
+    assert_eq!(
+        lines[5],
+        "   12.01    0.03  ├── Hierarchical4DEncoder::rd_optimize_hexadecatree"
+    );
+    assert_eq!(lines[6], "   70.76    0.00  └── (other)");
+
+    let output = Command::new("cargo")
+        .args([
+            "run",
+            "--",
+            "top",
+            "-H",
+            "-t",
+            "rd_optimize",
+            "-t",
+            "DCT4DBlock",
+            "--tree-style",
+            "ascii",
+            "perf-report.txt",
+        ])
+        .output()
+        .expect("Failed to execute command");
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(stdout.contains("   17.23    0.00  |-- DCT4DBlock::DCT4DBlock\n"));
+    assert!(stdout.contains("`-- (other)"));
+}
+
+#[test]
+fn test_top_command_mangled_report() {
+    // perf-report-narrow-tree.txt with two functions left mangled (perf report --no-demangle)
+    let output = Command::new("cargo")
+        .args([
+            "run",
+            "--",
+            "top",
+            "-H",
+            "-t",
+            "rd_optimize",
+            "-t",
+            "DCT4DBlock",
+            "--no-color",
+            "tests/fixtures/perf-report-no-demangle.txt",
+        ])
+        .output()
+        .expect("Failed to execute command");
+
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(output.status.success());
+    let lines: Vec<&str> = stdout.lines().collect();
+    assert_eq!(
+        lines[1],
+        "   71.80    0.00  TransformPartition::rd_optimize_transform"
+    );
+    assert_eq!(lines[2], "   17.23    0.00      DCT4DBlock::DCT4DBlock");
+    assert!(stdout.contains("   25.92    0.00  DCT4DBlock::DCT4DBlock\n"));
+    assert!(!stdout.contains("_Z"));
+}
+
+#[test]
+fn test_top_command_demangle_modes() {
+    let run = |mode: &str| {
+        let output = Command::new("cargo")
+            .args([
+                "run",
+                "--",
+                "--demangle",
+                mode,
+                "top",
+                "-n",
+                "2",
+                "--no-color",
+                "tests/fixtures/perf-report-no-demangle.txt",
+            ])
+            .output()
+            .expect("Failed to execute command");
+        assert!(output.status.success());
+        String::from_utf8_lossy(&output.stdout).into_owned()
+    };
+
+    let none = run("none");
+    assert!(none.contains("   38.29    0.00  _ZN10DCT4DBlockC1ERK7Block4Dd\n"));
+    let simplified = run("simplified");
+    assert!(simplified.contains("   38.29    0.00  DCT4DBlock::DCT4DBlock\n"));
+    let full = run("full");
+    assert!(full.contains("   38.29    0.00  DCT4DBlock::DCT4DBlock(Block4D const&, double)\n"));
+}
+
+#[test]
+fn test_top_command_raw_symbols_keeps_overloads() {
+    let output = Command::new("cargo")
+        .args([
+            "run",
+            "--",
+            "top",
+            "-H",
+            "-t",
+            "rd_optimize_transform",
+            "--raw-symbols",
+            "--no-color",
+            "perf-report.txt",
+        ])
+        .output()
+        .expect("Failed to execute command");
+
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(output.status.success());
+    let lines: Vec<&str> = stdout.lines().collect();
+    // Simplified, both overloads are one `rd_optimize_transform` entry
+    assert_eq!(
+        lines[1],
+        "   71.80    0.00  TransformPartition::rd_optimize_transform(Block4D const&)"
+    );
+    assert!(lines[2].starts_with(
+        "   71.78    0.00      TransformPartition::rd_optimize_transform(Block4D const&, Block4D&,"
+    ));
+    assert!(lines[4].starts_with(
+        "   20.24    0.00  TransformPartition::rd_optimize_transform(Block4D const&, Block4D&,"
+    ));
+}
+
+#[test]
+fn test_top_command_config_aliases() {
+    let output = Command::new("cargo")
+        .args([
+            "run",
+            "--",
+            "--config",
+            "tests/fixtures/config-aliases.toml",
+            "top",
+            "--no-color",
+            "-H",
+            "-t",
+            "rd_optimize_transform",
+            "-t",
+            "DCT4DBlock",
+            "-t",
+            "get_mSubbandLF",
+            "perf-report.txt",
+        ])
+        .output()
+        .expect("Failed to execute command");
+
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(output.status.success());
+    // Flat rows and nested hierarchy rows both show the labels
+    assert_eq!(
+        stdout,
+        "Children%   Self%  Function
+   71.80    0.00  TransformPartition::rd_optimize_transform
+   17.23    0.00      dct
+    0.00   11.94      significance
+   82.77    0.00      (other)
+   25.92    0.00  dct
+   21.72   11.94  significance
+"
+    );
+}
+
+#[test]
+fn test_preset_command() {
+    let run = |args: &[&str]| {
+        Command::new("cargo")
+            .args(["run", "--"])
+            .args(args)
+            .output()
+            .expect("Failed to execute command")
+    };
+
+    // The preset's command line, with the report (and further flags) appended
+    let preset = run(&[
+        "--config",
+        "tests/fixtures/config-presets.toml",
+        "preset",
+        "codec",
+        "perf-report.txt",
+    ]);
+    let direct = run(&[
+        "top",
+        "-H",
+        "-t",
+        "rd_optimize_transform",
+        "-t",
+        "DCT4DBlock|IDCT4DBlock",
+        "--no-color",
+        "perf-report.txt",
+    ]);
+    assert!(preset.status.success());
+    assert_eq!(preset.stdout, direct.stdout);
+
+    let preset = run(&[
+        "--config",
+        "tests/fixtures/config-presets.toml",
+        "preset",
+        "hot",
+        "-s",
+        "perf-report.txt",
+    ]);
+    let stdout = String::from_utf8_lossy(&preset.stdout);
+    assert_eq!(stdout.lines().count(), 4);
+    assert_eq!(
+        stdout.lines().nth(1),
+        Some("   16.30   16.30  0x000000000001fefe")
+    );
+
+    // Without a name, the presets are listed
+    let list = run(&["--config", "tests/fixtures/config-presets.toml", "preset"]);
+    let stdout = String::from_utf8_lossy(&list.stdout);
+    assert_eq!(stdout.lines().nth(1), Some("hot\ttop -n 3 --no-color"));
+
+    let unknown = run(&[
+        "--config",
+        "tests/fixtures/config-presets.toml",
+        "preset",
+        "codek",
+        "perf-report.txt",
+    ]);
+    assert_eq!(unknown.status.code(), Some(3));
+    let stderr = String::from_utf8_lossy(&unknown.stderr);
+    assert!(
+        stderr.contains("Unknown preset: codek (defined: codec, hot)"),
+        "{}",
+        stderr
+    );
+}
+
+#[test]
+fn test_preset_command_global_options_before_name() {
+    let run = |args: &[&str]| {
+        Command::new("cargo")
+            .args(["run", "--"])
+            .args(args)
+            .output()
+            .expect("Failed to execute command")
+    };
+    let direct = run(&["top", "-n", "3", "--no-color", "perf-report.txt"]);
+
+    // Global options may stand between `preset` and the name
+    let preset = run(&[
+        "preset",
+        "--config",
+        "tests/fixtures/config-presets.toml",
+        "hot",
+        "perf-report.txt",
+    ]);
+    let stderr = String::from_utf8_lossy(&preset.stderr);
+    assert!(preset.status.success(), "Command failed: {}", stderr);
+    assert_eq!(preset.stdout, direct.stdout);
+
+    let quiet = run(&[
+        "--config",
+        "tests/fixtures/config-presets.toml",
+        "preset",
+        "-q",
+        "hot",
+        "perf-report.txt",
+    ]);
+    let stdout = String::from_utf8_lossy(&quiet.stdout);
+    assert!(quiet.status.success());
+    assert_eq!(stdout.lines().count(), 3);
+    assert_eq!(
+        stdout.lines().next(),
+        Some("   90.74    0.00  parallel_for_with_progress")
+    );
+}
+
+#[test]
+fn test_top_command_config_rewrite_rules() {
+    let output = Command::new("cargo")
+        .args([
+            "run",
+            "--",
+            "--config",
+            "tests/fixtures/config.toml",
+            "top",
+            "-n",
+            "2",
+            "-t",
+            "Hierarchical4DEncoder",
+            "perf-report.txt",
+        ])
+        .output()
+        .expect("Failed to execute command");
+
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(output.status.success());
+    let lines: Vec<&str> = stdout.lines().collect();
+    // Targets still match the report's names
+    assert_eq!(
+        lines[1],
+        "   37.51    0.03  H4DEncoder::rd_optimize_hexadecatree"
+    );
+
+    let output = Command::new("cargo")
+        .args([
+            "run",
+            "--",
+            "--config",
+            "tests/fixtures/targets.toml",
+            "top",
+            "perf-report.txt",
+        ])
+        .output()
+        .expect("Failed to execute command");
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert_eq!(output.status.code(), Some(3));
+    assert!(stderr.contains("Invalid config file"), "{}", stderr);
+}
@@ -0,0 +1,83 @@
+use std::fs;
+use std::process::Command;
+
+const DE_LOCALE: &str = "tests/fixtures/perf-report-de-locale.txt";
+const REP2: &str = "tests/fixtures/perf-report-de-locale-rep2.txt";
+const DISTURBED: &str = "tests/fixtures/perf-report-de-locale-disturbed.txt";
+
+#[test]
+fn test_trend_command() {
+    let output = Command::new("cargo")
+        .args([
+            "run",
+            "--",
+            "trend",
+            "--no-color",
+            DISTURBED,
+            REP2,
+            DE_LOCALE,
+        ])
+        .output()
+        .expect("Failed to execute command");
+
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(output.status.success(), "Command failed: {}", stderr);
+
+    let lines: Vec<&str> = stdout.lines().collect();
+    assert_eq!(lines[0], format!("[1] {}", DISTURBED));
+    assert_eq!(lines[5], "     [1]       [2]       [3]     Slope  Function");
+    assert_eq!(
+        lines[6],
+        "   55.10     69.80     71.80     +8.35  TransformPartition::rd_optimize_transform  ! growing"
+    );
+    // Flat from the second report on, so not growing
+    assert_eq!(
+        lines[7],
+        "   25.02     38.29     38.29     +6.63  DCT4DBlock::DCT4DBlock"
+    );
+}
+
+#[test]
+fn test_trend_command_directory() {
+    let dir = std::env::temp_dir().join(format!("pperf-trend-{}", std::process::id()));
+    fs::create_dir_all(&dir).unwrap();
+    // Name order is series order
+    fs::copy(DE_LOCALE, dir.join("1.txt")).unwrap();
+    fs::copy(DISTURBED, dir.join("2.txt")).unwrap();
+
+    let output = Command::new("cargo")
+        .args(["run", "--", "trend", "--no-color", "-t", "DCT4DBlock"])
+        .arg(&dir)
+        .output()
+        .expect("Failed to execute command");
+    fs::remove_dir_all(&dir).unwrap();
+
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(output.status.success());
+    assert!(stdout.starts_with(&format!("[1] {}\n", dir.join("1.txt").display())));
+    assert_eq!(
+        stdout.lines().last(),
+        Some("   38.29     25.02    -13.27  DCT4DBlock::DCT4DBlock")
+    );
+}
+
+#[test]
+fn test_trend_command_no_children_title() {
+    let output = Command::new("cargo")
+        .args([
+            "run",
+            "--",
+            "trend",
+            "--no-color",
+            "tests/fixtures/perf-report-no-children.txt",
+            "tests/fixtures/perf-report-no-children.txt",
+        ])
+        .output()
+        .expect("Failed to execute command");
+
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(output.status.success());
+    // `--no-children` reports list their Overhead column
+    assert_eq!(stdout.lines().nth(3), Some("Overhead% per report:"));
+}